@@ -0,0 +1,28 @@
+//! The standard function blocks from IEC 61131-3.
+//!
+//! Function blocks keep their state between calls, so they'll normally be
+//! stored in a `static mut` and called once per `poll()`. Each block has a
+//! `const fn new()` to make that possible.
+//!
+//! ```rust,ignore
+//! use iec_std::function_blocks::Ton;
+//!
+//! static mut DEBOUNCE: Ton = Ton::new();
+//!
+//! #[no_mangle]
+//! pub extern "C" fn poll() {
+//!     let debounce_time = Duration::from_millis(50);
+//!     let pressed = unsafe { DEBOUNCE.call(button(), debounce_time) };
+//!     ...
+//! }
+//! ```
+
+mod bistables;
+mod counters;
+mod edges;
+mod timers;
+
+pub use bistables::{Rs, Sr};
+pub use counters::{Ctd, Ctu, Ctud};
+pub use edges::{FTrig, RTrig};
+pub use timers::{Tof, Ton, Tp};
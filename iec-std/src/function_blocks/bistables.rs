@@ -0,0 +1,62 @@
+/// `SR`, a set-dominant bistable.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Sr {
+    q1: bool,
+}
+
+impl Sr {
+    pub const fn new() -> Sr { Sr { q1: false } }
+
+    /// Execute the block, returning `Q1`. Setting wins when both inputs are
+    /// on.
+    pub fn call(&mut self, set1: bool, reset: bool) -> bool {
+        self.q1 = set1 || (!reset && self.q1);
+        self.q1
+    }
+
+    pub fn q1(&self) -> bool { self.q1 }
+}
+
+/// `RS`, a reset-dominant bistable.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Rs {
+    q1: bool,
+}
+
+impl Rs {
+    pub const fn new() -> Rs { Rs { q1: false } }
+
+    /// Execute the block, returning `Q1`. Resetting wins when both inputs
+    /// are on.
+    pub fn call(&mut self, set: bool, reset1: bool) -> bool {
+        self.q1 = !reset1 && (set || self.q1);
+        self.q1
+    }
+
+    pub fn q1(&self) -> bool { self.q1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_dominant() {
+        let mut sr = Sr::new();
+
+        assert!(sr.call(true, false));
+        assert!(sr.call(false, false));
+        assert!(sr.call(true, true));
+        assert!(!sr.call(false, true));
+    }
+
+    #[test]
+    fn reset_dominant() {
+        let mut rs = Rs::new();
+
+        assert!(rs.call(true, false));
+        assert!(rs.call(false, false));
+        assert!(!rs.call(true, true));
+        assert!(!rs.call(false, false));
+    }
+}
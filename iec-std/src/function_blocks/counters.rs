@@ -0,0 +1,193 @@
+use crate::function_blocks::RTrig;
+
+/// `CTU`, an up-counter.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Ctu {
+    cu: RTrig,
+    q: bool,
+    cv: i16,
+}
+
+impl Ctu {
+    pub const fn new() -> Ctu {
+        Ctu {
+            cu: RTrig::new(),
+            q: false,
+            cv: 0,
+        }
+    }
+
+    /// Execute the counter, returning `Q` (whether `CV` has reached `PV`).
+    ///
+    /// Each rising edge on `cu` increments the count.
+    pub fn call(&mut self, cu: bool, reset: bool, preset: i16) -> bool {
+        let count_up = self.cu.call(cu);
+
+        if reset {
+            self.cv = 0;
+        } else if count_up && self.cv < i16::max_value() {
+            self.cv += 1;
+        }
+
+        self.q = self.cv >= preset;
+        self.q
+    }
+
+    pub fn q(&self) -> bool { self.q }
+
+    /// The current count.
+    pub fn cv(&self) -> i16 { self.cv }
+}
+
+/// `CTD`, a down-counter.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Ctd {
+    cd: RTrig,
+    q: bool,
+    cv: i16,
+}
+
+impl Ctd {
+    pub const fn new() -> Ctd {
+        Ctd {
+            cd: RTrig::new(),
+            q: false,
+            cv: 0,
+        }
+    }
+
+    /// Execute the counter, returning `Q` (whether `CV` has reached zero).
+    ///
+    /// Setting `load` resets the count to `preset`, then each rising edge on
+    /// `cd` decrements it.
+    pub fn call(&mut self, cd: bool, load: bool, preset: i16) -> bool {
+        let count_down = self.cd.call(cd);
+
+        if load {
+            self.cv = preset;
+        } else if count_down && self.cv > i16::min_value() {
+            self.cv -= 1;
+        }
+
+        self.q = self.cv <= 0;
+        self.q
+    }
+
+    pub fn q(&self) -> bool { self.q }
+
+    /// The current count.
+    pub fn cv(&self) -> i16 { self.cv }
+}
+
+/// `CTUD`, an up-down counter.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Ctud {
+    cu: RTrig,
+    cd: RTrig,
+    qu: bool,
+    qd: bool,
+    cv: i16,
+}
+
+impl Ctud {
+    pub const fn new() -> Ctud {
+        Ctud {
+            cu: RTrig::new(),
+            cd: RTrig::new(),
+            qu: false,
+            qd: false,
+            cv: 0,
+        }
+    }
+
+    /// Execute the counter.
+    ///
+    /// `reset` takes priority over `load`, and simultaneous rising edges on
+    /// `cu` and `cd` cancel out.
+    pub fn call(
+        &mut self,
+        cu: bool,
+        cd: bool,
+        reset: bool,
+        load: bool,
+        preset: i16,
+    ) {
+        let count_up = self.cu.call(cu);
+        let count_down = self.cd.call(cd);
+
+        if reset {
+            self.cv = 0;
+        } else if load {
+            self.cv = preset;
+        } else if count_up && !count_down && self.cv < i16::max_value() {
+            self.cv += 1;
+        } else if count_down && !count_up && self.cv > i16::min_value() {
+            self.cv -= 1;
+        }
+
+        self.qu = self.cv >= preset;
+        self.qd = self.cv <= 0;
+    }
+
+    /// Has the count reached the preset value?
+    pub fn qu(&self) -> bool { self.qu }
+
+    /// Has the count reached zero?
+    pub fn qd(&self) -> bool { self.qd }
+
+    /// The current count.
+    pub fn cv(&self) -> i16 { self.cv }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_rising_edges() {
+        let mut ctu = Ctu::new();
+
+        assert!(!ctu.call(true, false, 2));
+        assert!(!ctu.call(true, false, 2));
+        assert!(!ctu.call(false, false, 2));
+        assert!(ctu.call(true, false, 2));
+        assert_eq!(ctu.cv(), 2);
+        assert!(!ctu.call(true, true, 2));
+        assert_eq!(ctu.cv(), 0);
+    }
+
+    #[test]
+    fn count_down_from_the_preset() {
+        let mut ctd = Ctd::new();
+
+        assert!(!ctd.call(false, true, 2));
+        assert!(!ctd.call(true, false, 2));
+        assert_eq!(ctd.cv(), 1);
+        assert!(!ctd.call(false, false, 2));
+        assert!(ctd.call(true, false, 2));
+        assert_eq!(ctd.cv(), 0);
+    }
+
+    #[test]
+    fn count_up_and_down() {
+        let mut ctud = Ctud::new();
+
+        ctud.call(false, false, false, true, 3);
+        assert_eq!(ctud.cv(), 3);
+        assert!(ctud.qu());
+
+        ctud.call(false, true, false, false, 3);
+        assert_eq!(ctud.cv(), 2);
+        assert!(!ctud.qu());
+
+        // simultaneous edges cancel out
+        ctud.call(true, false, false, false, 3);
+        ctud.call(false, false, false, false, 3);
+        ctud.call(true, true, false, false, 3);
+        assert_eq!(ctud.cv(), 3);
+
+        ctud.call(false, false, true, true, 3);
+        assert_eq!(ctud.cv(), 0);
+        assert!(ctud.qd());
+    }
+}
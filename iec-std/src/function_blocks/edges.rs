@@ -0,0 +1,84 @@
+/// `R_TRIG`, rising edge detection.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct RTrig {
+    previous: bool,
+    q: bool,
+}
+
+impl RTrig {
+    pub const fn new() -> RTrig {
+        RTrig {
+            previous: false,
+            q: false,
+        }
+    }
+
+    /// Execute the block, returning `true` if `clk` has just turned on.
+    pub fn call(&mut self, clk: bool) -> bool {
+        self.q = clk && !self.previous;
+        self.previous = clk;
+        self.q
+    }
+
+    pub fn q(&self) -> bool { self.q }
+}
+
+/// `F_TRIG`, falling edge detection.
+///
+/// The first call won't report an edge when `clk` starts off, so a signal
+/// needs to turn on before it can fall.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct FTrig {
+    previous: bool,
+    q: bool,
+}
+
+impl FTrig {
+    pub const fn new() -> FTrig {
+        FTrig {
+            previous: false,
+            q: false,
+        }
+    }
+
+    /// Execute the block, returning `true` if `clk` has just turned off.
+    pub fn call(&mut self, clk: bool) -> bool {
+        self.q = !clk && self.previous;
+        self.previous = clk;
+        self.q
+    }
+
+    pub fn q(&self) -> bool { self.q }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_edges() {
+        let clk = [false, true, true, false, false, true];
+        let mut r_trig = RTrig::new();
+        let mut f_trig = FTrig::new();
+
+        let rising: [bool; 6] = [
+            r_trig.call(clk[0]),
+            r_trig.call(clk[1]),
+            r_trig.call(clk[2]),
+            r_trig.call(clk[3]),
+            r_trig.call(clk[4]),
+            r_trig.call(clk[5]),
+        ];
+        let falling: [bool; 6] = [
+            f_trig.call(clk[0]),
+            f_trig.call(clk[1]),
+            f_trig.call(clk[2]),
+            f_trig.call(clk[3]),
+            f_trig.call(clk[4]),
+            f_trig.call(clk[5]),
+        ];
+
+        assert_eq!(rising, [false, true, false, false, false, true]);
+        assert_eq!(falling, [false, false, false, true, false, false]);
+    }
+}
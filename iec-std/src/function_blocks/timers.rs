@@ -0,0 +1,226 @@
+use crate::time::{Duration, Instant};
+
+/// How long it has been since `start`, capped at `preset`.
+fn elapsed_since(start: Instant, now: Instant, preset: Duration) -> Duration {
+    let elapsed = if now > start {
+        now.duration_since(start)
+    } else {
+        Duration::from_secs(0)
+    };
+
+    if elapsed > preset {
+        preset
+    } else {
+        elapsed
+    }
+}
+
+/// `TON`, an on-delay timer.
+///
+/// The output turns on once the input has been on for the preset time, and
+/// turns off as soon as the input does.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ton {
+    start: Option<Instant>,
+    q: bool,
+    et: Duration,
+}
+
+impl Ton {
+    pub const fn new() -> Ton {
+        Ton {
+            start: None,
+            q: false,
+            et: Duration::from_secs(0),
+        }
+    }
+
+    /// Execute the timer, returning `Q`.
+    pub fn call(&mut self, input: bool, preset: Duration) -> bool {
+        self.update(Instant::now(), input, preset)
+    }
+
+    fn update(&mut self, now: Instant, input: bool, preset: Duration) -> bool {
+        if input {
+            let start = *self.start.get_or_insert(now);
+            self.et = elapsed_since(start, now, preset);
+            self.q = self.et >= preset;
+        } else {
+            *self = Ton::new();
+        }
+
+        self.q
+    }
+
+    /// The timer's output.
+    pub fn q(&self) -> bool { self.q }
+
+    /// How long the input has been on, up to the preset time.
+    pub fn et(&self) -> Duration { self.et }
+}
+
+impl Default for Ton {
+    fn default() -> Ton { Ton::new() }
+}
+
+/// `TOF`, an off-delay timer.
+///
+/// The output turns on with the input, and stays on until the input has
+/// been off for the preset time.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Tof {
+    start: Option<Instant>,
+    q: bool,
+    et: Duration,
+}
+
+impl Tof {
+    pub const fn new() -> Tof {
+        Tof {
+            start: None,
+            q: false,
+            et: Duration::from_secs(0),
+        }
+    }
+
+    /// Execute the timer, returning `Q`.
+    pub fn call(&mut self, input: bool, preset: Duration) -> bool {
+        self.update(Instant::now(), input, preset)
+    }
+
+    fn update(&mut self, now: Instant, input: bool, preset: Duration) -> bool {
+        if input {
+            self.start = None;
+            self.q = true;
+            self.et = Duration::from_secs(0);
+        } else if self.q {
+            let start = *self.start.get_or_insert(now);
+            self.et = elapsed_since(start, now, preset);
+            self.q = self.et < preset;
+        }
+
+        self.q
+    }
+
+    /// The timer's output.
+    pub fn q(&self) -> bool { self.q }
+
+    /// How long the input has been off, up to the preset time.
+    pub fn et(&self) -> Duration { self.et }
+}
+
+impl Default for Tof {
+    fn default() -> Tof { Tof::new() }
+}
+
+/// `TP`, a pulse timer.
+///
+/// A rising edge on the input turns the output on for exactly the preset
+/// time, regardless of what the input does in the meantime.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Tp {
+    start: Option<Instant>,
+    previous_input: bool,
+    q: bool,
+    et: Duration,
+}
+
+impl Tp {
+    pub const fn new() -> Tp {
+        Tp {
+            start: None,
+            previous_input: false,
+            q: false,
+            et: Duration::from_secs(0),
+        }
+    }
+
+    /// Execute the timer, returning `Q`.
+    pub fn call(&mut self, input: bool, preset: Duration) -> bool {
+        self.update(Instant::now(), input, preset)
+    }
+
+    fn update(&mut self, now: Instant, input: bool, preset: Duration) -> bool {
+        let rising_edge = input && !self.previous_input;
+        self.previous_input = input;
+
+        if rising_edge && self.start.is_none() {
+            self.start = Some(now);
+        }
+
+        if let Some(start) = self.start {
+            self.et = elapsed_since(start, now, preset);
+            self.q = self.et < preset;
+
+            if !self.q && !input {
+                // the pulse is over and the input was released
+                self.start = None;
+                self.et = Duration::from_secs(0);
+            }
+        }
+
+        self.q
+    }
+
+    /// The timer's output.
+    pub fn q(&self) -> bool { self.q }
+
+    /// How long the current pulse has been running.
+    pub fn et(&self) -> Duration { self.et }
+}
+
+impl Default for Tp {
+    fn default() -> Tp { Tp::new() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(millis: u64) -> Instant {
+        Instant::from_duration(Duration::from_millis(millis))
+    }
+
+    const PRESET: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn on_delay() {
+        let mut ton = Ton::new();
+
+        assert!(!ton.update(at(10), true, PRESET));
+        assert!(!ton.update(at(60), true, PRESET));
+        assert_eq!(ton.et(), Duration::from_millis(50));
+        assert!(ton.update(at(110), true, PRESET));
+        assert!(ton.update(at(500), true, PRESET));
+        assert_eq!(ton.et(), PRESET);
+        assert!(!ton.update(at(510), false, PRESET));
+        assert_eq!(ton.et(), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn off_delay() {
+        let mut tof = Tof::new();
+
+        assert!(!tof.update(at(0), false, PRESET));
+        assert!(tof.update(at(10), true, PRESET));
+        assert!(tof.update(at(150), false, PRESET));
+        assert!(tof.update(at(200), false, PRESET));
+        assert!(!tof.update(at(250), false, PRESET));
+        assert_eq!(tof.et(), PRESET);
+    }
+
+    #[test]
+    fn pulse() {
+        let mut tp = Tp::new();
+
+        assert!(tp.update(at(10), true, PRESET));
+        assert!(tp.update(at(60), false, PRESET));
+        // rising edges are ignored during a pulse
+        assert!(tp.update(at(70), true, PRESET));
+        assert!(!tp.update(at(110), true, PRESET));
+        assert_eq!(tp.et(), PRESET);
+        assert!(!tp.update(at(150), false, PRESET));
+        assert_eq!(tp.et(), Duration::from_secs(0));
+        assert!(tp.update(at(300), true, PRESET));
+    }
+}
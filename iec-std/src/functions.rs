@@ -0,0 +1,25 @@
+//! The standard functions from IEC 61131-3.
+//!
+//! Function names are the lowercase version of their IEC counterpart (e.g.
+//! `REAL_TO_INT()` becomes [`real_to_int()`]). Extensible functions like
+//! `MAX()` take two arguments, so `MAX(a, b, c)` is written as
+//! `max(max(a, b), c)`.
+
+mod bit_string;
+mod character_string;
+mod comparison;
+mod conversions;
+mod numeric;
+mod selection;
+
+pub use bit_string::{rol, ror, shl, shr, BitString};
+pub use character_string::{
+    concat, delete, find, insert, left, len, mid, replace, right,
+};
+pub use comparison::{eq, ge, gt, le, lt, ne};
+pub use conversions::*;
+pub use numeric::{
+    abs, acos, asin, atan, cos, exp, expt, ln, log, sin, sqrt, tan, Abs,
+    Exponent, Real,
+};
+pub use selection::{limit, max, min, mux, sel};
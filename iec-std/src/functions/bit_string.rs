@@ -0,0 +1,76 @@
+/// The `ANY_BIT` types, `BYTE`, `WORD`, `DWORD` and `LWORD`.
+pub trait BitString: Copy {
+    const BITS: u32;
+
+    fn shift_left(self, n: u32) -> Self;
+    fn shift_right(self, n: u32) -> Self;
+    fn rotate_left(self, n: u32) -> Self;
+    fn rotate_right(self, n: u32) -> Self;
+}
+
+macro_rules! bit_string {
+    ($($ty:ty),*) => {
+        $(
+            impl BitString for $ty {
+                const BITS: u32 = (core::mem::size_of::<$ty>() * 8) as u32;
+
+                fn shift_left(self, n: u32) -> Self {
+                    self.checked_shl(n).unwrap_or(0)
+                }
+
+                fn shift_right(self, n: u32) -> Self {
+                    self.checked_shr(n).unwrap_or(0)
+                }
+
+                fn rotate_left(self, n: u32) -> Self {
+                    <$ty>::rotate_left(self, n)
+                }
+
+                fn rotate_right(self, n: u32) -> Self {
+                    <$ty>::rotate_right(self, n)
+                }
+            }
+        )*
+    };
+}
+
+bit_string!(u8, u16, u32, u64);
+
+/// `SHL()`, shift left by `n` bits, filling with zeroes.
+pub fn shl<B: BitString>(value: B, n: u32) -> B { value.shift_left(n) }
+
+/// `SHR()`, shift right by `n` bits, filling with zeroes.
+pub fn shr<B: BitString>(value: B, n: u32) -> B { value.shift_right(n) }
+
+/// `ROL()`, rotate left by `n` bits.
+pub fn rol<B: BitString>(value: B, n: u32) -> B { value.rotate_left(n) }
+
+/// `ROR()`, rotate right by `n` bits.
+pub fn ror<B: BitString>(value: B, n: u32) -> B { value.rotate_right(n) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shifts_fill_with_zeroes() {
+        assert_eq!(shl(0b1000_0001_u8, 1), 0b0000_0010);
+        assert_eq!(shr(0b1000_0001_u8, 1), 0b0100_0000);
+        assert_eq!(shl(0xFFFF_u16, 16), 0);
+        assert_eq!(shr(0xFFFF_FFFF_u32, 40), 0);
+    }
+
+    #[test]
+    fn rotates_wrap_around() {
+        assert_eq!(rol(0b1000_0001_u8, 1), 0b0000_0011);
+        assert_eq!(ror(0b1000_0001_u8, 1), 0b1100_0000);
+        assert_eq!(rol(0x1234_u16, 16), 0x1234);
+        assert_eq!(ror(1_u64, 1), 1 << 63);
+    }
+
+    #[test]
+    fn bit_counts() {
+        assert_eq!(<u8 as BitString>::BITS, 8);
+        assert_eq!(<u64 as BitString>::BITS, 64);
+    }
+}
@@ -0,0 +1,161 @@
+//! The character string functions.
+//!
+//! Character positions are 1-based, as in IEC 61131-3. Positions and lengths
+//! which go past the end of a string are clamped instead of being treated as
+//! an error, and any result which doesn't fit in the output string is
+//! truncated.
+
+use crate::strings::AnyString;
+use core::cmp;
+
+/// Turn a 1-based position into an index into `chars`.
+fn index<C>(chars: &[C], position: usize) -> usize {
+    cmp::min(position.saturating_sub(1), chars.len())
+}
+
+/// `LEN()`, the number of characters in a string.
+pub fn len<S: AnyString>(s: &S) -> usize { s.as_chars().len() }
+
+/// `LEFT()`, the leftmost `l` characters of a string.
+pub fn left<S: AnyString>(s: &S, l: usize) -> S {
+    let chars = s.as_chars();
+    S::from_chars(&chars[..cmp::min(l, chars.len())])
+}
+
+/// `RIGHT()`, the rightmost `l` characters of a string.
+pub fn right<S: AnyString>(s: &S, l: usize) -> S {
+    let chars = s.as_chars();
+    S::from_chars(&chars[chars.len().saturating_sub(l)..])
+}
+
+/// `MID()`, the `l` characters of a string starting at position `p`.
+pub fn mid<S: AnyString>(s: &S, l: usize, p: usize) -> S {
+    let chars = s.as_chars();
+    let start = index(chars, p);
+    let end = cmp::min(start.saturating_add(l), chars.len());
+
+    S::from_chars(&chars[start..end])
+}
+
+/// `CONCAT()`, append `in2` to the end of `in1`.
+pub fn concat<S, T>(in1: &S, in2: &T) -> S
+where
+    S: AnyString,
+    T: AnyString<Char = S::Char>,
+{
+    let mut s = S::from_chars(in1.as_chars());
+    s.extend_from_chars(in2.as_chars());
+    s
+}
+
+/// `INSERT()`, insert `in2` after the `p`'th character of `in1`.
+pub fn insert<S, T>(in1: &S, in2: &T, p: usize) -> S
+where
+    S: AnyString,
+    T: AnyString<Char = S::Char>,
+{
+    let chars = in1.as_chars();
+    let split = cmp::min(p, chars.len());
+
+    let mut s = S::from_chars(&chars[..split]);
+    s.extend_from_chars(in2.as_chars());
+    s.extend_from_chars(&chars[split..]);
+    s
+}
+
+/// `DELETE()`, remove `l` characters from a string, starting at position
+/// `p`.
+pub fn delete<S: AnyString>(s: &S, l: usize, p: usize) -> S {
+    replace(s, &S::default(), l, p)
+}
+
+/// `REPLACE()`, replace `l` characters of `in1` with `in2`, starting at
+/// position `p`.
+pub fn replace<S, T>(in1: &S, in2: &T, l: usize, p: usize) -> S
+where
+    S: AnyString,
+    T: AnyString<Char = S::Char>,
+{
+    let chars = in1.as_chars();
+    let start = index(chars, p);
+    let end = cmp::min(start.saturating_add(l), chars.len());
+
+    let mut s = S::from_chars(&chars[..start]);
+    s.extend_from_chars(in2.as_chars());
+    s.extend_from_chars(&chars[end..]);
+    s
+}
+
+/// `FIND()`, the position of the first occurrence of `in2` in `in1`, or `0`
+/// if it can't be found.
+pub fn find<S, T>(in1: &S, in2: &T) -> usize
+where
+    S: AnyString,
+    T: AnyString<Char = S::Char>,
+{
+    let haystack = in1.as_chars();
+    let needle = in2.as_chars();
+
+    if needle.is_empty() {
+        return 0;
+    }
+
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|i| i + 1)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strings::{IecString, WString};
+
+    fn s(text: &str) -> IecString<16> { text.parse().unwrap() }
+
+    #[test]
+    fn substrings() {
+        assert_eq!(len(&s("ASTUTE")), 6);
+        assert_eq!(left(&s("ASTR"), 3), s("AST"));
+        assert_eq!(right(&s("SUSIE"), 3), s("SIE"));
+        assert_eq!(mid(&s("ASTR"), 2, 2), s("ST"));
+        assert_eq!(left(&s("ASTR"), 10), s("ASTR"));
+        assert_eq!(mid(&s("ASTR"), 5, 3), s("TR"));
+        assert_eq!(mid(&s("ASTR"), 1, 9), s(""));
+    }
+
+    #[test]
+    fn concatenate() {
+        assert_eq!(concat(&s("AB"), &s("CD")), s("ABCD"));
+
+        let short: IecString<3> = "ABC".parse().unwrap();
+        let truncated = concat(&short, &s("DEF"));
+        assert_eq!(truncated.as_bytes(), b"ABC");
+    }
+
+    #[test]
+    fn insert_delete_and_replace() {
+        assert_eq!(insert(&s("ABC"), &s("XY"), 2), s("ABXYC"));
+        assert_eq!(insert(&s("ABC"), &s("XY"), 0), s("XYABC"));
+        assert_eq!(delete(&s("ABXYC"), 2, 3), s("ABC"));
+        assert_eq!(delete(&s("ABC"), 5, 2), s("A"));
+        assert_eq!(replace(&s("ABCDE"), &s("X"), 2, 3), s("ABXE"));
+    }
+
+    #[test]
+    fn find_a_substring() {
+        assert_eq!(find(&s("ABCBC"), &s("BC")), 2);
+        assert_eq!(find(&s("ABCBC"), &s("CA")), 0);
+        assert_eq!(find(&s("ABC"), &s("")), 0);
+    }
+
+    #[test]
+    fn wide_strings() {
+        let w: WString<8> = "Straße".parse().unwrap();
+        let expected: WString<8> = "aße".parse().unwrap();
+
+        assert_eq!(len(&w), 6);
+        assert_eq!(right(&w, 3), expected);
+    }
+}
@@ -0,0 +1,33 @@
+/// `GT()`, greater than.
+pub fn gt<T: PartialOrd>(in1: T, in2: T) -> bool { in1 > in2 }
+
+/// `GE()`, greater than or equal.
+pub fn ge<T: PartialOrd>(in1: T, in2: T) -> bool { in1 >= in2 }
+
+/// `EQ()`, equality.
+pub fn eq<T: PartialEq>(in1: T, in2: T) -> bool { in1 == in2 }
+
+/// `LE()`, less than or equal.
+pub fn le<T: PartialOrd>(in1: T, in2: T) -> bool { in1 <= in2 }
+
+/// `LT()`, less than.
+pub fn lt<T: PartialOrd>(in1: T, in2: T) -> bool { in1 < in2 }
+
+/// `NE()`, inequality.
+pub fn ne<T: PartialEq>(in1: T, in2: T) -> bool { in1 != in2 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_numbers() {
+        assert!(gt(2, 1));
+        assert!(!gt(1, 1));
+        assert!(ge(1, 1));
+        assert!(eq(1.5, 1.5));
+        assert!(le(-3_i8, 2));
+        assert!(lt(0_u32, 1));
+        assert!(ne(true, false));
+    }
+}
@@ -0,0 +1,448 @@
+//! The `*_TO_*` type conversion functions.
+//!
+//! Conversions follow IEC 61131-3 semantics:
+//!
+//! - `REAL` and `LREAL` are rounded to the nearest integer (halfway cases
+//!   round away from zero) when converted to an integer or bit string
+//! - Converting to `BOOL` checks whether the value is non-zero
+//! - `BOOL` becomes `0` or `1`
+//! - Integers which don't fit in the destination are truncated to the
+//!   destination's width
+//! - A `TIME` is converted to and from a number of milliseconds. Negative
+//!   numbers become `T#0s`, because a [`Duration`] can't be negative
+//! - `TRUNC()` converts a `REAL` or `LREAL` to an integer by rounding towards
+//!   zero
+//! - Numbers are written to a `STRING` or `WSTRING` in decimal, and `BOOL`
+//!   becomes `TRUE` or `FALSE`. The text is truncated if it doesn't fit
+//! - Parsing a `STRING` or `WSTRING` ignores leading and trailing whitespace,
+//!   returning `None` if the text isn't a valid number
+//! - `WSTRING_TO_STRING()` replaces characters which can't be stored in a
+//!   `STRING` with `?`
+//!
+//! The `DATE`, `TIME_OF_DAY` and `DATE_AND_TIME` types don't exist in this
+//! crate yet, so there are no conversions to or from them.
+
+use crate::{
+    strings::{AnyString, IecString, WString},
+    time::Duration,
+};
+use core::fmt::{self, Write};
+
+/// How one Rust type is converted to another.
+trait Convert<T> {
+    fn convert(self) -> T;
+}
+
+macro_rules! convert {
+    // integers and bit strings
+    (@integer $from:ty => [$($to:ty),*]) => {
+        $(
+            impl Convert<$to> for $from {
+                #[allow(clippy::cast_lossless)]
+                fn convert(self) -> $to { self as $to }
+            }
+        )*
+
+        impl Convert<bool> for $from {
+            fn convert(self) -> bool { self != 0 }
+        }
+
+        impl Convert<Duration> for $from {
+            #[allow(unused_comparisons)]
+            fn convert(self) -> Duration {
+                if self < 0 {
+                    Duration::from_secs(0)
+                } else {
+                    Duration::from_millis(self as u64)
+                }
+            }
+        }
+    };
+    (@real $from:ty => [$($to:ty),*]) => {
+        $(
+            impl Convert<$to> for $from {
+                fn convert(self) -> $to { libm::round(self as f64) as $to }
+            }
+        )*
+
+        impl Convert<bool> for $from {
+            fn convert(self) -> bool { self != 0.0 }
+        }
+
+        impl Convert<f32> for $from {
+            fn convert(self) -> f32 { self as f32 }
+        }
+
+        impl Convert<f64> for $from {
+            fn convert(self) -> f64 { self as f64 }
+        }
+
+        impl Convert<Duration> for $from {
+            fn convert(self) -> Duration {
+                let millis = libm::round(self as f64);
+
+                if millis > 0.0 {
+                    Duration::from_millis(millis as u64)
+                } else {
+                    Duration::from_secs(0)
+                }
+            }
+        }
+    };
+    (@bool [$($to:ty),*]) => {
+        $(
+            impl Convert<$to> for bool {
+                fn convert(self) -> $to { u8::from(self) as $to }
+            }
+        )*
+
+        impl Convert<bool> for bool {
+            fn convert(self) -> bool { self }
+        }
+
+        impl Convert<Duration> for bool {
+            fn convert(self) -> Duration {
+                Duration::from_millis(u64::from(self))
+            }
+        }
+    };
+    (@time [$($to:ty),*]) => {
+        $(
+            impl Convert<$to> for Duration {
+                fn convert(self) -> $to { self.as_millis() as $to }
+            }
+        )*
+
+        impl Convert<bool> for Duration {
+            fn convert(self) -> bool { self != Duration::from_secs(0) }
+        }
+
+        impl Convert<Duration> for Duration {
+            fn convert(self) -> Duration { self }
+        }
+    };
+}
+
+macro_rules! integers {
+    ($($from:ty),*) => {
+        $(
+            convert!(@integer $from => [
+                i8, i16, i32, i64, u8, u16, u32, u64, f32, f64
+            ]);
+        )*
+    };
+}
+
+integers!(i8, i16, i32, i64, u8, u16, u32, u64);
+convert!(@real f32 => [i8, i16, i32, i64, u8, u16, u32, u64]);
+convert!(@real f64 => [i8, i16, i32, i64, u8, u16, u32, u64]);
+convert!(@bool [i8, i16, i32, i64, u8, u16, u32, u64, f32, f64]);
+convert!(@time [i8, i16, i32, i64, u8, u16, u32, u64, f32, f64]);
+
+/// Generate a `from_to_to()` function for every pair of types.
+macro_rules! conversions {
+    ($($name:ident: $ty:ty),* $(,)*) => {
+        conversions!(@each [$($name: $ty),*] $($name: $ty),*);
+    };
+    (@each $all:tt $($from:ident: $from_ty:ty),*) => {
+        $(
+            conversions!(@from $from: $from_ty, $all);
+        )*
+    };
+    (@from $from:ident: $from_ty:ty, [$($to:ident: $to_ty:ty),*]) => {
+        paste::item! {
+            $(
+                pub fn [<$from _to_ $to>](value: $from_ty) -> $to_ty {
+                    Convert::<$to_ty>::convert(value)
+                }
+            )*
+        }
+    };
+}
+
+conversions! {
+    bool: bool,
+    sint: i8,
+    int: i16,
+    dint: i32,
+    lint: i64,
+    usint: u8,
+    uint: u16,
+    udint: u32,
+    ulint: u64,
+    real: f32,
+    lreal: f64,
+    byte: u8,
+    word: u16,
+    dword: u32,
+    lword: u64,
+    time: Duration,
+}
+
+/// `TRUNC()`, converting a `REAL` or `LREAL` to an integer by discarding its
+/// fractional part.
+pub trait Trunc<T> {
+    fn trunc(self) -> T;
+}
+
+macro_rules! trunc {
+    ($($from:ident: $from_ty:ty => [$($to:ident: $to_ty:ty),*]),* $(,)*) => {
+        $(
+            $(
+                impl Trunc<$to_ty> for $from_ty {
+                    fn trunc(self) -> $to_ty { self as $to_ty }
+                }
+            )*
+
+            paste::item! {
+                $(
+                    pub fn [<$from _trunc_ $to>](value: $from_ty) -> $to_ty {
+                        Trunc::<$to_ty>::trunc(value)
+                    }
+                )*
+            }
+        )*
+    };
+}
+
+trunc! {
+    real: f32 => [
+        sint: i8, int: i16, dint: i32, lint: i64,
+        usint: u8, uint: u16, udint: u32, ulint: u64
+    ],
+    lreal: f64 => [
+        sint: i8, int: i16, dint: i32, lint: i64,
+        usint: u8, uint: u16, udint: u32, ulint: u64
+    ],
+}
+
+/// `TRUNC()`, where the destination type is inferred.
+pub fn trunc<R: Trunc<I>, I>(value: R) -> I { value.trunc() }
+
+/// A value which can be converted to and from text.
+trait Text: Sized {
+    fn write_to<W: Write>(self, writer: &mut W) -> fmt::Result;
+    fn parse(text: &str) -> Option<Self>;
+}
+
+macro_rules! text {
+    (@integer $($ty:ty),*) => {
+        $(
+            impl Text for $ty {
+                fn write_to<W: Write>(self, writer: &mut W) -> fmt::Result {
+                    write!(writer, "{}", self)
+                }
+
+                fn parse(text: &str) -> Option<Self> { text.parse().ok() }
+            }
+        )*
+    };
+    (@real $($ty:ty),*) => {
+        $(
+            impl Text for $ty {
+                fn write_to<W: Write>(self, writer: &mut W) -> fmt::Result {
+                    // the debug representation always includes a decimal
+                    // point, so the text still looks like a REAL literal
+                    write!(writer, "{:?}", self)
+                }
+
+                fn parse(text: &str) -> Option<Self> { text.parse().ok() }
+            }
+        )*
+    };
+}
+
+text!(@integer i8, i16, i32, i64, u8, u16, u32, u64);
+text!(@real f32, f64);
+
+impl Text for bool {
+    fn write_to<W: Write>(self, writer: &mut W) -> fmt::Result {
+        writer.write_str(if self { "TRUE" } else { "FALSE" })
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        if text.eq_ignore_ascii_case("TRUE") {
+            Some(true)
+        } else if text.eq_ignore_ascii_case("FALSE") {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+fn to_text<T: Text, S: AnyString + Write>(value: T) -> S {
+    let mut s = S::default();
+    // running out of room leaves us with a truncated string
+    let _ = value.write_to(&mut s);
+    s
+}
+
+fn from_text<T: Text, const N: usize>(s: &IecString<N>) -> Option<T> {
+    let text = core::str::from_utf8(s.as_bytes()).ok()?;
+    T::parse(text.trim())
+}
+
+/// Generate the `from_to_string()` and `string_to_to()` functions (and
+/// their `WSTRING` equivalents) for every type.
+macro_rules! string_conversions {
+    ($($name:ident: $ty:ty),* $(,)*) => {
+        paste::item! {
+            $(
+                pub fn [<$name _to_string>]<const N: usize>(
+                    value: $ty,
+                ) -> IecString<N> {
+                    to_text(value)
+                }
+
+                pub fn [<$name _to_wstring>]<const N: usize>(
+                    value: $ty,
+                ) -> WString<N> {
+                    to_text(value)
+                }
+
+                pub fn [<string_to_ $name>]<const N: usize>(
+                    s: &IecString<N>,
+                ) -> Option<$ty> {
+                    from_text(s)
+                }
+
+                pub fn [<wstring_to_ $name>]<const N: usize>(
+                    s: &WString<N>,
+                ) -> Option<$ty> {
+                    from_text(&wstring_to_string(s))
+                }
+            )*
+        }
+    };
+}
+
+string_conversions! {
+    bool: bool,
+    sint: i8,
+    int: i16,
+    dint: i32,
+    lint: i64,
+    usint: u8,
+    uint: u16,
+    udint: u32,
+    ulint: u64,
+    real: f32,
+    lreal: f64,
+    byte: u8,
+    word: u16,
+    dword: u32,
+    lword: u64,
+}
+
+/// `STRING_TO_WSTRING()`.
+pub fn string_to_wstring<const N: usize>(s: &IecString<N>) -> WString<N> {
+    let mut w = WString::new();
+
+    for &c in s.as_bytes() {
+        w.extend_from_chars(&[u16::from(c)]);
+    }
+
+    w
+}
+
+/// `WSTRING_TO_STRING()`.
+pub fn wstring_to_string<const N: usize>(w: &WString<N>) -> IecString<N> {
+    let mut s = IecString::new();
+
+    for c in w.chars() {
+        if s.try_push(c).is_err() {
+            s.extend_from_chars(b"?");
+        }
+    }
+
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reals_are_rounded() {
+        assert_eq!(real_to_int(1.4), 1);
+        assert_eq!(real_to_int(1.5), 2);
+        assert_eq!(real_to_int(-1.5), -2);
+        assert_eq!(lreal_to_udint(2.5), 3);
+        assert_eq!(real_to_byte(254.6), 255);
+    }
+
+    #[test]
+    fn booleans() {
+        assert_eq!(bool_to_int(true), 1);
+        assert_eq!(bool_to_lreal(false), 0.0);
+        assert!(dint_to_bool(-7));
+        assert!(!real_to_bool(0.0));
+        assert!(!word_to_bool(0));
+    }
+
+    #[test]
+    fn integers_are_widened_and_truncated() {
+        assert_eq!(sint_to_lint(-5), -5);
+        assert_eq!(int_to_word(-1), 0xFFFF);
+        assert_eq!(dint_to_sint(0x1FF), -1);
+        assert_eq!(udint_to_usint(0x1234), 0x34);
+        assert_eq!(dint_to_real(16_777_216), 16_777_216.0);
+        assert_eq!(byte_to_usint(200), 200);
+    }
+
+    #[test]
+    fn time_is_measured_in_milliseconds() {
+        assert_eq!(time_to_dint(Duration::from_millis(1500)), 1500);
+        assert_eq!(time_to_lreal(Duration::from_micros(2500)), 2.0);
+        assert_eq!(dint_to_time(250), Duration::from_millis(250));
+        assert_eq!(int_to_time(-5), Duration::from_secs(0));
+        assert_eq!(real_to_time(1.6), Duration::from_millis(2));
+        assert_eq!(lreal_to_time(-3.0), Duration::from_secs(0));
+        assert!(time_to_bool(Duration::from_millis(1)));
+        assert_eq!(bool_to_time(true), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn trunc_rounds_towards_zero() {
+        assert_eq!(real_trunc_int(1.9), 1);
+        assert_eq!(real_trunc_int(-1.9), -1);
+        assert_eq!(lreal_trunc_udint(2.5), 2);
+        assert_eq!(trunc::<f64, i64>(-7.99), -7);
+    }
+
+    #[test]
+    fn numbers_to_strings() {
+        let int: IecString<8> = int_to_string(-1234);
+        let real: WString<8> = real_to_wstring(1.0);
+        let truncated: IecString<2> = udint_to_string(1234);
+
+        assert_eq!(int.as_bytes(), b"-1234");
+        assert!(real.chars().eq("1.0".chars()));
+        assert_eq!(truncated.as_bytes(), b"12");
+        assert_eq!(bool_to_string::<8>(true).as_bytes(), b"TRUE");
+    }
+
+    #[test]
+    fn strings_to_numbers() {
+        let s = |text: &str| -> IecString<16> { text.parse().unwrap() };
+        let w = |text: &str| -> WString<16> { text.parse().unwrap() };
+
+        assert_eq!(string_to_int(&s(" 42 ")), Some(42));
+        assert_eq!(string_to_sint(&s("300")), None);
+        assert_eq!(string_to_lreal(&s("-2.5")), Some(-2.5));
+        assert_eq!(string_to_dint(&s("twelve")), None);
+        assert_eq!(string_to_bool(&s("true")), Some(true));
+        assert_eq!(wstring_to_udint(&w("65536")), Some(65536));
+        assert_eq!(wstring_to_int(&w("४२")), None);
+    }
+
+    #[test]
+    fn strings_to_wide_strings() {
+        let s: IecString<8> = "Grüße".parse().unwrap();
+        let w: WString<8> = "Grüße €".parse().unwrap();
+
+        assert!(string_to_wstring(&s).chars().eq("Grüße".chars()));
+        assert_eq!(wstring_to_string(&w).as_bytes(), b"Gr\xFC\xDFe ?");
+    }
+}
@@ -0,0 +1,234 @@
+use core::ops::Mul;
+
+/// A number which has an absolute value.
+pub trait Abs: Copy {
+    fn abs(self) -> Self;
+}
+
+macro_rules! abs_signed {
+    ($($ty:ty),*) => {
+        $(
+            impl Abs for $ty {
+                fn abs(self) -> Self { self.wrapping_abs() }
+            }
+        )*
+    };
+}
+
+macro_rules! abs_unsigned {
+    ($($ty:ty),*) => {
+        $(
+            impl Abs for $ty {
+                fn abs(self) -> Self { self }
+            }
+        )*
+    };
+}
+
+abs_signed!(i8, i16, i32, i64);
+abs_unsigned!(u8, u16, u32, u64);
+
+impl Abs for f32 {
+    fn abs(self) -> Self { libm::fabsf(self) }
+}
+
+impl Abs for f64 {
+    fn abs(self) -> Self { libm::fabs(self) }
+}
+
+/// The `ANY_REAL` types, `REAL` and `LREAL`.
+///
+/// `core` doesn't provide floating point maths, so these are implemented
+/// using `libm`.
+pub trait Real: Copy + PartialOrd + Mul<Output = Self> {
+    fn sqrt(self) -> Self;
+    /// The natural logarithm.
+    fn ln(self) -> Self;
+    /// The base 10 logarithm.
+    fn log(self) -> Self;
+    fn exp(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn asin(self) -> Self;
+    fn acos(self) -> Self;
+    fn atan(self) -> Self;
+    fn pow(self, exponent: Self) -> Self;
+    fn one() -> Self;
+    fn reciprocal(self) -> Self;
+}
+
+macro_rules! real {
+    ($ty:ty, $sqrt:ident, $ln:ident, $log:ident, $exp:ident, $sin:ident,
+     $cos:ident, $tan:ident, $asin:ident, $acos:ident, $atan:ident,
+     $pow:ident) => {
+        impl Real for $ty {
+            fn sqrt(self) -> Self { libm::$sqrt(self) }
+
+            fn ln(self) -> Self { libm::$ln(self) }
+
+            fn log(self) -> Self { libm::$log(self) }
+
+            fn exp(self) -> Self { libm::$exp(self) }
+
+            fn sin(self) -> Self { libm::$sin(self) }
+
+            fn cos(self) -> Self { libm::$cos(self) }
+
+            fn tan(self) -> Self { libm::$tan(self) }
+
+            fn asin(self) -> Self { libm::$asin(self) }
+
+            fn acos(self) -> Self { libm::$acos(self) }
+
+            fn atan(self) -> Self { libm::$atan(self) }
+
+            fn pow(self, exponent: Self) -> Self { libm::$pow(self, exponent) }
+
+            fn one() -> Self { 1.0 }
+
+            fn reciprocal(self) -> Self { 1.0 / self }
+        }
+    };
+}
+
+real!(
+    f32, sqrtf, logf, log10f, expf, sinf, cosf, tanf, asinf, acosf, atanf, powf
+);
+real!(f64, sqrt, log, log10, exp, sin, cos, tan, asin, acos, atan, pow);
+
+/// Something a [`Real`] can be raised to the power of with [`expt()`].
+///
+/// IEC 61131-3 allows the exponent to be any number, not just a real.
+pub trait Exponent<R: Real> {
+    fn raise(self, base: R) -> R;
+}
+
+impl Exponent<f32> for f32 {
+    fn raise(self, base: f32) -> f32 { base.pow(self) }
+}
+
+impl Exponent<f64> for f64 {
+    fn raise(self, base: f64) -> f64 { base.pow(self) }
+}
+
+macro_rules! integer_exponent {
+    ($($ty:ty),*) => {
+        $(
+            impl<R: Real> Exponent<R> for $ty {
+                #[allow(unused_comparisons)]
+                fn raise(self, base: R) -> R {
+                    let negative = self < 0;
+                    // exponentiation by squaring keeps integer powers exact
+                    let exponent = self as i128;
+                    let mut remaining = if negative {
+                        -exponent
+                    } else {
+                        exponent
+                    } as u128;
+                    let mut base = base;
+                    let mut result = R::one();
+
+                    while remaining > 0 {
+                        if remaining & 1 == 1 {
+                            result = result * base;
+                        }
+                        base = base * base;
+                        remaining >>= 1;
+                    }
+
+                    if negative {
+                        result.reciprocal()
+                    } else {
+                        result
+                    }
+                }
+            }
+        )*
+    };
+}
+
+integer_exponent!(i8, i16, i32, i64, u8, u16, u32, u64);
+
+/// `ABS()`, the absolute value of a number.
+pub fn abs<N: Abs>(value: N) -> N { value.abs() }
+
+/// `SQRT()`, the square root of a number.
+pub fn sqrt<R: Real>(value: R) -> R { value.sqrt() }
+
+/// `LN()`, the natural logarithm.
+pub fn ln<R: Real>(value: R) -> R { value.ln() }
+
+/// `LOG()`, the base 10 logarithm.
+pub fn log<R: Real>(value: R) -> R { value.log() }
+
+/// `EXP()`, the natural exponential.
+pub fn exp<R: Real>(value: R) -> R { value.exp() }
+
+/// `SIN()`, with the input in radians.
+pub fn sin<R: Real>(value: R) -> R { value.sin() }
+
+/// `COS()`, with the input in radians.
+pub fn cos<R: Real>(value: R) -> R { value.cos() }
+
+/// `TAN()`, with the input in radians.
+pub fn tan<R: Real>(value: R) -> R { value.tan() }
+
+/// `ASIN()`, returning radians.
+pub fn asin<R: Real>(value: R) -> R { value.asin() }
+
+/// `ACOS()`, returning radians.
+pub fn acos<R: Real>(value: R) -> R { value.acos() }
+
+/// `ATAN()`, returning radians.
+pub fn atan<R: Real>(value: R) -> R { value.atan() }
+
+/// `EXPT()`, raising a real number to a power.
+pub fn expt<R: Real, E: Exponent<R>>(base: R, exponent: E) -> R {
+    exponent.raise(base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(left: f64, right: f64) -> bool { abs(left - right) < 1e-9 }
+
+    #[test]
+    fn absolute_values() {
+        assert_eq!(abs(-5_i16), 5);
+        assert_eq!(abs(5_i16), 5);
+        assert_eq!(abs(7_u8), 7);
+        assert_eq!(abs(-2.5_f32), 2.5);
+        // IEC leaves overflow undefined, we wrap
+        assert_eq!(abs(i8::min_value()), i8::min_value());
+    }
+
+    #[test]
+    fn logarithms_and_exponentials() {
+        assert!(close(sqrt(2.0_f64) * sqrt(2.0_f64), 2.0));
+        assert!(close(ln(exp(3.0_f64)), 3.0));
+        assert!(close(log(1000.0_f64), 3.0));
+        assert_eq!(sqrt(16.0_f32), 4.0);
+    }
+
+    #[test]
+    fn trigonometry() {
+        let quarter_turn = core::f64::consts::FRAC_PI_2;
+
+        assert!(close(sin(quarter_turn), 1.0));
+        assert!(close(cos(0.0_f64), 1.0));
+        assert!(close(tan(core::f64::consts::FRAC_PI_4), 1.0));
+        assert!(close(asin(1.0_f64), quarter_turn));
+        assert!(close(acos(1.0_f64), 0.0));
+        assert!(close(atan(1.0_f64), core::f64::consts::FRAC_PI_4));
+    }
+
+    #[test]
+    fn raise_to_a_power() {
+        assert_eq!(expt(2.0_f32, 10_i16), 1024.0);
+        assert_eq!(expt(2.0_f64, -2_i32), 0.25);
+        assert_eq!(expt(3.0_f64, 0_u8), 1.0);
+        assert!(close(expt(4.0_f64, 0.5_f64), 2.0));
+    }
+}
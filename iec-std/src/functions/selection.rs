@@ -0,0 +1,73 @@
+/// `SEL()`, binary selection. Returns `in1` when `g` is true, otherwise
+/// `in0`.
+pub fn sel<T>(g: bool, in0: T, in1: T) -> T {
+    if g {
+        in1
+    } else {
+        in0
+    }
+}
+
+/// `MAX()`, the larger of two values.
+pub fn max<T: PartialOrd>(in1: T, in2: T) -> T {
+    if in2 > in1 {
+        in2
+    } else {
+        in1
+    }
+}
+
+/// `MIN()`, the smaller of two values.
+pub fn min<T: PartialOrd>(in1: T, in2: T) -> T {
+    if in2 < in1 {
+        in2
+    } else {
+        in1
+    }
+}
+
+/// `LIMIT()`, clamp `value` to the range `mn..=mx`.
+pub fn limit<T: PartialOrd>(mn: T, value: T, mx: T) -> T {
+    min(max(value, mn), mx)
+}
+
+/// `MUX()`, select the `k`'th input.
+///
+/// IEC 61131-3 treats a `k` outside of `inputs` as an error, so this returns
+/// `None`.
+pub fn mux<T: Copy>(k: usize, inputs: &[T]) -> Option<T> {
+    inputs.get(k).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_selection() {
+        assert_eq!(sel(false, 1, 2), 1);
+        assert_eq!(sel(true, 1, 2), 2);
+    }
+
+    #[test]
+    fn minimum_and_maximum() {
+        assert_eq!(max(3_i16, -7), 3);
+        assert_eq!(min(3_i16, -7), -7);
+        assert_eq!(max(max(1.5_f32, 4.0), 2.0), 4.0);
+    }
+
+    #[test]
+    fn limit_a_value() {
+        assert_eq!(limit(0, 50, 100), 50);
+        assert_eq!(limit(0, -5, 100), 0);
+        assert_eq!(limit(0, 105, 100), 100);
+    }
+
+    #[test]
+    fn multiplexer() {
+        let inputs = [10_u8, 20, 30];
+
+        assert_eq!(mux(1, &inputs), Some(20));
+        assert_eq!(mux(3, &inputs), None);
+    }
+}
@@ -0,0 +1,222 @@
+//! Typed access to the global variables stored by the host.
+//!
+//! A [`Global`] is just a name, so it can be declared as a `const` and used
+//! from anywhere in the program. The [`globals!`](crate::globals!) macro
+//! declares a whole struct of them in one go.
+//!
+//! ```rust,ignore
+//! use rustmatic_iec_std::globals::Global;
+//!
+//! const COUNTER: Global<i32> = Global::new("counter");
+//!
+//! #[no_mangle]
+//! pub extern "C" fn poll() {
+//!     let count = COUNTER.get().unwrap_or(0);
+//!     COUNTER.set(count + 1).unwrap();
+//! }
+//! ```
+
+use crate::intrinsics::{
+    self, wasm_result_t,
+    wasm_result_t_WASM_BAD_VARIABLE_TYPE as WASM_BAD_VARIABLE_TYPE,
+    wasm_result_t_WASM_SUCCESS as WASM_SUCCESS,
+    wasm_result_t_WASM_UNKNOWN_VARIABLE as WASM_UNKNOWN_VARIABLE,
+};
+use core::{fmt, marker::PhantomData};
+
+/// Things that can go wrong when accessing a global variable.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The variable hasn't been declared yet.
+    UnknownVariable,
+    /// The variable was declared with a different type.
+    BadVariableType,
+    /// The host returned an unexpected error code.
+    Host(wasm_result_t),
+}
+
+impl Error {
+    fn check(ret: wasm_result_t) -> Result<(), Error> {
+        match ret {
+            WASM_SUCCESS => Ok(()),
+            WASM_UNKNOWN_VARIABLE => Err(Error::UnknownVariable),
+            WASM_BAD_VARIABLE_TYPE => Err(Error::BadVariableType),
+            other => Err(Error::Host(other)),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnknownVariable => write!(f, "Unknown variable"),
+            Error::BadVariableType => write!(f, "Incorrect variable type"),
+            Error::Host(code) => {
+                write!(f, "The host returned error code {}", code)
+            },
+        }
+    }
+}
+
+/// A type which the host can store as a global variable.
+pub trait Variable: Copy {
+    fn read(name: &str) -> Result<Self, Error>;
+    fn write(name: &str, value: Self) -> Result<(), Error>;
+}
+
+macro_rules! variable {
+    ($($ty:ty => $read:ident, $write:ident;)*) => {
+        $(
+            impl Variable for $ty {
+                fn read(name: &str) -> Result<$ty, Error> {
+                    let mut value = <$ty>::default();
+                    let ret = unsafe {
+                        intrinsics::$read(
+                            name.as_ptr() as *const _,
+                            name.len() as _,
+                            &mut value,
+                        )
+                    };
+                    Error::check(ret)?;
+
+                    Ok(value)
+                }
+
+                fn write(name: &str, value: $ty) -> Result<(), Error> {
+                    let ret = unsafe {
+                        intrinsics::$write(
+                            name.as_ptr() as *const _,
+                            name.len() as _,
+                            value,
+                        )
+                    };
+
+                    Error::check(ret)
+                }
+            }
+        )*
+    };
+}
+
+variable! {
+    bool => wasm_variable_read_boolean, wasm_variable_write_boolean;
+    i32 => wasm_variable_read_int, wasm_variable_write_int;
+    f64 => wasm_variable_read_double, wasm_variable_write_double;
+}
+
+/// A handle to a global variable.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Global<T> {
+    name: &'static str,
+    _type: PhantomData<T>,
+}
+
+impl<T> Global<T> {
+    pub const fn new(name: &'static str) -> Global<T> {
+        Global {
+            name,
+            _type: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &'static str { self.name }
+}
+
+impl<T: Variable> Global<T> {
+    /// Read the variable's current value.
+    pub fn get(&self) -> Result<T, Error> { T::read(self.name) }
+
+    /// Update the variable, declaring it if it doesn't already exist.
+    pub fn set(&self, value: T) -> Result<(), Error> {
+        T::write(self.name, value)
+    }
+}
+
+/// Declare a struct where each field is a [`Global`] named after the field.
+///
+/// ```rust,ignore
+/// rustmatic_iec_std::globals! {
+///     pub struct Plant {
+///         pub counter: i32,
+///         pub running: bool,
+///         setpoint: f64,
+///     }
+/// }
+///
+/// const PLANT: Plant = Plant::new();
+///
+/// PLANT.counter.set(42).unwrap();
+/// ```
+#[macro_export]
+macro_rules! globals {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            $( $field_vis:vis $field:ident : $ty:ty ),* $(,)*
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Debug, Copy, Clone, PartialEq)]
+        $vis struct $name {
+            $( $field_vis $field: $crate::globals::Global<$ty> ),*
+        }
+
+        impl $name {
+            pub const fn new() -> $name {
+                $name {
+                    $(
+                        $field: $crate::globals::Global::new(
+                            stringify!($field),
+                        )
+                    ),*
+                }
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> $name { $name::new() }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intrinsics::{
+        wasm_result_t_WASM_GENERIC_ERROR as WASM_GENERIC_ERROR,
+    };
+
+    #[test]
+    fn map_error_codes() {
+        assert_eq!(Error::check(WASM_SUCCESS), Ok(()));
+        assert_eq!(
+            Error::check(WASM_UNKNOWN_VARIABLE),
+            Err(Error::UnknownVariable)
+        );
+        assert_eq!(
+            Error::check(WASM_BAD_VARIABLE_TYPE),
+            Err(Error::BadVariableType)
+        );
+        assert_eq!(
+            Error::check(WASM_GENERIC_ERROR),
+            Err(Error::Host(WASM_GENERIC_ERROR))
+        );
+    }
+
+    crate::globals! {
+        struct Plant {
+            counter: i32,
+            running: bool,
+            setpoint: f64,
+        }
+    }
+
+    #[test]
+    fn globals_are_named_after_their_fields() {
+        const PLANT: Plant = Plant::new();
+
+        assert_eq!(PLANT.counter.name(), "counter");
+        assert_eq!(PLANT.running.name(), "running");
+        assert_eq!(PLANT.setpoint, Global::new("setpoint"));
+    }
+}
@@ -0,0 +1,358 @@
+//! Safe access to the process image.
+//!
+//! Bits are accessed with [`read_bit()`] and [`write_bit()`], while numbers
+//! have functions like [`read_u16()`] and [`write_f32()`]. Values are stored
+//! in little-endian byte order, the same as `rustmatic_core::ProcessImage`,
+//! and all addresses are byte offsets into the input or output image.
+//!
+//! Locations can also be written using IEC 61131-3 style directly
+//! represented variables, where `%IX0.1` becomes `ix(0, 1)` and `%QW4`
+//! becomes `qw(4)`.
+//!
+//! ```rust,ignore
+//! use rustmatic_iec_std::io::{self, ix, qw, InputBit, Output};
+//!
+//! const START: InputBit = ix(0, 1);
+//! const SPEED: Output<u16> = qw(4);
+//!
+//! #[no_mangle]
+//! pub extern "C" fn poll() {
+//!     if START.read().unwrap() {
+//!         SPEED.write(1500).unwrap();
+//!     }
+//!
+//!     io::write_bit(0, 3, true).unwrap();
+//! }
+//! ```
+
+use crate::intrinsics::{
+    self, wasm_result_t,
+    wasm_result_t_WASM_ADDRESS_OUT_OF_BOUNDS as WASM_ADDRESS_OUT_OF_BOUNDS,
+    wasm_result_t_WASM_SUCCESS as WASM_SUCCESS,
+};
+use core::{fmt, marker::PhantomData};
+
+/// How many bytes of the output image [`write_bit()`] can update.
+///
+/// The host doesn't let us read outputs back, so every output byte written
+/// through this module is remembered. That way setting one bit won't clear
+/// its neighbours. This matches the default size of a
+/// `rustmatic_core::ProcessImage`.
+pub const OUTPUT_IMAGE_LEN: usize = 128;
+
+static mut OUTPUTS: [u8; OUTPUT_IMAGE_LEN] = [0; OUTPUT_IMAGE_LEN];
+
+/// Things that can go wrong when accessing the process image.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The address lies outside of the process image.
+    AddressOutOfBounds,
+    /// Bits are numbered from `0` to `7`.
+    InvalidBit(u8),
+    /// The host returned an unexpected error code.
+    Host(wasm_result_t),
+}
+
+impl Error {
+    fn check(ret: wasm_result_t) -> Result<(), Error> {
+        match ret {
+            WASM_SUCCESS => Ok(()),
+            WASM_ADDRESS_OUT_OF_BOUNDS => Err(Error::AddressOutOfBounds),
+            other => Err(Error::Host(other)),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::AddressOutOfBounds => {
+                write!(f, "Tried to access an out-of-bounds address")
+            },
+            Error::InvalidBit(bit) => write!(f, "{} isn't a valid bit", bit),
+            Error::Host(code) => {
+                write!(f, "The host returned error code {}", code)
+            },
+        }
+    }
+}
+
+/// Read a sequence of bytes from the inputs.
+pub fn read_input(address: usize, buffer: &mut [u8]) -> Result<(), Error> {
+    let ret = unsafe {
+        intrinsics::wasm_read_input(
+            address as _,
+            buffer.as_mut_ptr() as *mut _,
+            buffer.len() as _,
+        )
+    };
+
+    Error::check(ret)
+}
+
+/// Write a sequence of bytes to the outputs.
+pub fn write_output(address: usize, buffer: &[u8]) -> Result<(), Error> {
+    let ret = unsafe {
+        intrinsics::wasm_write_output(
+            address as _,
+            buffer.as_ptr() as *const _,
+            buffer.len() as _,
+        )
+    };
+    Error::check(ret)?;
+
+    for (i, &byte) in buffer.iter().enumerate() {
+        let index = address + i;
+
+        if index < OUTPUT_IMAGE_LEN {
+            unsafe {
+                OUTPUTS[index] = byte;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn mask(bit: u8) -> Result<u8, Error> {
+    if bit < 8 {
+        Ok(1 << bit)
+    } else {
+        Err(Error::InvalidBit(bit))
+    }
+}
+
+fn set_bit(byte: u8, mask: u8, state: bool) -> u8 {
+    if state {
+        byte | mask
+    } else {
+        byte & !mask
+    }
+}
+
+/// Read a single bit from the inputs.
+pub fn read_bit(byte: usize, bit: u8) -> Result<bool, Error> {
+    let mask = mask(bit)?;
+    let value = read_u8(byte)?;
+
+    Ok(value & mask != 0)
+}
+
+/// Write a single bit to the outputs, leaving the rest of the byte alone.
+pub fn write_bit(byte: usize, bit: u8, state: bool) -> Result<(), Error> {
+    let mask = mask(bit)?;
+
+    if byte >= OUTPUT_IMAGE_LEN {
+        return Err(Error::AddressOutOfBounds);
+    }
+
+    let current = unsafe { OUTPUTS[byte] };
+    write_u8(byte, set_bit(current, mask, state))
+}
+
+/// A value which can be stored in the process image.
+pub trait ProcessValue: Copy {
+    /// The number of bytes used by this value.
+    const SIZE: usize;
+
+    /// Read the value from its little-endian representation.
+    fn decode(bytes: &[u8]) -> Self;
+
+    /// Write the value's little-endian representation to `buffer`.
+    fn encode(self, buffer: &mut [u8]);
+}
+
+macro_rules! process_value {
+    ($($ty:ident),*) => {
+        $(
+            impl ProcessValue for $ty {
+                const SIZE: usize = core::mem::size_of::<$ty>();
+
+                fn decode(bytes: &[u8]) -> $ty {
+                    let mut buffer = [0; core::mem::size_of::<$ty>()];
+                    buffer.copy_from_slice(bytes);
+                    $ty::from_le_bytes(buffer)
+                }
+
+                fn encode(self, buffer: &mut [u8]) {
+                    buffer.copy_from_slice(&self.to_le_bytes());
+                }
+            }
+
+            paste::item! {
+                pub fn [<read_ $ty>](address: usize) -> Result<$ty, Error> {
+                    read(address)
+                }
+
+                pub fn [<write_ $ty>](
+                    address: usize,
+                    value: $ty,
+                ) -> Result<(), Error> {
+                    write(address, value)
+                }
+            }
+        )*
+    };
+}
+
+process_value!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+/// Read a value from the inputs.
+pub fn read<T: ProcessValue>(address: usize) -> Result<T, Error> {
+    let mut buffer = [0; 8];
+    let bytes = &mut buffer[..T::SIZE];
+    read_input(address, bytes)?;
+
+    Ok(T::decode(bytes))
+}
+
+/// Write a value to the outputs.
+pub fn write<T: ProcessValue>(address: usize, value: T) -> Result<(), Error> {
+    let mut buffer = [0; 8];
+    let bytes = &mut buffer[..T::SIZE];
+    value.encode(bytes);
+
+    write_output(address, bytes)
+}
+
+/// A single input bit, `%IX`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InputBit {
+    pub byte: usize,
+    pub bit: u8,
+}
+
+impl InputBit {
+    pub const fn new(byte: usize, bit: u8) -> InputBit {
+        InputBit { byte, bit }
+    }
+
+    pub fn read(self) -> Result<bool, Error> { read_bit(self.byte, self.bit) }
+}
+
+/// A single output bit, `%QX`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OutputBit {
+    pub byte: usize,
+    pub bit: u8,
+}
+
+impl OutputBit {
+    pub const fn new(byte: usize, bit: u8) -> OutputBit {
+        OutputBit { byte, bit }
+    }
+
+    pub fn write(self, state: bool) -> Result<(), Error> {
+        write_bit(self.byte, self.bit, state)
+    }
+}
+
+/// A value in the input image, `%IB`, `%IW`, `%ID` or `%IL`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Input<T> {
+    pub byte: usize,
+    _type: PhantomData<T>,
+}
+
+impl<T> Input<T> {
+    pub const fn new(byte: usize) -> Input<T> {
+        Input {
+            byte,
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<T: ProcessValue> Input<T> {
+    pub fn read(self) -> Result<T, Error> { read(self.byte) }
+}
+
+/// A value in the output image, `%QB`, `%QW`, `%QD` or `%QL`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Output<T> {
+    pub byte: usize,
+    _type: PhantomData<T>,
+}
+
+impl<T> Output<T> {
+    pub const fn new(byte: usize) -> Output<T> {
+        Output {
+            byte,
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<T: ProcessValue> Output<T> {
+    pub fn write(self, value: T) -> Result<(), Error> {
+        write(self.byte, value)
+    }
+}
+
+/// `%IX<byte>.<bit>`, an input bit.
+pub const fn ix(byte: usize, bit: u8) -> InputBit { InputBit::new(byte, bit) }
+
+/// `%IB<byte>`, an input byte.
+pub const fn ib(byte: usize) -> Input<u8> { Input::new(byte) }
+
+/// `%IW<byte>`, an input word.
+pub const fn iw(byte: usize) -> Input<u16> { Input::new(byte) }
+
+/// `%ID<byte>`, an input double word.
+pub const fn id(byte: usize) -> Input<u32> { Input::new(byte) }
+
+/// `%IL<byte>`, an input long word.
+pub const fn il(byte: usize) -> Input<u64> { Input::new(byte) }
+
+/// `%QX<byte>.<bit>`, an output bit.
+pub const fn qx(byte: usize, bit: u8) -> OutputBit { OutputBit::new(byte, bit) }
+
+/// `%QB<byte>`, an output byte.
+pub const fn qb(byte: usize) -> Output<u8> { Output::new(byte) }
+
+/// `%QW<byte>`, an output word.
+pub const fn qw(byte: usize) -> Output<u16> { Output::new(byte) }
+
+/// `%QD<byte>`, an output double word.
+pub const fn qd(byte: usize) -> Output<u32> { Output::new(byte) }
+
+/// `%QL<byte>`, an output long word.
+pub const fn ql(byte: usize) -> Output<u64> { Output::new(byte) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_little_endian() {
+        let mut buffer = [0; 4];
+
+        0x1234_u16.encode(&mut buffer[..2]);
+        assert_eq!(buffer[..2], [0x34, 0x12]);
+
+        1.0_f32.encode(&mut buffer);
+        assert_eq!(buffer, [0x00, 0x00, 0x80, 0x3F]);
+        assert_eq!(f32::decode(&buffer), 1.0);
+        assert_eq!(i16::decode(&[0xFE, 0xFF]), -2);
+    }
+
+    #[test]
+    fn only_bits_0_to_7_are_valid() {
+        assert_eq!(mask(0), Ok(0b0000_0001));
+        assert_eq!(mask(7), Ok(0b1000_0000));
+        assert_eq!(mask(8), Err(Error::InvalidBit(8)));
+    }
+
+    #[test]
+    fn setting_a_bit_leaves_the_others_alone() {
+        assert_eq!(set_bit(0b1010_0000, 0b0000_0001, true), 0b1010_0001);
+        assert_eq!(set_bit(0b1010_0000, 0b0010_0000, false), 0b1000_0000);
+    }
+
+    #[test]
+    fn directly_represented_variables() {
+        assert_eq!(ix(0, 1), InputBit { byte: 0, bit: 1 });
+        assert_eq!(qw(4).byte, 4);
+        assert_eq!(<u64 as ProcessValue>::SIZE, 8);
+    }
+}
@@ -8,8 +8,13 @@
 pub extern crate arrayvec;
 
 pub(crate) mod ctypes;
+pub mod function_blocks;
+pub mod functions;
+pub mod globals;
 #[allow(bad_style)]
 pub mod intrinsics;
+pub mod io;
 mod macros;
+pub mod strings;
 mod sys;
 pub mod time;
@@ -0,0 +1,316 @@
+//! Fixed-capacity `STRING` and `WSTRING` types which don't need an allocator.
+//!
+//! IEC 61131-3 strings are declared with a maximum length (e.g.
+//! `STRING[80]`), which maps onto [`IecString<80>`](IecString). The
+//! characters are stored inline, so a string can live in a `static mut` or on
+//! the stack. A `STRING` declared without a length holds up to 254
+//! characters, which is what [`DefaultString`] and [`DefaultWString`] are for.
+//!
+//! A `STRING` is a sequence of single-byte (ISO 8859-1) characters, while a
+//! `WSTRING` is a sequence of UCS-2 characters. Characters outside of the
+//! Basic Multilingual Plane can't be stored in either, so the length of a
+//! string is always measured in characters, never in bytes.
+//!
+//! ```rust
+//! use rustmatic_iec_std::strings::{IecString, WString};
+//!
+//! let greeting: IecString<16> = "Grüße".parse().unwrap();
+//! assert_eq!(greeting.len(), 5);
+//!
+//! let wide: WString<16> = "Grüße".parse().unwrap();
+//! assert_eq!(wide.len(), 5);
+//! ```
+
+use core::{cmp::Ordering, fmt, str::FromStr};
+
+/// The `ANY_STRING` types, `STRING` and `WSTRING`.
+pub trait AnyString: Default {
+    /// The type used to store a single character.
+    type Char: Copy + PartialEq;
+
+    /// The characters currently in the string.
+    fn as_chars(&self) -> &[Self::Char];
+
+    /// Append as many `chars` as will fit, silently dropping the rest.
+    fn extend_from_chars(&mut self, chars: &[Self::Char]);
+
+    /// Create a string from a sequence of characters, truncating it to the
+    /// string's capacity.
+    fn from_chars(chars: &[Self::Char]) -> Self {
+        let mut s = Self::default();
+        s.extend_from_chars(chars);
+        s
+    }
+}
+
+/// The reasons a string may be rejected.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StringError {
+    /// There isn't enough room left in the string.
+    CapacityExceeded,
+    /// The character can't be represented by this type of string.
+    InvalidCharacter(char),
+}
+
+impl fmt::Display for StringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StringError::CapacityExceeded => {
+                write!(f, "The string's capacity was exceeded")
+            },
+            StringError::InvalidCharacter(c) => {
+                write!(f, "{:?} can't be stored in this string", c)
+            },
+        }
+    }
+}
+
+macro_rules! bounded_string {
+    ($(#[$attr:meta])* $name:ident, $char:ty) => {
+        $(#[$attr])*
+        #[derive(Copy, Clone)]
+        pub struct $name<const N: usize> {
+            len: usize,
+            chars: [$char; N],
+        }
+
+        impl<const N: usize> $name<N> {
+            pub const fn new() -> $name<N> {
+                $name {
+                    len: 0,
+                    chars: [0; N],
+                }
+            }
+
+            /// The number of characters in the string.
+            pub fn len(&self) -> usize { self.len }
+
+            pub fn is_empty(&self) -> bool { self.len == 0 }
+
+            /// The maximum number of characters this string can hold.
+            pub const fn capacity(&self) -> usize { N }
+
+            pub fn clear(&mut self) { self.len = 0; }
+
+            fn push_char(&mut self, c: $char) -> Result<(), StringError> {
+                if self.len < N {
+                    self.chars[self.len] = c;
+                    self.len += 1;
+                    Ok(())
+                } else {
+                    Err(StringError::CapacityExceeded)
+                }
+            }
+        }
+
+        impl<const N: usize> AnyString for $name<N> {
+            type Char = $char;
+
+            fn as_chars(&self) -> &[$char] { &self.chars[..self.len] }
+
+            fn extend_from_chars(&mut self, chars: &[$char]) {
+                for &c in chars {
+                    if self.push_char(c).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        impl<const N: usize> Default for $name<N> {
+            fn default() -> $name<N> { $name::new() }
+        }
+
+        impl<const N: usize> PartialEq for $name<N> {
+            fn eq(&self, other: &$name<N>) -> bool {
+                self.as_chars() == other.as_chars()
+            }
+        }
+
+        impl<const N: usize> Eq for $name<N> {}
+
+        impl<const N: usize> PartialOrd for $name<N> {
+            fn partial_cmp(&self, other: &$name<N>) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl<const N: usize> Ord for $name<N> {
+            fn cmp(&self, other: &$name<N>) -> Ordering {
+                self.as_chars().cmp(other.as_chars())
+            }
+        }
+
+        impl<const N: usize> fmt::Write for $name<N> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                for c in s.chars() {
+                    self.try_push(c).map_err(|_| fmt::Error)?;
+                }
+
+                Ok(())
+            }
+        }
+
+        impl<const N: usize> FromStr for $name<N> {
+            type Err = StringError;
+
+            fn from_str(s: &str) -> Result<$name<N>, StringError> {
+                let mut string = $name::new();
+
+                for c in s.chars() {
+                    string.try_push(c)?;
+                }
+
+                Ok(string)
+            }
+        }
+
+        impl<const N: usize> fmt::Debug for $name<N> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "\"")?;
+                for c in self.chars() {
+                    write!(f, "{}", c.escape_debug())?;
+                }
+                write!(f, "\"")
+            }
+        }
+
+        impl<const N: usize> fmt::Display for $name<N> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                for c in self.chars() {
+                    write!(f, "{}", c)?;
+                }
+
+                Ok(())
+            }
+        }
+    };
+}
+
+bounded_string! {
+    /// A `STRING[N]`, holding up to `N` single-byte characters.
+    IecString, u8
+}
+
+bounded_string! {
+    /// A `WSTRING[N]`, holding up to `N` UCS-2 characters.
+    WString, u16
+}
+
+/// The capacity of a `STRING` or `WSTRING` declared without a length.
+pub const DEFAULT_STRING_LEN: usize = 254;
+
+/// A `STRING` declared without a length.
+pub type DefaultString = IecString<DEFAULT_STRING_LEN>;
+
+/// A `WSTRING` declared without a length.
+pub type DefaultWString = WString<DEFAULT_STRING_LEN>;
+
+impl<const N: usize> IecString<N> {
+    /// Append a character, failing if it lies outside of ISO 8859-1.
+    pub fn try_push(&mut self, c: char) -> Result<(), StringError> {
+        if (c as u32) <= 0xFF {
+            self.push_char(c as u8)
+        } else {
+            Err(StringError::InvalidCharacter(c))
+        }
+    }
+
+    /// The string's raw bytes.
+    pub fn as_bytes(&self) -> &[u8] { self.as_chars() }
+
+    /// Iterate over the string's characters.
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.as_chars().iter().map(|&b| char::from(b))
+    }
+}
+
+impl<const N: usize> WString<N> {
+    /// Append a character, failing if it lies outside of the Basic
+    /// Multilingual Plane.
+    pub fn try_push(&mut self, c: char) -> Result<(), StringError> {
+        if (c as u32) <= 0xFFFF {
+            self.push_char(c as u16)
+        } else {
+            Err(StringError::InvalidCharacter(c))
+        }
+    }
+
+    /// Iterate over the string's characters, replacing any surrogates with
+    /// [`char::REPLACEMENT_CHARACTER`].
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.as_chars().iter().map(|&c| {
+            char::from_u32(u32::from(c)).unwrap_or(char::REPLACEMENT_CHARACTER)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Write;
+
+    #[test]
+    fn length_is_measured_in_characters() {
+        let s: IecString<10> = "Grüße".parse().unwrap();
+        let w: WString<10> = "Grüße".parse().unwrap();
+
+        assert_eq!(s.len(), 5);
+        assert_eq!(s.as_bytes(), b"Gr\xFC\xDFe");
+        assert_eq!(w.len(), 5);
+        assert_eq!(s.capacity(), 10);
+    }
+
+    #[test]
+    fn reject_strings_which_dont_fit() {
+        assert_eq!(
+            "Too long".parse::<IecString<4>>(),
+            Err(StringError::CapacityExceeded)
+        );
+        assert_eq!(
+            "€".parse::<IecString<4>>(),
+            Err(StringError::InvalidCharacter('€'))
+        );
+        assert_eq!(
+            "a🦀".parse::<WString<4>>(),
+            Err(StringError::InvalidCharacter('🦀'))
+        );
+    }
+
+    #[test]
+    fn from_chars_truncates() {
+        let s = IecString::<3>::from_chars(b"Hello");
+
+        assert_eq!(s.as_bytes(), b"Hel");
+    }
+
+    #[test]
+    fn round_trip_through_display() {
+        let w: WString<8> = "€ ☃".parse().unwrap();
+        let mut s: IecString<8> = IecString::new();
+
+        s.write_str("Grüße").unwrap();
+
+        assert_eq!(w.len(), 3);
+        assert!(w.chars().eq("€ ☃".chars()));
+        assert!(s.chars().eq("Grüße".chars()));
+    }
+
+    #[test]
+    fn strings_compare_by_character() {
+        let a: IecString<10> = "abc".parse().unwrap();
+        let b: IecString<10> = "abd".parse().unwrap();
+        let mut c = b;
+        c.clear();
+        c.extend_from_chars(b"abc");
+
+        assert!(a < b);
+        assert_eq!(a, c);
+    }
+
+    #[test]
+    fn strings_without_a_length_hold_254_characters() {
+        assert_eq!(DefaultString::new().capacity(), 254);
+        assert_eq!(DefaultWString::new().capacity(), 254);
+    }
+}
@@ -24,6 +24,15 @@ impl Instant {
         Instant { secs, nanos }
     }
 
+    /// Create an [`Instant`] without asking the host for the current time.
+    #[cfg(test)]
+    pub(crate) fn from_duration(duration: Duration) -> Instant {
+        Instant {
+            secs: duration.as_secs(),
+            nanos: duration.subsec_nanos(),
+        }
+    }
+
     pub fn duration_since(self, earlier: Instant) -> Duration { self - earlier }
 
     /// How much time has elapsed since the [`Instant`] was created?
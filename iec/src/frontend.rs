@@ -1,14 +1,1836 @@
 //! Translate from individual languages (e.g. *Structured Text*) to our
 //! mid-level internal representation.
 
-use codespan::FileId;
-use rustmatic_structured_text::File;
+mod body;
+
+use crate::mir::{
+    self, Address, Area, Configuration, Constant, ConstantValue, DataType,
+    EnumeratedValue, Field, Function, FunctionBlock, InitialValue, Location,
+    Name, Program, ProgramInstance, Resource, Task, Trigger, Type, Variable,
+    VariableKind,
+};
+use codespan::{FileId, Span};
+use rustmatic_structured_text::{
+    self as st, resolve, types, Diagnostic, Label, Severity,
+};
 use specs::prelude::*;
+use std::{collections::HashMap, time::Duration};
 
 /// Translate a set of *Structured Text* files into a [`Configuration`].
-pub fn translate_structured_text<I>(_items: I, _world: &World) -> Entity
+///
+/// Every file can use the items declared by the others. If any errors are
+/// found, translation stops and every diagnostic is returned.
+pub fn translate_structured_text<I>(
+    items: I,
+    world: &mut World,
+) -> Result<Entity, Vec<(FileId, Diagnostic)>>
 where
-    I: IntoIterator<Item = (FileId, File)>,
+    I: IntoIterator<Item = (FileId, st::File)>,
 {
-    unimplemented!()
+    let files: Vec<_> = items.into_iter().collect();
+
+    let mut diagnostics = Vec::new();
+    let mut type_info = Vec::new();
+
+    for (file_id, file) in &files {
+        let others: Vec<_> = files
+            .iter()
+            .filter(|(other_id, _)| other_id != file_id)
+            .map(|(_, other)| other)
+            .collect();
+        let resolution = resolve::resolve_with(file, &others);
+        let info = types::check(file, &resolution);
+
+        diagnostics.extend(
+            resolution
+                .diagnostics
+                .into_iter()
+                .chain(info.diagnostics.iter().cloned())
+                .map(|diag| (*file_id, diag)),
+        );
+        type_info.push(info);
+    }
+
+    if diagnostics
+        .iter()
+        .any(|(_, d)| d.severity == Severity::Error)
+    {
+        return Err(diagnostics);
+    }
+
+    mir::register(world);
+    let mut translator = Translator {
+        world,
+        items: Items::default(),
+        diagnostics: Vec::new(),
+    };
+
+    for (file_id, file) in &files {
+        translator.declare_items(*file_id, file);
+    }
+    // everything after this looks items up by name, so duplicates would
+    // just cause confusing errors further down
+    if !translator.diagnostics.is_empty() {
+        return Err(translator.diagnostics);
+    }
+    for (file_id, file) in &files {
+        translator.define_types(*file_id, file);
+    }
+    for (file_id, file) in &files {
+        translator.declare_variables(*file_id, file);
+    }
+    for ((file_id, file), info) in files.iter().zip(&type_info) {
+        translator.lower_bodies(*file_id, file, info);
+    }
+    let configuration = translator.configuration(&files);
+
+    if translator.diagnostics.is_empty() {
+        Ok(configuration)
+    } else {
+        Err(translator.diagnostics)
+    }
+}
+
+/// The inputs and outputs of a function or function block.
+#[derive(Debug, Clone, PartialEq)]
+struct Parameter {
+    /// The parameter's name, in uppercase.
+    name: String,
+    ty: Type,
+    input: bool,
+    /// The value used when an input isn't provided.
+    default: Option<Constant>,
+}
+
+/// The variables declared by a single item.
+#[derive(Debug, Default)]
+struct Declarations {
+    entities: Vec<Entity>,
+    scope: HashMap<String, (Entity, Type)>,
+    parameters: Vec<Parameter>,
+}
+
+/// Everything a program organisation unit can refer to, keyed by the
+/// uppercase version of its name.
+#[derive(Debug, Default)]
+struct Items {
+    types: HashMap<String, (Entity, st::DataType)>,
+    anonymous_enums: HashMap<String, Entity>,
+    programs: HashMap<String, Entity>,
+    function_blocks: HashMap<String, Entity>,
+    functions: HashMap<String, Entity>,
+    definitions: HashMap<Entity, DataType>,
+    /// The variables each program organisation unit can see.
+    scopes: HashMap<Entity, HashMap<String, (Entity, Type)>>,
+    parameters: HashMap<Entity, Vec<Parameter>>,
+    return_types: HashMap<Entity, Type>,
+    /// Where each type and program organisation unit was declared, so
+    /// duplicates in other files can be reported.
+    declared: HashMap<String, (FileId, Span)>,
+}
+
+impl Items {
+    /// Convert a type from the type checker into its MIR equivalent.
+    fn convert(&self, ty: &types::Type) -> Option<Type> {
+        use rustmatic_structured_text::types::Type as T;
+
+        if let Some(elementary) = convert_elementary(ty) {
+            return Some(elementary);
+        }
+
+        let converted = match ty {
+            T::Enumerated(name) | T::Struct(name) | T::FunctionBlock(name) => {
+                let key = name.to_uppercase();
+
+                if let Some(&(entity, _)) = self.types.get(&key) {
+                    Type::Derived(entity)
+                } else if let Some(&entity) = self.anonymous_enums.get(name) {
+                    Type::Derived(entity)
+                } else if let Some(&entity) = self.function_blocks.get(&key) {
+                    Type::Derived(entity)
+                } else {
+                    Type::StandardFunctionBlock(key)
+                }
+            },
+            T::Array {
+                element,
+                dimensions,
+            } => Type::Array {
+                element: Box::new(self.convert(element)?),
+                dimensions: dimensions.clone(),
+            },
+            _ => return None,
+        };
+
+        Some(converted)
+    }
+
+    /// Look up one of an enumeration's values.
+    fn enumerated_value(&self, ty: &Type, name: &str) -> Option<Constant> {
+        let entity = match ty {
+            Type::Derived(entity) => *entity,
+            _ => return None,
+        };
+
+        match self.definitions.get(&entity) {
+            Some(DataType::Enumeration(values)) => values
+                .iter()
+                .find(|v| v.name.eq_ignore_ascii_case(name))
+                .map(|v| {
+                    Constant::new(
+                        ConstantValue::Integer(v.value.into()),
+                        ty.clone(),
+                    )
+                }),
+            _ => None,
+        }
+    }
+}
+
+/// Guards against type aliases which refer to themselves.
+const MAX_TYPE_DEPTH: usize = 32;
+
+struct Translator<'w> {
+    world: &'w mut World,
+    items: Items,
+    diagnostics: Vec<(FileId, Diagnostic)>,
+}
+
+impl<'w> Translator<'w> {
+    fn error(&mut self, file: FileId, message: &str, span: Span, label: &str) {
+        self.diagnostics
+            .push((file, Diagnostic::error(message, Label::new(span, label))));
+    }
+
+    /// Create an entity tagged with its [`Name`] and [`Location`].
+    fn create(&mut self, file: FileId, name: &str, span: Span) -> Entity {
+        self.world
+            .create_entity()
+            .with(Name(name.to_string()))
+            .with(Location { span, file })
+            .build()
+    }
+
+    fn attach<C: Component>(&mut self, entity: Entity, component: C) {
+        self.world
+            .write_storage::<C>()
+            .insert(entity, component)
+            .expect("The entity was created by us so it's always alive");
+    }
+
+    /// Create an entity for every type and program organisation unit so
+    /// they can be referred to before they are defined.
+    fn declare_items(&mut self, file: FileId, ast: &st::File) {
+        for ty in &ast.types {
+            if !self.claim_name(file, &ty.name) {
+                continue;
+            }
+            let entity = self.create(file, &ty.name.value, ty.span);
+            self.items
+                .types
+                .insert(key(&ty.name), (entity, ty.data_type.clone()));
+        }
+
+        for program in &ast.programs {
+            if !self.claim_name(file, &program.name) {
+                continue;
+            }
+            let entity = self.create(file, &program.name.value, program.span);
+            self.items.programs.insert(key(&program.name), entity);
+        }
+
+        for fb in &ast.function_blocks {
+            if !self.claim_name(file, &fb.name) {
+                continue;
+            }
+            let entity = self.create(file, &fb.name.value, fb.span);
+            self.items.function_blocks.insert(key(&fb.name), entity);
+        }
+
+        for function in &ast.functions {
+            if !self.claim_name(file, &function.name) {
+                continue;
+            }
+            let entity = self.create(file, &function.name.value, function.span);
+            self.items.functions.insert(key(&function.name), entity);
+        }
+    }
+
+    /// Types and program organisation units share a single namespace, so
+    /// make sure nothing else (possibly from another file) already uses
+    /// this name.
+    fn claim_name(&mut self, file: FileId, name: &st::Identifier) -> bool {
+        match self.items.declared.get(&key(name)) {
+            Some(&(first_file, first)) => {
+                let diag = Diagnostic::error(
+                    format!("`{}` is defined more than once", name.value),
+                    Label::new(name.span, "defined again here"),
+                );
+                let first_defined = Label::new(first, "first defined here");
+
+                // a label can only point into the diagnostic's own file
+                if first_file == file {
+                    let diag = diag.with_secondary_label(first_defined);
+                    self.diagnostics.push((file, diag));
+                } else {
+                    self.diagnostics.push((file, diag));
+                    let note = Diagnostic::note(
+                        format!("`{}` was first defined here", name.value),
+                        first_defined,
+                    );
+                    self.diagnostics.push((first_file, note));
+                }
+                false
+            },
+            None => {
+                self.items.declared.insert(key(name), (file, name.span));
+                true
+            },
+        }
+    }
+
+    fn define_types(&mut self, file: FileId, ast: &st::File) {
+        // initial values may refer to types declared further down, so
+        // every definition needs to be known before they are translated
+        for declaration in &ast.types {
+            let (entity, _) = self.items.types[&key(&declaration.name)];
+            let definition = self.type_definition(file, &declaration.data_type);
+            self.items.definitions.insert(entity, definition);
+        }
+
+        for declaration in &ast.types {
+            let (entity, _) = self.items.types[&key(&declaration.name)];
+
+            if let st::DataType::Struct(ref s) = declaration.data_type {
+                let mut fields = Vec::new();
+
+                for field in &s.fields {
+                    let ty = self.lower_type(file, &field.declared_type);
+                    let initial_value = field
+                        .initial_value
+                        .as_ref()
+                        .and_then(|init| self.initial_value(file, init, &ty));
+                    fields.push(Field {
+                        name: field.name.value.clone(),
+                        ty,
+                        initial_value,
+                    });
+                }
+
+                self.items
+                    .definitions
+                    .insert(entity, DataType::Struct(fields));
+            }
+
+            if let Some(ref init) = declaration.initial_value {
+                let ty = self.named_type(file, &declaration.name, 0);
+                if let Some(initial_value) = self.initial_value(file, init, &ty)
+                {
+                    self.attach(entity, initial_value);
+                }
+            }
+
+            let definition = self.items.definitions[&entity].clone();
+            self.attach(entity, definition);
+        }
+    }
+
+    fn type_definition(
+        &mut self,
+        file: FileId,
+        data_type: &st::DataType,
+    ) -> DataType {
+        match data_type {
+            st::DataType::Enumerated(e) => enumeration(e),
+            st::DataType::Subrange(s) => DataType::Subrange {
+                base: self.named_type(file, &s.base_type, 0),
+                range: s.range.start.value..=s.range.end.value,
+            },
+            // the fields are filled in once every type is known
+            st::DataType::Struct(_) => DataType::Struct(Vec::new()),
+            other => DataType::Alias(self.lower_type(file, other)),
+        }
+    }
+
+    fn lower_type(&mut self, file: FileId, data_type: &st::DataType) -> Type {
+        self.lower_type_at_depth(file, data_type, 0)
+    }
+
+    fn lower_type_at_depth(
+        &mut self,
+        file: FileId,
+        data_type: &st::DataType,
+        depth: usize,
+    ) -> Type {
+        match data_type {
+            st::DataType::Named(name) => self.named_type(file, name, depth),
+            st::DataType::Enumerated(e) => self.anonymous_enum(file, e),
+            st::DataType::Subrange(s) => {
+                self.named_type(file, &s.base_type, depth + 1)
+            },
+            st::DataType::Array(a) => Type::Array {
+                element: Box::new(self.lower_type_at_depth(
+                    file,
+                    &a.element_type,
+                    depth + 1,
+                )),
+                dimensions: a
+                    .dimensions
+                    .iter()
+                    .map(|d| d.start.value..=d.end.value)
+                    .collect(),
+            },
+            st::DataType::Struct(s) => {
+                self.error(
+                    file,
+                    "Structs can only be declared in a TYPE block",
+                    s.span,
+                    "declared here",
+                );
+                Type::Bool
+            },
+        }
+    }
+
+    /// Find the type an identifier refers to, skipping over any aliases and
+    /// subranges.
+    fn named_type(
+        &mut self,
+        file: FileId,
+        name: &st::Identifier,
+        depth: usize,
+    ) -> Type {
+        if let Some(ty) = elementary(&name.value) {
+            return ty;
+        }
+
+        let key = key(name);
+
+        let (entity, definition) = match self.items.types.get(&key) {
+            Some((entity, definition)) => (*entity, definition.clone()),
+            None => {
+                return match self.items.function_blocks.get(&key) {
+                    Some(&fb) => Type::Derived(fb),
+                    // name resolution has already checked it exists
+                    None => Type::StandardFunctionBlock(key),
+                };
+            },
+        };
+
+        match definition {
+            st::DataType::Struct(_) | st::DataType::Enumerated(_) => {
+                Type::Derived(entity)
+            },
+            _ if depth > MAX_TYPE_DEPTH => {
+                self.error(
+                    file,
+                    "Recursive type alias",
+                    name.span,
+                    "this type refers to itself",
+                );
+                Type::Bool
+            },
+            other => self.lower_type_at_depth(file, &other, depth + 1),
+        }
+    }
+
+    fn anonymous_enum(&mut self, file: FileId, e: &st::EnumeratedType) -> Type {
+        let name = anonymous_enum_name(e);
+
+        if let Some(&entity) = self.items.anonymous_enums.get(&name) {
+            return Type::Derived(entity);
+        }
+
+        let definition = enumeration(e);
+        let entity = self
+            .world
+            .create_entity()
+            .with(Location { span: e.span, file })
+            .with(definition.clone())
+            .build();
+        self.items.anonymous_enums.insert(name, entity);
+        self.items.definitions.insert(entity, definition);
+
+        Type::Derived(entity)
+    }
+
+    /// Translate an initializer, which must only contain constants.
+    fn initial_value(
+        &mut self,
+        file: FileId,
+        initializer: &st::Initializer,
+        ty: &Type,
+    ) -> Option<InitialValue> {
+        match initializer {
+            st::Initializer::Expression(e) => self
+                .constant_expression(file, e, ty)
+                .map(InitialValue::Constant),
+            st::Initializer::Array(array) => {
+                let element = match ty {
+                    Type::Array { element, .. } => (**element).clone(),
+                    _ => ty.clone(),
+                };
+                let mut values = Vec::new();
+
+                for item in &array.elements {
+                    let value =
+                        self.initial_value(file, &item.value, &element)?;
+                    let repetitions =
+                        item.repetitions.as_ref().map(|r| r.value).unwrap_or(1);
+
+                    for _ in 0..repetitions {
+                        values.push(value.clone());
+                    }
+                }
+
+                Some(InitialValue::Array(values))
+            },
+            st::Initializer::Struct(s) => {
+                let fields = match ty {
+                    Type::Derived(entity) => {
+                        match self.items.definitions.get(entity) {
+                            Some(DataType::Struct(fields)) => fields.clone(),
+                            _ => Vec::new(),
+                        }
+                    },
+                    _ => Vec::new(),
+                };
+                let mut values = Vec::new();
+
+                for field in &s.fields {
+                    let field_type = fields
+                        .iter()
+                        .find(|f| {
+                            f.name.eq_ignore_ascii_case(&field.name.value)
+                        })
+                        .map(|f| f.ty.clone())?;
+                    let value =
+                        self.initial_value(file, &field.value, &field_type)?;
+                    values.push((field.name.value.clone(), value));
+                }
+
+                Some(InitialValue::Struct(values))
+            },
+        }
+    }
+
+    fn constant_expression(
+        &mut self,
+        file: FileId,
+        expression: &st::Expression,
+        ty: &Type,
+    ) -> Option<Constant> {
+        let constant = match expression {
+            st::Expression::Literal(literal) => literal_constant(literal),
+            st::Expression::UnaryExpression(st::UnaryExpression {
+                op: st::UnaryOp::Negate,
+                value,
+                ..
+            }) => match **value {
+                st::Expression::Literal(ref literal) => {
+                    literal_constant(literal).and_then(negate)
+                },
+                _ => None,
+            },
+            st::Expression::Variable(name) => {
+                self.items.enumerated_value(ty, &name.value)
+            },
+            _ => None,
+        };
+
+        match constant {
+            Some(constant) => {
+                Some(convert_constant(&constant, ty).unwrap_or(constant))
+            },
+            None => {
+                self.error(
+                    file,
+                    "Initial values must be constant",
+                    expression.span(),
+                    "this can't be evaluated at compile time",
+                );
+                None
+            },
+        }
+    }
+
+    fn declare_variables(&mut self, file: FileId, ast: &st::File) {
+        for program in &ast.programs {
+            let entity = self.items.programs[&key(&program.name)];
+            let variables =
+                self.pou_variables(file, entity, &program.var_blocks);
+            self.attach(entity, Program { variables });
+        }
+
+        for fb in &ast.function_blocks {
+            let entity = self.items.function_blocks[&key(&fb.name)];
+            let variables = self.pou_variables(file, entity, &fb.var_blocks);
+            self.attach(entity, FunctionBlock { variables });
+        }
+
+        for function in &ast.functions {
+            let entity = self.items.functions[&key(&function.name)];
+            let mut variables =
+                self.pou_variables(file, entity, &function.var_blocks);
+
+            // assigning to the function's name sets its return value
+            let ty = self.named_type(file, &function.return_type, 0);
+            let return_value =
+                self.create(file, &function.name.value, function.name.span);
+            self.attach(
+                return_value,
+                Variable {
+                    kind: VariableKind::Return,
+                    ty: ty.clone(),
+                },
+            );
+            variables.push(return_value);
+
+            self.items
+                .scopes
+                .entry(entity)
+                .or_default()
+                .insert(key(&function.name), (return_value, ty.clone()));
+            self.items.return_types.insert(entity, ty);
+            self.attach(
+                entity,
+                Function {
+                    variables,
+                    return_value,
+                },
+            );
+        }
+    }
+
+    /// Declare a program organisation unit's variables, recording its
+    /// scope and parameters.
+    fn pou_variables(
+        &mut self,
+        file: FileId,
+        pou: Entity,
+        var_blocks: &[st::VarBlock],
+    ) -> Vec<Entity> {
+        let declarations = self.variables(file, var_blocks);
+
+        self.items.scopes.insert(pou, declarations.scope);
+        self.items.parameters.insert(pou, declarations.parameters);
+
+        declarations.entities
+    }
+
+    /// Create an entity for each variable in a set of `VAR` blocks.
+    fn variables(
+        &mut self,
+        file: FileId,
+        var_blocks: &[st::VarBlock],
+    ) -> Declarations {
+        let mut declarations = Declarations::default();
+
+        for block in var_blocks {
+            let kind = match block.kind {
+                st::VarBlockKind::Normal => VariableKind::Local,
+                st::VarBlockKind::Global => VariableKind::Global,
+                st::VarBlockKind::External => VariableKind::External,
+                st::VarBlockKind::Input => VariableKind::Input,
+                st::VarBlockKind::Output => VariableKind::Output,
+            };
+
+            for declaration in &block.declarations {
+                let (entity, ty, initial_value) =
+                    self.variable(file, kind, declaration);
+
+                if kind == VariableKind::Input || kind == VariableKind::Output {
+                    let default = match initial_value {
+                        Some(InitialValue::Constant(c)) => Some(c),
+                        _ => default_value(&ty),
+                    };
+
+                    declarations.parameters.push(Parameter {
+                        name: key(&declaration.name),
+                        ty: ty.clone(),
+                        input: kind == VariableKind::Input,
+                        default,
+                    });
+                }
+
+                declarations
+                    .scope
+                    .insert(key(&declaration.name), (entity, ty));
+                declarations.entities.push(entity);
+            }
+        }
+
+        declarations
+    }
+
+    fn variable(
+        &mut self,
+        file: FileId,
+        kind: VariableKind,
+        declaration: &st::VariableDeclaration,
+    ) -> (Entity, Type, Option<InitialValue>) {
+        let ty = self.lower_type(file, &declaration.declared_type);
+        let initial_value = declaration
+            .initial_value
+            .as_ref()
+            .and_then(|init| self.initial_value(file, init, &ty));
+
+        let entity =
+            self.create(file, &declaration.name.value, declaration.span);
+        self.attach(
+            entity,
+            Variable {
+                kind,
+                ty: ty.clone(),
+            },
+        );
+
+        if let Some(ref initial_value) = initial_value {
+            self.attach(entity, initial_value.clone());
+        }
+
+        if let Some(ref address) = declaration.address {
+            let area = match address.area {
+                st::AddressArea::Input => Area::Input,
+                st::AddressArea::Output => Area::Output,
+                st::AddressArea::Memory => Area::Memory,
+            };
+            self.attach(
+                entity,
+                Address {
+                    area,
+                    byte: address.byte as usize,
+                    bit: address.bit,
+                    size_in_bytes: address.size.size_in_bytes(),
+                },
+            );
+        }
+
+        (entity, ty, initial_value)
+    }
+
+    fn lower_bodies(
+        &mut self,
+        file: FileId,
+        ast: &st::File,
+        info: &types::TypeInfo,
+    ) {
+        let programs = ast
+            .programs
+            .iter()
+            .map(|p| (self.items.programs[&key(&p.name)], &p.body));
+        let function_blocks = ast
+            .function_blocks
+            .iter()
+            .map(|fb| (self.items.function_blocks[&key(&fb.name)], &fb.body));
+        let functions = ast
+            .functions
+            .iter()
+            .map(|f| (self.items.functions[&key(&f.name)], &f.body));
+        let pous: Vec<_> =
+            programs.chain(function_blocks).chain(functions).collect();
+
+        for (entity, block) in pous {
+            let (body, diagnostics) =
+                body::lower(&self.items, entity, block, info);
+            self.diagnostics
+                .extend(diagnostics.into_iter().map(|diag| (file, diag)));
+            self.attach(entity, body);
+        }
+    }
+
+    /// Gather every resource, task and program instance into a single
+    /// [`Configuration`].
+    fn configuration(&mut self, files: &[(FileId, st::File)]) -> Entity {
+        let mut configuration = Configuration {
+            resources: Vec::new(),
+            globals: Vec::new(),
+        };
+        let mut declared: Option<(FileId, &st::Configuration)> = None;
+
+        for (file, ast) in files {
+            for config in &ast.configurations {
+                if let Some((_, first)) = declared {
+                    let diag = Diagnostic::error(
+                        "Only one configuration may be declared",
+                        Label::new(config.name.span, "declared again here"),
+                    )
+                    .with_secondary_label(Label::new(
+                        first.name.span,
+                        "first declared here",
+                    ));
+                    self.diagnostics.push((*file, diag));
+                    continue;
+                }
+                declared = Some((*file, config));
+
+                let globals = self.variables(*file, &config.var_blocks);
+                configuration.globals = globals.entities;
+
+                for resource in &config.resources {
+                    let entity = self.resource(*file, resource, &globals.scope);
+                    configuration.resources.push(entity);
+                }
+            }
+        }
+
+        match declared {
+            Some((file, config)) => {
+                let entity = self.create(file, &config.name.value, config.span);
+                self.attach(entity, configuration);
+                entity
+            },
+            None => self.world.create_entity().with(configuration).build(),
+        }
+    }
+
+    fn resource(
+        &mut self,
+        file: FileId,
+        resource: &st::Resource,
+        configuration_globals: &HashMap<String, (Entity, Type)>,
+    ) -> Entity {
+        let entity = match resource.name {
+            Some(ref name) => self.create(file, &name.value, resource.span),
+            None => self
+                .world
+                .create_entity()
+                .with(Location {
+                    span: resource.span,
+                    file,
+                })
+                .build(),
+        };
+
+        let globals = self.variables(file, &resource.var_blocks);
+        let mut visible = configuration_globals.clone();
+        visible.extend(globals.scope);
+
+        let mut tasks = Vec::new();
+        let mut task_names = HashMap::new();
+        for task in &resource.tasks {
+            let task_entity = self.task(file, task, &visible);
+            task_names.insert(key(&task.name), task_entity);
+            tasks.push(task_entity);
+        }
+
+        let mut programs = Vec::new();
+        for instance in &resource.programs {
+            let program =
+                match self.items.programs.get(&key(&instance.program_type)) {
+                    Some(&program) => program,
+                    None => {
+                        self.error(
+                            file,
+                            "Unknown program",
+                            instance.program_type.span,
+                            "no program with this name was declared",
+                        );
+                        continue;
+                    },
+                };
+            let task = instance
+                .task
+                .as_ref()
+                .and_then(|name| task_names.get(&key(name)).copied());
+
+            let instance_entity =
+                self.create(file, &instance.name.value, instance.span);
+            self.attach(instance_entity, ProgramInstance { program, task });
+            programs.push(instance_entity);
+        }
+
+        self.attach(
+            entity,
+            Resource {
+                tasks,
+                programs,
+                globals: globals.entities,
+            },
+        );
+
+        entity
+    }
+
+    fn task(
+        &mut self,
+        file: FileId,
+        task: &st::Task,
+        globals: &HashMap<String, (Entity, Type)>,
+    ) -> Entity {
+        let trigger = match (&task.single, &task.interval) {
+            (Some(st::Expression::Variable(name)), _) => {
+                match globals.get(&key(name)) {
+                    Some(&(variable, Type::Bool)) => {
+                        Trigger::RisingEdge { variable }
+                    },
+                    Some(_) => {
+                        self.error(
+                            file,
+                            "Invalid task trigger",
+                            name.span,
+                            "SINGLE must refer to a BOOL variable",
+                        );
+                        Trigger::Periodic {
+                            interval: Duration::default(),
+                        }
+                    },
+                    None => {
+                        self.error(
+                            file,
+                            "Unknown variable",
+                            name.span,
+                            "SINGLE must refer to a global variable",
+                        );
+                        Trigger::Periodic {
+                            interval: Duration::default(),
+                        }
+                    },
+                }
+            },
+            (Some(other), _) => {
+                self.error(
+                    file,
+                    "Invalid task trigger",
+                    other.span(),
+                    "SINGLE must refer to a global variable",
+                );
+                Trigger::Periodic {
+                    interval: Duration::default(),
+                }
+            },
+            (
+                None,
+                Some(st::Expression::Literal(st::Literal::Duration(duration))),
+            ) => Trigger::Periodic {
+                interval: duration.value,
+            },
+            (None, Some(other)) => {
+                self.error(
+                    file,
+                    "Invalid task interval",
+                    other.span(),
+                    "INTERVAL must be a TIME literal",
+                );
+                Trigger::Periodic {
+                    interval: Duration::default(),
+                }
+            },
+            (None, None) => Trigger::Periodic {
+                interval: Duration::default(),
+            },
+        };
+        let priority = task
+            .priority
+            .as_ref()
+            .map(|p| p.value.min(u64::from(u32::max_value())) as u32)
+            .unwrap_or(0);
+
+        let entity = self.create(file, &task.name.value, task.span);
+        self.attach(entity, Task { trigger, priority });
+        entity
+    }
+}
+
+fn key(name: &st::Identifier) -> String { name.value.to_uppercase() }
+
+fn elementary(name: &str) -> Option<Type> {
+    types::Type::elementary(name).and_then(|ty| convert_elementary(&ty))
+}
+
+fn convert_elementary(ty: &types::Type) -> Option<Type> {
+    use rustmatic_structured_text::types::Type as T;
+
+    let converted = match ty {
+        T::Bool => Type::Bool,
+        T::SInt => Type::SInt,
+        T::Int => Type::Int,
+        T::DInt | T::AnyInt => Type::DInt,
+        T::LInt => Type::LInt,
+        T::USInt => Type::USInt,
+        T::UInt => Type::UInt,
+        T::UDInt => Type::UDInt,
+        T::ULInt => Type::ULInt,
+        T::Real => Type::Real,
+        T::LReal | T::AnyReal => Type::LReal,
+        T::Byte => Type::Byte,
+        T::Word => Type::Word,
+        T::DWord => Type::DWord,
+        T::LWord => Type::LWord,
+        T::Time => Type::Time,
+        T::Date => Type::Date,
+        T::TimeOfDay => Type::TimeOfDay,
+        T::DateAndTime => Type::DateAndTime,
+        T::String => Type::String,
+        T::WString => Type::WString,
+        _ => return None,
+    };
+
+    Some(converted)
+}
+
+fn enumeration(e: &st::EnumeratedType) -> DataType {
+    let mut values = Vec::new();
+    let mut next = 0;
+
+    for value in &e.values {
+        let value = EnumeratedValue {
+            name: value.name.value.clone(),
+            value: value.value.as_ref().map(|v| v.value).unwrap_or(next),
+        };
+        next = value.value + 1;
+        values.push(value);
+    }
+
+    DataType::Enumeration(values)
+}
+
+/// The name the type checker gives an anonymous enumeration (e.g.
+/// `(Idle, Running)`).
+fn anonymous_enum_name(e: &st::EnumeratedType) -> String {
+    let values: Vec<_> =
+        e.values.iter().map(|v| v.name.value.as_str()).collect();
+    format!("({})", values.join(", "))
+}
+
+fn literal_constant(literal: &st::Literal) -> Option<Constant> {
+    let (value, ty) = match literal {
+        st::Literal::Integer(i) => {
+            (ConstantValue::Integer(i.value.into()), Type::DInt)
+        },
+        st::Literal::Float(f) => (ConstantValue::Real(f.value), Type::LReal),
+        st::Literal::String(s) => {
+            (ConstantValue::String(s.value.clone()), Type::String)
+        },
+        st::Literal::WString(s) => {
+            (ConstantValue::String(s.value.clone()), Type::WString)
+        },
+        st::Literal::Boolean(b) => (ConstantValue::Bool(b.value), Type::Bool),
+        st::Literal::Typed(typed) => {
+            let ty = elementary(&typed.declared_type.value)?;
+            let mut value = literal_constant(&typed.value)?;
+            if typed.negative {
+                value = negate(value)?;
+            }
+
+            return match (value.value, ty) {
+                (ConstantValue::Integer(i), Type::Bool) => {
+                    Some(Constant::bool(i != 0))
+                },
+                (value, ty) => {
+                    convert_constant(&Constant::new(value, ty.clone()), &ty)
+                },
+            };
+        },
+        st::Literal::Duration(d) => (nanoseconds(d.value), Type::Time),
+        st::Literal::TimeOfDay(tod) => {
+            (ConstantValue::Integer(time_of_day(tod)), Type::TimeOfDay)
+        },
+        st::Literal::Date(date) => {
+            (ConstantValue::Integer(date_nanos(date)), Type::Date)
+        },
+        st::Literal::DateAndTime(dt) => (
+            ConstantValue::Integer(
+                date_nanos(&dt.date) + time_of_day(&dt.time),
+            ),
+            Type::DateAndTime,
+        ),
+    };
+
+    Some(Constant::new(value, ty))
+}
+
+fn negate(constant: Constant) -> Option<Constant> {
+    let value = match constant.value {
+        ConstantValue::Integer(i) => ConstantValue::Integer(-i),
+        ConstantValue::Real(r) => ConstantValue::Real(-r),
+        _ => return None,
+    };
+
+    Some(Constant::new(value, constant.ty))
+}
+
+/// Try to change a constant's type at compile time (e.g. using an integer
+/// literal as a `REAL`).
+fn convert_constant(constant: &Constant, ty: &Type) -> Option<Constant> {
+    if constant.ty == *ty {
+        return Some(constant.clone());
+    }
+
+    let value = match (&constant.value, ty) {
+        (ConstantValue::Integer(i), _)
+            if ty.is_integer() || (ty.is_bit_string() && *ty != Type::Bool) =>
+        {
+            ConstantValue::Integer(*i)
+        },
+        (ConstantValue::Integer(i), _) if ty.is_real() => {
+            ConstantValue::Real(*i as f64)
+        },
+        (ConstantValue::Real(r), _) if ty.is_real() => ConstantValue::Real(*r),
+        _ => return None,
+    };
+
+    Some(Constant::new(value, ty.clone()))
+}
+
+/// The value a variable starts with when it has no initializer.
+fn default_value(ty: &Type) -> Option<Constant> {
+    let value = match ty {
+        Type::Bool => ConstantValue::Bool(false),
+        Type::Real | Type::LReal => ConstantValue::Real(0.0),
+        Type::String | Type::WString => ConstantValue::String(String::new()),
+        _ if ty.is_integer() || ty.is_bit_string() || ty.is_time() => {
+            ConstantValue::Integer(0)
+        },
+        _ => return None,
+    };
+
+    Some(Constant::new(value, ty.clone()))
+}
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+const SECONDS_PER_DAY: i128 = 24 * 60 * 60;
+
+fn nanoseconds(duration: Duration) -> ConstantValue {
+    ConstantValue::Integer(duration.as_nanos() as i128)
+}
+
+/// The number of nanoseconds since midnight.
+fn time_of_day(tod: &st::TimeOfDayLiteral) -> i128 {
+    let seconds = (i128::from(tod.hour) * 60 + i128::from(tod.minute)) * 60
+        + i128::from(tod.second);
+
+    seconds * NANOS_PER_SECOND + i128::from(tod.nanosecond)
+}
+
+/// The number of nanoseconds between the Unix epoch and midnight on a
+/// particular date.
+fn date_nanos(date: &st::DateLiteral) -> i128 {
+    days_since_epoch(
+        i64::from(date.year),
+        i64::from(date.month),
+        i64::from(date.day),
+    ) * SECONDS_PER_DAY
+        * NANOS_PER_SECOND
+}
+
+/// Howard Hinnant's `days_from_civil()` algorithm.
+fn days_since_epoch(year: i64, month: i64, day: i64) -> i128 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era =
+        year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    i128::from(era * 146_097 + day_of_era - 719_468)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mir::{
+        BlockId, Body, Callee, Instruction, Operand, Place, Temporary,
+        Terminator,
+    };
+    use codespan::Files;
+
+    fn translate(src: &str) -> (World, Entity) {
+        let mut files = Files::new();
+        let id = files.add("main.st", src);
+        let file = rustmatic_structured_text::parse(src).unwrap();
+        let mut world = World::new();
+
+        let configuration =
+            translate_structured_text(vec![(id, file)], &mut world).unwrap();
+
+        (world, configuration)
+    }
+
+    fn named(world: &World, name: &str) -> Entity {
+        (&world.entities(), &world.read_storage::<Name>())
+            .join()
+            .find(|(_, n)| n.0 == name)
+            .map(|(entity, _)| entity)
+            .unwrap_or_else(|| panic!("No entity called \"{}\"", name))
+    }
+
+    fn component<C: Component + Clone>(world: &World, entity: Entity) -> C {
+        world.read_storage::<C>().get(entity).cloned().unwrap()
+    }
+
+    fn body(world: &World, pou: &str) -> Body {
+        component(world, named(world, pou))
+    }
+
+    fn constant(value: ConstantValue, ty: Type) -> Operand {
+        Operand::Constant(Constant::new(value, ty))
+    }
+
+    #[test]
+    fn every_item_gets_an_entity() {
+        let src = "
+            CONFIGURATION Plant
+                VAR_GLOBAL
+                    emergency_stop : BOOL;
+                END_VAR
+
+                RESOURCE Cpu1 ON PLC_CPU
+                    TASK Fast(INTERVAL := T#10ms, PRIORITY := 1);
+                    TASK OnAlarm(SINGLE := emergency_stop);
+                    PROGRAM Main WITH Fast : MainProg;
+                END_RESOURCE
+            END_CONFIGURATION
+
+            PROGRAM MainProg
+                VAR
+                    ticks : INT;
+                END_VAR
+
+                ticks := ticks + 1;
+            END_PROGRAM
+        ";
+        let (world, configuration) = translate(src);
+
+        assert_eq!(
+            component::<Name>(&world, configuration),
+            Name(String::from("Plant"))
+        );
+        let location: Location = component(&world, configuration);
+        assert_eq!(
+            &src[location.span.start().to_usize()..][..13],
+            "CONFIGURATION"
+        );
+
+        let emergency_stop = named(&world, "emergency_stop");
+        let cpu = named(&world, "Cpu1");
+        let fast = named(&world, "Fast");
+        let on_alarm = named(&world, "OnAlarm");
+        let main = named(&world, "Main");
+        let main_prog = named(&world, "MainProg");
+        let ticks = named(&world, "ticks");
+
+        assert_eq!(
+            component::<Configuration>(&world, configuration),
+            Configuration {
+                resources: vec![cpu],
+                globals: vec![emergency_stop],
+            }
+        );
+        assert_eq!(
+            component::<Resource>(&world, cpu),
+            Resource {
+                tasks: vec![fast, on_alarm],
+                programs: vec![main],
+                globals: Vec::new(),
+            }
+        );
+        assert_eq!(
+            component::<Task>(&world, fast),
+            Task {
+                trigger: Trigger::Periodic {
+                    interval: Duration::from_millis(10)
+                },
+                priority: 1,
+            }
+        );
+        assert_eq!(
+            component::<Task>(&world, on_alarm).trigger,
+            Trigger::RisingEdge {
+                variable: emergency_stop
+            }
+        );
+        assert_eq!(
+            component::<ProgramInstance>(&world, main),
+            ProgramInstance {
+                program: main_prog,
+                task: Some(fast),
+            }
+        );
+        assert_eq!(
+            component::<Program>(&world, main_prog),
+            Program {
+                variables: vec![ticks],
+            }
+        );
+        assert_eq!(
+            component::<Variable>(&world, ticks),
+            Variable {
+                kind: VariableKind::Local,
+                ty: Type::Int,
+            }
+        );
+    }
+
+    #[test]
+    fn user_defined_types() {
+        let src =
+            include_str!("../../structured-text/tests/data/user_types.st");
+        let (world, _) = translate(src);
+
+        let mode = named(&world, "Mode");
+        assert_eq!(
+            component::<DataType>(&world, mode),
+            DataType::Enumeration(vec![
+                EnumeratedValue {
+                    name: String::from("Idle"),
+                    value: 0,
+                },
+                EnumeratedValue {
+                    name: String::from("Running"),
+                    value: 1,
+                },
+                EnumeratedValue {
+                    name: String::from("Stopping"),
+                    value: 10,
+                },
+            ])
+        );
+        assert_eq!(
+            component::<DataType>(&world, named(&world, "Percent")),
+            DataType::Subrange {
+                base: Type::Int,
+                range: 0..=100,
+            }
+        );
+        assert_eq!(
+            component::<DataType>(&world, named(&world, "Matrix")),
+            DataType::Alias(Type::Array {
+                element: Box::new(Type::Int),
+                dimensions: vec![1..=3, 0..=2],
+            })
+        );
+
+        // aliases and subranges are replaced by the type they refer to
+        let load = named(&world, "load");
+        assert_eq!(component::<Variable>(&world, load).ty, Type::Int);
+
+        let variable = named(&world, "mode");
+        assert_eq!(
+            component::<Variable>(&world, variable).ty,
+            Type::Derived(mode)
+        );
+        assert_eq!(
+            component::<InitialValue>(&world, variable),
+            InitialValue::Constant(Constant::new(
+                ConstantValue::Integer(1),
+                Type::Derived(mode)
+            ))
+        );
+
+        let table = named(&world, "table");
+        let zero = InitialValue::Constant(Constant::new(
+            ConstantValue::Integer(0),
+            Type::Int,
+        ));
+        match component::<InitialValue>(&world, table) {
+            InitialValue::Array(values) => {
+                assert_eq!(values.len(), 10);
+                assert_eq!(values[9], zero);
+            },
+            other => panic!("Expected an array, found {:?}", other),
+        }
+
+        let axis = named(&world, "axis");
+        assert_eq!(
+            component::<InitialValue>(&world, axis),
+            InitialValue::Struct(vec![
+                (
+                    String::from("position"),
+                    InitialValue::Constant(Constant::new(
+                        ConstantValue::Real(0.0),
+                        Type::Real
+                    ))
+                ),
+                (
+                    String::from("limits"),
+                    InitialValue::Array(vec![
+                        InitialValue::Constant(Constant::new(
+                            ConstantValue::Real(-5.0),
+                            Type::Real
+                        )),
+                        InitialValue::Constant(Constant::new(
+                            ConstantValue::Real(5.0),
+                            Type::Real
+                        )),
+                    ])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn implicit_widening_inserts_conversions() {
+        let src = "
+            PROGRAM main
+                VAR
+                    i : INT;
+                    r : REAL;
+                END_VAR
+
+                r := i;
+                r := 1;
+            END_PROGRAM
+        ";
+        let (world, _) = translate(src);
+        let i = named(&world, "i");
+        let r = named(&world, "r");
+
+        let body = body(&world, "main");
+
+        assert_eq!(body.temporaries, vec![Type::Int, Type::Real]);
+        assert_eq!(
+            body.block(Body::ENTRY).instructions,
+            vec![
+                Instruction::Load {
+                    destination: Temporary(0),
+                    source: Place::Variable(i),
+                },
+                Instruction::Convert {
+                    destination: Temporary(1),
+                    value: Operand::Temporary(Temporary(0)),
+                },
+                Instruction::Store {
+                    destination: Place::Variable(r),
+                    value: Operand::Temporary(Temporary(1)),
+                },
+                // literals are converted at compile time
+                Instruction::Store {
+                    destination: Place::Variable(r),
+                    value: constant(ConstantValue::Real(1.0), Type::Real),
+                },
+            ]
+        );
+        assert_eq!(body.block(Body::ENTRY).terminator, Terminator::Return);
+    }
+
+    #[test]
+    fn temporaries_keep_array_dimensions() {
+        let src = "
+            PROGRAM main
+                VAR
+                    a : ARRAY [1..3, 0..1] OF INT;
+                    b : ARRAY [1..3, 0..1] OF INT;
+                END_VAR
+
+                a := b;
+            END_PROGRAM
+        ";
+        let (world, _) = translate(src);
+
+        let body = body(&world, "main");
+
+        assert_eq!(
+            body.temporaries,
+            vec![Type::Array {
+                element: Box::new(Type::Int),
+                dimensions: vec![1..=3, 0..=1],
+            }]
+        );
+    }
+
+    #[test]
+    fn if_statements_become_branches() {
+        let src = "
+            PROGRAM main
+                VAR
+                    x : INT;
+                END_VAR
+
+                IF x > 5 THEN
+                    x := 0;
+                ELSE
+                    x := x + 1;
+                END_IF;
+            END_PROGRAM
+        ";
+        let (world, _) = translate(src);
+        let body = body(&world, "main");
+
+        let entry = body.block(Body::ENTRY);
+        let (then, otherwise) = match entry.terminator {
+            Terminator::Branch {
+                condition: Operand::Temporary(condition),
+                then,
+                otherwise,
+            } => {
+                assert_eq!(*body.type_of(condition), Type::Bool);
+                (then, otherwise)
+            },
+            ref other => panic!("Expected a branch, found {:?}", other),
+        };
+
+        let end = match body.block(then).terminator {
+            Terminator::Goto(end) => end,
+            ref other => panic!("Expected a goto, found {:?}", other),
+        };
+        assert_eq!(body.block(otherwise).terminator, Terminator::Goto(end));
+        assert_eq!(body.block(end).terminator, Terminator::Return);
+        assert_eq!(
+            body.block(then).instructions,
+            vec![Instruction::Store {
+                destination: Place::Variable(named(&world, "x")),
+                value: constant(ConstantValue::Integer(0), Type::Int),
+            }]
+        );
+    }
+
+    #[test]
+    fn loops_jump_back_to_their_header() {
+        let src = "
+            PROGRAM main
+                VAR
+                    i : INT;
+                    total : DINT;
+                END_VAR
+
+                FOR i := 1 TO 10 DO
+                    IF i = 5 THEN
+                        EXIT;
+                    END_IF;
+                    total := total + i;
+                END_FOR;
+            END_PROGRAM
+        ";
+        let (world, _) = translate(src);
+        let body = body(&world, "main");
+
+        // the entry block initializes the counter then enters the loop
+        let header = match body.block(Body::ENTRY).terminator {
+            Terminator::Goto(header) => header,
+            ref other => panic!("Expected a goto, found {:?}", other),
+        };
+        let (loop_body, exit) = match body.block(header).terminator {
+            Terminator::Branch {
+                then, otherwise, ..
+            } => (then, otherwise),
+            ref other => panic!("Expected a branch, found {:?}", other),
+        };
+        assert_eq!(body.block(exit).terminator, Terminator::Return);
+
+        // every block in the loop ends up back at the header, or leaves
+        // through the exit
+        let mut pending = vec![loop_body];
+        let mut seen = Vec::new();
+        while let Some(block) = pending.pop() {
+            if block == header || block == exit || seen.contains(&block) {
+                continue;
+            }
+            seen.push(block);
+
+            let targets: Vec<BlockId> = match body.block(block).terminator {
+                Terminator::Goto(target) => vec![target],
+                Terminator::Branch {
+                    then, otherwise, ..
+                } => vec![then, otherwise],
+                Terminator::Return => panic!("{:?} returns early", block),
+            };
+            pending.extend(targets);
+        }
+
+        // "total + i" widens the counter to a DINT
+        let widened = body.blocks.iter().flat_map(|b| &b.instructions).any(
+            |instruction| match instruction {
+                Instruction::Convert { destination, .. } => {
+                    *body.type_of(*destination) == Type::DInt
+                },
+                _ => false,
+            },
+        );
+        assert!(widened);
+    }
+
+    #[test]
+    fn function_arguments_are_passed_in_declaration_order() {
+        let src = "
+            FUNCTION Clamp : INT
+                VAR_INPUT
+                    value : INT;
+                    low : INT;
+                    high : INT := 100;
+                END_VAR
+
+                Clamp := MIN(MAX(value, low), high);
+            END_FUNCTION
+
+            PROGRAM main
+                VAR
+                    x : INT;
+                END_VAR
+
+                x := Clamp(low := 0, value := x);
+            END_PROGRAM
+        ";
+        let (world, _) = translate(src);
+        let clamp = named(&world, "Clamp");
+        let x = named(&world, "x");
+
+        let function: Function = component(&world, clamp);
+        assert_eq!(function.variables.len(), 4);
+        assert_eq!(
+            component::<Variable>(&world, function.return_value).kind,
+            VariableKind::Return
+        );
+
+        let body = body(&world, "main");
+        let call = body
+            .block(Body::ENTRY)
+            .instructions
+            .iter()
+            .find_map(|instruction| match instruction {
+                Instruction::Call {
+                    function,
+                    arguments,
+                    ..
+                } => Some((function.clone(), arguments.clone())),
+                _ => None,
+            })
+            .unwrap();
+
+        assert_eq!(call.0, Callee::Function(clamp));
+        assert_eq!(
+            call.1,
+            vec![
+                Operand::Temporary(Temporary(0)),
+                constant(ConstantValue::Integer(0), Type::Int),
+                constant(ConstantValue::Integer(100), Type::Int),
+            ]
+        );
+        assert_eq!(
+            body.block(Body::ENTRY).instructions[0],
+            Instruction::Load {
+                destination: Temporary(0),
+                source: Place::Variable(x),
+            }
+        );
+    }
+
+    #[test]
+    fn function_block_calls_copy_inputs_and_outputs() {
+        let src = "
+            PROGRAM main
+                VAR
+                    timer : TON;
+                    start : BOOL;
+                    done : BOOL;
+                END_VAR
+
+                timer(IN := start, PT := T#5s, Q => done);
+            END_PROGRAM
+        ";
+        let (world, _) = translate(src);
+        let timer = named(&world, "timer");
+
+        assert_eq!(
+            component::<Variable>(&world, timer).ty,
+            Type::StandardFunctionBlock(String::from("TON"))
+        );
+
+        let body = body(&world, "main");
+        assert_eq!(
+            body.block(Body::ENTRY).instructions[1],
+            Instruction::CallFunctionBlock {
+                instance: Place::Variable(timer),
+                inputs: vec![
+                    (String::from("IN"), Operand::Temporary(Temporary(0))),
+                    (
+                        String::from("PT"),
+                        constant(
+                            ConstantValue::Integer(5_000_000_000),
+                            Type::Time
+                        )
+                    ),
+                ],
+                outputs: vec![(
+                    String::from("Q"),
+                    Place::Variable(named(&world, "done"))
+                )],
+            }
+        );
+    }
+
+    #[test]
+    fn date_literals_are_measured_from_the_unix_epoch() {
+        let epoch = st::DateLiteral {
+            year: 1970,
+            month: 1,
+            day: 1,
+            span: Span::initial(),
+        };
+        assert_eq!(date_nanos(&epoch), 0);
+
+        let leap_day = st::DateLiteral {
+            year: 2020,
+            month: 2,
+            day: 29,
+            ..epoch
+        };
+        assert_eq!(
+            date_nanos(&leap_day),
+            18321 * SECONDS_PER_DAY * NANOS_PER_SECOND
+        );
+    }
+
+    #[test]
+    fn errors_stop_translation() {
+        let src = "
+            PROGRAM main
+                x := 1;
+            END_PROGRAM
+        ";
+        let mut files = Files::new();
+        let id = files.add("main.st", src);
+        let file = rustmatic_structured_text::parse(src).unwrap();
+        let mut world = World::new();
+
+        let diagnostics =
+            translate_structured_text(vec![(id, file)], &mut world)
+                .unwrap_err();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].0, id);
+        assert_eq!(diagnostics[0].1.severity, Severity::Error);
+    }
+
+    #[test]
+    fn diagnostics_can_be_rendered_with_the_source_files() {
+        use codespan_reporting::term::{self, termcolor::NoColor};
+
+        let src = "
+            PROGRAM main
+                x := 1;
+            END_PROGRAM
+        ";
+        let mut files = Files::new();
+        let id = files.add("main.st", src);
+        let file = rustmatic_structured_text::parse(src).unwrap();
+        let mut world = World::new();
+        let diagnostics =
+            translate_structured_text(vec![(id, file)], &mut world)
+                .unwrap_err();
+
+        let mut writer = NoColor::new(Vec::new());
+        let config = term::Config::default();
+        for (file, diagnostic) in &diagnostics {
+            let diagnostic = diagnostic.to_codespan(*file);
+            term::emit(&mut writer, &config, &files, &diagnostic).unwrap();
+        }
+
+        let rendered = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(rendered.contains("main.st:3:17"), "{}", rendered);
+        assert!(rendered.contains("x := 1;"), "{}", rendered);
+    }
+
+    #[test]
+    fn single_must_refer_to_a_bool() {
+        let src = "
+            CONFIGURATION Plant
+                VAR_GLOBAL
+                    level : INT;
+                END_VAR
+
+                RESOURCE Cpu1 ON PLC_CPU
+                    TASK OnLevel(SINGLE := level);
+                    PROGRAM Main WITH OnLevel : MainProg;
+                END_RESOURCE
+            END_CONFIGURATION
+
+            PROGRAM MainProg END_PROGRAM
+        ";
+        let mut files = Files::new();
+        let id = files.add("main.st", src);
+        let file = rustmatic_structured_text::parse(src).unwrap();
+        let mut world = World::new();
+
+        let diagnostics =
+            translate_structured_text(vec![(id, file)], &mut world)
+                .unwrap_err();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].1.message, "Invalid task trigger");
+    }
+
+    #[test]
+    fn functions_can_be_called_from_another_file() {
+        let library = "
+            FUNCTION Double : INT
+                VAR_INPUT value : INT; END_VAR
+                Double := value * 2;
+            END_FUNCTION
+        ";
+        let src = "
+            PROGRAM main
+                VAR x : INT; END_VAR
+                x := Double(x);
+            END_PROGRAM
+        ";
+        let mut files = Files::new();
+        let library_id = files.add("library.st", library);
+        let id = files.add("main.st", src);
+        let parsed = vec![
+            (library_id, rustmatic_structured_text::parse(library).unwrap()),
+            (id, rustmatic_structured_text::parse(src).unwrap()),
+        ];
+        let mut world = World::new();
+
+        translate_structured_text(parsed, &mut world).unwrap();
+
+        let double = named(&world, "Double");
+        let calls: Vec<_> = body(&world, "main")
+            .block(Body::ENTRY)
+            .instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::Call { function, .. } => Some(function.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(calls, vec![Callee::Function(double)]);
+    }
+
+    #[test]
+    fn items_may_only_be_defined_once() {
+        let first = "
+            TYPE Mode : (Idle, Running); END_TYPE
+            PROGRAM main END_PROGRAM
+        ";
+        let second = "
+            FUNCTION_BLOCK MODE END_FUNCTION_BLOCK
+            PROGRAM Main END_PROGRAM
+        ";
+        let mut files = Files::new();
+        let first_id = files.add("first.st", first);
+        let second_id = files.add("second.st", second);
+        let parsed = vec![
+            (first_id, rustmatic_structured_text::parse(first).unwrap()),
+            (second_id, rustmatic_structured_text::parse(second).unwrap()),
+        ];
+        let mut world = World::new();
+
+        let diagnostics =
+            translate_structured_text(parsed, &mut world).unwrap_err();
+
+        let got: Vec<_> = diagnostics
+            .iter()
+            .map(|(file, diag)| {
+                let src = if *file == first_id { first } else { second };
+                let span = diag.primary_label.span;
+                assert!(diag.secondary_labels.is_empty());
+                (
+                    *file,
+                    diag.severity,
+                    &src[span.start().to_usize()..span.end().to_usize()],
+                )
+            })
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (second_id, Severity::Error, "Main"),
+                (first_id, Severity::Note, "main"),
+                (second_id, Severity::Error, "MODE"),
+                (first_id, Severity::Note, "Mode"),
+            ]
+        );
+    }
 }
@@ -0,0 +1,984 @@
+//! Lowering statements and expressions into a control flow graph.
+
+use super::{
+    convert_constant, key, literal_constant, negate, Items, Parameter,
+};
+use crate::mir::{
+    BasicBlock, BinaryOp, BlockId, Body, Callee, Constant, ConstantValue,
+    Instruction, Operand, Place, Temporary, Terminator, Type, UnaryOp,
+};
+use codespan::Span;
+use rustmatic_structured_text::{
+    self as st, types::TypeInfo, Diagnostic, Label,
+};
+use specs::Entity;
+use std::collections::HashMap;
+
+/// Lower the body of a program organisation unit.
+pub(super) fn lower(
+    items: &Items,
+    pou: Entity,
+    block: &st::Block,
+    types: &TypeInfo,
+) -> (Body, Vec<Diagnostic>) {
+    let empty = HashMap::new();
+    let mut builder = Builder {
+        items,
+        types,
+        locals: items.scopes.get(&pou).unwrap_or(&empty),
+        temporaries: Vec::new(),
+        blocks: Vec::new(),
+        current: Body::ENTRY,
+        loop_exits: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+
+    let entry = builder.new_block();
+    builder.switch_to(entry);
+    builder.block(block);
+    builder.terminate(Terminator::Return);
+
+    let blocks = builder
+        .blocks
+        .into_iter()
+        .map(|(instructions, terminator)| BasicBlock {
+            instructions,
+            // blocks after an EXIT are never terminated, but they're also
+            // unreachable
+            terminator: terminator.unwrap_or(Terminator::Return),
+        })
+        .collect();
+    let body = Body {
+        temporaries: builder.temporaries,
+        blocks,
+    };
+
+    (body, builder.diagnostics)
+}
+
+struct Builder<'a> {
+    items: &'a Items,
+    types: &'a TypeInfo,
+    locals: &'a HashMap<String, (Entity, Type)>,
+    temporaries: Vec<Type>,
+    blocks: Vec<(Vec<Instruction>, Option<Terminator>)>,
+    current: BlockId,
+    /// Where an `EXIT` statement should jump to.
+    loop_exits: Vec<BlockId>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Builder<'a> {
+    fn error(&mut self, message: &str, span: Span, label: &str) {
+        self.diagnostics
+            .push(Diagnostic::error(message, Label::new(span, label)));
+    }
+
+    fn new_block(&mut self) -> BlockId {
+        self.blocks.push((Vec::new(), None));
+        BlockId(self.blocks.len() - 1)
+    }
+
+    fn switch_to(&mut self, block: BlockId) { self.current = block; }
+
+    fn emit(&mut self, instruction: Instruction) {
+        self.blocks[self.current.0].0.push(instruction);
+    }
+
+    fn terminate(&mut self, terminator: Terminator) {
+        let (_, current) = &mut self.blocks[self.current.0];
+
+        if current.is_none() {
+            *current = Some(terminator);
+        }
+    }
+
+    fn temporary(&mut self, ty: Type) -> Temporary {
+        self.temporaries.push(ty);
+        Temporary(self.temporaries.len() - 1)
+    }
+
+    fn operand_type(&self, operand: &Operand) -> Type {
+        match operand {
+            Operand::Constant(c) => c.ty.clone(),
+            Operand::Temporary(t) => self.temporaries[t.0].clone(),
+        }
+    }
+
+    /// The type the type checker gave an expression.
+    fn type_of(&mut self, expression: &st::Expression) -> Type {
+        match self.types.type_of(expression) {
+            Some(ty) => match self.items.convert(ty) {
+                Some(ty) => ty,
+                None => {
+                    self.error(
+                        "Unable to determine the type of this expression",
+                        expression.span(),
+                        "the type is unknown",
+                    );
+                    Type::Bool
+                },
+            },
+            None => {
+                self.error(
+                    "Unable to determine the type of this expression",
+                    expression.span(),
+                    "the expression wasn't type checked",
+                );
+                Type::Bool
+            },
+        }
+    }
+
+    /// The type both sides of a comparison are converted to before being
+    /// compared.
+    fn common_type(
+        &mut self,
+        left: &st::Expression,
+        right: &st::Expression,
+    ) -> Type {
+        match (self.types.type_of(left), self.types.type_of(right)) {
+            (Some(l), Some(r)) if l.widens_to(r) => self.type_of(right),
+            _ => self.type_of(left),
+        }
+    }
+
+    /// Make sure an operand has the desired type, inserting a conversion if
+    /// necessary.
+    fn coerce(&mut self, operand: Operand, ty: &Type) -> Operand {
+        if self.operand_type(&operand) == *ty {
+            return operand;
+        }
+
+        if let Operand::Constant(ref constant) = operand {
+            if let Some(converted) = convert_constant(constant, ty) {
+                return Operand::Constant(converted);
+            }
+        }
+
+        let destination = self.temporary(ty.clone());
+        self.emit(Instruction::Convert {
+            destination,
+            value: operand,
+        });
+        Operand::Temporary(destination)
+    }
+
+    fn local(&self, name: &st::Identifier) -> Option<(Entity, Type)> {
+        self.locals.get(&key(name)).cloned()
+    }
+
+    fn block(&mut self, block: &st::Block) {
+        for statement in &block.statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &st::Statement) {
+        match statement {
+            st::Statement::Assignment(a) => {
+                self.assignment(a);
+            },
+            st::Statement::Repeat(r) => self.repeat(r),
+            st::Statement::Conditional(c) => self.conditional(c),
+            st::Statement::While(w) => self.while_loop(w),
+            st::Statement::For(f) => self.for_loop(f),
+            st::Statement::Case(c) => self.case(c),
+            st::Statement::Call(c) => self.call_statement(c),
+            st::Statement::Exit(e) => self.exit(e),
+        }
+    }
+
+    /// Store a value in a variable, returning the value that was stored.
+    fn assignment(&mut self, assignment: &st::Assignment) -> Operand {
+        let value = self.expression(&assignment.value);
+
+        match self.local(&assignment.variable) {
+            Some((variable, ty)) => {
+                let value = self.coerce(value, &ty);
+                self.emit(Instruction::Store {
+                    destination: Place::Variable(variable),
+                    value: value.clone(),
+                });
+                value
+            },
+            None => {
+                self.error(
+                    "Unknown variable",
+                    assignment.variable.span,
+                    "only variables can be assigned to",
+                );
+                value
+            },
+        }
+    }
+
+    fn condition(&mut self, condition: &st::Expression) -> Operand {
+        let value = self.expression(condition);
+        self.coerce(value, &Type::Bool)
+    }
+
+    fn conditional(&mut self, conditional: &st::Conditional) {
+        let end = self.new_block();
+        let branches = std::iter::once(&conditional.true_branch)
+            .chain(&conditional.else_if_branches);
+
+        for branch in branches {
+            let condition = self.condition(&branch.condition);
+            let then = self.new_block();
+            let otherwise = self.new_block();
+            self.terminate(Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            });
+
+            self.switch_to(then);
+            self.block(&branch.block);
+            self.terminate(Terminator::Goto(end));
+
+            self.switch_to(otherwise);
+        }
+
+        if let Some(ref else_branch) = conditional.else_branch {
+            self.block(else_branch);
+        }
+        self.terminate(Terminator::Goto(end));
+        self.switch_to(end);
+    }
+
+    fn loop_body(&mut self, body: &st::Block, exit: BlockId) {
+        self.loop_exits.push(exit);
+        self.block(body);
+        self.loop_exits.pop();
+    }
+
+    fn while_loop(&mut self, while_loop: &st::While) {
+        let header = self.new_block();
+        let body = self.new_block();
+        let exit = self.new_block();
+
+        self.terminate(Terminator::Goto(header));
+        self.switch_to(header);
+        let condition = self.condition(&while_loop.condition);
+        self.terminate(Terminator::Branch {
+            condition,
+            then: body,
+            otherwise: exit,
+        });
+
+        self.switch_to(body);
+        self.loop_body(&while_loop.body, exit);
+        self.terminate(Terminator::Goto(header));
+
+        self.switch_to(exit);
+    }
+
+    fn repeat(&mut self, repeat: &st::Repeat) {
+        let body = self.new_block();
+        let exit = self.new_block();
+
+        self.terminate(Terminator::Goto(body));
+        self.switch_to(body);
+        self.loop_body(&repeat.block, exit);
+
+        // the loop stops once the value assigned in the UNTIL clause is true
+        let value = self.assignment(&repeat.condition);
+        let condition = self.coerce(value, &Type::Bool);
+        self.terminate(Terminator::Branch {
+            condition,
+            then: exit,
+            otherwise: body,
+        });
+
+        self.switch_to(exit);
+    }
+
+    fn for_loop(&mut self, for_loop: &st::For) {
+        let (counter, ty) = match self.local(&for_loop.variable) {
+            Some(local) => local,
+            None => {
+                self.error(
+                    "Unknown variable",
+                    for_loop.variable.span,
+                    "FOR loops need a variable to use as a counter",
+                );
+                return;
+            },
+        };
+        let counter = Place::Variable(counter);
+
+        let start = self.expression(&for_loop.start);
+        let start = self.coerce(start, &ty);
+        self.emit(Instruction::Store {
+            destination: counter.clone(),
+            value: start,
+        });
+
+        // the end and step are only evaluated once
+        let end = self.expression(&for_loop.end);
+        let end = self.coerce(end, &ty);
+        let step = match for_loop.step {
+            Some(ref step) => self.expression(step),
+            None => Operand::Constant(Constant::new(
+                ConstantValue::Integer(1),
+                ty.clone(),
+            )),
+        };
+        let step = self.coerce(step, &ty);
+
+        let header = self.new_block();
+        let body = self.new_block();
+        let exit = self.new_block();
+
+        self.terminate(Terminator::Goto(header));
+        self.switch_to(header);
+        let current = self.load(counter.clone(), ty.clone());
+
+        let comparison = match step {
+            Operand::Constant(Constant {
+                value: ConstantValue::Integer(i),
+                ..
+            }) if i < 0 => Some(BinaryOp::GreaterThanOrEqual),
+            Operand::Constant(_) => Some(BinaryOp::LessThanOrEqual),
+            Operand::Temporary(_) => None,
+        };
+
+        match comparison {
+            Some(op) => {
+                let condition = self.binary(op, current, end, Type::Bool);
+                self.terminate(Terminator::Branch {
+                    condition,
+                    then: body,
+                    otherwise: exit,
+                });
+            },
+            None => {
+                // we only know which way to count at runtime
+                let zero = Operand::Constant(Constant::new(
+                    ConstantValue::Integer(0),
+                    ty.clone(),
+                ));
+                let counting_up = self.binary(
+                    BinaryOp::GreaterThanOrEqual,
+                    step.clone(),
+                    zero,
+                    Type::Bool,
+                );
+                let up = self.new_block();
+                let down = self.new_block();
+                self.terminate(Terminator::Branch {
+                    condition: counting_up,
+                    then: up,
+                    otherwise: down,
+                });
+
+                let directions = [
+                    (up, BinaryOp::LessThanOrEqual),
+                    (down, BinaryOp::GreaterThanOrEqual),
+                ];
+                for &(block, op) in &directions {
+                    self.switch_to(block);
+                    let condition = self.binary(
+                        op,
+                        current.clone(),
+                        end.clone(),
+                        Type::Bool,
+                    );
+                    self.terminate(Terminator::Branch {
+                        condition,
+                        then: body,
+                        otherwise: exit,
+                    });
+                }
+            },
+        }
+
+        self.switch_to(body);
+        self.loop_body(&for_loop.body, exit);
+        let current = self.load(counter.clone(), ty.clone());
+        let next = self.binary(BinaryOp::Add, current, step, ty);
+        self.emit(Instruction::Store {
+            destination: counter,
+            value: next,
+        });
+        self.terminate(Terminator::Goto(header));
+
+        self.switch_to(exit);
+    }
+
+    fn case(&mut self, case: &st::Case) {
+        let selector = self.expression(&case.selector);
+        let ty = self.type_of(&case.selector);
+        let selector = self.coerce(selector, &ty);
+        let end = self.new_block();
+
+        for branch in &case.branches {
+            let mut matched = None;
+
+            for label in &branch.labels {
+                let condition = self.case_label(label, &selector, &ty);
+                matched = Some(match matched {
+                    Some(previous) => self.binary(
+                        BinaryOp::Or,
+                        previous,
+                        condition,
+                        Type::Bool,
+                    ),
+                    None => condition,
+                });
+            }
+
+            let then = self.new_block();
+            let otherwise = self.new_block();
+            self.terminate(Terminator::Branch {
+                condition: matched.unwrap_or_else(|| {
+                    Operand::Constant(Constant::bool(false))
+                }),
+                then,
+                otherwise,
+            });
+
+            self.switch_to(then);
+            self.block(&branch.block);
+            self.terminate(Terminator::Goto(end));
+
+            self.switch_to(otherwise);
+        }
+
+        if let Some(ref else_branch) = case.else_branch {
+            self.block(else_branch);
+        }
+        self.terminate(Terminator::Goto(end));
+        self.switch_to(end);
+    }
+
+    /// Check whether the selector matches a [`st::CaseLabel`].
+    fn case_label(
+        &mut self,
+        label: &st::CaseLabel,
+        selector: &Operand,
+        ty: &Type,
+    ) -> Operand {
+        let integer = |value: i64| {
+            Operand::Constant(Constant::new(
+                ConstantValue::Integer(value.into()),
+                ty.clone(),
+            ))
+        };
+
+        match label {
+            st::CaseLabel::Integer(i) => self.binary(
+                BinaryOp::Equals,
+                selector.clone(),
+                integer(i.value),
+                Type::Bool,
+            ),
+            st::CaseLabel::Range(range) => {
+                let above = self.binary(
+                    BinaryOp::GreaterThanOrEqual,
+                    selector.clone(),
+                    integer(range.start.value),
+                    Type::Bool,
+                );
+                let below = self.binary(
+                    BinaryOp::LessThanOrEqual,
+                    selector.clone(),
+                    integer(range.end.value),
+                    Type::Bool,
+                );
+                self.binary(BinaryOp::And, above, below, Type::Bool)
+            },
+            st::CaseLabel::Enumerated(name) => {
+                match self.items.enumerated_value(ty, &name.value) {
+                    Some(value) => self.binary(
+                        BinaryOp::Equals,
+                        selector.clone(),
+                        Operand::Constant(value),
+                        Type::Bool,
+                    ),
+                    None => {
+                        self.error(
+                            "Unknown enumerated value",
+                            name.span,
+                            "the selector has no value with this name",
+                        );
+                        Operand::Constant(Constant::bool(false))
+                    },
+                }
+            },
+        }
+    }
+
+    fn exit(&mut self, exit: &st::Exit) {
+        match self.loop_exits.last() {
+            Some(&target) => {
+                self.terminate(Terminator::Goto(target));
+                // anything after the EXIT is unreachable
+                let unreachable = self.new_block();
+                self.switch_to(unreachable);
+            },
+            None => self.error(
+                "EXIT outside of a loop",
+                exit.span,
+                "EXIT can only be used inside a FOR, WHILE or REPEAT",
+            ),
+        }
+    }
+
+    fn load(&mut self, source: Place, ty: Type) -> Operand {
+        let destination = self.temporary(ty);
+        self.emit(Instruction::Load {
+            destination,
+            source,
+        });
+        Operand::Temporary(destination)
+    }
+
+    fn binary(
+        &mut self,
+        op: BinaryOp,
+        left: Operand,
+        right: Operand,
+        ty: Type,
+    ) -> Operand {
+        let destination = self.temporary(ty);
+        self.emit(Instruction::Binary {
+            destination,
+            op,
+            left,
+            right,
+        });
+        Operand::Temporary(destination)
+    }
+
+    fn expression(&mut self, expression: &st::Expression) -> Operand {
+        match expression {
+            st::Expression::Variable(name) => self.variable(name, expression),
+            st::Expression::Literal(literal) => match literal_constant(literal)
+            {
+                Some(constant) => Operand::Constant(constant),
+                None => {
+                    self.error(
+                        "Unsupported literal",
+                        literal.span(),
+                        "this literal can't be translated",
+                    );
+                    Operand::Constant(Constant::bool(false))
+                },
+            },
+            st::Expression::BinaryExpression(b) => {
+                self.binary_expression(b, expression)
+            },
+            st::Expression::UnaryExpression(u) => {
+                self.unary_expression(u, expression)
+            },
+            st::Expression::Call(call) => {
+                let ty = self.type_of_call(call, Some(expression));
+                self.call(call, ty)
+            },
+            st::Expression::MemberAccess(_) => {
+                let place = self.place(expression);
+                let ty = self.type_of(expression);
+                self.load(place, ty)
+            },
+        }
+    }
+
+    fn variable(
+        &mut self,
+        name: &st::Identifier,
+        expression: &st::Expression,
+    ) -> Operand {
+        if let Some((variable, ty)) = self.local(name) {
+            return self.load(Place::Variable(variable), ty);
+        }
+
+        // otherwise it must be an enumerated value
+        let ty = self.type_of(expression);
+        match self.items.enumerated_value(&ty, &name.value) {
+            Some(value) => Operand::Constant(value),
+            None => {
+                self.error(
+                    "Unknown variable",
+                    name.span,
+                    "not a variable or enumerated value",
+                );
+                Operand::Constant(Constant::bool(false))
+            },
+        }
+    }
+
+    /// Get something which can be read from or written to.
+    fn place(&mut self, expression: &st::Expression) -> Place {
+        match expression {
+            st::Expression::Variable(name) => {
+                if let Some((variable, _)) = self.local(name) {
+                    return Place::Variable(variable);
+                }
+            },
+            st::Expression::MemberAccess(access) => {
+                return Place::Member {
+                    base: Box::new(self.place(&access.value)),
+                    member: key(&access.member),
+                };
+            },
+            _ => {},
+        }
+
+        match self.expression(expression) {
+            Operand::Temporary(temporary) => Place::Temporary(temporary),
+            Operand::Constant(constant) => {
+                self.error(
+                    "Expected a variable",
+                    expression.span(),
+                    "constants don't have any members",
+                );
+                Place::Temporary(self.temporary(constant.ty))
+            },
+        }
+    }
+
+    fn binary_expression(
+        &mut self,
+        binary: &st::BinaryExpression,
+        expression: &st::Expression,
+    ) -> Operand {
+        let op = binary_op(binary.op);
+        let left = self.expression(&binary.left);
+        let right = self.expression(&binary.right);
+        let ty = self.type_of(expression);
+
+        let scaling_a_duration = ty == Type::Time
+            && (op == BinaryOp::Multiply || op == BinaryOp::Divide)
+            && self.operand_type(&right) != Type::Time;
+
+        let (left, right) = if scaling_a_duration {
+            (left, right)
+        } else {
+            let operands = if op.is_comparison() {
+                self.common_type(&binary.left, &binary.right)
+            } else {
+                ty.clone()
+            };
+            (self.coerce(left, &operands), self.coerce(right, &operands))
+        };
+
+        self.binary(op, left, right, ty)
+    }
+
+    fn unary_expression(
+        &mut self,
+        unary: &st::UnaryExpression,
+        expression: &st::Expression,
+    ) -> Operand {
+        let value = self.expression(&unary.value);
+        let ty = self.type_of(expression);
+
+        let op = match unary.op {
+            st::UnaryOp::Plus => return value,
+            st::UnaryOp::Not => UnaryOp::Not,
+            st::UnaryOp::Negate => {
+                if let Operand::Constant(ref constant) = value {
+                    if let Some(negated) = negate(constant.clone()) {
+                        return Operand::Constant(negated);
+                    }
+                }
+                UnaryOp::Negate
+            },
+        };
+
+        let value = self.coerce(value, &ty);
+        let destination = self.temporary(ty);
+        self.emit(Instruction::Unary {
+            destination,
+            op,
+            value,
+        });
+        Operand::Temporary(destination)
+    }
+
+    /// The value returned by a function call.
+    fn type_of_call(
+        &mut self,
+        call: &st::Call,
+        expression: Option<&st::Expression>,
+    ) -> Type {
+        if let Some(&function) = self.items.functions.get(&key(&call.function))
+        {
+            return self.items.return_types[&function].clone();
+        }
+
+        let known = expression
+            .and_then(|e| self.types.type_of(e))
+            .and_then(|ty| self.items.convert(ty));
+        if let Some(ty) = known {
+            return ty;
+        }
+
+        // some standard functions are generic over their arguments, so
+        // the type checker doesn't know what they return
+        let name = key(&call.function);
+        let arguments: Vec<_> = call
+            .arguments
+            .iter()
+            .filter_map(|arg| match arg {
+                st::Argument::Positional(value) => Some(value),
+                st::Argument::Input(input) => Some(&input.value),
+                st::Argument::Output(_) => None,
+            })
+            .collect();
+
+        let generic_argument = match name.as_str() {
+            "TRUNC" => return Type::DInt,
+            // the first argument is the selector
+            "SEL" | "MUX" => arguments.get(1),
+            _ => arguments.first(),
+        };
+
+        match generic_argument {
+            Some(argument) => self.type_of(argument),
+            None => {
+                self.error(
+                    "Unable to determine the type of this expression",
+                    call.span,
+                    "the function's return type is unknown",
+                );
+                Type::Bool
+            },
+        }
+    }
+
+    fn call_statement(&mut self, call: &st::Call) {
+        match self.local(&call.function) {
+            Some((instance, ty)) => {
+                self.call_function_block(call, Place::Variable(instance), &ty)
+            },
+            None => {
+                let ty = self.type_of_call(call, None);
+                // the return value is thrown away
+                self.call(call, ty);
+            },
+        }
+    }
+
+    fn call_function_block(
+        &mut self,
+        call: &st::Call,
+        instance: Place,
+        ty: &Type,
+    ) {
+        let parameters = match ty {
+            Type::Derived(fb) => self.items.parameters.get(fb),
+            _ => None,
+        };
+        let mut positional = parameters
+            .into_iter()
+            .flatten()
+            .filter(|p| p.input)
+            .map(|p| (p.name.clone(), p.ty.clone()));
+        let parameter_type = |name: &str| {
+            parameters
+                .and_then(|ps| ps.iter().find(|p| p.name == name))
+                .map(|p| p.ty.clone())
+        };
+
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+
+        for argument in &call.arguments {
+            match argument {
+                st::Argument::Positional(value) => match positional.next() {
+                    Some((name, ty)) => {
+                        let value = self.expression(value);
+                        inputs.push((name, self.coerce(value, &ty)));
+                    },
+                    None => self.error(
+                        "Unnamed argument",
+                        value.span(),
+                        "arguments to this function block must be named",
+                    ),
+                },
+                st::Argument::Input(input) => {
+                    let name = key(&input.name);
+                    let value = self.expression(&input.value);
+                    let value = match parameter_type(&name) {
+                        Some(ty) => self.coerce(value, &ty),
+                        None => value,
+                    };
+                    inputs.push((name, value));
+                },
+                st::Argument::Output(output) => {
+                    match self.local(&output.variable) {
+                        Some((variable, _)) => outputs.push((
+                            key(&output.name),
+                            Place::Variable(variable),
+                        )),
+                        None => self.error(
+                            "Unknown variable",
+                            output.variable.span,
+                            "outputs must be copied into a variable",
+                        ),
+                    }
+                },
+            }
+        }
+
+        self.emit(Instruction::CallFunctionBlock {
+            instance,
+            inputs,
+            outputs,
+        });
+    }
+
+    fn call(&mut self, call: &st::Call, ty: Type) -> Operand {
+        let name = key(&call.function);
+
+        let (function, arguments) = match self.items.functions.get(&name) {
+            Some(&function) => {
+                let parameters = self
+                    .items
+                    .parameters
+                    .get(&function)
+                    .map(|ps| ps.as_slice())
+                    .unwrap_or_default();
+                let arguments = self.function_arguments(call, parameters);
+                (Callee::Function(function), arguments)
+            },
+            None => {
+                let arguments = self.standard_arguments(call, &name, &ty);
+                (Callee::Standard(name), arguments)
+            },
+        };
+
+        let destination = self.temporary(ty);
+        self.emit(Instruction::Call {
+            destination,
+            function,
+            arguments,
+        });
+        Operand::Temporary(destination)
+    }
+
+    /// Match each argument up with the corresponding input, using the
+    /// default value for any which weren't provided.
+    fn function_arguments(
+        &mut self,
+        call: &st::Call,
+        parameters: &[Parameter],
+    ) -> Vec<Operand> {
+        let inputs: Vec<_> = parameters.iter().filter(|p| p.input).collect();
+        let mut arguments = vec![None; inputs.len()];
+        let mut next_positional = 0;
+
+        for argument in &call.arguments {
+            let (index, value) = match argument {
+                st::Argument::Positional(value) => {
+                    next_positional += 1;
+                    (next_positional - 1, value)
+                },
+                st::Argument::Input(input) => {
+                    let name = key(&input.name);
+                    match inputs.iter().position(|p| p.name == name) {
+                        Some(index) => (index, &input.value),
+                        None => continue,
+                    }
+                },
+                st::Argument::Output(output) => {
+                    self.error(
+                        "Unsupported argument",
+                        output.span,
+                        "function outputs can't be read by the caller",
+                    );
+                    continue;
+                },
+            };
+
+            if let Some(parameter) = inputs.get(index) {
+                let value = self.expression(value);
+                arguments[index] = Some(self.coerce(value, &parameter.ty));
+            }
+        }
+
+        let mut operands = Vec::new();
+        for (parameter, argument) in inputs.iter().zip(arguments) {
+            match argument
+                .or_else(|| parameter.default.clone().map(Operand::Constant))
+            {
+                Some(operand) => operands.push(operand),
+                None => self.error(
+                    "Missing argument",
+                    call.span,
+                    &format!("no value was provided for `{}`", parameter.name),
+                ),
+            }
+        }
+
+        operands
+    }
+
+    fn standard_arguments(
+        &mut self,
+        call: &st::Call,
+        name: &str,
+        ty: &Type,
+    ) -> Vec<Operand> {
+        // explicit conversions like REAL_TO_INT take their declared type
+        let conversion_input = name
+            .find("_TO_")
+            .and_then(|ix| super::elementary(&name[..ix]));
+        let generic = match name {
+            "ABS" | "MIN" | "MAX" | "LIMIT" | "MOVE" | "SQRT" | "LN"
+            | "LOG" | "EXP" | "SIN" | "COS" | "TAN" | "ASIN" | "ACOS"
+            | "ATAN" | "ADD" | "SUB" | "MUL" | "DIV" | "MOD" | "EXPT" => true,
+            _ => false,
+        };
+
+        let mut arguments = Vec::new();
+
+        for argument in &call.arguments {
+            let value = match argument {
+                st::Argument::Positional(value) => value,
+                st::Argument::Input(input) => &input.value,
+                st::Argument::Output(output) => {
+                    self.error(
+                        "Unsupported argument",
+                        output.span,
+                        "standard functions don't have outputs",
+                    );
+                    continue;
+                },
+            };
+
+            let value = self.expression(value);
+            let value = match conversion_input {
+                Some(ref input) => self.coerce(value, input),
+                None if generic => self.coerce(value, ty),
+                None => value,
+            };
+            arguments.push(value);
+        }
+
+        arguments
+    }
+}
+
+fn binary_op(op: st::BinaryOp) -> BinaryOp {
+    match op {
+        st::BinaryOp::Equals => BinaryOp::Equals,
+        st::BinaryOp::NotEquals => BinaryOp::NotEquals,
+        st::BinaryOp::LessThan => BinaryOp::LessThan,
+        st::BinaryOp::LessThanOrEqual => BinaryOp::LessThanOrEqual,
+        st::BinaryOp::GreaterThan => BinaryOp::GreaterThan,
+        st::BinaryOp::GreaterThanOrEqual => BinaryOp::GreaterThanOrEqual,
+        st::BinaryOp::Add => BinaryOp::Add,
+        st::BinaryOp::Subtract => BinaryOp::Subtract,
+        st::BinaryOp::Multiply => BinaryOp::Multiply,
+        st::BinaryOp::Divide => BinaryOp::Divide,
+        st::BinaryOp::Modulo => BinaryOp::Modulo,
+        st::BinaryOp::Exponent => BinaryOp::Exponent,
+        st::BinaryOp::And => BinaryOp::And,
+        st::BinaryOp::Xor => BinaryOp::Xor,
+        st::BinaryOp::Or => BinaryOp::Or,
+    }
+}
@@ -0,0 +1,173 @@
+use crate::mir::{Constant, Type};
+use specs::prelude::*;
+use specs_derive::Component;
+
+/// The executable part of a program, function or function block, as a
+/// control flow graph.
+///
+/// Execution starts at [`Body::ENTRY`] and continues until a block ends with
+/// [`Terminator::Return`].
+#[derive(Debug, Clone, PartialEq, Component)]
+#[storage(DenseVecStorage)]
+pub struct Body {
+    /// The type of each [`Temporary`].
+    pub temporaries: Vec<Type>,
+    pub blocks: Vec<BasicBlock>,
+}
+
+impl Body {
+    pub const ENTRY: BlockId = BlockId(0);
+
+    pub fn block(&self, id: BlockId) -> &BasicBlock { &self.blocks[id.0] }
+
+    pub fn type_of(&self, temporary: Temporary) -> &Type {
+        &self.temporaries[temporary.0]
+    }
+
+    pub fn operand_type<'a>(&'a self, operand: &'a Operand) -> &'a Type {
+        match operand {
+            Operand::Constant(constant) => &constant.ty,
+            Operand::Temporary(temporary) => self.type_of(*temporary),
+        }
+    }
+}
+
+/// The index of a [`BasicBlock`] within its [`Body`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(pub usize);
+
+/// A value which is only used while evaluating an expression.
+///
+/// Each temporary is written to exactly once.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Temporary(pub usize);
+
+/// A list of instructions which are always executed in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    pub instructions: Vec<Instruction>,
+    pub terminator: Terminator,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    /// Read a variable.
+    Load {
+        destination: Temporary,
+        source: Place,
+    },
+    /// Write to a variable.
+    Store { destination: Place, value: Operand },
+    /// Apply a binary operator, where both operands have the same type.
+    ///
+    /// The exception is multiplying or dividing a `TIME` by a number.
+    Binary {
+        destination: Temporary,
+        op: BinaryOp,
+        left: Operand,
+        right: Operand,
+    },
+    Unary {
+        destination: Temporary,
+        op: UnaryOp,
+        value: Operand,
+    },
+    /// Convert a value to the destination's type (e.g. `INT` to `REAL`).
+    Convert {
+        destination: Temporary,
+        value: Operand,
+    },
+    /// Call a function, passing the arguments in the order its inputs were
+    /// declared.
+    Call {
+        destination: Temporary,
+        function: Callee,
+        arguments: Vec<Operand>,
+    },
+    /// Set a function block's inputs, execute it, then copy its outputs
+    /// into variables.
+    CallFunctionBlock {
+        instance: Place,
+        inputs: Vec<(String, Operand)>,
+        outputs: Vec<(String, Place)>,
+    },
+}
+
+/// How control leaves a [`BasicBlock`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Goto(BlockId),
+    Branch {
+        condition: Operand,
+        then: BlockId,
+        otherwise: BlockId,
+    },
+    Return,
+}
+
+/// A value which can be used directly by an [`Instruction`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Constant(Constant),
+    Temporary(Temporary),
+}
+
+/// Something which can be read from or written to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Place {
+    /// An entity with a [`Variable`] component.
+    Variable(Entity),
+    /// A struct field or one of a function block's inputs and outputs.
+    Member { base: Box<Place>, member: String },
+    /// A temporary holding a struct or function block.
+    Temporary(Temporary),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Callee {
+    /// An entity with a [`Function`] component.
+    Function(Entity),
+    /// One of the functions from the standard library (e.g. `ABS` or
+    /// `REAL_TO_INT`).
+    Standard(String),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Exponent,
+    And,
+    Or,
+    Xor,
+    Equals,
+    NotEquals,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+}
+
+impl BinaryOp {
+    /// Does this operator produce a `BOOL`?
+    pub fn is_comparison(self) -> bool {
+        match self {
+            BinaryOp::Equals
+            | BinaryOp::NotEquals
+            | BinaryOp::LessThan
+            | BinaryOp::LessThanOrEqual
+            | BinaryOp::GreaterThan
+            | BinaryOp::GreaterThanOrEqual => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum UnaryOp {
+    Not,
+    Negate,
+}
@@ -4,23 +4,134 @@
 //!
 //! [model]: https://www.automation.com/library/articles-white-papers/coder146s-corner-the-iec-61131-3-software-model
 
+mod body;
+mod types;
+
+pub use self::{body::*, types::*};
+
 use codespan::{FileId, Span};
 use specs::prelude::*;
 use specs_derive::Component;
+use std::time::Duration;
+
+/// Register every MIR component with the [`World`].
+pub fn register(world: &mut World) {
+    world.register::<Configuration>();
+    world.register::<Resource>();
+    world.register::<Task>();
+    world.register::<ProgramInstance>();
+    world.register::<Program>();
+    world.register::<FunctionBlock>();
+    world.register::<Function>();
+    world.register::<Body>();
+    world.register::<Variable>();
+    world.register::<Address>();
+    world.register::<InitialValue>();
+    world.register::<DataType>();
+    world.register::<Name>();
+    world.register::<Location>();
+}
 
 #[derive(Debug, Clone, PartialEq, Component)]
 #[storage(HashMapStorage)]
 pub struct Configuration {
     pub resources: Vec<Entity>,
+    /// Variables declared in the configuration's `VAR_GLOBAL` blocks.
+    pub globals: Vec<Entity>,
+}
+
+#[derive(Debug, Clone, PartialEq, Component)]
+#[storage(VecStorage)]
+pub struct Resource {
+    pub tasks: Vec<Entity>,
+    /// The [`ProgramInstance`]s running on this resource.
+    pub programs: Vec<Entity>,
+    pub globals: Vec<Entity>,
+}
+
+/// A program being executed by a [`Resource`].
+#[derive(Debug, Clone, PartialEq, Component)]
+#[storage(VecStorage)]
+pub struct ProgramInstance {
+    /// The entity with a [`Program`] component.
+    pub program: Entity,
+    /// The [`Task`] which triggers this program, or `None` if it should be
+    /// executed on every cycle.
+    pub task: Option<Entity>,
 }
 
+/// A program organisation unit which may be instantiated by a
+/// [`Resource`].
+///
+/// The entity will also have a [`Body`] component.
 #[derive(Debug, Clone, PartialEq, Component)]
 #[storage(VecStorage)]
-pub struct Resource {}
+pub struct Program {
+    pub variables: Vec<Entity>,
+}
 
+/// A program organisation unit which keeps its state between calls.
+///
+/// The entity will also have a [`Body`] component.
 #[derive(Debug, Clone, PartialEq, Component)]
 #[storage(VecStorage)]
-pub struct Program {}
+pub struct FunctionBlock {
+    pub variables: Vec<Entity>,
+}
+
+/// A program organisation unit without any state.
+///
+/// The entity will also have a [`Body`] component.
+#[derive(Debug, Clone, PartialEq, Component)]
+#[storage(VecStorage)]
+pub struct Function {
+    pub variables: Vec<Entity>,
+    /// The [`Variable`] the return value is assigned to.
+    pub return_value: Entity,
+}
+
+/// A variable declared by a program organisation unit, resource or
+/// configuration.
+///
+/// The entity may also have an [`Address`] and an [`InitialValue`].
+#[derive(Debug, Clone, PartialEq, Component)]
+#[storage(VecStorage)]
+pub struct Variable {
+    pub kind: VariableKind,
+    pub ty: Type,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum VariableKind {
+    /// Declared in a `VAR` block.
+    Local,
+    Input,
+    Output,
+    Global,
+    /// A reference to a `VAR_GLOBAL` with the same name.
+    External,
+    /// The value returned from a function.
+    Return,
+}
+
+/// Where a located variable lives in the process image (e.g. `%IX4.0`).
+#[derive(Debug, Copy, Clone, PartialEq, Component)]
+#[storage(HashMapStorage)]
+pub struct Address {
+    pub area: Area,
+    /// The byte offset into the process image.
+    pub byte: usize,
+    /// The bit within the byte, for single-bit addresses.
+    pub bit: Option<u8>,
+    pub size_in_bytes: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Area {
+    Input,
+    Output,
+    Memory,
+}
 
 /// A control element for starting one or more programs.
 ///
@@ -35,11 +146,15 @@ pub struct Program {}
 #[storage(VecStorage)]
 pub struct Task {
     pub trigger: Trigger,
+    /// Lower numbers have a higher priority.
+    pub priority: u32,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Trigger {
-    Periodic,
+    /// Run the task every `interval`, where an interval of zero means it
+    /// runs on every cycle.
+    Periodic { interval: Duration },
     RisingEdge { variable: Entity },
 }
 
@@ -0,0 +1,153 @@
+use specs::prelude::*;
+use specs_derive::Component;
+use std::ops::RangeInclusive;
+
+/// The type of a value.
+///
+/// Aliases and subranges are replaced by the type they refer to, so only
+/// structs, enumerations and function blocks need to be looked up in the
+/// [`World`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Bool,
+    SInt,
+    Int,
+    DInt,
+    LInt,
+    USInt,
+    UInt,
+    UDInt,
+    ULInt,
+    Real,
+    LReal,
+    Byte,
+    Word,
+    DWord,
+    LWord,
+    Time,
+    Date,
+    TimeOfDay,
+    DateAndTime,
+    String,
+    WString,
+    Array {
+        element: Box<Type>,
+        dimensions: Vec<RangeInclusive<i64>>,
+    },
+    /// A struct, enumeration or function block, which will have either a
+    /// [`DataType`] or [`FunctionBlock`] component.
+    Derived(Entity),
+    /// One of the function blocks from the standard library (e.g. `TON`).
+    StandardFunctionBlock(String),
+}
+
+impl Type {
+    pub fn is_integer(&self) -> bool {
+        match self {
+            Type::SInt
+            | Type::Int
+            | Type::DInt
+            | Type::LInt
+            | Type::USInt
+            | Type::UInt
+            | Type::UDInt
+            | Type::ULInt => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_real(&self) -> bool {
+        match self {
+            Type::Real | Type::LReal => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_bit_string(&self) -> bool {
+        match self {
+            Type::Bool
+            | Type::Byte
+            | Type::Word
+            | Type::DWord
+            | Type::LWord => true,
+            _ => false,
+        }
+    }
+
+    /// Durations and points in time are all stored as a number of
+    /// nanoseconds.
+    pub fn is_time(&self) -> bool {
+        match self {
+            Type::Time | Type::Date | Type::TimeOfDay | Type::DateAndTime => {
+                true
+            },
+            _ => false,
+        }
+    }
+}
+
+/// The definition of a user-defined type.
+#[derive(Debug, Clone, PartialEq, Component)]
+#[storage(HashMapStorage)]
+pub enum DataType {
+    /// Another name for an existing type (e.g. `Speed : REAL` or
+    /// `Setpoints : ARRAY [1..4] OF REAL`).
+    Alias(Type),
+    /// An integer which may only hold a subset of its base type's values.
+    Subrange {
+        base: Type,
+        range: RangeInclusive<i64>,
+    },
+    Enumeration(Vec<EnumeratedValue>),
+    Struct(Vec<Field>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumeratedValue {
+    pub name: String,
+    pub value: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub ty: Type,
+    pub initial_value: Option<InitialValue>,
+}
+
+/// The value a variable, field or type starts with.
+#[derive(Debug, Clone, PartialEq, Component)]
+#[storage(HashMapStorage)]
+pub enum InitialValue {
+    Constant(Constant),
+    /// The value for each element in an array, with any repetitions already
+    /// expanded.
+    Array(Vec<InitialValue>),
+    Struct(Vec<(String, InitialValue)>),
+}
+
+/// A value which is known at compile time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constant {
+    pub value: ConstantValue,
+    pub ty: Type,
+}
+
+impl Constant {
+    pub fn new(value: ConstantValue, ty: Type) -> Constant {
+        Constant { value, ty }
+    }
+
+    pub fn bool(value: bool) -> Constant {
+        Constant::new(ConstantValue::Bool(value), Type::Bool)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstantValue {
+    Bool(bool),
+    /// An integer, bit string, enumerated value, or time.
+    Integer(i128),
+    Real(f64),
+    String(String),
+}
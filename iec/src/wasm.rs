@@ -0,0 +1,1979 @@
+//! Compile the MIR into a WebAssembly module which can be loaded by
+//! `rustmatic_wasm::Program`.
+//!
+//! Every variable lives in linear memory at an address decided when the
+//! module is compiled, so the memory's initial contents double as each
+//! variable's initial value. Programs, function blocks and functions become
+//! WASM functions which take a pointer to their variables. Functions can't
+//! be recursive, so each function gets a single, statically allocated frame.
+//!
+//! The module exports its `memory` and a `poll()` function which:
+//!
+//! 1. Asks the host for the current time
+//! 2. Reads located inputs (e.g. `start AT %IX0.1 : BOOL`) using
+//!    `wasm_read_input()`
+//! 3. Reads global variables the host may have changed, using
+//!    `wasm_variable_read_*()`
+//! 4. Executes each program instance whose task was triggered, in order of
+//!    priority
+//! 5. Writes located outputs back using `wasm_write_output()`, and global
+//!    variables using `wasm_variable_write_*()`
+//!
+//! Only global `BOOL`, `REAL` and `LREAL` variables, and integers of up to
+//! 32 bits, are shared with the host. If the source code didn't declare a
+//! configuration, every program is executed on each poll.
+//!
+//! Not all of IEC 61131-3 can be compiled yet. The following are reported as
+//! errors instead:
+//!
+//! - `STRING` and `WSTRING` variables, and the character string functions
+//!   (e.g. `LEN()` and `CONCAT()`)
+//! - The standard function blocks (e.g. `TON`, `CTU` and `R_TRIG`)
+//! - Standard functions other than the arithmetic, bitwise, comparison,
+//!   selection and type conversion functions, plus `ABS()` and `SQRT()`
+//! - Recursive functions
+
+mod body;
+mod layout;
+mod scalar;
+
+use crate::mir::{
+    Address, Area, Body, Callee, Configuration, ConstantValue, DataType,
+    Function, InitialValue, Instruction as MirInstruction, Location, Program,
+    ProgramInstance, Resource, Task, Trigger, Type, Variable, VariableKind,
+};
+use codespan::FileId;
+use layout::{align_up, name_of, Layout, Layouts};
+use parity_wasm::elements::{
+    BlockType, CodeSection, DataSection, DataSegment, ExportEntry,
+    ExportSection, External, Func, FuncBody, FunctionSection, FunctionType,
+    ImportEntry, ImportSection, InitExpr, Instruction, Instructions, Internal,
+    Local, MemorySection, MemoryType, Module, Section, Type as WasmType,
+    TypeSection, ValueType,
+};
+use rustmatic_structured_text::{Diagnostic, Label};
+use scalar::Scalar;
+use specs::prelude::*;
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+
+/// Compile a [`Configuration`] and every program organisation unit in the
+/// [`World`] to a WebAssembly module.
+pub fn compile(
+    world: &World,
+    configuration: Entity,
+) -> Result<Vec<u8>, Vec<(FileId, Diagnostic)>> {
+    let mut compiler = Compiler::new(world);
+
+    compiler.declare_functions();
+    compiler.check_for_recursion();
+    compiler.check_externals();
+    let instances = compiler.instantiate(configuration);
+    let bodies = compiler.compile_bodies();
+    let poll = compiler.poll(&instances);
+
+    if compiler.diagnostics.is_empty() {
+        Ok(compiler.assemble(poll, bodies))
+    } else {
+        Err(compiler.diagnostics)
+    }
+}
+
+/// The functions imported from the host's `env` module, in order.
+///
+/// Every host function returns a status code.
+const IMPORTS: &[(&str, &[ValueType])] = &[
+    (
+        "wasm_read_input",
+        &[ValueType::I32, ValueType::I32, ValueType::I32],
+    ),
+    (
+        "wasm_write_output",
+        &[ValueType::I32, ValueType::I32, ValueType::I32],
+    ),
+    ("wasm_current_time", &[ValueType::I32, ValueType::I32]),
+    ("wasm_log", &[ValueType::I32; 6]),
+    (
+        "wasm_variable_read_boolean",
+        &[ValueType::I32, ValueType::I32, ValueType::I32],
+    ),
+    (
+        "wasm_variable_read_int",
+        &[ValueType::I32, ValueType::I32, ValueType::I32],
+    ),
+    (
+        "wasm_variable_read_double",
+        &[ValueType::I32, ValueType::I32, ValueType::I32],
+    ),
+    (
+        "wasm_variable_write_boolean",
+        &[ValueType::I32, ValueType::I32, ValueType::I32],
+    ),
+    (
+        "wasm_variable_write_int",
+        &[ValueType::I32, ValueType::I32, ValueType::I32],
+    ),
+    (
+        "wasm_variable_write_double",
+        &[ValueType::I32, ValueType::I32, ValueType::F64],
+    ),
+];
+
+const WASM_READ_INPUT: u32 = 0;
+const WASM_WRITE_OUTPUT: u32 = 1;
+const WASM_CURRENT_TIME: u32 = 2;
+const WASM_LOG: u32 = 3;
+const WASM_VARIABLE_READ_BOOLEAN: u32 = 4;
+const WASM_VARIABLE_READ_INT: u32 = 5;
+const WASM_VARIABLE_READ_DOUBLE: u32 = 6;
+const WASM_VARIABLE_WRITE_BOOLEAN: u32 = 7;
+const WASM_VARIABLE_WRITE_INT: u32 = 8;
+const WASM_VARIABLE_WRITE_DOUBLE: u32 = 9;
+const POLL: u32 = 10;
+/// `memcpy(dest, src, len)`.
+const MEMCPY: u32 = 11;
+const FIRST_POU: u32 = 12;
+
+const WASM_UNKNOWN_VARIABLE: i32 = 3;
+const LOG_ERROR: i32 = 0;
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+const PAGE_SIZE: usize = 64 * 1024;
+
+/// The initial contents of linear memory.
+#[derive(Debug)]
+struct Memory {
+    bytes: Vec<u8>,
+    strings: HashMap<String, u32>,
+}
+
+impl Memory {
+    fn new() -> Memory {
+        Memory {
+            // keep address 0 free so it can never be a valid pointer
+            bytes: vec![0; 8],
+            strings: HashMap::new(),
+        }
+    }
+
+    fn allocate(&mut self, size: u32, align: u32) -> u32 {
+        let address = align_up(self.bytes.len() as u32, align.max(1));
+        self.bytes.resize((address + size) as usize, 0);
+        address
+    }
+
+    fn write(&mut self, address: u32, data: &[u8]) {
+        let start = address as usize;
+        self.bytes[start..start + data.len()].copy_from_slice(data);
+    }
+
+    fn copy(&mut self, from: u32, to: u32, len: u32) {
+        let from = from as usize;
+        self.bytes
+            .copy_within(from..from + len as usize, to as usize);
+    }
+
+    /// Store a string, returning its address and length.
+    fn string(&mut self, text: &str) -> (u32, u32) {
+        let len = text.len() as u32;
+
+        if let Some(&address) = self.strings.get(text) {
+            return (address, len);
+        }
+
+        let address = self.allocate(len, 1);
+        self.write(address, text.as_bytes());
+        self.strings.insert(text.to_string(), address);
+        (address, len)
+    }
+}
+
+/// Where a function's variables are stored while it runs.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Frame {
+    address: u32,
+    /// A copy of the frame's initial contents, used to reset the frame
+    /// before each call.
+    initial_values: u32,
+    size: u32,
+}
+
+/// A program which will be executed by `poll()`.
+#[derive(Debug, Clone, PartialEq)]
+struct Instance {
+    program: Entity,
+    /// Where the program's variables are stored.
+    address: u32,
+    task: Option<Entity>,
+}
+
+struct Compiler<'w> {
+    world: &'w World,
+    layouts: Layouts,
+    memory: Memory,
+    /// The WASM function for each program organisation unit.
+    functions: HashMap<Entity, u32>,
+    frames: HashMap<Entity, Frame>,
+    /// The address of every global variable.
+    globals: HashMap<Entity, u32>,
+    /// Global variables, keyed by the uppercase version of their name.
+    globals_by_name: HashMap<String, Vec<Entity>>,
+    /// Space for the host to write the results of a function call.
+    scratch: u32,
+    /// The time at the start of the current poll, in nanoseconds.
+    now: u32,
+    diagnostics: Vec<(FileId, Diagnostic)>,
+}
+
+impl<'w> Compiler<'w> {
+    fn new(world: &'w World) -> Compiler<'w> {
+        let mut memory = Memory::new();
+        let scratch = memory.allocate(16, 8);
+        let now = memory.allocate(8, 8);
+
+        Compiler {
+            world,
+            layouts: Layouts::default(),
+            memory,
+            functions: HashMap::new(),
+            frames: HashMap::new(),
+            globals: HashMap::new(),
+            globals_by_name: HashMap::new(),
+            scratch,
+            now,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn error(&mut self, entity: Entity, message: &str, label: &str) {
+        let location = self
+            .world
+            .read_storage::<Location>()
+            .get(entity)
+            .cloned()
+            .expect("The frontend gives every item a location");
+
+        self.diagnostics.push((
+            location.file,
+            Diagnostic::error(message, Label::new(location.span, label)),
+        ));
+    }
+
+    fn layout(
+        &mut self,
+        entity: Entity,
+        ty: &Type,
+    ) -> Option<std::rc::Rc<Layout>> {
+        match self.layouts.of_type(self.world, ty) {
+            Ok(layout) => Some(layout),
+            Err(reason) => {
+                self.error(entity, "Unsupported type", &reason);
+                None
+            },
+        }
+    }
+
+    /// Give every program organisation unit a WASM function, allocating
+    /// frames for functions.
+    fn declare_functions(&mut self) {
+        let pous: Vec<Entity> =
+            (&self.world.entities(), &self.world.read_storage::<Body>())
+                .join()
+                .map(|(entity, _)| entity)
+                .collect();
+
+        for (index, &pou) in pous.iter().enumerate() {
+            self.functions.insert(pou, FIRST_POU + index as u32);
+
+            if self.world.read_storage::<Function>().contains(pou) {
+                self.allocate_frame(pou);
+            }
+        }
+    }
+
+    fn allocate_frame(&mut self, function: Entity) {
+        let layout = match self.layout(function, &Type::Derived(function)) {
+            Some(layout) => layout,
+            None => return,
+        };
+
+        let address = self.memory.allocate(layout.size, layout.align);
+        self.initialize(address, &Type::Derived(function), None);
+        let initial_values = self.memory.allocate(layout.size, layout.align);
+        self.memory.copy(address, initial_values, layout.size);
+
+        self.frames.insert(
+            function,
+            Frame {
+                address,
+                initial_values,
+                size: layout.size,
+            },
+        );
+    }
+
+    /// IEC 61131-3 doesn't allow recursion, and a function's variables are
+    /// stored in a single frame.
+    fn check_for_recursion(&mut self) {
+        let mut calls: HashMap<Entity, Vec<Entity>> = HashMap::new();
+
+        for (entity, body, _) in (
+            &self.world.entities(),
+            &self.world.read_storage::<Body>(),
+            &self.world.read_storage::<Function>(),
+        )
+            .join()
+        {
+            let callees = body
+                .blocks
+                .iter()
+                .flat_map(|block| &block.instructions)
+                .filter_map(|instruction| match instruction {
+                    MirInstruction::Call {
+                        function: Callee::Function(callee),
+                        ..
+                    } => Some(*callee),
+                    _ => None,
+                });
+            calls.insert(entity, callees.collect());
+        }
+
+        let mut functions: Vec<Entity> = calls.keys().copied().collect();
+        functions.sort();
+
+        for function in functions {
+            if calls_itself(&calls, function) {
+                self.error(
+                    function,
+                    "Recursive functions aren't supported",
+                    "this function ends up calling itself",
+                );
+            }
+        }
+    }
+
+    /// Make sure every `VAR_EXTERNAL` refers to exactly one global variable.
+    fn check_externals(&mut self) {
+        self.declare_globals();
+
+        let externals: Vec<(Entity, String)> = (
+            &self.world.entities(),
+            &self.world.read_storage::<Variable>(),
+        )
+            .join()
+            .filter(|(_, variable)| variable.kind == VariableKind::External)
+            .map(|(entity, _)| (entity, name_of(self.world, entity)))
+            .collect();
+
+        for (external, name) in externals {
+            match self.globals_by_name.get(&name.to_uppercase()) {
+                Some(globals) if globals.len() == 1 => {},
+                Some(_) => self.error(
+                    external,
+                    "Ambiguous global variable",
+                    &format!("more than one global is called \"{}\"", name),
+                ),
+                None => self.error(
+                    external,
+                    "Unknown global variable",
+                    &format!("there is no VAR_GLOBAL called \"{}\"", name),
+                ),
+            }
+        }
+    }
+
+    fn declare_globals(&mut self) {
+        let globals: Vec<Entity> = (
+            &self.world.entities(),
+            &self.world.read_storage::<Variable>(),
+        )
+            .join()
+            .filter(|(_, variable)| variable.kind == VariableKind::Global)
+            .map(|(entity, _)| entity)
+            .collect();
+
+        for global in globals {
+            let name = name_of(self.world, global).to_uppercase();
+            self.globals_by_name.entry(name).or_default().push(global);
+        }
+    }
+
+    /// The address of the global variable a `VAR_EXTERNAL` refers to.
+    fn external(&self, variable: Entity) -> Option<u32> {
+        let name = name_of(self.world, variable).to_uppercase();
+
+        match self.globals_by_name.get(&name).map(Vec::as_slice) {
+            Some(&[global]) => self.globals.get(&global).copied(),
+            _ => None,
+        }
+    }
+
+    /// Allocate memory for global variables and each program instance.
+    fn instantiate(&mut self, configuration: Entity) -> Vec<Instance> {
+        let configuration = self
+            .world
+            .read_storage::<Configuration>()
+            .get(configuration)
+            .cloned()
+            .expect("The configuration entity should have a Configuration");
+
+        let mut globals = configuration.globals.clone();
+        let mut instances = Vec::new();
+
+        for &resource in &configuration.resources {
+            let resource = match self
+                .world
+                .read_storage::<Resource>()
+                .get(resource)
+                .cloned()
+            {
+                Some(resource) => resource,
+                None => continue,
+            };
+            globals.extend(resource.globals);
+
+            for instance in resource.programs {
+                if let Some(ProgramInstance { program, task }) = self
+                    .world
+                    .read_storage::<ProgramInstance>()
+                    .get(instance)
+                    .cloned()
+                {
+                    instances.push((program, task));
+                }
+            }
+        }
+
+        if configuration.resources.is_empty() {
+            let programs = self.world.read_storage::<Program>();
+            instances.extend(
+                (&self.world.entities(), &programs)
+                    .join()
+                    .map(|(entity, _)| (entity, None)),
+            );
+        }
+
+        for global in globals {
+            let ty = match self.world.read_storage::<Variable>().get(global) {
+                Some(variable) => variable.ty.clone(),
+                None => continue,
+            };
+
+            if let Some(layout) = self.layout(global, &ty) {
+                let address = self.memory.allocate(layout.size, layout.align);
+                let initial_value = self
+                    .world
+                    .read_storage::<InitialValue>()
+                    .get(global)
+                    .cloned();
+                self.initialize(address, &ty, initial_value.as_ref());
+                self.globals.insert(global, address);
+            }
+        }
+
+        let mut allocated = Vec::new();
+
+        for (program, task) in instances {
+            let ty = Type::Derived(program);
+
+            if let Some(layout) = self.layout(program, &ty) {
+                let address = self.memory.allocate(layout.size, layout.align);
+                self.initialize(address, &ty, None);
+                allocated.push(Instance {
+                    program,
+                    address,
+                    task,
+                });
+            }
+        }
+
+        // programs without a task have the lowest priority
+        let tasks = self.world.read_storage::<Task>();
+        allocated.sort_by_key(|instance| {
+            instance
+                .task
+                .and_then(|task| tasks.get(task))
+                .map(|task| task.priority)
+                .unwrap_or(u32::max_value())
+        });
+
+        allocated
+    }
+
+    /// Write a value's initial value to memory.
+    fn initialize(
+        &mut self,
+        address: u32,
+        ty: &Type,
+        initial_value: Option<&InitialValue>,
+    ) {
+        if let Some(scalar) = Scalar::of(self.world, ty) {
+            let value = match initial_value {
+                Some(InitialValue::Constant(constant)) => {
+                    constant.value.clone()
+                },
+                _ => match first_enumerated_value(self.world, ty) {
+                    Some(value) => ConstantValue::Integer(value.into()),
+                    None => return,
+                },
+            };
+            self.memory.write(address, &constant_bytes(scalar, &value));
+            return;
+        }
+
+        match ty {
+            Type::Array { element, .. } => {
+                let (array, element_layout) = match (
+                    self.layouts.of_type(self.world, ty),
+                    self.layouts.of_type(self.world, element),
+                ) {
+                    (Ok(array), Ok(element)) => (array, element),
+                    _ => return,
+                };
+                if element_layout.size == 0 {
+                    return;
+                }
+
+                for i in 0..array.size / element_layout.size {
+                    let value = match initial_value {
+                        Some(InitialValue::Array(values)) => {
+                            values.get(i as usize)
+                        },
+                        _ => None,
+                    };
+                    self.initialize(
+                        address + i * element_layout.size,
+                        element,
+                        value,
+                    );
+                }
+            },
+            Type::Derived(entity) => {
+                let layout =
+                    match self.layouts.of_pou_or_struct(self.world, *entity) {
+                        Ok(layout) => layout,
+                        Err(_) => return,
+                    };
+
+                for member in &layout.members {
+                    let value = match initial_value {
+                        Some(InitialValue::Struct(values)) => values
+                            .iter()
+                            .find(|(name, _)| {
+                                name.eq_ignore_ascii_case(&member.name)
+                            })
+                            .map(|(_, value)| value.clone()),
+                        _ => None,
+                    }
+                    .or_else(|| {
+                        self.default_value(
+                            *entity,
+                            &member.name,
+                            member.variable,
+                        )
+                    });
+
+                    self.initialize(
+                        address + member.offset,
+                        &member.ty,
+                        value.as_ref(),
+                    );
+                }
+            },
+            _ => {},
+        }
+    }
+
+    /// The value a struct field or variable starts with when it isn't
+    /// overridden.
+    fn default_value(
+        &self,
+        owner: Entity,
+        name: &str,
+        variable: Option<Entity>,
+    ) -> Option<InitialValue> {
+        if let Some(variable) = variable {
+            return self
+                .world
+                .read_storage::<InitialValue>()
+                .get(variable)
+                .cloned();
+        }
+
+        match self.world.read_storage::<DataType>().get(owner) {
+            Some(DataType::Struct(fields)) => fields
+                .iter()
+                .find(|field| field.name.eq_ignore_ascii_case(name))
+                .and_then(|field| field.initial_value.clone()),
+            _ => None,
+        }
+    }
+
+    fn compile_bodies(&mut self) -> Vec<FuncBody> {
+        let mut pous: Vec<(Entity, u32)> = self
+            .functions
+            .iter()
+            .map(|(&pou, &index)| (pou, index))
+            .collect();
+        pous.sort_by_key(|&(_, index)| index);
+
+        pous.into_iter()
+            .map(|(pou, _)| {
+                let body = self
+                    .world
+                    .read_storage::<Body>()
+                    .get(pou)
+                    .cloned()
+                    .expect("Only entities with a Body have a function");
+                body::compile(self, pou, &body)
+            })
+            .collect()
+    }
+
+    fn log_error(&mut self, message: &str) -> Vec<Instruction> {
+        let (address, len) = self.memory.string(message);
+
+        vec![
+            Instruction::I32Const(LOG_ERROR),
+            // we don't have a file or line number
+            Instruction::I32Const(0),
+            Instruction::I32Const(0),
+            Instruction::I32Const(0),
+            Instruction::I32Const(address as i32),
+            Instruction::I32Const(len as i32),
+            Instruction::Call(WASM_LOG),
+            Instruction::Drop,
+        ]
+    }
+
+    /// Call a host function and log an error if it fails.
+    fn call_host(
+        &mut self,
+        function: u32,
+        message: &str,
+        instructions: &mut Vec<Instruction>,
+    ) {
+        instructions.push(Instruction::Call(function));
+        instructions.push(Instruction::If(BlockType::NoResult));
+        instructions.extend(self.log_error(message));
+        instructions.push(Instruction::End);
+    }
+
+    /// Generate the `poll()` function.
+    fn poll(&mut self, instances: &[Instance]) -> FuncBody {
+        let mut poll = Poll::default();
+
+        self.read_time(&mut poll);
+
+        let located = self.located_variables(instances);
+        for &(variable, address, ref location) in &located {
+            if location.area == Area::Input {
+                self.read_input(&mut poll, variable, address, location);
+            }
+        }
+
+        let shared = self.shared_globals();
+        for &(global, address, scalar) in &shared {
+            self.read_global(&mut poll, global, address, scalar);
+        }
+
+        let mut triggered = HashMap::new();
+        for instance in instances {
+            if let Some(task) = instance.task {
+                if let Entry::Vacant(entry) = triggered.entry(task) {
+                    entry.insert(self.check_task(&mut poll, task));
+                }
+            }
+        }
+
+        for instance in instances {
+            let index = self.functions[&instance.program];
+            let flag = instance.task.and_then(|task| triggered[&task]);
+
+            if let Some(flag) = flag {
+                poll.instructions.push(Instruction::GetLocal(flag));
+                poll.instructions.push(Instruction::If(BlockType::NoResult));
+            }
+            poll.instructions.extend(vec![
+                Instruction::I32Const(instance.address as i32),
+                Instruction::Call(index),
+            ]);
+            if flag.is_some() {
+                poll.instructions.push(Instruction::End);
+            }
+        }
+
+        for &(variable, address, ref location) in &located {
+            if location.area == Area::Output {
+                self.write_output(&mut poll, variable, address, location);
+            }
+        }
+
+        for &(global, address, scalar) in &shared {
+            self.write_global(&mut poll, global, address, scalar);
+        }
+
+        poll.instructions.push(Instruction::End);
+        FuncBody::new(
+            poll.locals
+                .into_iter()
+                .map(|ty| Local::new(1, ty))
+                .collect(),
+            Instructions::new(poll.instructions),
+        )
+    }
+
+    fn read_time(&mut self, poll: &mut Poll) {
+        let secs = self.scratch;
+        let nanos = self.scratch + 8;
+
+        poll.instructions.extend(vec![
+            Instruction::I32Const(secs as i32),
+            Instruction::I32Const(nanos as i32),
+            Instruction::Call(WASM_CURRENT_TIME),
+            Instruction::If(BlockType::NoResult),
+        ]);
+        poll.instructions
+            .extend(self.log_error("Unable to read the current time"));
+        poll.instructions.extend(vec![
+            Instruction::Else,
+            Instruction::I32Const(0),
+            Instruction::I32Const(0),
+            Instruction::I64Load(3, secs),
+            Instruction::I64Const(NANOS_PER_SECOND),
+            Instruction::I64Mul,
+            Instruction::I32Const(0),
+            Instruction::I64Load32U(2, nanos),
+            Instruction::I64Add,
+            Instruction::I64Store(3, self.now),
+            Instruction::End,
+        ]);
+    }
+
+    /// Find every variable with an `AT %...` address, along with where it
+    /// is stored.
+    fn located_variables(
+        &mut self,
+        instances: &[Instance],
+    ) -> Vec<(Entity, u32, Address)> {
+        let addresses = self.world.read_storage::<Address>();
+        let mut located = Vec::new();
+
+        let mut globals: Vec<(Entity, u32)> =
+            self.globals.iter().map(|(&e, &a)| (e, a)).collect();
+        globals.sort();
+
+        for (global, address) in globals {
+            if let Some(location) = addresses.get(global) {
+                located.push((global, address, *location));
+            }
+        }
+
+        for instance in instances {
+            let layout = match self
+                .layouts
+                .of_pou_or_struct(self.world, instance.program)
+            {
+                Ok(layout) => layout,
+                Err(_) => continue,
+            };
+
+            for member in &layout.members {
+                let location = member
+                    .variable
+                    .and_then(|variable| addresses.get(variable));
+
+                if let (Some(variable), Some(location)) =
+                    (member.variable, location)
+                {
+                    located.push((
+                        variable,
+                        instance.address + member.offset,
+                        *location,
+                    ));
+                }
+            }
+        }
+
+        located
+    }
+
+    fn read_input(
+        &mut self,
+        poll: &mut Poll,
+        variable: Entity,
+        address: u32,
+        location: &Address,
+    ) {
+        let message = format!(
+            "Unable to read {} into \"{}\"",
+            describe(location),
+            name_of(self.world, variable)
+        );
+
+        match location.bit {
+            Some(bit) => {
+                if !self.is_bool(
+                    variable,
+                    "only BOOL variables can be located at a single bit",
+                ) {
+                    return;
+                }
+
+                poll.instructions.extend(vec![
+                    Instruction::I32Const(location.byte as i32),
+                    Instruction::I32Const(self.scratch as i32),
+                    Instruction::I32Const(1),
+                    Instruction::Call(WASM_READ_INPUT),
+                    Instruction::If(BlockType::NoResult),
+                ]);
+                poll.instructions.extend(self.log_error(&message));
+                poll.instructions.extend(vec![
+                    Instruction::Else,
+                    Instruction::I32Const(address as i32),
+                    Instruction::I32Const(0),
+                    Instruction::I32Load8U(0, self.scratch),
+                    Instruction::I32Const(i32::from(bit)),
+                    Instruction::I32ShrU,
+                    Instruction::I32Const(1),
+                    Instruction::I32And,
+                    Instruction::I32Store8(0, 0),
+                    Instruction::End,
+                ]);
+            },
+            None => {
+                let size = self.size_of(variable);
+                poll.instructions.extend(vec![
+                    Instruction::I32Const(location.byte as i32),
+                    Instruction::I32Const(address as i32),
+                    Instruction::I32Const(size as i32),
+                ]);
+                self.call_host(
+                    WASM_READ_INPUT,
+                    &message,
+                    &mut poll.instructions,
+                );
+            },
+        }
+    }
+
+    fn write_output(
+        &mut self,
+        poll: &mut Poll,
+        variable: Entity,
+        address: u32,
+        location: &Address,
+    ) {
+        let message = format!(
+            "Unable to write \"{}\" to {}",
+            name_of(self.world, variable),
+            describe(location)
+        );
+
+        match location.bit {
+            Some(bit) => {
+                if !self.is_bool(
+                    variable,
+                    "only BOOL variables can be located at a single bit",
+                ) {
+                    return;
+                }
+
+                // outputs are written a byte at a time, so we need to
+                // remember the other bits
+                let image = *poll
+                    .output_image
+                    .entry(location.byte)
+                    .or_insert_with(|| self.memory.allocate(1, 1));
+                let mask = !(1_u8 << bit);
+
+                poll.instructions.extend(vec![
+                    Instruction::I32Const(image as i32),
+                    Instruction::I32Const(0),
+                    Instruction::I32Load8U(0, image),
+                    Instruction::I32Const(i32::from(mask)),
+                    Instruction::I32And,
+                    Instruction::I32Const(0),
+                    Instruction::I32Load8U(0, address),
+                    Instruction::I32Const(i32::from(bit)),
+                    Instruction::I32Shl,
+                    Instruction::I32Or,
+                    Instruction::I32Store8(0, 0),
+                    Instruction::I32Const(location.byte as i32),
+                    Instruction::I32Const(image as i32),
+                    Instruction::I32Const(1),
+                ]);
+            },
+            None => {
+                let size = self.size_of(variable);
+                poll.instructions.extend(vec![
+                    Instruction::I32Const(location.byte as i32),
+                    Instruction::I32Const(address as i32),
+                    Instruction::I32Const(size as i32),
+                ]);
+            },
+        }
+
+        self.call_host(WASM_WRITE_OUTPUT, &message, &mut poll.instructions);
+    }
+
+    fn is_bool(&mut self, variable: Entity, reason: &str) -> bool {
+        let is_bool = self
+            .world
+            .read_storage::<Variable>()
+            .get(variable)
+            .map(|v| v.ty == Type::Bool)
+            .unwrap_or(false);
+
+        if !is_bool {
+            self.error(variable, "Expected a BOOL", reason);
+        }
+
+        is_bool
+    }
+
+    fn size_of(&mut self, variable: Entity) -> u32 {
+        let ty = self
+            .world
+            .read_storage::<Variable>()
+            .get(variable)
+            .map(|v| v.ty.clone())
+            .unwrap_or(Type::Bool);
+
+        self.layouts
+            .of_type(self.world, &ty)
+            .map(|layout| layout.size)
+            .unwrap_or(0)
+    }
+
+    /// Global variables which can be read and written by the host.
+    fn shared_globals(&self) -> Vec<(Entity, u32, Scalar)> {
+        let variables = self.world.read_storage::<Variable>();
+        let addresses = self.world.read_storage::<Address>();
+
+        let mut shared: Vec<_> = self
+            .globals
+            .iter()
+            .filter(|(&global, _)| !addresses.contains(global))
+            .filter_map(|(&global, &address)| {
+                let variable = variables.get(global)?;
+                let scalar = Scalar::of(self.world, &variable.ty)?;
+
+                if scalar == Scalar::Bool
+                    || scalar.is_float()
+                    || scalar.value_type() == ValueType::I32
+                {
+                    Some((global, address, scalar))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        shared.sort_by_key(|&(global, _, _)| global);
+        shared
+    }
+
+    fn read_global(
+        &mut self,
+        poll: &mut Poll,
+        global: Entity,
+        address: u32,
+        scalar: Scalar,
+    ) {
+        let name = name_of(self.world, global);
+        let (name_address, name_len) = self.memory.string(&name);
+        let status = poll.status();
+        let (function, host_scalar) = host_variable(scalar, true);
+
+        poll.instructions.extend(vec![
+            Instruction::I32Const(name_address as i32),
+            Instruction::I32Const(name_len as i32),
+            Instruction::I32Const(self.scratch as i32),
+            Instruction::Call(function),
+            Instruction::TeeLocal(status),
+            Instruction::I32Eqz,
+            Instruction::If(BlockType::NoResult),
+            Instruction::I32Const(address as i32),
+            Instruction::I32Const(0),
+            host_scalar.load(self.scratch),
+        ]);
+        poll.instructions
+            .extend(scalar::convert(host_scalar, scalar, false));
+        poll.instructions.extend(vec![
+            scalar.store(0),
+            Instruction::Else,
+            // the host doesn't know about the variable until we write it
+            Instruction::GetLocal(status),
+            Instruction::I32Const(WASM_UNKNOWN_VARIABLE),
+            Instruction::I32Ne,
+            Instruction::If(BlockType::NoResult),
+        ]);
+        poll.instructions
+            .extend(self.log_error(&format!("Unable to read \"{}\"", name)));
+        poll.instructions.push(Instruction::End);
+        poll.instructions.push(Instruction::End);
+    }
+
+    fn write_global(
+        &mut self,
+        poll: &mut Poll,
+        global: Entity,
+        address: u32,
+        scalar: Scalar,
+    ) {
+        let name = name_of(self.world, global);
+        let (name_address, name_len) = self.memory.string(&name);
+        let (function, host_scalar) = host_variable(scalar, false);
+
+        poll.instructions.extend(vec![
+            Instruction::I32Const(name_address as i32),
+            Instruction::I32Const(name_len as i32),
+            Instruction::I32Const(0),
+            scalar.load(address),
+        ]);
+        poll.instructions
+            .extend(scalar::convert(scalar, host_scalar, false));
+        self.call_host(
+            function,
+            &format!("Unable to write \"{}\"", name),
+            &mut poll.instructions,
+        );
+    }
+
+    /// Check whether a task should be executed on this poll, returning the
+    /// local which holds the result, or `None` if the task always runs.
+    fn check_task(&mut self, poll: &mut Poll, task: Entity) -> Option<u32> {
+        let trigger = self
+            .world
+            .read_storage::<Task>()
+            .get(task)
+            .map(|task| task.trigger)?;
+
+        match trigger {
+            Trigger::Periodic { interval } if interval.as_nanos() == 0 => None,
+            Trigger::Periodic { interval } => {
+                let flag = poll.local(ValueType::I32);
+                let next = self.memory.allocate(8, 8);
+                let interval = interval.as_nanos() as i64;
+
+                poll.instructions.extend(vec![
+                    Instruction::I32Const(0),
+                    Instruction::I64Load(3, self.now),
+                    Instruction::I32Const(0),
+                    Instruction::I64Load(3, next),
+                    Instruction::I64GeS,
+                    Instruction::TeeLocal(flag),
+                    Instruction::If(BlockType::NoResult),
+                    Instruction::I32Const(0),
+                    Instruction::I32Const(0),
+                    Instruction::I64Load(3, self.now),
+                    Instruction::I64Const(interval),
+                    Instruction::I64Add,
+                    Instruction::I64Store(3, next),
+                    Instruction::End,
+                ]);
+
+                Some(flag)
+            },
+            Trigger::RisingEdge { variable } => {
+                let flag = poll.local(ValueType::I32);
+                let address = match self.globals.get(&variable).copied() {
+                    Some(address) => address,
+                    None => {
+                        self.error(
+                            task,
+                            "Invalid task trigger",
+                            "SINGLE must refer to a global variable",
+                        );
+                        return Some(flag);
+                    },
+                };
+                if !self.is_bool(variable, "SINGLE must refer to a BOOL") {
+                    return Some(flag);
+                }
+                let current = poll.local(ValueType::I32);
+                let previous = self.memory.allocate(1, 1);
+
+                poll.instructions.extend(vec![
+                    Instruction::I32Const(0),
+                    Instruction::I32Load8U(0, address),
+                    Instruction::TeeLocal(current),
+                    Instruction::I32Const(0),
+                    Instruction::I32Load8U(0, previous),
+                    Instruction::I32Eqz,
+                    Instruction::I32And,
+                    Instruction::SetLocal(flag),
+                    Instruction::I32Const(0),
+                    Instruction::GetLocal(current),
+                    Instruction::I32Store8(0, previous),
+                ]);
+
+                Some(flag)
+            },
+        }
+    }
+
+    /// Put everything together into a single WASM module.
+    fn assemble(self, poll: FuncBody, bodies: Vec<FuncBody>) -> Vec<u8> {
+        let mut types = Vec::new();
+        let mut signature = |params: &[ValueType], ret: Option<ValueType>| {
+            let ty =
+                WasmType::Function(FunctionType::new(params.to_vec(), ret));
+            match types.iter().position(|existing| *existing == ty) {
+                Some(index) => index as u32,
+                None => {
+                    types.push(ty);
+                    types.len() as u32 - 1
+                },
+            }
+        };
+
+        let imports = IMPORTS
+            .iter()
+            .map(|(name, params)| {
+                ImportEntry::new(
+                    String::from("env"),
+                    name.to_string(),
+                    External::Function(signature(params, Some(ValueType::I32))),
+                )
+            })
+            .collect();
+
+        let mut functions = vec![
+            Func::new(signature(&[], None)),
+            Func::new(signature(&[ValueType::I32; 3], None)),
+        ];
+        functions.extend(
+            bodies
+                .iter()
+                .map(|_| Func::new(signature(&[ValueType::I32], None))),
+        );
+
+        let mut code = vec![poll, memcpy()];
+        code.extend(bodies);
+
+        let pages = (self.memory.bytes.len() + PAGE_SIZE - 1) / PAGE_SIZE;
+
+        let module = Module::new(vec![
+            Section::Type(TypeSection::with_types(types)),
+            Section::Import(ImportSection::with_entries(imports)),
+            Section::Function(FunctionSection::with_entries(functions)),
+            Section::Memory(MemorySection::with_entries(vec![
+                MemoryType::new(pages.max(1) as u32, None),
+            ])),
+            Section::Export(ExportSection::with_entries(vec![
+                ExportEntry::new(
+                    String::from("poll"),
+                    Internal::Function(POLL),
+                ),
+                ExportEntry::new(String::from("memory"), Internal::Memory(0)),
+            ])),
+            Section::Code(CodeSection::with_bodies(code)),
+            Section::Data(DataSection::with_entries(vec![DataSegment::new(
+                0,
+                Some(InitExpr::new(vec![
+                    Instruction::I32Const(0),
+                    Instruction::End,
+                ])),
+                self.memory.bytes,
+            )])),
+        ]);
+
+        parity_wasm::serialize(module)
+            .expect("Serializing to a Vec<u8> should never fail")
+    }
+}
+
+/// The instructions and locals for `poll()`.
+#[derive(Debug, Default)]
+struct Poll {
+    instructions: Vec<Instruction>,
+    locals: Vec<ValueType>,
+    status: Option<u32>,
+    /// The current value of each output byte which is written a bit at a
+    /// time.
+    output_image: HashMap<usize, u32>,
+}
+
+impl Poll {
+    fn local(&mut self, ty: ValueType) -> u32 {
+        self.locals.push(ty);
+        self.locals.len() as u32 - 1
+    }
+
+    /// A local for holding a host function's return code.
+    fn status(&mut self) -> u32 {
+        match self.status {
+            Some(status) => status,
+            None => {
+                let status = self.local(ValueType::I32);
+                self.status = Some(status);
+                status
+            },
+        }
+    }
+}
+
+/// The host function used to read or write a global variable, and the
+/// [`Scalar`] it expects.
+fn host_variable(scalar: Scalar, read: bool) -> (u32, Scalar) {
+    match (scalar, read) {
+        (Scalar::Bool, true) => (WASM_VARIABLE_READ_BOOLEAN, Scalar::Bool),
+        (Scalar::Bool, false) => (WASM_VARIABLE_WRITE_BOOLEAN, Scalar::Bool),
+        (Scalar::F32, true) | (Scalar::F64, true) => {
+            (WASM_VARIABLE_READ_DOUBLE, Scalar::F64)
+        },
+        (Scalar::F32, false) | (Scalar::F64, false) => {
+            (WASM_VARIABLE_WRITE_DOUBLE, Scalar::F64)
+        },
+        (_, true) => (WASM_VARIABLE_READ_INT, Scalar::I32),
+        (_, false) => (WASM_VARIABLE_WRITE_INT, Scalar::I32),
+    }
+}
+
+/// `memcpy(dest, src, len)`, copying one byte at a time.
+fn memcpy() -> FuncBody {
+    let (dest, src, len) = (0, 1, 2);
+
+    let instructions = vec![
+        Instruction::Block(BlockType::NoResult),
+        Instruction::Loop(BlockType::NoResult),
+        Instruction::GetLocal(len),
+        Instruction::I32Eqz,
+        Instruction::BrIf(1),
+        Instruction::GetLocal(dest),
+        Instruction::GetLocal(src),
+        Instruction::I32Load8U(0, 0),
+        Instruction::I32Store8(0, 0),
+        Instruction::GetLocal(dest),
+        Instruction::I32Const(1),
+        Instruction::I32Add,
+        Instruction::SetLocal(dest),
+        Instruction::GetLocal(src),
+        Instruction::I32Const(1),
+        Instruction::I32Add,
+        Instruction::SetLocal(src),
+        Instruction::GetLocal(len),
+        Instruction::I32Const(1),
+        Instruction::I32Sub,
+        Instruction::SetLocal(len),
+        Instruction::Br(0),
+        Instruction::End,
+        Instruction::End,
+        Instruction::End,
+    ];
+
+    FuncBody::new(Vec::new(), Instructions::new(instructions))
+}
+
+fn calls_itself(
+    calls: &HashMap<Entity, Vec<Entity>>,
+    function: Entity,
+) -> bool {
+    let mut seen = HashSet::new();
+    let mut to_visit = calls.get(&function).cloned().unwrap_or_default();
+
+    while let Some(callee) = to_visit.pop() {
+        if callee == function {
+            return true;
+        }
+        if seen.insert(callee) {
+            if let Some(next) = calls.get(&callee) {
+                to_visit.extend(next);
+            }
+        }
+    }
+
+    false
+}
+
+fn first_enumerated_value(world: &World, ty: &Type) -> Option<i64> {
+    let entity = match ty {
+        Type::Derived(entity) => *entity,
+        _ => return None,
+    };
+
+    match world.read_storage::<DataType>().get(entity) {
+        Some(DataType::Enumeration(values)) => {
+            values.first().map(|value| value.value)
+        },
+        _ => None,
+    }
+}
+
+/// A constant, as it would be stored in memory.
+fn constant_bytes(scalar: Scalar, value: &ConstantValue) -> Vec<u8> {
+    let bytes = match scalar {
+        Scalar::F32 => (real(value) as f32).to_le_bytes().to_vec(),
+        Scalar::F64 => real(value).to_le_bytes().to_vec(),
+        _ => integer(value).to_le_bytes().to_vec(),
+    };
+
+    bytes[..scalar.size() as usize].to_vec()
+}
+
+fn integer(value: &ConstantValue) -> i128 {
+    match *value {
+        ConstantValue::Bool(b) => b.into(),
+        ConstantValue::Integer(i) => i,
+        ConstantValue::Real(r) => r as i128,
+        ConstantValue::String(_) => 0,
+    }
+}
+
+fn real(value: &ConstantValue) -> f64 {
+    match *value {
+        ConstantValue::Bool(b) => f64::from(u8::from(b)),
+        ConstantValue::Integer(i) => i as f64,
+        ConstantValue::Real(r) => r,
+        ConstantValue::String(_) => 0.0,
+    }
+}
+
+/// Format an address the way it'd be written in source code (e.g.
+/// `%QX1.2` or `%IW4`).
+fn describe(address: &Address) -> String {
+    let area = match address.area {
+        Area::Input => 'I',
+        Area::Output => 'Q',
+        Area::Memory => 'M',
+    };
+
+    match address.bit {
+        Some(bit) => format!("%{}X{}.{}", area, address.byte, bit),
+        None => {
+            let size = match address.size_in_bytes {
+                1 => 'B',
+                2 => 'W',
+                4 => 'D',
+                _ => 'L',
+            };
+            format!("%{}{}{}", area, size, address.byte)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend;
+    use codespan::Files;
+    use std::time::Duration;
+    use wasmi::{
+        nan_preserving_float::F64, Externals, FuncInstance, FuncRef,
+        ImportsBuilder, MemoryRef, ModuleImportResolver, ModuleInstance,
+        ModuleRef, RuntimeArgs, RuntimeValue, Signature, Trap,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Value {
+        Bool(bool),
+        Int(i32),
+        Double(f64),
+    }
+
+    /// A fake version of the `rustmatic_wasm` host.
+    #[derive(Debug, Default)]
+    struct Host {
+        memory: Option<MemoryRef>,
+        inputs: Vec<u8>,
+        outputs: Vec<u8>,
+        variables: HashMap<String, Value>,
+        now: Duration,
+        log: Vec<String>,
+    }
+
+    impl Host {
+        fn new() -> Host {
+            Host {
+                inputs: vec![0; 8],
+                outputs: vec![0; 8],
+                ..Default::default()
+            }
+        }
+
+        fn memory(&self) -> &MemoryRef { self.memory.as_ref().unwrap() }
+
+        fn string(&self, args: &RuntimeArgs, index: usize) -> String {
+            let address: u32 = args.nth(index);
+            let len: u32 = args.nth(index + 1);
+            let bytes = self.memory().get(address, len as usize).unwrap();
+            String::from_utf8(bytes).unwrap()
+        }
+
+        fn invoke(&mut self, index: u32, args: &RuntimeArgs) -> i32 {
+            match index {
+                WASM_READ_INPUT => {
+                    let (address, ptr, len): (u32, u32, u32) =
+                        (args.nth(0), args.nth(1), args.nth(2));
+                    let start = address as usize;
+                    match self.inputs.get(start..start + len as usize) {
+                        Some(bytes) => {
+                            self.memory().set(ptr, bytes).unwrap();
+                            0
+                        },
+                        None => 2,
+                    }
+                },
+                WASM_WRITE_OUTPUT => {
+                    let (address, ptr, len): (u32, u32, u32) =
+                        (args.nth(0), args.nth(1), args.nth(2));
+                    let bytes = self.memory().get(ptr, len as usize).unwrap();
+                    let start = address as usize;
+                    match self.outputs.get_mut(start..start + len as usize) {
+                        Some(outputs) => {
+                            outputs.copy_from_slice(&bytes);
+                            0
+                        },
+                        None => 2,
+                    }
+                },
+                WASM_CURRENT_TIME => {
+                    let (secs, nanos): (u32, u32) = (args.nth(0), args.nth(1));
+                    let memory = self.memory();
+                    memory.set_value(secs, self.now.as_secs() as i64).unwrap();
+                    memory.set_value(nanos, self.now.subsec_nanos()).unwrap();
+                    0
+                },
+                WASM_LOG => {
+                    let message = self.string(args, 4);
+                    self.log.push(message);
+                    0
+                },
+                WASM_VARIABLE_READ_BOOLEAN
+                | WASM_VARIABLE_READ_INT
+                | WASM_VARIABLE_READ_DOUBLE => {
+                    let name = self.string(args, 0);
+                    let ptr: u32 = args.nth(2);
+                    let memory = self.memory();
+
+                    match (index, self.variables.get(&name)) {
+                        (_, None) => 3,
+                        (WASM_VARIABLE_READ_BOOLEAN, Some(Value::Bool(b))) => {
+                            memory.set_value(ptr, u8::from(*b)).unwrap();
+                            0
+                        },
+                        (WASM_VARIABLE_READ_INT, Some(Value::Int(i))) => {
+                            memory.set_value(ptr, *i).unwrap();
+                            0
+                        },
+                        (WASM_VARIABLE_READ_DOUBLE, Some(Value::Double(d))) => {
+                            memory.set_value(ptr, *d).unwrap();
+                            0
+                        },
+                        _ => 4,
+                    }
+                },
+                WASM_VARIABLE_WRITE_BOOLEAN => {
+                    let name = self.string(args, 0);
+                    let value: i32 = args.nth(2);
+                    self.variables.insert(name, Value::Bool(value != 0));
+                    0
+                },
+                WASM_VARIABLE_WRITE_INT => {
+                    let name = self.string(args, 0);
+                    self.variables.insert(name, Value::Int(args.nth(2)));
+                    0
+                },
+                WASM_VARIABLE_WRITE_DOUBLE => {
+                    let name = self.string(args, 0);
+                    let value: F64 = args.nth(2);
+                    self.variables
+                        .insert(name, Value::Double(value.to_float()));
+                    0
+                },
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    impl Externals for Host {
+        fn invoke_index(
+            &mut self,
+            index: usize,
+            args: RuntimeArgs,
+        ) -> Result<Option<RuntimeValue>, Trap> {
+            let status = self.invoke(index as u32, &args);
+            Ok(Some(RuntimeValue::I32(status)))
+        }
+    }
+
+    struct Env;
+
+    impl ModuleImportResolver for Env {
+        fn resolve_func(
+            &self,
+            field_name: &str,
+            signature: &Signature,
+        ) -> Result<FuncRef, wasmi::Error> {
+            let index = IMPORTS
+                .iter()
+                .position(|(name, _)| *name == field_name)
+                .ok_or_else(|| {
+                    wasmi::Error::Instantiation(field_name.to_string())
+                })?;
+
+            Ok(FuncInstance::alloc_host(signature.clone(), index))
+        }
+    }
+
+    fn translate(src: &str) -> (World, Entity) {
+        let mut files = Files::new();
+        let id = files.add("main.st", src);
+        let file = rustmatic_structured_text::parse(src).unwrap();
+        let mut world = World::new();
+
+        let configuration =
+            frontend::translate_structured_text(vec![(id, file)], &mut world)
+                .unwrap();
+
+        (world, configuration)
+    }
+
+    fn compile_str(src: &str) -> Result<Vec<u8>, Vec<(FileId, Diagnostic)>> {
+        let (world, configuration) = translate(src);
+        compile(&world, configuration)
+    }
+
+    fn named(world: &World, name: &str) -> Entity {
+        (&world.entities(), &world.read_storage::<crate::mir::Name>())
+            .join()
+            .find(|(_, n)| n.0 == name)
+            .map(|(entity, _)| entity)
+            .unwrap()
+    }
+
+    fn load(src: &str) -> (ModuleRef, Host) {
+        let wasm = compile_str(src).unwrap();
+        let module = wasmi::Module::from_buffer(&wasm).unwrap();
+        let imports = ImportsBuilder::new().with_resolver("env", &Env);
+        let instance = ModuleInstance::new(&module, &imports)
+            .unwrap()
+            .assert_no_start();
+
+        let mut host = Host::new();
+        host.memory = instance
+            .export_by_name("memory")
+            .and_then(|export| export.as_memory().cloned());
+
+        (instance, host)
+    }
+
+    fn poll(instance: &ModuleRef, host: &mut Host) {
+        instance.invoke_export("poll", &[], host).unwrap();
+        assert!(host.log.is_empty(), "{:?}", host.log);
+    }
+
+    #[test]
+    fn global_variables_are_shared_with_the_host() {
+        let src = "
+            CONFIGURATION Plant
+                VAR_GLOBAL
+                    counter : DINT := 40;
+                    ratio : LREAL;
+                END_VAR
+
+                RESOURCE Cpu ON PLC_CPU
+                    PROGRAM Main : MainProg;
+                END_RESOURCE
+            END_CONFIGURATION
+
+            PROGRAM MainProg
+                VAR_EXTERNAL
+                    counter : DINT;
+                    ratio : LREAL;
+                END_VAR
+
+                counter := counter + 1;
+                ratio := DINT_TO_LREAL(counter) / 4.0;
+            END_PROGRAM
+        ";
+        let (instance, mut host) = load(src);
+
+        poll(&instance, &mut host);
+        assert_eq!(host.variables["counter"], Value::Int(41));
+        assert_eq!(host.variables["ratio"], Value::Double(10.25));
+
+        host.variables
+            .insert(String::from("counter"), Value::Int(99));
+        poll(&instance, &mut host);
+        assert_eq!(host.variables["counter"], Value::Int(100));
+    }
+
+    #[test]
+    fn copy_an_input_bit_to_an_output_bit() {
+        let src = "
+            PROGRAM main
+                VAR
+                    start AT %IX0.1 : BOOL;
+                    running AT %QX1.3 : BOOL;
+                    other AT %QX1.0 : BOOL := TRUE;
+                END_VAR
+
+                running := start;
+            END_PROGRAM
+        ";
+        let (instance, mut host) = load(src);
+
+        poll(&instance, &mut host);
+        assert_eq!(host.outputs[1], 0b0000_0001);
+
+        host.inputs[0] = 0b0000_0010;
+        poll(&instance, &mut host);
+        assert_eq!(host.outputs[1], 0b0000_1001);
+    }
+
+    #[test]
+    fn call_a_function_with_a_loop() {
+        let src = "
+            FUNCTION triangle : INT
+                VAR_INPUT
+                    n : INT;
+                END_VAR
+                VAR
+                    i : INT;
+                END_VAR
+
+                FOR i := 1 TO n DO
+                    triangle := triangle + i;
+                END_FOR;
+            END_FUNCTION
+
+            PROGRAM main
+                VAR
+                    size AT %IB0 : BYTE;
+                    total AT %QW2 : INT;
+                END_VAR
+
+                total := triangle(BYTE_TO_INT(size)) + triangle(2);
+            END_PROGRAM
+        ";
+        let (instance, mut host) = load(src);
+
+        host.inputs[0] = 4;
+        poll(&instance, &mut host);
+        assert_eq!(&host.outputs[2..4], &(10_i16 + 3).to_le_bytes());
+    }
+
+    #[test]
+    fn if_and_case_pick_a_branch() {
+        let src = "
+            PROGRAM main
+                VAR
+                    selector AT %IB0 : BYTE;
+                    sign AT %QB0 : SINT;
+                    group AT %QB1 : BYTE;
+                    n : INT;
+                END_VAR
+
+                n := BYTE_TO_INT(selector) - 5;
+
+                IF n < 0 THEN
+                    sign := -1;
+                ELSIF n = 0 THEN
+                    sign := 0;
+                ELSE
+                    sign := 1;
+                END_IF;
+
+                CASE n OF
+                    -5: group := 10;
+                    0, 1: group := 20;
+                    2..4: group := 30;
+                ELSE
+                    group := 40;
+                END_CASE;
+            END_PROGRAM
+        ";
+        let (instance, mut host) = load(src);
+
+        for &(selector, sign, group) in &[
+            (0, -1, 10),
+            (2, -1, 40),
+            (5, 0, 20),
+            (6, 1, 20),
+            (8, 1, 30),
+            (9, 1, 30),
+            (10, 1, 40),
+        ] {
+            host.inputs[0] = selector;
+            poll(&instance, &mut host);
+            assert_eq!(host.outputs[0] as i8, sign, "selector {}", selector);
+            assert_eq!(host.outputs[1], group, "selector {}", selector);
+        }
+    }
+
+    #[test]
+    fn loops_and_exit() {
+        let src = "
+            PROGRAM main
+                VAR
+                    limit AT %IB0 : BYTE;
+                    sum AT %QW0 : INT;
+                    countdown AT %QW2 : INT;
+                    repeats AT %QW4 : INT;
+                    pairs AT %QW6 : INT;
+                    i : INT;
+                    j : INT;
+                    done : BOOL;
+                END_VAR
+
+                sum := 0;
+                FOR i := 1 TO 100 DO
+                    IF i > BYTE_TO_INT(limit) THEN
+                        EXIT;
+                    END_IF;
+                    sum := sum + i;
+                END_FOR;
+
+                countdown := 0;
+                FOR i := 10 TO 1 BY -3 DO
+                    countdown := countdown * 10 + i;
+                END_FOR;
+
+                repeats := 0;
+                REPEAT
+                    repeats := repeats + 1;
+                UNTIL done := repeats >= 3;
+                END_REPEAT;
+
+                (* EXIT only leaves the innermost loop *)
+                pairs := 0;
+                i := 0;
+                WHILE i < 4 DO
+                    i := i + 1;
+                    FOR j := 1 TO 10 DO
+                        IF j > i THEN
+                            EXIT;
+                        END_IF;
+                        pairs := pairs + 1;
+                    END_FOR;
+                END_WHILE;
+            END_PROGRAM
+        ";
+        let (instance, mut host) = load(src);
+        let output = |host: &Host, i: usize| {
+            i16::from_le_bytes([host.outputs[i], host.outputs[i + 1]])
+        };
+
+        for &(limit, sum) in &[(0, 0), (4, 10), (200, 5050)] {
+            host.inputs[0] = limit;
+            poll(&instance, &mut host);
+            assert_eq!(output(&host, 0), sum, "limit {}", limit);
+        }
+
+        // 10, 7, 4, 1
+        assert_eq!(output(&host, 2), 10741);
+        assert_eq!(output(&host, 4), 3);
+        assert_eq!(output(&host, 6), 1 + 2 + 3 + 4);
+    }
+
+    #[test]
+    fn operands_are_widened_to_a_common_type() {
+        let src = "
+            CONFIGURATION Plant
+                VAR_GLOBAL
+                    small : SINT;
+                    wide : DINT;
+                    ratio : LREAL;
+                    wrapped : SINT;
+                    signed_less : BOOL;
+                    unsigned_less : BOOL;
+                END_VAR
+
+                RESOURCE Cpu ON PLC_CPU
+                    PROGRAM Main : MainProg;
+                END_RESOURCE
+            END_CONFIGURATION
+
+            PROGRAM MainProg
+                VAR_EXTERNAL
+                    small : SINT;
+                    wide : DINT;
+                    ratio : LREAL;
+                    wrapped : SINT;
+                    signed_less : BOOL;
+                    unsigned_less : BOOL;
+                END_VAR
+                VAR
+                    scale : DINT := 1000;
+                    half : REAL := 0.5;
+                    minus_one : INT := -1;
+                    unsigned : UINT := 65535;
+                    large : UDINT := 65536;
+                END_VAR
+
+                wide := small * scale;
+                ratio := half * small;
+                wrapped := small + small;
+                signed_less := minus_one < scale;
+                unsigned_less := unsigned < large;
+            END_PROGRAM
+        ";
+        let (instance, mut host) = load(src);
+
+        host.variables.insert(String::from("small"), Value::Int(-100));
+        poll(&instance, &mut host);
+
+        assert_eq!(host.variables["wide"], Value::Int(-100_000));
+        assert_eq!(host.variables["ratio"], Value::Double(-50.0));
+        assert_eq!(host.variables["wrapped"], Value::Int(56));
+        assert_eq!(host.variables["signed_less"], Value::Bool(true));
+        assert_eq!(host.variables["unsigned_less"], Value::Bool(true));
+    }
+
+    #[test]
+    fn function_blocks_remember_their_state() {
+        let src = "
+            FUNCTION_BLOCK counter
+                VAR_INPUT
+                    step : INT := 1;
+                END_VAR
+                VAR_OUTPUT
+                    count : INT;
+                END_VAR
+
+                count := count + step;
+            END_FUNCTION_BLOCK
+
+            PROGRAM main
+                VAR
+                    slow : counter;
+                    fast : counter;
+                    total AT %QW0 : INT;
+                END_VAR
+
+                slow();
+                fast(step := 10);
+                total := MAX(slow.count, fast.count) + slow.count;
+            END_PROGRAM
+        ";
+        let (instance, mut host) = load(src);
+
+        poll(&instance, &mut host);
+        poll(&instance, &mut host);
+        poll(&instance, &mut host);
+
+        assert_eq!(&host.outputs[0..2], &(30_i16 + 3).to_le_bytes());
+    }
+
+    #[test]
+    fn periodic_tasks_only_run_when_they_are_due() {
+        let src = "
+            CONFIGURATION Plant
+                VAR_GLOBAL
+                    ticks : DINT;
+                END_VAR
+
+                RESOURCE Cpu ON PLC_CPU
+                    TASK Slow(INTERVAL := T#100ms, PRIORITY := 1);
+                    PROGRAM Main WITH Slow : MainProg;
+                END_RESOURCE
+            END_CONFIGURATION
+
+            PROGRAM MainProg
+                VAR_EXTERNAL
+                    ticks : DINT;
+                END_VAR
+
+                ticks := ticks + 1;
+            END_PROGRAM
+        ";
+        let (instance, mut host) = load(src);
+
+        for millis in &[0, 50, 99, 100, 150, 250] {
+            host.now = Duration::from_millis(*millis);
+            poll(&instance, &mut host);
+        }
+
+        // triggered at 0ms, 100ms and 250ms
+        assert_eq!(host.variables["ticks"], Value::Int(3));
+    }
+
+    const SINGLE_TASK: &str = "
+        CONFIGURATION Plant
+            VAR_GLOBAL
+                start : BOOL;
+                ticks : DINT;
+            END_VAR
+
+            RESOURCE Cpu ON PLC_CPU
+                TASK OnStart(SINGLE := start);
+                PROGRAM Main WITH OnStart : MainProg;
+            END_RESOURCE
+        END_CONFIGURATION
+
+        PROGRAM MainProg
+            VAR_EXTERNAL
+                ticks : DINT;
+            END_VAR
+            VAR
+                local : BOOL;
+            END_VAR
+
+            ticks := ticks + 1;
+        END_PROGRAM
+    ";
+
+    #[test]
+    fn single_tasks_run_on_a_rising_edge() {
+        let (instance, mut host) = load(SINGLE_TASK);
+
+        for &start in &[false, true, true, false, true] {
+            host.variables.insert(String::from("start"), Value::Bool(start));
+            poll(&instance, &mut host);
+        }
+
+        assert_eq!(host.variables["ticks"], Value::Int(2));
+    }
+
+    #[test]
+    fn single_must_refer_to_a_global_bool() {
+        let (world, configuration) = translate(SINGLE_TASK);
+        let task = named(&world, "OnStart");
+        let trigger = Trigger::RisingEdge {
+            variable: named(&world, "local"),
+        };
+        world.write_storage::<Task>().get_mut(task).unwrap().trigger = trigger;
+
+        let diagnostics = compile(&world, configuration).unwrap_err();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].1.message, "Invalid task trigger");
+    }
+
+    #[test]
+    fn huge_arrays_are_reported() {
+        let src = "
+            PROGRAM main
+                VAR
+                    samples : ARRAY [0..65535, 0..65535] OF DINT;
+                END_VAR
+            END_PROGRAM
+        ";
+
+        let diagnostics = compile_str(src).unwrap_err();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].1.primary_label.message,
+            "the array is too large"
+        );
+    }
+
+    #[test]
+    fn standard_function_blocks_are_reported() {
+        let src = "
+            PROGRAM main
+                VAR
+                    timer : TON;
+                END_VAR
+
+                timer(IN := TRUE, PT := T#1s);
+            END_PROGRAM
+        ";
+
+        let diagnostics = compile_str(src).unwrap_err();
+
+        assert!(!diagnostics.is_empty());
+    }
+}
@@ -0,0 +1,1205 @@
+use crate::{
+    mir::{
+        BinaryOp, BlockId, Body, Callee, Constant, Function, FunctionBlock,
+        Instruction as MirInstruction, Operand, Place, Temporary, Terminator,
+        Type, UnaryOp, Variable, VariableKind,
+    },
+    wasm::{
+        integer,
+        layout::{name_of, Layout},
+        real,
+        scalar::{self, Scalar},
+        Compiler, MEMCPY,
+    },
+};
+use parity_wasm::elements::{
+    BlockType, BrTableData, FuncBody, Instruction, Instructions, Local,
+    ValueType,
+};
+use specs::prelude::*;
+use std::rc::Rc;
+
+/// Compile a program organisation unit's [`Body`] into a WASM function
+/// which takes a pointer to its variables.
+pub(super) fn compile(
+    compiler: &mut Compiler<'_>,
+    pou: Entity,
+    body: &Body,
+) -> FuncBody {
+    let layout = compiler.layouts.of_pou_or_struct(compiler.world, pou).ok();
+
+    let layout = match layout {
+        Some(layout) => layout,
+        // the error was reported when allocating the POU's variables
+        None => {
+            return FuncBody::new(
+                Vec::new(),
+                Instructions::new(vec![Instruction::End]),
+            )
+        },
+    };
+
+    let mut builder = Builder {
+        compiler,
+        pou,
+        layout,
+        body,
+        // the first local is NEXT_BLOCK
+        locals: vec![ValueType::I32],
+        temporaries: Vec::new(),
+        instructions: Vec::new(),
+    };
+
+    builder.declare_temporaries();
+    builder.blocks();
+    builder.instructions.push(Instruction::End);
+
+    FuncBody::new(
+        builder
+            .locals
+            .into_iter()
+            .map(|ty| Local::new(1, ty))
+            .collect(),
+        Instructions::new(builder.instructions),
+    )
+}
+
+/// The local which holds the pointer to a POU's variables.
+const THIS: u32 = 0;
+/// The local which holds the next block to execute.
+const NEXT_BLOCK: u32 = 1;
+
+/// Where a [`Temporary`] is kept.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Slot {
+    Local(u32, Scalar),
+    /// Structs and arrays are kept in memory.
+    Memory(u32),
+}
+
+/// The location of something in memory, relative to either address 0 or
+/// the current POU's variables.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Address {
+    relative_to_this: bool,
+    offset: u32,
+}
+
+impl Address {
+    fn absolute(offset: u32) -> Address {
+        Address {
+            relative_to_this: false,
+            offset,
+        }
+    }
+}
+
+struct Builder<'a, 'w> {
+    compiler: &'a mut Compiler<'w>,
+    pou: Entity,
+    layout: Rc<Layout>,
+    body: &'a Body,
+    /// The type of every local after the `this` parameter.
+    locals: Vec<ValueType>,
+    temporaries: Vec<Slot>,
+    instructions: Vec<Instruction>,
+}
+
+impl<'a, 'w> Builder<'a, 'w> {
+    fn unsupported(&mut self, label: &str) {
+        self.compiler.error(
+            self.pou,
+            "Unable to compile to WebAssembly",
+            label,
+        );
+    }
+
+    fn local(&mut self, ty: ValueType) -> u32 {
+        // parameters come before locals
+        self.locals.push(ty);
+        self.locals.len() as u32
+    }
+
+    fn scalar(&self, ty: &Type) -> Option<Scalar> {
+        Scalar::of(self.compiler.world, ty)
+    }
+
+    fn layout_of(&mut self, ty: &Type) -> Option<Rc<Layout>> {
+        match self.compiler.layouts.of_type(self.compiler.world, ty) {
+            Ok(layout) => Some(layout),
+            Err(reason) => {
+                self.unsupported(&reason);
+                None
+            },
+        }
+    }
+
+    fn declare_temporaries(&mut self) {
+        for ty in &self.body.temporaries {
+            let slot = match Scalar::of(self.compiler.world, ty) {
+                Some(scalar) => {
+                    Slot::Local(self.local(scalar.value_type()), scalar)
+                },
+                None => {
+                    let size = match self.layout_of(ty) {
+                        Some(layout) => self
+                            .compiler
+                            .memory
+                            .allocate(layout.size, layout.align),
+                        None => 0,
+                    };
+                    Slot::Memory(size)
+                },
+            };
+            self.temporaries.push(slot);
+        }
+    }
+
+    /// Lay out the basic blocks inside a loop which uses `br_table` to jump
+    /// to the next block.
+    ///
+    /// ```text
+    /// loop
+    ///   block ;; N-1
+    ///     ...
+    ///       block ;; 0
+    ///         local.get $next_block
+    ///         br_table 0 1 ... N-1
+    ///       end
+    ///       ;; code for block 0
+    ///     ...
+    ///   end
+    ///   ;; code for block N-1
+    /// end
+    /// ```
+    fn blocks(&mut self) {
+        let count = self.body.blocks.len() as u32;
+
+        self.instructions
+            .push(Instruction::Loop(BlockType::NoResult));
+        for _ in 0..count {
+            self.instructions
+                .push(Instruction::Block(BlockType::NoResult));
+        }
+        self.instructions.push(Instruction::GetLocal(NEXT_BLOCK));
+        self.instructions
+            .push(Instruction::BrTable(Box::new(BrTableData {
+                table: (0..count).collect::<Vec<_>>().into_boxed_slice(),
+                default: count.saturating_sub(1),
+            })));
+
+        for (index, block) in self.body.blocks.iter().enumerate() {
+            self.instructions.push(Instruction::End);
+
+            for instruction in &block.instructions {
+                self.instruction(instruction);
+            }
+
+            // the outer loop is this many blocks away
+            let depth = count - 1 - index as u32;
+            self.terminator(&block.terminator, BlockId(index), depth);
+        }
+
+        self.instructions.push(Instruction::End);
+    }
+
+    fn terminator(
+        &mut self,
+        terminator: &Terminator,
+        current: BlockId,
+        depth: u32,
+    ) {
+        match *terminator {
+            Terminator::Goto(BlockId(next)) if next == current.0 + 1 => {
+                // the next block's code comes straight after ours
+            },
+            Terminator::Goto(BlockId(next)) => {
+                self.instructions.extend(vec![
+                    Instruction::I32Const(next as i32),
+                    Instruction::SetLocal(NEXT_BLOCK),
+                    Instruction::Br(depth),
+                ]);
+            },
+            Terminator::Branch {
+                ref condition,
+                then,
+                otherwise,
+            } => {
+                self.instructions.push(Instruction::I32Const(then.0 as i32));
+                self.instructions
+                    .push(Instruction::I32Const(otherwise.0 as i32));
+                self.operand(condition, Scalar::Bool);
+                self.instructions.extend(vec![
+                    Instruction::Select,
+                    Instruction::SetLocal(NEXT_BLOCK),
+                    Instruction::Br(depth),
+                ]);
+            },
+            Terminator::Return => self.instructions.push(Instruction::Return),
+        }
+    }
+
+    fn instruction(&mut self, instruction: &MirInstruction) {
+        match instruction {
+            MirInstruction::Load {
+                destination,
+                source,
+            } => {
+                if let Some((address, ty)) = self.place(source) {
+                    let destination = self.slot(*destination);
+                    self.copy(destination, address, &ty);
+                }
+            },
+            MirInstruction::Store { destination, value } => {
+                if let Some((address, ty)) = self.place(destination) {
+                    self.store(address, &ty, value);
+                }
+            },
+            MirInstruction::Binary {
+                destination,
+                op,
+                left,
+                right,
+            } => self.binary(*destination, *op, left, right),
+            MirInstruction::Unary {
+                destination,
+                op,
+                value,
+            } => self.unary(*destination, *op, value),
+            MirInstruction::Convert { destination, value } => {
+                if let Slot::Local(local, to) = self.slot(*destination) {
+                    self.operand(value, to);
+                    self.instructions.push(Instruction::SetLocal(local));
+                } else {
+                    self.unsupported("only numbers can be converted");
+                }
+            },
+            MirInstruction::Call {
+                destination,
+                function: Callee::Function(function),
+                arguments,
+            } => self.call(*destination, *function, arguments),
+            MirInstruction::Call {
+                destination,
+                function: Callee::Standard(name),
+                arguments,
+            } => self.standard_function(*destination, name, arguments),
+            MirInstruction::CallFunctionBlock {
+                instance,
+                inputs,
+                outputs,
+            } => self.call_function_block(instance, inputs, outputs),
+        }
+    }
+
+    fn slot(&self, temporary: Temporary) -> Slot {
+        self.temporaries[temporary.0]
+    }
+
+    /// Find where a [`Place`] is stored and its type.
+    fn place(&mut self, place: &Place) -> Option<(Address, Type)> {
+        match place {
+            Place::Variable(variable) => self.variable(*variable),
+            Place::Member { base, member } => {
+                let (address, ty) = self.place(base)?;
+                let layout = self.layout_of(&ty)?;
+
+                match layout.member(member) {
+                    Some(m) => Some((
+                        Address {
+                            offset: address.offset + m.offset,
+                            ..address
+                        },
+                        m.ty.clone(),
+                    )),
+                    None => {
+                        self.unsupported(&format!(
+                            "unknown member, {}",
+                            member
+                        ));
+                        None
+                    },
+                }
+            },
+            Place::Temporary(temporary) => match self.slot(*temporary) {
+                Slot::Memory(address) => Some((
+                    Address::absolute(address),
+                    self.body.type_of(*temporary).clone(),
+                )),
+                Slot::Local(..) => {
+                    self.unsupported("numbers don't have members");
+                    None
+                },
+            },
+        }
+    }
+
+    fn variable(&mut self, variable: Entity) -> Option<(Address, Type)> {
+        let world = self.compiler.world;
+        let (kind, ty) = match world.read_storage::<Variable>().get(variable) {
+            Some(v) => (v.kind, v.ty.clone()),
+            None => return None,
+        };
+
+        if let Some(&address) = self.compiler.globals.get(&variable) {
+            return Some((Address::absolute(address), ty));
+        }
+
+        if kind == VariableKind::External {
+            // unknown externals were reported by check_externals()
+            return self
+                .compiler
+                .external(variable)
+                .map(|address| (Address::absolute(address), ty));
+        }
+
+        match self.layout.variable(variable) {
+            Some(member) => Some((
+                Address {
+                    relative_to_this: true,
+                    offset: member.offset,
+                },
+                ty,
+            )),
+            None => {
+                let name = name_of(world, variable);
+                self.unsupported(&format!("{} isn't accessible here", name));
+                None
+            },
+        }
+    }
+
+    /// Push the base pointer an [`Address`] is relative to.
+    fn base(&mut self, address: Address) {
+        self.instructions.push(if address.relative_to_this {
+            Instruction::GetLocal(THIS)
+        } else {
+            Instruction::I32Const(0)
+        });
+    }
+
+    /// Push an [`Address`] as a single pointer.
+    fn pointer(&mut self, address: Address) {
+        if address.relative_to_this {
+            self.instructions.extend(vec![
+                Instruction::GetLocal(THIS),
+                Instruction::I32Const(address.offset as i32),
+                Instruction::I32Add,
+            ]);
+        } else {
+            self.instructions
+                .push(Instruction::I32Const(address.offset as i32));
+        }
+    }
+
+    fn memcpy(&mut self, destination: Address, source: Address, size: u32) {
+        self.pointer(destination);
+        self.pointer(source);
+        self.instructions.push(Instruction::I32Const(size as i32));
+        self.instructions.push(Instruction::Call(MEMCPY));
+    }
+
+    /// Copy a value from memory into a temporary.
+    fn copy(&mut self, destination: Slot, source: Address, ty: &Type) {
+        match (destination, self.scalar(ty)) {
+            (Slot::Local(local, to), Some(from)) => {
+                self.base(source);
+                self.instructions.push(from.load(source.offset));
+                self.instructions.extend(scalar::convert(from, to, false));
+                self.instructions.push(Instruction::SetLocal(local));
+            },
+            (Slot::Memory(address), None) => {
+                if let Some(layout) = self.layout_of(ty) {
+                    self.memcpy(
+                        Address::absolute(address),
+                        source,
+                        layout.size,
+                    );
+                }
+            },
+            _ => self.unsupported("mismatched types"),
+        }
+    }
+
+    /// Write a value to memory.
+    fn store(&mut self, destination: Address, ty: &Type, value: &Operand) {
+        match self.scalar(ty) {
+            Some(scalar) => {
+                self.base(destination);
+                self.operand(value, scalar);
+                self.instructions.push(scalar.store(destination.offset));
+            },
+            None => match value {
+                Operand::Temporary(temporary) => {
+                    if let Slot::Memory(address) = self.slot(*temporary) {
+                        if let Some(layout) = self.layout_of(ty) {
+                            self.memcpy(
+                                destination,
+                                Address::absolute(address),
+                                layout.size,
+                            );
+                        }
+                    }
+                },
+                Operand::Constant(_) => {
+                    self.unsupported("only numbers can be constants")
+                },
+            },
+        }
+    }
+
+    /// Push an operand, converting it to a particular [`Scalar`].
+    fn operand(&mut self, operand: &Operand, to: Scalar) {
+        match operand {
+            Operand::Constant(constant) => self.constant(constant, to),
+            Operand::Temporary(temporary) => match self.slot(*temporary) {
+                Slot::Local(local, from) => {
+                    self.instructions.push(Instruction::GetLocal(local));
+                    self.instructions.extend(scalar::convert(from, to, false));
+                },
+                Slot::Memory(_) => self.unsupported("expected a number"),
+            },
+        }
+    }
+
+    fn constant(&mut self, constant: &Constant, to: Scalar) {
+        let value = &constant.value;
+
+        let instruction = match to {
+            Scalar::Bool => Instruction::I32Const((integer(value) != 0) as i32),
+            Scalar::I8 => Instruction::I32Const(integer(value) as i8 as i32),
+            Scalar::U8 => Instruction::I32Const(integer(value) as u8 as i32),
+            Scalar::I16 => Instruction::I32Const(integer(value) as i16 as i32),
+            Scalar::U16 => Instruction::I32Const(integer(value) as u16 as i32),
+            Scalar::I32 | Scalar::U32 => {
+                Instruction::I32Const(integer(value) as i32)
+            },
+            Scalar::I64 | Scalar::U64 => {
+                Instruction::I64Const(integer(value) as i64)
+            },
+            Scalar::F32 => {
+                Instruction::F32Const((real(value) as f32).to_bits())
+            },
+            Scalar::F64 => Instruction::F64Const(real(value).to_bits()),
+        };
+
+        self.instructions.push(instruction);
+    }
+
+    fn operand_scalar(&self, operand: &Operand) -> Option<Scalar> {
+        self.scalar(self.body.operand_type(operand))
+    }
+
+    /// Finish an expression by saving the value on top of the stack.
+    fn set(&mut self, destination: Temporary) {
+        match self.slot(destination) {
+            Slot::Local(local, _) => {
+                self.instructions.push(Instruction::SetLocal(local))
+            },
+            Slot::Memory(_) => {
+                self.instructions.push(Instruction::Drop);
+                self.unsupported("expected a number");
+            },
+        }
+    }
+
+    fn destination_scalar(&mut self, destination: Temporary) -> Option<Scalar> {
+        match self.slot(destination) {
+            Slot::Local(_, scalar) => Some(scalar),
+            Slot::Memory(_) => {
+                self.unsupported("expected a number");
+                None
+            },
+        }
+    }
+
+    fn binary(
+        &mut self,
+        destination: Temporary,
+        op: BinaryOp,
+        left: &Operand,
+        right: &Operand,
+    ) {
+        let result = match self.destination_scalar(destination) {
+            Some(result) => result,
+            None => return,
+        };
+        let right_scalar = self.operand_scalar(right);
+
+        // multiplying or dividing a duration by a number
+        if self.body.type_of(destination) == &Type::Time
+            && (op == BinaryOp::Multiply || op == BinaryOp::Divide)
+            && self.body.operand_type(right) != &Type::Time
+        {
+            if let Some(factor) = right_scalar {
+                self.scale_duration(op, left, right, factor);
+                self.set(destination);
+            }
+            return;
+        }
+
+        let operands = if op.is_comparison() {
+            match self.operand_scalar(left) {
+                Some(scalar) => scalar,
+                None => {
+                    return self.unsupported("only numbers can be compared")
+                },
+            }
+        } else {
+            result
+        };
+
+        match scalar::binary(op, operands) {
+            Some(instructions) => {
+                self.operand(left, operands);
+                self.operand(right, operands);
+                self.instructions.extend(instructions);
+                self.set(destination);
+            },
+            None => self.unsupported(&format!(
+                "the {:?} operator isn't supported for {:?}",
+                op, operands
+            )),
+        }
+    }
+
+    fn scale_duration(
+        &mut self,
+        op: BinaryOp,
+        duration: &Operand,
+        factor: &Operand,
+        factor_scalar: Scalar,
+    ) {
+        if factor_scalar.is_float() {
+            self.operand(duration, Scalar::F64);
+            self.operand(factor, Scalar::F64);
+            self.instructions.push(if op == BinaryOp::Multiply {
+                Instruction::F64Mul
+            } else {
+                Instruction::F64Div
+            });
+            self.instructions.extend(scalar::convert(
+                Scalar::F64,
+                Scalar::I64,
+                false,
+            ));
+        } else {
+            self.operand(duration, Scalar::I64);
+            self.operand(factor, Scalar::I64);
+            self.instructions.push(if op == BinaryOp::Multiply {
+                Instruction::I64Mul
+            } else {
+                Instruction::I64DivS
+            });
+        }
+    }
+
+    fn unary(&mut self, destination: Temporary, op: UnaryOp, value: &Operand) {
+        let scalar = match self.destination_scalar(destination) {
+            Some(scalar) => scalar,
+            None => return,
+        };
+
+        let instructions = match (op, scalar.value_type()) {
+            (UnaryOp::Not, _) if scalar == Scalar::Bool => {
+                self.operand(value, scalar);
+                vec![Instruction::I32Eqz]
+            },
+            (UnaryOp::Not, ValueType::I32) => {
+                self.operand(value, scalar);
+                vec![Instruction::I32Const(-1), Instruction::I32Xor]
+            },
+            (UnaryOp::Not, ValueType::I64) => {
+                self.operand(value, scalar);
+                vec![Instruction::I64Const(-1), Instruction::I64Xor]
+            },
+            (UnaryOp::Negate, ValueType::I32) => {
+                self.instructions.push(Instruction::I32Const(0));
+                self.operand(value, scalar);
+                vec![Instruction::I32Sub]
+            },
+            (UnaryOp::Negate, ValueType::I64) => {
+                self.instructions.push(Instruction::I64Const(0));
+                self.operand(value, scalar);
+                vec![Instruction::I64Sub]
+            },
+            (UnaryOp::Negate, ValueType::F32) => {
+                self.operand(value, scalar);
+                vec![Instruction::F32Neg]
+            },
+            (UnaryOp::Negate, ValueType::F64) => {
+                self.operand(value, scalar);
+                vec![Instruction::F64Neg]
+            },
+            _ => {
+                return self.unsupported(&format!(
+                    "{:?} isn't supported for {:?}",
+                    op, scalar
+                ))
+            },
+        };
+
+        self.instructions.extend(instructions);
+        self.instructions.extend(scalar.normalize());
+        self.set(destination);
+    }
+
+    /// Call a user-defined function.
+    ///
+    /// The function's frame is reset to its initial values before copying
+    /// across the arguments.
+    fn call(
+        &mut self,
+        destination: Temporary,
+        function: Entity,
+        arguments: &[Operand],
+    ) {
+        let world = self.compiler.world;
+        let (frame, index) = match (
+            self.compiler.frames.get(&function).copied(),
+            self.compiler.functions.get(&function).copied(),
+        ) {
+            (Some(frame), Some(index)) => (frame, index),
+            _ => return,
+        };
+        let (inputs, return_value) =
+            match world.read_storage::<Function>().get(function) {
+                Some(f) => (inputs(world, &f.variables), f.return_value),
+                None => return,
+            };
+        let layout =
+            match self.compiler.layouts.of_pou_or_struct(world, function) {
+                Ok(layout) => layout,
+                Err(_) => return,
+            };
+
+        let frame_address = Address::absolute(frame.address);
+        self.memcpy(
+            frame_address,
+            Address::absolute(frame.initial_values),
+            frame.size,
+        );
+
+        for (input, argument) in inputs.into_iter().zip(arguments) {
+            if let Some(member) = layout.variable(input) {
+                let address = Address::absolute(frame.address + member.offset);
+                self.store(address, &member.ty, argument);
+            }
+        }
+
+        self.instructions.extend(vec![
+            Instruction::I32Const(frame.address as i32),
+            Instruction::Call(index),
+        ]);
+
+        if let Some(member) = layout.variable(return_value) {
+            let address = Address::absolute(frame.address + member.offset);
+            let destination = self.slot(destination);
+            self.copy(destination, address, &member.ty);
+        }
+    }
+
+    fn call_function_block(
+        &mut self,
+        instance: &Place,
+        inputs: &[(String, Operand)],
+        outputs: &[(String, Place)],
+    ) {
+        let (address, ty) = match self.place(instance) {
+            Some(found) => found,
+            None => return,
+        };
+        let world = self.compiler.world;
+
+        let function_block = match ty {
+            Type::Derived(entity)
+                if world.read_storage::<FunctionBlock>().contains(entity) =>
+            {
+                entity
+            },
+            Type::StandardFunctionBlock(name) => {
+                return self.unsupported(&format!(
+                    "the standard function block {} isn't supported yet",
+                    name
+                ))
+            },
+            _ => return self.unsupported("only function blocks can be called"),
+        };
+        let index = self.compiler.functions[&function_block];
+        let layout = match self.layout_of(&ty) {
+            Some(layout) => layout,
+            None => return,
+        };
+
+        for (name, value) in inputs {
+            if let Some(member) = layout.member(name) {
+                let member_address = Address {
+                    offset: address.offset + member.offset,
+                    ..address
+                };
+                self.store(member_address, &member.ty, value);
+            }
+        }
+
+        self.pointer(address);
+        self.instructions.push(Instruction::Call(index));
+
+        for (name, place) in outputs {
+            let member = match layout.member(name) {
+                Some(member) => member,
+                None => continue,
+            };
+            let source = Address {
+                offset: address.offset + member.offset,
+                ..address
+            };
+            let (destination, destination_ty) = match self.place(place) {
+                Some(found) => found,
+                None => continue,
+            };
+
+            match (self.scalar(&member.ty), self.scalar(&destination_ty)) {
+                (Some(from), Some(to)) => {
+                    self.base(destination);
+                    self.base(source);
+                    self.instructions.push(from.load(source.offset));
+                    self.instructions.extend(scalar::convert(from, to, false));
+                    self.instructions.push(to.store(destination.offset));
+                },
+                _ => {
+                    if let Some(member_layout) = self.layout_of(&member.ty) {
+                        self.memcpy(destination, source, member_layout.size);
+                    }
+                },
+            }
+        }
+    }
+
+    fn standard_function(
+        &mut self,
+        destination: Temporary,
+        name: &str,
+        arguments: &[Operand],
+    ) {
+        let result = match self.destination_scalar(destination) {
+            Some(result) => result,
+            None => return,
+        };
+        let name = name.to_uppercase();
+
+        if name == "NOT" {
+            match arguments {
+                [value] => return self.unary(destination, UnaryOp::Not, value),
+                _ => return self.unsupported("NOT() takes a single argument"),
+            }
+        }
+
+        let handled = match name.as_str() {
+            "MOVE" => self.fold(arguments, result, &[]),
+            "ADD" => self.fold(arguments, result, &[BinaryOp::Add]),
+            "MUL" => self.fold(arguments, result, &[BinaryOp::Multiply]),
+            "SUB" => self.fold(arguments, result, &[BinaryOp::Subtract]),
+            "DIV" => self.fold(arguments, result, &[BinaryOp::Divide]),
+            "MOD" => self.fold(arguments, result, &[BinaryOp::Modulo]),
+            "AND" => self.fold(arguments, result, &[BinaryOp::And]),
+            "OR" => self.fold(arguments, result, &[BinaryOp::Or]),
+            "XOR" => self.fold(arguments, result, &[BinaryOp::Xor]),
+            "GT" => self.compare(arguments, BinaryOp::GreaterThan),
+            "GE" => self.compare(arguments, BinaryOp::GreaterThanOrEqual),
+            "EQ" => self.compare(arguments, BinaryOp::Equals),
+            "LE" => self.compare(arguments, BinaryOp::LessThanOrEqual),
+            "LT" => self.compare(arguments, BinaryOp::LessThan),
+            "NE" => self.compare(arguments, BinaryOp::NotEquals),
+            "ABS" => self.abs(arguments, result),
+            "SQRT" => self.sqrt(arguments, result),
+            "MIN" => self.min_max(arguments, result, BinaryOp::LessThan),
+            "MAX" => self.min_max(arguments, result, BinaryOp::GreaterThan),
+            "LIMIT" => self.limit(arguments, result),
+            "SEL" => self.sel(arguments, result),
+            "MUX" => self.mux(arguments, result),
+            "SHL" | "SHR" | "ROL" | "ROR" => {
+                self.shift(&name, arguments, result)
+            },
+            "TRUNC" => self.conversion(arguments, result, false),
+            _ if name.contains("_TO_") => {
+                self.conversion(arguments, result, true)
+            },
+            _ => false,
+        };
+
+        if handled {
+            self.set(destination);
+        } else {
+            self.unsupported(&format!(
+                "the standard function {} isn't supported yet",
+                name
+            ));
+        }
+    }
+
+    /// Combine every argument using a binary operator (e.g. `ADD(a, b, c)`
+    /// is `a + b + c`).
+    fn fold(
+        &mut self,
+        arguments: &[Operand],
+        result: Scalar,
+        op: &[BinaryOp],
+    ) -> bool {
+        let (first, rest) = match arguments.split_first() {
+            Some(split) => split,
+            None => return false,
+        };
+
+        let instructions = match op.first() {
+            Some(&op) => match scalar::binary(op, result) {
+                Some(instructions) => instructions,
+                None => return false,
+            },
+            None if rest.is_empty() => Vec::new(),
+            None => return false,
+        };
+
+        self.operand(first, result);
+        for argument in rest {
+            self.operand(argument, result);
+            self.instructions.extend(instructions.iter().cloned());
+        }
+
+        true
+    }
+
+    fn compare(&mut self, arguments: &[Operand], op: BinaryOp) -> bool {
+        let (left, right) = match arguments {
+            [left, right] => (left, right),
+            _ => return false,
+        };
+        let scalar = match self.operand_scalar(left) {
+            Some(scalar) => scalar,
+            None => return false,
+        };
+
+        match scalar::binary(op, scalar) {
+            Some(instructions) => {
+                self.operand(left, scalar);
+                self.operand(right, scalar);
+                self.instructions.extend(instructions);
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn abs(&mut self, arguments: &[Operand], result: Scalar) -> bool {
+        let value = match arguments {
+            [value] => value,
+            _ => return false,
+        };
+
+        self.operand(value, result);
+
+        match result.value_type() {
+            ValueType::F32 => self.instructions.push(Instruction::F32Abs),
+            ValueType::F64 => self.instructions.push(Instruction::F64Abs),
+            _ if !result.is_signed() => {},
+            ty => {
+                let (zero, sub, less_than) = if ty == ValueType::I64 {
+                    (
+                        Instruction::I64Const(0),
+                        Instruction::I64Sub,
+                        Instruction::I64LtS,
+                    )
+                } else {
+                    (
+                        Instruction::I32Const(0),
+                        Instruction::I32Sub,
+                        Instruction::I32LtS,
+                    )
+                };
+                let temp = self.local(ty);
+
+                // select(-x, x, x < 0)
+                self.instructions.extend(vec![
+                    Instruction::SetLocal(temp),
+                    zero.clone(),
+                    Instruction::GetLocal(temp),
+                    sub,
+                    Instruction::GetLocal(temp),
+                    Instruction::GetLocal(temp),
+                    zero,
+                    less_than,
+                    Instruction::Select,
+                ]);
+                self.instructions.extend(result.normalize());
+            },
+        }
+
+        true
+    }
+
+    fn sqrt(&mut self, arguments: &[Operand], result: Scalar) -> bool {
+        match (arguments, result) {
+            ([value], Scalar::F32) => {
+                self.operand(value, result);
+                self.instructions.push(Instruction::F32Sqrt);
+                true
+            },
+            ([value], Scalar::F64) => {
+                self.operand(value, result);
+                self.instructions.push(Instruction::F64Sqrt);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// `MIN()` and `MAX()`, where `keep` is the comparison which is true
+    /// when the current value should be kept.
+    fn min_max(
+        &mut self,
+        arguments: &[Operand],
+        result: Scalar,
+        keep: BinaryOp,
+    ) -> bool {
+        let (first, rest) = match arguments.split_first() {
+            Some(split) => split,
+            None => return false,
+        };
+        let compare = match scalar::binary(keep, result) {
+            Some(compare) => compare,
+            None => return false,
+        };
+
+        let ty = result.value_type();
+        let current = self.local(ty);
+        let next = self.local(ty);
+
+        self.operand(first, result);
+        self.instructions.push(Instruction::SetLocal(current));
+
+        for argument in rest {
+            self.operand(argument, result);
+            self.instructions.extend(vec![
+                Instruction::SetLocal(next),
+                Instruction::GetLocal(current),
+                Instruction::GetLocal(next),
+                Instruction::GetLocal(current),
+                Instruction::GetLocal(next),
+            ]);
+            self.instructions.extend(compare.iter().cloned());
+            self.instructions.push(Instruction::Select);
+            self.instructions.push(Instruction::SetLocal(current));
+        }
+
+        self.instructions.push(Instruction::GetLocal(current));
+        true
+    }
+
+    /// `LIMIT(MN, IN, MX)` is `MIN(MAX(IN, MN), MX)`.
+    fn limit(&mut self, arguments: &[Operand], result: Scalar) -> bool {
+        let (min, value, max) = match arguments {
+            [min, value, max] => (min, value, max),
+            _ => return false,
+        };
+
+        let ty = result.value_type();
+        let bounded = self.local(ty);
+        let (greater, less) = match (
+            scalar::binary(BinaryOp::GreaterThan, result),
+            scalar::binary(BinaryOp::LessThan, result),
+        ) {
+            (Some(greater), Some(less)) => (greater, less),
+            _ => return false,
+        };
+
+        self.operand(value, result);
+        self.instructions.push(Instruction::SetLocal(bounded));
+
+        for &(bound, ref keep) in &[(min, greater), (max, less)] {
+            let limit = self.local(ty);
+            self.operand(bound, result);
+            self.instructions.extend(vec![
+                Instruction::SetLocal(limit),
+                Instruction::GetLocal(bounded),
+                Instruction::GetLocal(limit),
+                Instruction::GetLocal(bounded),
+                Instruction::GetLocal(limit),
+            ]);
+            self.instructions.extend(keep.iter().cloned());
+            self.instructions.push(Instruction::Select);
+            self.instructions.push(Instruction::SetLocal(bounded));
+        }
+
+        self.instructions.push(Instruction::GetLocal(bounded));
+        true
+    }
+
+    /// `SEL(G, IN0, IN1)` picks `IN1` when `G` is true.
+    fn sel(&mut self, arguments: &[Operand], result: Scalar) -> bool {
+        match arguments {
+            [selector, first, second] => {
+                self.operand(second, result);
+                self.operand(first, result);
+                self.operand(selector, Scalar::Bool);
+                self.instructions.push(Instruction::Select);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// `MUX(K, IN0, IN1, ...)` picks the `K`'th input.
+    fn mux(&mut self, arguments: &[Operand], result: Scalar) -> bool {
+        let (selector, inputs) = match arguments.split_first() {
+            Some((selector, inputs)) if !inputs.is_empty() => {
+                (selector, inputs)
+            },
+            _ => return false,
+        };
+
+        let k = self.local(ValueType::I32);
+        self.operand(selector, Scalar::I32);
+        self.instructions.push(Instruction::SetLocal(k));
+
+        self.operand(&inputs[0], result);
+        for (i, input) in inputs.iter().enumerate().skip(1) {
+            let current = self.local(result.value_type());
+            self.instructions.push(Instruction::SetLocal(current));
+            self.operand(input, result);
+            self.instructions.extend(vec![
+                Instruction::GetLocal(current),
+                Instruction::GetLocal(k),
+                Instruction::I32Const(i as i32),
+                Instruction::I32Eq,
+                Instruction::Select,
+            ]);
+        }
+
+        true
+    }
+
+    /// `SHL()`, `SHR()`, `ROL()` and `ROR()` on bit strings and integers.
+    fn shift(
+        &mut self,
+        name: &str,
+        arguments: &[Operand],
+        result: Scalar,
+    ) -> bool {
+        let (value, amount) = match arguments {
+            [value, amount] => (value, amount),
+            _ => return false,
+        };
+
+        let ty = result.value_type();
+        let is_64_bit = ty == ValueType::I64;
+        if result.is_float() || result == Scalar::Bool {
+            return false;
+        }
+
+        // treat the value as unsigned so bits are shifted in as zeroes
+        let unsigned = match result {
+            Scalar::I8 => Scalar::U8,
+            Scalar::I16 => Scalar::U16,
+            Scalar::I32 => Scalar::U32,
+            Scalar::I64 => Scalar::U64,
+            other => other,
+        };
+        let amount_scalar = if is_64_bit { Scalar::U64 } else { Scalar::U32 };
+
+        let native = |op: &str| match (op, is_64_bit) {
+            ("SHL", false) => Instruction::I32Shl,
+            ("SHL", true) => Instruction::I64Shl,
+            ("SHR", false) => Instruction::I32ShrU,
+            ("SHR", true) => Instruction::I64ShrU,
+            ("ROL", false) => Instruction::I32Rotl,
+            ("ROL", true) => Instruction::I64Rotl,
+            (_, false) => Instruction::I32Rotr,
+            (_, true) => Instruction::I64Rotr,
+        };
+
+        let narrow_rotate =
+            (name == "ROL" || name == "ROR") && result.bits() < 32;
+
+        if !narrow_rotate {
+            self.operand(value, result);
+            self.instructions
+                .extend(scalar::convert(result, unsigned, false));
+            self.operand(amount, amount_scalar);
+            self.instructions.push(native(name));
+            self.instructions
+                .extend(scalar::convert(unsigned, result, false));
+            return true;
+        }
+
+        // 8 and 16 bit rotates are done by hand:
+        //   (x << n) | (x >> (bits - n))
+        let x = self.local(ValueType::I32);
+        let n = self.local(ValueType::I32);
+        let bits = result.bits() as i32;
+        let (first, second) = if name == "ROL" {
+            (Instruction::I32Shl, Instruction::I32ShrU)
+        } else {
+            (Instruction::I32ShrU, Instruction::I32Shl)
+        };
+
+        self.operand(value, result);
+        self.instructions
+            .extend(scalar::convert(result, unsigned, false));
+        self.instructions.push(Instruction::SetLocal(x));
+        self.operand(amount, Scalar::U32);
+        self.instructions.extend(vec![
+            Instruction::I32Const(bits),
+            Instruction::I32RemU,
+            Instruction::SetLocal(n),
+            Instruction::GetLocal(x),
+            Instruction::GetLocal(n),
+            first,
+            Instruction::GetLocal(x),
+            Instruction::I32Const(bits),
+            Instruction::GetLocal(n),
+            Instruction::I32Sub,
+            second,
+            Instruction::I32Or,
+        ]);
+        self.instructions.extend(result.normalize());
+
+        true
+    }
+
+    /// Type conversion functions like `REAL_TO_INT()` and `TRUNC()`.
+    fn conversion(
+        &mut self,
+        arguments: &[Operand],
+        result: Scalar,
+        round: bool,
+    ) -> bool {
+        let value = match arguments {
+            [value] => value,
+            _ => return false,
+        };
+        let from = match self.operand_scalar(value) {
+            Some(from) => from,
+            None => return false,
+        };
+
+        self.operand(value, from);
+        self.instructions
+            .extend(scalar::convert(from, result, round));
+        true
+    }
+}
+
+/// A function's `VAR_INPUT` variables, in the order they were declared.
+fn inputs(world: &World, variables: &[Entity]) -> Vec<Entity> {
+    let storage = world.read_storage::<Variable>();
+
+    variables
+        .iter()
+        .copied()
+        .filter(|&variable| {
+            storage
+                .get(variable)
+                .map(|v| v.kind == VariableKind::Input)
+                .unwrap_or(false)
+        })
+        .collect()
+}
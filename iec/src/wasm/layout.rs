@@ -0,0 +1,328 @@
+use crate::mir::{
+    DataType, Function, FunctionBlock, Name, Program, Type, Variable,
+    VariableKind,
+};
+use crate::wasm::scalar::Scalar;
+use specs::prelude::*;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    ops::RangeInclusive,
+    rc::Rc,
+};
+
+/// The largest value we will lay out, small enough that adding an offset
+/// or rounding up for alignment can never overflow a `u32`.
+const MAX_SIZE: u32 = i32::max_value() as u32;
+
+/// Where a value lives in linear memory.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Layout {
+    pub size: u32,
+    pub align: u32,
+    /// The struct fields or variables inside this value.
+    pub members: Vec<Member>,
+}
+
+impl Layout {
+    fn scalar(scalar: Scalar) -> Layout {
+        Layout {
+            size: scalar.size(),
+            align: scalar.size(),
+            members: Vec::new(),
+        }
+    }
+
+    pub fn member(&self, name: &str) -> Option<&Member> {
+        self.members
+            .iter()
+            .find(|member| member.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn variable(&self, variable: Entity) -> Option<&Member> {
+        self.members
+            .iter()
+            .find(|member| member.variable == Some(variable))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Member {
+    pub name: String,
+    pub offset: u32,
+    pub ty: Type,
+    /// The entity with a [`Variable`] component, if this member is one of
+    /// a program organisation unit's variables.
+    pub variable: Option<Entity>,
+}
+
+/// Calculates (and caches) the memory layout for types and program
+/// organisation units.
+#[derive(Debug, Default)]
+pub(crate) struct Layouts {
+    cache: HashMap<Entity, Rc<Layout>>,
+    in_progress: HashSet<Entity>,
+}
+
+impl Layouts {
+    /// Get the layout for a value of a particular type, or a message
+    /// explaining why it isn't supported.
+    pub fn of_type(
+        &mut self,
+        world: &World,
+        ty: &Type,
+    ) -> Result<Rc<Layout>, String> {
+        if let Some(scalar) = Scalar::of(world, ty) {
+            return Ok(Rc::new(Layout::scalar(scalar)));
+        }
+
+        match ty {
+            Type::Array {
+                element,
+                dimensions,
+            } => {
+                let element = self.of_type(world, element)?;
+                let size = element_count(dimensions)
+                    .and_then(|count| element.size.checked_mul(count))
+                    .filter(|&size| size <= MAX_SIZE)
+                    .ok_or_else(|| String::from("the array is too large"))?;
+
+                Ok(Rc::new(Layout {
+                    size,
+                    align: element.align,
+                    members: Vec::new(),
+                }))
+            },
+            Type::Derived(entity) => self.of_pou_or_struct(world, *entity),
+            Type::String | Type::WString => {
+                Err(String::from("strings aren't supported yet"))
+            },
+            Type::StandardFunctionBlock(name) => Err(format!(
+                "the standard function block {} isn't supported yet",
+                name
+            )),
+            _ => unreachable!("{:?} is a scalar", ty),
+        }
+    }
+
+    /// Get the layout of a struct or program organisation unit's
+    /// variables.
+    pub fn of_pou_or_struct(
+        &mut self,
+        world: &World,
+        entity: Entity,
+    ) -> Result<Rc<Layout>, String> {
+        if let Some(layout) = self.cache.get(&entity) {
+            return Ok(Rc::clone(layout));
+        }
+
+        if !self.in_progress.insert(entity) {
+            return Err(format!("{} contains itself", name_of(world, entity)));
+        }
+
+        let members = members(world, entity);
+        let layout = self.lay_out(world, members);
+        self.in_progress.remove(&entity);
+
+        let layout = Rc::new(layout?);
+        self.cache.insert(entity, Rc::clone(&layout));
+        Ok(layout)
+    }
+
+    fn lay_out(
+        &mut self,
+        world: &World,
+        members: Vec<(String, Type, Option<Entity>)>,
+    ) -> Result<Layout, String> {
+        let mut layout = Layout {
+            size: 0,
+            align: 1,
+            members: Vec::new(),
+        };
+
+        for (name, ty, variable) in members {
+            let member = self.of_type(world, &ty)?;
+            let offset = align_up(layout.size, member.align);
+
+            layout.size = offset
+                .checked_add(member.size)
+                .filter(|&size| size <= MAX_SIZE)
+                .ok_or_else(|| format!("{} doesn't fit in memory", name))?;
+            layout.align = layout.align.max(member.align);
+            layout.members.push(Member {
+                name,
+                offset,
+                ty,
+                variable,
+            });
+        }
+
+        layout.size = align_up(layout.size, layout.align);
+        Ok(layout)
+    }
+}
+
+/// The fields of a struct or the variables stored inside a program
+/// organisation unit.
+///
+/// `VAR_EXTERNAL` variables are left out because they refer to a global
+/// variable declared elsewhere.
+fn members(
+    world: &World,
+    entity: Entity,
+) -> Vec<(String, Type, Option<Entity>)> {
+    if let Some(DataType::Struct(fields)) =
+        world.read_storage::<DataType>().get(entity)
+    {
+        return fields
+            .iter()
+            .map(|field| (field.name.to_uppercase(), field.ty.clone(), None))
+            .collect();
+    }
+
+    let variables = world
+        .read_storage::<Program>()
+        .get(entity)
+        .map(|p| p.variables.clone())
+        .or_else(|| {
+            world
+                .read_storage::<FunctionBlock>()
+                .get(entity)
+                .map(|fb| fb.variables.clone())
+        })
+        .or_else(|| {
+            world
+                .read_storage::<Function>()
+                .get(entity)
+                .map(|f| f.variables.clone())
+        })
+        .unwrap_or_default();
+
+    let storage = world.read_storage::<Variable>();
+
+    variables
+        .into_iter()
+        .filter_map(|entity| {
+            let variable = storage.get(entity)?;
+            if variable.kind == VariableKind::External {
+                return None;
+            }
+            let name = name_of(world, entity).to_uppercase();
+            Some((name, variable.ty.clone(), Some(entity)))
+        })
+        .collect()
+}
+
+pub(crate) fn name_of(world: &World, entity: Entity) -> String {
+    world
+        .read_storage::<Name>()
+        .get(entity)
+        .map(|name| name.0.clone())
+        .unwrap_or_default()
+}
+
+/// The number of elements in an array, or `None` if it overflows.
+fn element_count(dimensions: &[RangeInclusive<i64>]) -> Option<u32> {
+    dimensions.iter().try_fold(1_u32, |count, range| {
+        let len = range.end().checked_sub(*range.start())?.checked_add(1)?;
+        count.checked_mul(u32::try_from(len.max(0)).ok()?)
+    })
+}
+
+pub(crate) fn align_up(value: u32, align: u32) -> u32 {
+    (value + align - 1) / align * align
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend;
+    use codespan::Files;
+
+    fn layout_of(src: &str, name: &str) -> Rc<Layout> {
+        let mut files = Files::new();
+        let id = files.add("main.st", src);
+        let file = rustmatic_structured_text::parse(src).unwrap();
+        let mut world = World::new();
+        frontend::translate_structured_text(vec![(id, file)], &mut world)
+            .unwrap();
+
+        let entity = (&world.entities(), &world.read_storage::<Name>())
+            .join()
+            .find(|(_, n)| n.0 == name)
+            .map(|(entity, _)| entity)
+            .unwrap_or_else(|| panic!("No entity called \"{}\"", name));
+
+        Layouts::default().of_pou_or_struct(&world, entity).unwrap()
+    }
+
+    fn offsets(layout: &Layout) -> Vec<(&str, u32)> {
+        layout
+            .members
+            .iter()
+            .map(|member| (member.name.as_str(), member.offset))
+            .collect()
+    }
+
+    const POINT: &str = "
+        TYPE
+            Point : STRUCT
+                flag : BOOL;
+                x : DINT;
+                y : INT;
+                big : LREAL;
+            END_STRUCT;
+        END_TYPE
+
+        PROGRAM main
+            VAR
+                a : BOOL;
+                p : Point;
+                grid : ARRAY [1..3, 0..1] OF INT;
+            END_VAR
+        END_PROGRAM
+    ";
+
+    #[test]
+    fn struct_fields_are_aligned() {
+        let layout = layout_of(POINT, "Point");
+
+        assert_eq!(
+            offsets(&layout),
+            vec![("FLAG", 0), ("X", 4), ("Y", 8), ("BIG", 16)]
+        );
+        assert_eq!(layout.size, 24);
+        assert_eq!(layout.align, 8);
+        assert_eq!(layout.member("big").unwrap().ty, Type::LReal);
+    }
+
+    #[test]
+    fn variables_are_laid_out_in_declaration_order() {
+        let layout = layout_of(POINT, "main");
+
+        assert_eq!(offsets(&layout), vec![("A", 0), ("P", 8), ("GRID", 32)]);
+        // the 12 byte array is padded to the struct's alignment
+        assert_eq!(layout.size, 48);
+        assert_eq!(layout.align, 8);
+        assert!(layout.members.iter().all(|m| m.variable.is_some()));
+    }
+
+    #[test]
+    fn count_array_elements() {
+        assert_eq!(element_count(&[]), Some(1));
+        assert_eq!(element_count(&[1..=3, 0..=1]), Some(6));
+        assert_eq!(element_count(&[-5..=5]), Some(11));
+        assert_eq!(element_count(&[RangeInclusive::new(3, 1)]), Some(0));
+        assert_eq!(element_count(&[0..=65535, 0..=65535]), None);
+        assert_eq!(element_count(&[i64::min_value()..=i64::max_value()]), None);
+    }
+
+    #[test]
+    fn round_up_to_the_alignment() {
+        assert_eq!(align_up(0, 8), 0);
+        assert_eq!(align_up(1, 8), 8);
+        assert_eq!(align_up(8, 8), 8);
+        assert_eq!(align_up(13, 4), 16);
+        assert_eq!(align_up(13, 1), 13);
+    }
+}
@@ -0,0 +1,510 @@
+use crate::mir::{BinaryOp, DataType, Type};
+use parity_wasm::elements::{Instruction, ValueType};
+use specs::prelude::*;
+
+/// How a value is represented while it's on the WASM stack.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Scalar {
+    Bool,
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    I64,
+    U64,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    /// Get the [`Scalar`] used for a type, or `None` if it lives in memory.
+    pub fn of(world: &World, ty: &Type) -> Option<Scalar> {
+        let scalar = match ty {
+            Type::Bool => Scalar::Bool,
+            Type::SInt => Scalar::I8,
+            Type::USInt | Type::Byte => Scalar::U8,
+            Type::Int => Scalar::I16,
+            Type::UInt | Type::Word => Scalar::U16,
+            Type::DInt => Scalar::I32,
+            Type::UDInt | Type::DWord => Scalar::U32,
+            Type::LInt
+            | Type::Time
+            | Type::Date
+            | Type::TimeOfDay
+            | Type::DateAndTime => Scalar::I64,
+            Type::ULInt | Type::LWord => Scalar::U64,
+            Type::Real => Scalar::F32,
+            Type::LReal => Scalar::F64,
+            Type::Derived(entity) => {
+                match world.read_storage::<DataType>().get(*entity) {
+                    Some(DataType::Enumeration(_)) => Scalar::I32,
+                    _ => return None,
+                }
+            },
+            _ => return None,
+        };
+
+        Some(scalar)
+    }
+
+    pub fn size(self) -> u32 {
+        match self {
+            Scalar::Bool | Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::I64 | Scalar::U64 | Scalar::F64 => 8,
+        }
+    }
+
+    pub fn value_type(self) -> ValueType {
+        match self {
+            Scalar::I64 | Scalar::U64 => ValueType::I64,
+            Scalar::F32 => ValueType::F32,
+            Scalar::F64 => ValueType::F64,
+            _ => ValueType::I32,
+        }
+    }
+
+    pub fn is_signed(self) -> bool {
+        match self {
+            Scalar::I8 | Scalar::I16 | Scalar::I32 | Scalar::I64 => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_float(self) -> bool {
+        match self {
+            Scalar::F32 | Scalar::F64 => true,
+            _ => false,
+        }
+    }
+
+    /// The `align` immediate for a load or store (i.e. `log2(size)`).
+    fn alignment(self) -> u32 { self.size().trailing_zeros() }
+
+    pub fn load(self, offset: u32) -> Instruction {
+        let align = self.alignment();
+
+        match self {
+            Scalar::Bool | Scalar::U8 => Instruction::I32Load8U(align, offset),
+            Scalar::I8 => Instruction::I32Load8S(align, offset),
+            Scalar::I16 => Instruction::I32Load16S(align, offset),
+            Scalar::U16 => Instruction::I32Load16U(align, offset),
+            Scalar::I32 | Scalar::U32 => Instruction::I32Load(align, offset),
+            Scalar::I64 | Scalar::U64 => Instruction::I64Load(align, offset),
+            Scalar::F32 => Instruction::F32Load(align, offset),
+            Scalar::F64 => Instruction::F64Load(align, offset),
+        }
+    }
+
+    pub fn store(self, offset: u32) -> Instruction {
+        let align = self.alignment();
+
+        match self {
+            Scalar::Bool | Scalar::I8 | Scalar::U8 => {
+                Instruction::I32Store8(align, offset)
+            },
+            Scalar::I16 | Scalar::U16 => Instruction::I32Store16(align, offset),
+            Scalar::I32 | Scalar::U32 => Instruction::I32Store(align, offset),
+            Scalar::I64 | Scalar::U64 => Instruction::I64Store(align, offset),
+            Scalar::F32 => Instruction::F32Store(align, offset),
+            Scalar::F64 => Instruction::F64Store(align, offset),
+        }
+    }
+
+    /// Instructions which wrap an `i32` holding the result of some
+    /// arithmetic back into the range of a narrower integer.
+    pub fn normalize(self) -> Vec<Instruction> {
+        match self {
+            Scalar::I8 => sign_extend(24),
+            Scalar::I16 => sign_extend(16),
+            Scalar::U8 => {
+                vec![Instruction::I32Const(0xFF), Instruction::I32And]
+            },
+            Scalar::U16 => {
+                vec![Instruction::I32Const(0xFFFF), Instruction::I32And]
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// The number of bits in an integer.
+    pub fn bits(self) -> u32 { self.size() * 8 }
+}
+
+fn sign_extend(shift: i32) -> Vec<Instruction> {
+    vec![
+        Instruction::I32Const(shift),
+        Instruction::I32Shl,
+        Instruction::I32Const(shift),
+        Instruction::I32ShrS,
+    ]
+}
+
+/// Convert the value on top of the stack from one [`Scalar`] to another.
+///
+/// When `round` is set, converting a float to an integer rounds to the
+/// nearest integer (e.g. `REAL_TO_INT`) instead of truncating.
+pub(crate) fn convert(
+    from: Scalar,
+    to: Scalar,
+    round: bool,
+) -> Vec<Instruction> {
+    use parity_wasm::elements::ValueType::{F32, F64, I32, I64};
+
+    if from == to {
+        return Vec::new();
+    }
+
+    if to == Scalar::Bool {
+        let zero = match from.value_type() {
+            I32 => vec![Instruction::I32Const(0), Instruction::I32Ne],
+            I64 => vec![Instruction::I64Const(0), Instruction::I64Ne],
+            F32 => vec![Instruction::F32Const(0), Instruction::F32Ne],
+            F64 => vec![Instruction::F64Const(0), Instruction::F64Ne],
+        };
+        return zero;
+    }
+
+    let signed = from.is_signed();
+    let mut instructions = match (from.value_type(), to.value_type()) {
+        (I32, I32) | (I64, I64) | (F32, F32) | (F64, F64) => Vec::new(),
+        (I32, I64) if signed => vec![Instruction::I64ExtendSI32],
+        (I32, I64) => vec![Instruction::I64ExtendUI32],
+        (I64, I32) => vec![Instruction::I32WrapI64],
+        (I32, F32) if signed => vec![Instruction::F32ConvertSI32],
+        (I32, F32) => vec![Instruction::F32ConvertUI32],
+        (I32, F64) if signed => vec![Instruction::F64ConvertSI32],
+        (I32, F64) => vec![Instruction::F64ConvertUI32],
+        (I64, F32) if signed => vec![Instruction::F32ConvertSI64],
+        (I64, F32) => vec![Instruction::F32ConvertUI64],
+        (I64, F64) if signed => vec![Instruction::F64ConvertSI64],
+        (I64, F64) => vec![Instruction::F64ConvertUI64],
+        (F32, F64) => vec![Instruction::F64PromoteF32],
+        (F64, F32) => vec![Instruction::F32DemoteF64],
+        (float, integer) => {
+            let mut instructions = Vec::new();
+            let is_f32 = float == F32;
+
+            if round {
+                instructions.push(if is_f32 {
+                    Instruction::F32Nearest
+                } else {
+                    Instruction::F64Nearest
+                });
+            }
+
+            instructions.push(match (integer, to, is_f32) {
+                (I64, Scalar::U64, true) => Instruction::I64TruncUF32,
+                (I64, Scalar::U64, false) => Instruction::I64TruncUF64,
+                (I64, _, true) => Instruction::I64TruncSF32,
+                (I64, _, false) => Instruction::I64TruncSF64,
+                (_, Scalar::U32, true) => Instruction::I32TruncUF32,
+                (_, Scalar::U32, false) => Instruction::I32TruncUF64,
+                (_, _, true) => Instruction::I32TruncSF32,
+                (_, _, false) => Instruction::I32TruncSF64,
+            });
+
+            instructions
+        },
+    };
+
+    instructions.extend(to.normalize());
+    instructions
+}
+
+/// The instructions for a [`BinaryOp`] applied to two values of the same
+/// [`Scalar`], or `None` if the operation isn't supported.
+pub(crate) fn binary(op: BinaryOp, scalar: Scalar) -> Option<Vec<Instruction>> {
+    use parity_wasm::elements::ValueType::{F32, F64, I32, I64};
+
+    let signed = scalar.is_signed();
+    let ty = scalar.value_type();
+
+    let instruction = match (op, ty) {
+        (BinaryOp::Add, I32) => Instruction::I32Add,
+        (BinaryOp::Add, I64) => Instruction::I64Add,
+        (BinaryOp::Add, F32) => Instruction::F32Add,
+        (BinaryOp::Add, F64) => Instruction::F64Add,
+        (BinaryOp::Subtract, I32) => Instruction::I32Sub,
+        (BinaryOp::Subtract, I64) => Instruction::I64Sub,
+        (BinaryOp::Subtract, F32) => Instruction::F32Sub,
+        (BinaryOp::Subtract, F64) => Instruction::F64Sub,
+        (BinaryOp::Multiply, I32) => Instruction::I32Mul,
+        (BinaryOp::Multiply, I64) => Instruction::I64Mul,
+        (BinaryOp::Multiply, F32) => Instruction::F32Mul,
+        (BinaryOp::Multiply, F64) => Instruction::F64Mul,
+        (BinaryOp::Divide, I32) if signed => Instruction::I32DivS,
+        (BinaryOp::Divide, I32) => Instruction::I32DivU,
+        (BinaryOp::Divide, I64) if signed => Instruction::I64DivS,
+        (BinaryOp::Divide, I64) => Instruction::I64DivU,
+        (BinaryOp::Divide, F32) => Instruction::F32Div,
+        (BinaryOp::Divide, F64) => Instruction::F64Div,
+        (BinaryOp::Modulo, I32) if signed => Instruction::I32RemS,
+        (BinaryOp::Modulo, I32) => Instruction::I32RemU,
+        (BinaryOp::Modulo, I64) if signed => Instruction::I64RemS,
+        (BinaryOp::Modulo, I64) => Instruction::I64RemU,
+        (BinaryOp::And, I32) => Instruction::I32And,
+        (BinaryOp::And, I64) => Instruction::I64And,
+        (BinaryOp::Or, I32) => Instruction::I32Or,
+        (BinaryOp::Or, I64) => Instruction::I64Or,
+        (BinaryOp::Xor, I32) => Instruction::I32Xor,
+        (BinaryOp::Xor, I64) => Instruction::I64Xor,
+        (comparison, _) if comparison.is_comparison() => {
+            return Some(vec![compare(comparison, ty, signed)]);
+        },
+        _ => return None,
+    };
+
+    let mut instructions = vec![instruction];
+    instructions.extend(scalar.normalize());
+    Some(instructions)
+}
+
+fn compare(op: BinaryOp, ty: ValueType, signed: bool) -> Instruction {
+    use parity_wasm::elements::ValueType::{F32, F64, I32, I64};
+
+    match (op, ty, signed) {
+        (BinaryOp::Equals, I32, _) => Instruction::I32Eq,
+        (BinaryOp::Equals, I64, _) => Instruction::I64Eq,
+        (BinaryOp::Equals, F32, _) => Instruction::F32Eq,
+        (BinaryOp::Equals, F64, _) => Instruction::F64Eq,
+        (BinaryOp::NotEquals, I32, _) => Instruction::I32Ne,
+        (BinaryOp::NotEquals, I64, _) => Instruction::I64Ne,
+        (BinaryOp::NotEquals, F32, _) => Instruction::F32Ne,
+        (BinaryOp::NotEquals, F64, _) => Instruction::F64Ne,
+        (BinaryOp::LessThan, I32, true) => Instruction::I32LtS,
+        (BinaryOp::LessThan, I32, false) => Instruction::I32LtU,
+        (BinaryOp::LessThan, I64, true) => Instruction::I64LtS,
+        (BinaryOp::LessThan, I64, false) => Instruction::I64LtU,
+        (BinaryOp::LessThan, F32, _) => Instruction::F32Lt,
+        (BinaryOp::LessThan, F64, _) => Instruction::F64Lt,
+        (BinaryOp::LessThanOrEqual, I32, true) => Instruction::I32LeS,
+        (BinaryOp::LessThanOrEqual, I32, false) => Instruction::I32LeU,
+        (BinaryOp::LessThanOrEqual, I64, true) => Instruction::I64LeS,
+        (BinaryOp::LessThanOrEqual, I64, false) => Instruction::I64LeU,
+        (BinaryOp::LessThanOrEqual, F32, _) => Instruction::F32Le,
+        (BinaryOp::LessThanOrEqual, F64, _) => Instruction::F64Le,
+        (BinaryOp::GreaterThan, I32, true) => Instruction::I32GtS,
+        (BinaryOp::GreaterThan, I32, false) => Instruction::I32GtU,
+        (BinaryOp::GreaterThan, I64, true) => Instruction::I64GtS,
+        (BinaryOp::GreaterThan, I64, false) => Instruction::I64GtU,
+        (BinaryOp::GreaterThan, F32, _) => Instruction::F32Gt,
+        (BinaryOp::GreaterThan, F64, _) => Instruction::F64Gt,
+        (BinaryOp::GreaterThanOrEqual, I32, true) => Instruction::I32GeS,
+        (BinaryOp::GreaterThanOrEqual, I32, false) => Instruction::I32GeU,
+        (BinaryOp::GreaterThanOrEqual, I64, true) => Instruction::I64GeS,
+        (BinaryOp::GreaterThanOrEqual, I64, false) => Instruction::I64GeU,
+        (BinaryOp::GreaterThanOrEqual, F32, _) => Instruction::F32Ge,
+        (BinaryOp::GreaterThanOrEqual, F64, _) => Instruction::F64Ge,
+        _ => unreachable!("{:?} isn't a comparison", op),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parity_wasm::elements::{
+        CodeSection, ExportEntry, ExportSection, Func, FuncBody,
+        FunctionSection, FunctionType, Instructions, Internal, Module, Section,
+        Type as WasmType, TypeSection,
+    };
+    use wasmi::{
+        nan_preserving_float::{F32, F64},
+        ImportsBuilder, ModuleInstance, NopExternals, RuntimeValue,
+    };
+
+    /// Run some instructions inside a function which pushes its arguments
+    /// onto the stack, returning whatever is left on top.
+    fn run(
+        param: ValueType,
+        args: &[RuntimeValue],
+        ret: ValueType,
+        mut instructions: Vec<Instruction>,
+    ) -> RuntimeValue {
+        let params = vec![param; args.len()];
+        let mut body: Vec<_> =
+            (0..args.len() as u32).map(Instruction::GetLocal).collect();
+        body.append(&mut instructions);
+        body.push(Instruction::End);
+
+        let module = Module::new(vec![
+            Section::Type(TypeSection::with_types(vec![WasmType::Function(
+                FunctionType::new(params, Some(ret)),
+            )])),
+            Section::Function(FunctionSection::with_entries(vec![Func::new(
+                0,
+            )])),
+            Section::Export(ExportSection::with_entries(vec![
+                ExportEntry::new(String::from("f"), Internal::Function(0)),
+            ])),
+            Section::Code(CodeSection::with_bodies(vec![FuncBody::new(
+                Vec::new(),
+                Instructions::new(body),
+            )])),
+        ]);
+        let wasm = parity_wasm::serialize(module).unwrap();
+        let module = wasmi::Module::from_buffer(&wasm).unwrap();
+        let instance = ModuleInstance::new(&module, &ImportsBuilder::default())
+            .unwrap()
+            .assert_no_start();
+
+        instance
+            .invoke_export("f", args, &mut NopExternals)
+            .unwrap()
+            .unwrap()
+    }
+
+    fn apply(op: BinaryOp, scalar: Scalar, a: i64, b: i64) -> RuntimeValue {
+        let ret = if op.is_comparison() {
+            ValueType::I32
+        } else {
+            scalar.value_type()
+        };
+        let args = match scalar.value_type() {
+            ValueType::I64 => [RuntimeValue::I64(a), RuntimeValue::I64(b)],
+            _ => [RuntimeValue::I32(a as i32), RuntimeValue::I32(b as i32)],
+        };
+
+        run(scalar.value_type(), &args, ret, binary(op, scalar).unwrap())
+    }
+
+    fn cast(
+        from: Scalar,
+        to: Scalar,
+        round: bool,
+        value: RuntimeValue,
+    ) -> RuntimeValue {
+        run(
+            from.value_type(),
+            &[value],
+            to.value_type(),
+            convert(from, to, round),
+        )
+    }
+
+    #[test]
+    fn narrow_integers_wrap_around() {
+        let i32 = RuntimeValue::I32;
+
+        assert_eq!(apply(BinaryOp::Add, Scalar::I8, 100, 100), i32(-56));
+        assert_eq!(apply(BinaryOp::Add, Scalar::U8, 200, 100), i32(44));
+        assert_eq!(
+            apply(BinaryOp::Multiply, Scalar::I16, 300, 300),
+            i32(24464)
+        );
+        assert_eq!(apply(BinaryOp::Subtract, Scalar::U16, 0, 1), i32(65535));
+        assert_eq!(
+            apply(BinaryOp::Subtract, Scalar::I16, -32768, 1),
+            i32(32767)
+        );
+        assert_eq!(
+            apply(BinaryOp::Add, Scalar::I64, i64::max_value(), 1),
+            RuntimeValue::I64(i64::min_value())
+        );
+    }
+
+    #[test]
+    fn division_respects_signedness() {
+        let i32 = RuntimeValue::I32;
+
+        assert_eq!(apply(BinaryOp::Divide, Scalar::I32, -7, 2), i32(-3));
+        assert_eq!(apply(BinaryOp::Modulo, Scalar::I32, -7, 2), i32(-1));
+        assert_eq!(
+            apply(BinaryOp::Divide, Scalar::U32, -2, 2),
+            i32(0x7FFF_FFFF)
+        );
+        assert_eq!(apply(BinaryOp::Divide, Scalar::U8, 200, 3), i32(66));
+        assert_eq!(
+            apply(BinaryOp::Divide, Scalar::U64, -2, 2),
+            RuntimeValue::I64(i64::max_value())
+        );
+    }
+
+    #[test]
+    fn comparisons_respect_signedness() {
+        let (yes, no) = (RuntimeValue::I32(1), RuntimeValue::I32(0));
+
+        assert_eq!(apply(BinaryOp::LessThan, Scalar::I32, -1, 1), yes);
+        assert_eq!(apply(BinaryOp::LessThan, Scalar::U32, -1, 1), no);
+        assert_eq!(apply(BinaryOp::GreaterThan, Scalar::I64, -1, 1), no);
+        assert_eq!(apply(BinaryOp::GreaterThan, Scalar::U64, -1, 1), yes);
+        assert_eq!(apply(BinaryOp::LessThanOrEqual, Scalar::I8, 5, 5), yes);
+        assert_eq!(apply(BinaryOp::NotEquals, Scalar::U16, 5, 5), no);
+
+        let halves = [
+            RuntimeValue::F64(F64::from(0.5)),
+            RuntimeValue::F64(F64::from(1.5)),
+        ];
+        let less = binary(BinaryOp::LessThan, Scalar::F64).unwrap();
+        assert_eq!(run(ValueType::F64, &halves, ValueType::I32, less), yes);
+    }
+
+    #[test]
+    fn bitwise_operations_on_floats_are_unsupported() {
+        assert!(binary(BinaryOp::And, Scalar::F64).is_none());
+        assert!(binary(BinaryOp::Xor, Scalar::F32).is_none());
+    }
+
+    #[test]
+    fn widening_preserves_the_value() {
+        assert_eq!(
+            cast(Scalar::I8, Scalar::I64, false, RuntimeValue::I32(-5)),
+            RuntimeValue::I64(-5)
+        );
+        assert_eq!(
+            cast(Scalar::U16, Scalar::I32, false, RuntimeValue::I32(65535)),
+            RuntimeValue::I32(65535)
+        );
+        assert_eq!(
+            cast(Scalar::U32, Scalar::I64, false, RuntimeValue::I32(-1)),
+            RuntimeValue::I64(0xFFFF_FFFF)
+        );
+        assert_eq!(
+            cast(Scalar::I16, Scalar::F64, false, RuntimeValue::I32(-300)),
+            RuntimeValue::F64(F64::from(-300.0))
+        );
+        assert_eq!(
+            cast(
+                Scalar::F32,
+                Scalar::F64,
+                false,
+                RuntimeValue::F32(F32::from(1.5))
+            ),
+            RuntimeValue::F64(F64::from(1.5))
+        );
+    }
+
+    #[test]
+    fn narrowing_wraps_rounds_or_truncates() {
+        let float = |f: f64| RuntimeValue::F64(F64::from(f));
+
+        assert_eq!(
+            cast(Scalar::I32, Scalar::I8, false, RuntimeValue::I32(300)),
+            RuntimeValue::I32(44)
+        );
+        assert_eq!(
+            cast(Scalar::I64, Scalar::U16, false, RuntimeValue::I64(70000)),
+            RuntimeValue::I32(4464)
+        );
+        assert_eq!(
+            cast(Scalar::F64, Scalar::I16, false, float(2.7)),
+            RuntimeValue::I32(2)
+        );
+        // rounding goes to the nearest even number
+        assert_eq!(
+            cast(Scalar::F64, Scalar::I16, true, float(2.5)),
+            RuntimeValue::I32(2)
+        );
+        assert_eq!(
+            cast(Scalar::F64, Scalar::I16, true, float(3.5)),
+            RuntimeValue::I32(4)
+        );
+        assert_eq!(
+            cast(Scalar::I32, Scalar::Bool, false, RuntimeValue::I32(5)),
+            RuntimeValue::I32(1)
+        );
+    }
+}
@@ -0,0 +1,43 @@
+//! Compile *Structured Text* all the way to WebAssembly and make sure the
+//! result behaves when it is loaded by `rustmatic_wasm`.
+
+use codespan::Files;
+use rustmatic_iec::{frontend, wasm};
+use rustmatic_wasm::Program;
+use rustmatic_wasm_test::TestCase;
+use specs::World;
+
+fn compile(name: &str, src: &str) -> Vec<u8> {
+    let mut files = Files::new();
+    let id = files.add(name, src);
+    let file = rustmatic_structured_text::parse(src).unwrap();
+    let mut world = World::new();
+
+    let configuration =
+        frontend::translate_structured_text(vec![(id, file)], &mut world)
+            .unwrap();
+
+    wasm::compile(&world, configuration).unwrap()
+}
+
+macro_rules! st_test {
+    ($( $filename:ident ),* $(,)?) => {
+        $(
+            #[test]
+            fn $filename() {
+                let name = stringify!($filename);
+                let src = include_str!(concat!("data/", stringify!($filename), ".st"));
+                let recipe = include_str!(concat!("data/", stringify!($filename), ".json"));
+                let tc = TestCase::parse(name, src, recipe).unwrap();
+
+                let wasm = compile(name, src);
+                let mut program = Program::load(name, &wasm).unwrap();
+
+                rustmatic_wasm_test::run_recipe(name, &mut program, &tc.recipe)
+                    .unwrap();
+            }
+        )*
+    };
+}
+
+st_test!(start_stop, periodic_task);
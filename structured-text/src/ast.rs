@@ -3,8 +3,8 @@ use crate::{
     ParseError,
 };
 use codespan::Span;
-use pest::{iterators::Pair, Parser};
-use std::str::FromStr;
+use pest::{iterators::Pair, prec_climber::PrecClimber, Parser};
+use std::{convert::TryFrom, str::FromStr, time::Duration};
 
 #[cfg(test)]
 use pretty_assertions::assert_eq;
@@ -26,10 +26,68 @@ fn preamble(pair: Pair<'_, Rule>) -> Result<Vec<VarBlock>, ParseError> {
     serde(rename_all = "kebab-case")
 )]
 pub struct File {
+    pub configurations: Vec<Configuration>,
+    pub programs: Vec<Program>,
+    pub function_blocks: Vec<FunctionBlock>,
     pub functions: Vec<Function>,
+    pub types: Vec<TypeDeclaration>,
     pub span: Span,
 }
 
+impl File {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<File, ParseError> {
+        ParseError::expect_rule(Rule::file, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut configurations = Vec::new();
+        let mut programs = Vec::new();
+        let mut function_blocks = Vec::new();
+        let mut functions = Vec::new();
+        let mut types = Vec::new();
+
+        for item in pair.into_inner() {
+            match item.as_rule() {
+                Rule::configuration => {
+                    configurations.push(Configuration::from_pair(item)?)
+                },
+                Rule::program => programs.push(Program::from_pair(item)?),
+                Rule::function_block => {
+                    function_blocks.push(FunctionBlock::from_pair(item)?)
+                },
+                Rule::function => functions.push(Function::from_pair(item)?),
+                Rule::type_declarations => {
+                    for decl in item.into_inner() {
+                        types.push(TypeDeclaration::from_pair(decl)?);
+                    }
+                },
+                Rule::EOI => {},
+                _ => {
+                    return Err(ParseError::expected_one_of(
+                        &[
+                            Rule::configuration,
+                            Rule::program,
+                            Rule::function_block,
+                            Rule::function,
+                            Rule::type_declarations,
+                        ],
+                        item.as_span(),
+                    ))
+                },
+            }
+        }
+
+        Ok(File {
+            configurations,
+            programs,
+            function_blocks,
+            functions,
+            types,
+            span,
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(
     feature = "serde-1",
@@ -132,6 +190,268 @@ impl Program {
     }
 }
 
+/// A `CONFIGURATION`, describing how programs are deployed to the resources
+/// in a PLC.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct Configuration {
+    pub name: Identifier,
+    pub var_blocks: Vec<VarBlock>,
+    pub resources: Vec<Resource>,
+    pub span: Span,
+}
+
+impl Configuration {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<Configuration, ParseError> {
+        ParseError::expect_rule(Rule::configuration, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut items = pair.into_inner();
+        let name = Identifier::from_pair(items.next().unwrap())?;
+
+        let mut var_blocks = Vec::new();
+        let mut resources = Vec::new();
+
+        for item in items {
+            match item.as_rule() {
+                Rule::var_block => var_blocks.push(VarBlock::from_pair(item)?),
+                // tasks and programs declared directly inside the
+                // configuration belong to an anonymous resource
+                Rule::resource | Rule::resource_body => {
+                    resources.push(Resource::from_pair(item)?)
+                },
+                _ => {
+                    return Err(ParseError::expected_one_of(
+                        &[Rule::var_block, Rule::resource, Rule::resource_body],
+                        item.as_span(),
+                    ))
+                },
+            }
+        }
+
+        Ok(Configuration {
+            name,
+            var_blocks,
+            resources,
+            span,
+        })
+    }
+}
+
+/// Something capable of executing programs (e.g. a CPU).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct Resource {
+    /// The resource's name, or `None` when the tasks and programs were
+    /// declared directly inside a [`Configuration`].
+    pub name: Option<Identifier>,
+    /// The kind of processor this resource runs `ON`.
+    pub processor: Option<Identifier>,
+    pub var_blocks: Vec<VarBlock>,
+    pub tasks: Vec<Task>,
+    pub programs: Vec<ProgramConfiguration>,
+    pub span: Span,
+}
+
+impl Resource {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<Resource, ParseError> {
+        let span = to_span(pair.as_span());
+
+        let mut resource = Resource {
+            name: None,
+            processor: None,
+            var_blocks: Vec::new(),
+            tasks: Vec::new(),
+            programs: Vec::new(),
+            span,
+        };
+
+        match pair.as_rule() {
+            Rule::resource_body => {
+                resource.add_body(pair)?;
+                return Ok(resource);
+            },
+            Rule::resource => {},
+            _ => {
+                return Err(ParseError::expected_one_of(
+                    &[Rule::resource, Rule::resource_body],
+                    pair.as_span(),
+                ))
+            },
+        }
+
+        for item in pair.into_inner() {
+            match item.as_rule() {
+                Rule::identifier if resource.name.is_none() => {
+                    resource.name = Some(Identifier::from_pair(item)?)
+                },
+                Rule::identifier => {
+                    resource.processor = Some(Identifier::from_pair(item)?)
+                },
+                Rule::var_block => {
+                    resource.var_blocks.push(VarBlock::from_pair(item)?)
+                },
+                Rule::resource_body => resource.add_body(item)?,
+                _ => {
+                    return Err(ParseError::expected_one_of(
+                        &[
+                            Rule::identifier,
+                            Rule::var_block,
+                            Rule::resource_body,
+                        ],
+                        item.as_span(),
+                    ))
+                },
+            }
+        }
+
+        Ok(resource)
+    }
+
+    fn add_body(&mut self, pair: Pair<'_, Rule>) -> Result<(), ParseError> {
+        ParseError::expect_rule(Rule::resource_body, &pair)?;
+
+        for item in pair.into_inner() {
+            match item.as_rule() {
+                Rule::task => self.tasks.push(Task::from_pair(item)?),
+                _ => self.programs.push(ProgramConfiguration::from_pair(item)?),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A task which controls when programs are executed (e.g.
+/// `TASK Fast(INTERVAL := T#10ms, PRIORITY := 1)`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct Task {
+    pub name: Identifier,
+    /// Run the task on the rising edge of this value.
+    pub single: Option<Expression>,
+    /// Run the task periodically.
+    pub interval: Option<Expression>,
+    pub priority: Option<IntegerLiteral>,
+    pub span: Span,
+}
+
+impl Task {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<Task, ParseError> {
+        ParseError::expect_rule(Rule::task, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut items = pair.into_inner();
+        let name = Identifier::from_pair(items.next().unwrap())?;
+
+        let mut single = None;
+        let mut interval = None;
+        let mut priority = None;
+
+        for item in items {
+            let pest_span = item.as_span();
+            let rule = item.as_rule();
+            let value = item.into_inner().nth(1).unwrap();
+
+            let already_set = match rule {
+                Rule::task_single => {
+                    single.replace(Expression::from_pair(value)?).is_some()
+                },
+                Rule::task_interval => {
+                    interval.replace(Expression::from_pair(value)?).is_some()
+                },
+                Rule::task_priority => priority
+                    .replace(IntegerLiteral::from_pair(value)?)
+                    .is_some(),
+                _ => {
+                    return Err(ParseError::expected_one_of(
+                        &[
+                            Rule::task_single,
+                            Rule::task_interval,
+                            Rule::task_priority,
+                        ],
+                        pest_span,
+                    ))
+                },
+            };
+
+            if already_set {
+                return Err(ParseError::custom(
+                    "The task property was already set",
+                    pest_span,
+                ));
+            }
+        }
+
+        Ok(Task {
+            name,
+            single,
+            interval,
+            priority,
+            span,
+        })
+    }
+}
+
+/// Create an instance of a program, optionally associating it with a
+/// [`Task`] (e.g. `PROGRAM Main WITH Fast : MainProg`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct ProgramConfiguration {
+    pub name: Identifier,
+    pub task: Option<Identifier>,
+    pub program_type: Identifier,
+    pub span: Span,
+}
+
+impl ProgramConfiguration {
+    fn from_pair(
+        pair: Pair<'_, Rule>,
+    ) -> Result<ProgramConfiguration, ParseError> {
+        ParseError::expect_rule(Rule::program_configuration, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut identifiers = pair
+            .into_inner()
+            .map(Identifier::from_pair)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let program_type = identifiers.pop().unwrap();
+        let task = if identifiers.len() > 1 {
+            identifiers.pop()
+        } else {
+            None
+        };
+        let name = identifiers.pop().unwrap();
+
+        Ok(ProgramConfiguration {
+            name,
+            task,
+            program_type,
+            span,
+        })
+    }
+}
+
 /// An identifier, typically used when naming variables or functions.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(
@@ -244,13 +564,16 @@ impl VarBlockKind {
 )]
 pub struct VariableDeclaration {
     pub name: Identifier,
-    pub declared_type: Identifier,
-    pub initial_value: Option<Expression>,
+    /// Where the variable is located in the process image, for variables
+    /// declared using `AT`.
+    pub address: Option<DirectAddress>,
+    pub declared_type: DataType,
+    pub initial_value: Option<Initializer>,
     pub span: Span,
 }
 
 impl VariableDeclaration {
-    fn from_pair(
+    pub(crate) fn from_pair(
         pair: Pair<'_, Rule>,
     ) -> Result<VariableDeclaration, ParseError> {
         ParseError::expect_rule(Rule::variable_decl, &pair)?;
@@ -259,453 +582,2219 @@ impl VariableDeclaration {
 
         let mut items = pair.into_inner();
         let name = Identifier::from_pair(items.next().unwrap())?;
-        let declared_type = Identifier::from_pair(items.next().unwrap())?;
+
+        let mut next = items.next().unwrap();
+        let address = if next.as_rule() == Rule::direct_address {
+            let address = DirectAddress::from_pair(next)?;
+            next = items.next().unwrap();
+            Some(address)
+        } else {
+            None
+        };
+
+        let declared_type = DataType::from_pair(next)?;
 
         // dont' forget to step past the assignment, if there was one
         let initial_value = match items.skip(1).next() {
-            Some(pair) => Some(Expression::from_pair(pair)?),
+            Some(pair) => Some(Initializer::from_pair(pair)?),
             None => None,
         };
 
         Ok(VariableDeclaration {
             span,
             name,
+            address,
             declared_type,
             initial_value,
         })
     }
 }
 
-/// An expression.
-#[derive(Debug, Clone, PartialEq)]
+/// A location in the process image (e.g. `%IX4.0` or `%QW2`).
+#[derive(Debug, Copy, Clone, PartialEq)]
 #[cfg_attr(
     feature = "serde-1",
     derive(serde_derive::Serialize, serde_derive::Deserialize),
     serde(rename_all = "kebab-case")
 )]
-pub enum Expression {
-    Variable(Identifier),
-    Literal(Literal),
-    BinaryExpression(BinaryExpression),
+pub struct DirectAddress {
+    pub area: AddressArea,
+    pub size: AddressSize,
+    /// The byte offset into the process image.
+    pub byte: u32,
+    /// Which bit to use, for [`AddressSize::Bit`] addresses.
+    pub bit: Option<u8>,
+    pub span: Span,
 }
 
-impl Expression {
-    fn from_pair(pair: Pair<'_, Rule>) -> Result<Expression, ParseError> {
-        match pair.as_rule() {
-            Rule::infix => Ok(Expression::BinaryExpression(
-                BinaryExpression::from_pair(pair)?,
-            )),
-            Rule::identifier => {
-                Ok(Expression::Variable(Identifier::from_pair(pair)?))
-            },
-            Rule::boolean | Rule::float | Rule::integer | Rule::string => {
-                Ok(Expression::Literal(Literal::from_pair(pair)?))
-            },
-            other => unimplemented!("{:#?}", other),
-        }
-    }
-}
+impl DirectAddress {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<DirectAddress, ParseError> {
+        ParseError::expect_rule(Rule::direct_address, &pair)?;
 
-#[derive(Debug, Clone, PartialEq)]
-#[cfg_attr(
-    feature = "serde-1",
-    derive(serde_derive::Serialize, serde_derive::Deserialize),
-    serde(rename_all = "kebab-case")
-)]
-pub struct Repeat {
-    pub block: Block,
-    pub condition: Assignment,
-    pub span: Span,
-}
+        let pest_span = pair.as_span();
+        let mut items = pair.into_inner();
 
-impl Repeat {
-    fn from_pair(pair: Pair<'_, Rule>) -> Result<Repeat, ParseError> {
-        ParseError::expect_rule(Rule::repeat, &pair)?;
+        let area = AddressArea::from_pair(items.next().unwrap())?;
 
-        let span = to_span(pair.as_span());
+        let mut next = items.next().unwrap();
+        let size = if next.as_rule() == Rule::address_byte {
+            // addresses without an explicit size refer to a single bit
+            AddressSize::Bit
+        } else {
+            let size = AddressSize::from_pair(next)?;
+            next = items.next().unwrap();
+            size
+        };
 
-        let mut items = pair.into_inner();
-        let block = Block::from_pair(items.next().unwrap())?;
-        let condition = Assignment::from_pair(items.next().unwrap())?;
+        let byte = next.as_str().parse().map_err(|_| {
+            ParseError::custom("The byte offset is too large", next.as_span())
+        })?;
 
-        Ok(Repeat {
-            block,
-            condition,
-            span,
+        let bit = match (size, items.next()) {
+            (AddressSize::Bit, Some(bit)) => match bit.as_str().parse() {
+                Ok(b) if b < 8 => Some(b),
+                _ => {
+                    return Err(ParseError::custom(
+                        "The bit offset must be between 0 and 7",
+                        bit.as_span(),
+                    ))
+                },
+            },
+            (AddressSize::Bit, None) => {
+                return Err(ParseError::custom(
+                    "Bit addresses need a bit offset (e.g. %IX4.0)",
+                    pest_span,
+                ))
+            },
+            (_, Some(bit)) => {
+                return Err(ParseError::custom(
+                    "Only bit addresses can have a bit offset",
+                    bit.as_span(),
+                ))
+            },
+            (_, None) => None,
+        };
+
+        Ok(DirectAddress {
+            area,
+            size,
+            byte,
+            bit,
+            span: to_span(pest_span),
         })
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// The part of the process image a [`DirectAddress`] refers to.
+#[derive(Debug, Copy, Clone, PartialEq)]
 #[cfg_attr(
     feature = "serde-1",
     derive(serde_derive::Serialize, serde_derive::Deserialize),
     serde(rename_all = "kebab-case")
 )]
-pub struct Block {
-    pub statements: Vec<Statement>,
-    pub span: Span,
+pub enum AddressArea {
+    /// `%I`
+    Input,
+    /// `%Q`
+    Output,
+    /// `%M`
+    Memory,
 }
 
-impl Block {
-    fn from_pair(pair: Pair<'_, Rule>) -> Result<Block, ParseError> {
-        ParseError::expect_rule(Rule::block, &pair)?;
-
-        let span = to_span(pair.as_span());
-        let statements = pair
-            .into_inner()
-            .map(Statement::from_pair)
-            .collect::<Result<Vec<_>, _>>()?;
-
-        Ok(Block { statements, span })
+impl AddressArea {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<AddressArea, ParseError> {
+        match pair.as_rule() {
+            Rule::input_area => Ok(AddressArea::Input),
+            Rule::output_area => Ok(AddressArea::Output),
+            Rule::memory_area => Ok(AddressArea::Memory),
+            _ => Err(ParseError::expected_one_of(
+                &[Rule::input_area, Rule::output_area, Rule::memory_area],
+                pair.as_span(),
+            )),
+        }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// How much data a [`DirectAddress`] refers to.
+#[derive(Debug, Copy, Clone, PartialEq)]
 #[cfg_attr(
     feature = "serde-1",
     derive(serde_derive::Serialize, serde_derive::Deserialize),
     serde(rename_all = "kebab-case")
 )]
-pub enum Statement {
-    Assignment(Assignment),
+pub enum AddressSize {
+    /// A single bit (`X`).
+    Bit,
+    /// 8 bits (`B`).
+    Byte,
+    /// 16 bits (`W`).
+    Word,
+    /// 32 bits (`D`).
+    DoubleWord,
+    /// 64 bits (`L`).
+    LongWord,
 }
 
-impl Statement {
-    fn from_pair(pair: Pair<'_, Rule>) -> Result<Statement, ParseError> {
-        if pair.as_rule() == Rule::statement {}
+impl AddressSize {
+    /// The number of bytes a value of this size takes up, rounding bits up.
+    pub fn size_in_bytes(self) -> usize {
+        match self {
+            AddressSize::Bit | AddressSize::Byte => 1,
+            AddressSize::Word => 2,
+            AddressSize::DoubleWord => 4,
+            AddressSize::LongWord => 8,
+        }
+    }
 
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<AddressSize, ParseError> {
         match pair.as_rule() {
-            Rule::statement => {
-                Statement::from_pair(pair.into_inner().next().unwrap())
-            },
-            Rule::assignment => {
-                Ok(Statement::Assignment(Assignment::from_pair(pair)?))
-            },
-            _ => unimplemented!(),
+            Rule::bit_size => Ok(AddressSize::Bit),
+            Rule::byte_size => Ok(AddressSize::Byte),
+            Rule::word_size => Ok(AddressSize::Word),
+            Rule::double_word_size => Ok(AddressSize::DoubleWord),
+            Rule::long_word_size => Ok(AddressSize::LongWord),
+            _ => Err(ParseError::expected_one_of(
+                &[
+                    Rule::bit_size,
+                    Rule::byte_size,
+                    Rule::word_size,
+                    Rule::double_word_size,
+                    Rule::long_word_size,
+                ],
+                pair.as_span(),
+            )),
         }
     }
 }
 
-/// An assignment statement (e.g. `x := 42`).
+/// A user-defined type from inside a `TYPE ... END_TYPE` block (e.g.
+/// `Percent : INT (0..100) := 50`).
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(
     feature = "serde-1",
     derive(serde_derive::Serialize, serde_derive::Deserialize),
     serde(rename_all = "kebab-case")
 )]
-pub struct Assignment {
-    pub variable: Identifier,
-    pub value: Expression,
+pub struct TypeDeclaration {
+    pub name: Identifier,
+    pub data_type: DataType,
+    pub initial_value: Option<Initializer>,
     pub span: Span,
 }
 
-impl Assignment {
-    fn from_pair(pair: Pair<'_, Rule>) -> Result<Assignment, ParseError> {
-        ParseError::expect_rule(Rule::assignment, &pair)?;
+impl TypeDeclaration {
+    pub(crate) fn from_pair(
+        pair: Pair<'_, Rule>,
+    ) -> Result<TypeDeclaration, ParseError> {
+        ParseError::expect_rule(Rule::type_declaration, &pair)?;
 
         let span = to_span(pair.as_span());
 
         let mut items = pair.into_inner();
-        let variable = Identifier::from_pair(items.next().unwrap())?;
-        ParseError::expect_rule(Rule::assign, &items.next().unwrap())?;
-        let value = Expression::from_pair(items.next().unwrap())?;
+        let name = Identifier::from_pair(items.next().unwrap())?;
+        let data_type = DataType::from_pair(items.next().unwrap())?;
 
-        Ok(Assignment {
-            variable,
+        let initial_value = match items.nth(1) {
+            Some(pair) => Some(Initializer::from_pair(pair)?),
+            None => None,
+        };
+
+        Ok(TypeDeclaration {
+            name,
+            data_type,
+            initial_value,
             span,
-            value,
         })
     }
 }
 
+/// The type used in a declaration.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(
     feature = "serde-1",
     derive(serde_derive::Serialize, serde_derive::Deserialize),
     serde(rename_all = "kebab-case")
 )]
-pub struct BinaryExpression {
-    pub left: Box<Expression>,
-    pub right: Box<Expression>,
-    pub op: BinaryOp,
+pub enum DataType {
+    /// An elementary type or a reference to another type by name (e.g.
+    /// `INT` or `MotorState`).
+    Named(Identifier),
+    Struct(StructType),
+    Enumerated(EnumeratedType),
+    Subrange(SubrangeType),
+    Array(ArrayType),
+}
+
+impl DataType {
+    pub fn span(&self) -> Span {
+        match self {
+            DataType::Named(name) => name.span,
+            DataType::Struct(s) => s.span,
+            DataType::Enumerated(e) => e.span,
+            DataType::Subrange(s) => s.span,
+            DataType::Array(a) => a.span,
+        }
+    }
+
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<DataType, ParseError> {
+        match pair.as_rule() {
+            Rule::identifier => {
+                Ok(DataType::Named(Identifier::from_pair(pair)?))
+            },
+            Rule::struct_type => {
+                Ok(DataType::Struct(StructType::from_pair(pair)?))
+            },
+            Rule::enumerated_type => {
+                Ok(DataType::Enumerated(EnumeratedType::from_pair(pair)?))
+            },
+            Rule::subrange_type => {
+                Ok(DataType::Subrange(SubrangeType::from_pair(pair)?))
+            },
+            Rule::array_type => {
+                Ok(DataType::Array(ArrayType::from_pair(pair)?))
+            },
+            _ => Err(ParseError::expected_one_of(
+                &[
+                    Rule::identifier,
+                    Rule::struct_type,
+                    Rule::enumerated_type,
+                    Rule::subrange_type,
+                    Rule::array_type,
+                ],
+                pair.as_span(),
+            )),
+        }
+    }
+}
+
+/// A `STRUCT ... END_STRUCT` type.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct StructType {
+    pub fields: Vec<VariableDeclaration>,
     pub span: Span,
 }
 
-impl BinaryExpression {
-    fn from_pair(pair: Pair<'_, Rule>) -> Result<BinaryExpression, ParseError> {
-        ParseError::expect_rule(Rule::infix, &pair)?;
+impl StructType {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<StructType, ParseError> {
+        ParseError::expect_rule(Rule::struct_type, &pair)?;
 
         let span = to_span(pair.as_span());
+        let fields = pair
+            .into_inner()
+            .map(VariableDeclaration::from_pair)
+            .collect::<Result<_, _>>()?;
 
-        let mut items = pair.into_inner();
-        let left = Box::new(Expression::from_pair(items.next().unwrap())?);
-        let op = BinaryOp::from_pair(items.next().unwrap())?;
-        let right = Box::new(Expression::from_pair(items.next().unwrap())?);
-
-        Ok(BinaryExpression {
-            left,
-            right,
-            op,
-            span,
-        })
+        Ok(StructType { fields, span })
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Hash, Eq)]
+/// An enumerated type (e.g. `(Idle, Running := 5, Stopped)`).
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(
     feature = "serde-1",
     derive(serde_derive::Serialize, serde_derive::Deserialize),
     serde(rename_all = "kebab-case")
 )]
-pub enum BinaryOp {
-    Equals,
-    Add,
-    Subtract,
-    Multiply,
-    Divide,
+pub struct EnumeratedType {
+    pub values: Vec<EnumeratedValue>,
+    pub span: Span,
 }
 
-impl BinaryOp {
-    fn from_pair(pair: Pair<'_, Rule>) -> Result<BinaryOp, ParseError> {
-        match pair.as_rule() {
-            Rule::plus => Ok(BinaryOp::Add),
-            Rule::minus => Ok(BinaryOp::Subtract),
-            Rule::multiply => Ok(BinaryOp::Multiply),
-            Rule::divide => Ok(BinaryOp::Divide),
-            Rule::equal => Ok(BinaryOp::Equals),
-            _ => Err(ParseError::custom(
-                "Unknown binary operator",
-                pair.as_span(),
-            )),
-        }
+impl EnumeratedType {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<EnumeratedType, ParseError> {
+        ParseError::expect_rule(Rule::enumerated_type, &pair)?;
+
+        let span = to_span(pair.as_span());
+        let values = pair
+            .into_inner()
+            .map(EnumeratedValue::from_pair)
+            .collect::<Result<_, _>>()?;
+
+        Ok(EnumeratedType { values, span })
     }
 }
 
+/// One of the values in an [`EnumeratedType`], optionally with an explicit
+/// integer value.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(
     feature = "serde-1",
     derive(serde_derive::Serialize, serde_derive::Deserialize),
     serde(rename_all = "kebab-case")
 )]
-pub enum Literal {
-    Integer(IntegerLiteral),
-    Float(FloatLiteral),
-    String(StringLiteral),
-    Boolean(BooleanLiteral),
+pub struct EnumeratedValue {
+    pub name: Identifier,
+    pub value: Option<SignedInteger>,
+    pub span: Span,
 }
 
-impl Literal {
-    fn from_pair(pair: Pair<'_, Rule>) -> Result<Literal, ParseError> {
-        match pair.as_rule() {
-            Rule::boolean => {
-                Ok(Literal::Boolean(BooleanLiteral::from_pair(pair)?))
-            },
-            Rule::float => Ok(Literal::Float(FloatLiteral::from_pair(pair)?)),
-            Rule::integer => {
-                Ok(Literal::Integer(IntegerLiteral::from_pair(pair)?))
-            },
-            Rule::string => {
-                Ok(Literal::String(StringLiteral::from_pair(pair)?))
-            },
-            _ => Err(ParseError::expected_one_of(
-                &[Rule::boolean, Rule::float, Rule::integer, Rule::string],
-                pair.as_span(),
-            )),
-        }
+impl EnumeratedValue {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<EnumeratedValue, ParseError> {
+        ParseError::expect_rule(Rule::enumerated_value, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut items = pair.into_inner();
+        let name = Identifier::from_pair(items.next().unwrap())?;
+        let value = match items.nth(1) {
+            Some(pair) => Some(SignedInteger::from_pair(pair)?),
+            None => None,
+        };
+
+        Ok(EnumeratedValue { name, value, span })
     }
 }
 
+/// An integer type restricted to a range of values (e.g. `INT (0..100)`).
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(
     feature = "serde-1",
     derive(serde_derive::Serialize, serde_derive::Deserialize),
     serde(rename_all = "kebab-case")
 )]
-pub struct IntegerLiteral {
-    pub value: u64,
+pub struct SubrangeType {
+    pub base_type: Identifier,
+    pub range: Subrange,
     pub span: Span,
 }
 
-impl IntegerLiteral {
-    fn from_pair(pair: Pair<'_, Rule>) -> Result<IntegerLiteral, ParseError> {
-        let span = to_span(pair.as_span());
-
-        if pair.as_rule() == Rule::integer {
-            // we need to re-wrap the IntegerLiteral so the span includes
-            // the leading 0x or 0b sigil
-            let lit =
-                IntegerLiteral::from_pair(pair.into_inner().next().unwrap())?;
+impl SubrangeType {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<SubrangeType, ParseError> {
+        ParseError::expect_rule(Rule::subrange_type, &pair)?;
 
-            return Ok(IntegerLiteral {
-                value: lit.value,
-                span,
-            });
-        }
+        let span = to_span(pair.as_span());
 
-        let radix = match pair.as_rule() {
-            Rule::integer_decimal => 10,
-            Rule::integer_hexadecimal => 16,
-            Rule::integer_binary => 2,
-            _ => {
-                return Err(ParseError::expected_one_of(
-                    &[
-                        Rule::integer_decimal,
-                        Rule::integer_hexadecimal,
-                        Rule::integer_binary,
-                    ],
-                    pair.as_span(),
-                ));
-            },
-        };
+        let mut items = pair.into_inner();
+        let base_type = Identifier::from_pair(items.next().unwrap())?;
+        let range = Subrange::from_pair(items.next().unwrap())?;
 
-        Ok(IntegerLiteral {
-            value: u64::from_str_radix(pair.as_str(), radix).unwrap(),
+        Ok(SubrangeType {
+            base_type,
+            range,
             span,
         })
     }
 }
 
+/// A (possibly multi-dimensional) array (e.g.
+/// `ARRAY [1..10, 0..3] OF REAL`).
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(
     feature = "serde-1",
     derive(serde_derive::Serialize, serde_derive::Deserialize),
     serde(rename_all = "kebab-case")
 )]
-pub struct StringLiteral {
-    pub value: String,
+pub struct ArrayType {
+    pub dimensions: Vec<Subrange>,
+    pub element_type: Box<DataType>,
     pub span: Span,
 }
 
-impl StringLiteral {
-    fn from_pair(pair: Pair<'_, Rule>) -> Result<StringLiteral, ParseError> {
-        ParseError::expect_rule(Rule::string, &pair)?;
+impl ArrayType {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<ArrayType, ParseError> {
+        ParseError::expect_rule(Rule::array_type, &pair)?;
 
-        Ok(StringLiteral {
-            value: pair.as_str().to_string(),
-            span: to_span(pair.as_span()),
+        let span = to_span(pair.as_span());
+
+        let mut dimensions = Vec::new();
+        let mut element_type = None;
+
+        for item in pair.into_inner() {
+            match item.as_rule() {
+                Rule::subrange => dimensions.push(Subrange::from_pair(item)?),
+                _ => element_type = Some(DataType::from_pair(item)?),
+            }
+        }
+
+        Ok(ArrayType {
+            dimensions,
+            element_type: Box::new(element_type.unwrap()),
+            span,
         })
     }
 }
 
+/// The initial value given to a variable or type.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(
     feature = "serde-1",
     derive(serde_derive::Serialize, serde_derive::Deserialize),
     serde(rename_all = "kebab-case")
 )]
-pub struct FloatLiteral {
-    pub value: f64,
+pub enum Initializer {
+    Expression(Expression),
+    Array(ArrayInitializer),
+    Struct(StructInitializer),
+}
+
+impl Initializer {
+    pub fn span(&self) -> Span {
+        match self {
+            Initializer::Expression(e) => e.span(),
+            Initializer::Array(a) => a.span,
+            Initializer::Struct(s) => s.span,
+        }
+    }
+
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<Initializer, ParseError> {
+        match pair.as_rule() {
+            Rule::array_initializer => {
+                Ok(Initializer::Array(ArrayInitializer::from_pair(pair)?))
+            },
+            Rule::struct_initializer => {
+                Ok(Initializer::Struct(StructInitializer::from_pair(pair)?))
+            },
+            _ => Ok(Initializer::Expression(Expression::from_pair(pair)?)),
+        }
+    }
+}
+
+/// An array initializer (e.g. `[1, 2, 3(0)]`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct ArrayInitializer {
+    pub elements: Vec<ArrayElement>,
     pub span: Span,
 }
 
-impl FloatLiteral {
-    fn from_pair(pair: Pair<'_, Rule>) -> Result<FloatLiteral, ParseError> {
-        ParseError::expect_rule(Rule::float, &pair)?;
+impl ArrayInitializer {
+    fn from_pair(
+        pair: Pair<'_, Rule>,
+    ) -> Result<ArrayInitializer, ParseError> {
+        ParseError::expect_rule(Rule::array_initializer, &pair)?;
 
-        Ok(FloatLiteral {
-            value: pair.as_str().parse().unwrap(),
-            span: to_span(pair.as_span()),
-        })
+        let span = to_span(pair.as_span());
+        let elements = pair
+            .into_inner()
+            .map(ArrayElement::from_pair)
+            .collect::<Result<_, _>>()?;
+
+        Ok(ArrayInitializer { elements, span })
     }
 }
 
+/// A single element in an [`ArrayInitializer`], which may be repeated
+/// several times (e.g. the `3(0)` in `[1, 2, 3(0)]`).
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(
     feature = "serde-1",
     derive(serde_derive::Serialize, serde_derive::Deserialize),
     serde(rename_all = "kebab-case")
 )]
-pub struct BooleanLiteral {
-    pub value: bool,
+pub struct ArrayElement {
+    pub repetitions: Option<IntegerLiteral>,
+    pub value: Initializer,
     pub span: Span,
 }
 
-impl BooleanLiteral {
-    fn from_pair(pair: Pair<'_, Rule>) -> Result<BooleanLiteral, ParseError> {
-        ParseError::expect_rule(Rule::boolean, &pair)?;
+impl ArrayElement {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<ArrayElement, ParseError> {
+        let span = to_span(pair.as_span());
 
-        let span = pair.as_span();
-        let inner = pair.into_inner().next().unwrap();
+        if pair.as_rule() != Rule::repeated_element {
+            return Ok(ArrayElement {
+                repetitions: None,
+                value: Initializer::from_pair(pair)?,
+                span,
+            });
+        }
 
-        let value = match inner.as_rule() {
-            Rule::boolean_true => true,
-            Rule::boolean_false => false,
-            _ => {
-                return Err(ParseError::expected_one_of(
-                    &[Rule::boolean_true, Rule::boolean_false],
-                    span,
-                ));
-            },
-        };
+        let mut items = pair.into_inner();
+        let repetitions = IntegerLiteral::from_pair(items.next().unwrap())?;
+        let value = Initializer::from_pair(items.next().unwrap())?;
 
-        Ok(BooleanLiteral {
+        Ok(ArrayElement {
+            repetitions: Some(repetitions),
             value,
-            span: to_span(span),
+            span,
         })
     }
 }
 
+/// A structure initializer (e.g. `(x := 1, y := 2)`).
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(
     feature = "serde-1",
     derive(serde_derive::Serialize, serde_derive::Deserialize),
     serde(rename_all = "kebab-case")
 )]
-pub struct ConditionalBranch {
-    pub condition: Expression,
-    pub block: Block,
+pub struct StructInitializer {
+    pub fields: Vec<FieldInitializer>,
+    pub span: Span,
 }
 
-impl ConditionalBranch {
+impl StructInitializer {
     fn from_pair(
         pair: Pair<'_, Rule>,
-    ) -> Result<ConditionalBranch, ParseError> {
-        let mut items = pair.into_inner();
+    ) -> Result<StructInitializer, ParseError> {
+        ParseError::expect_rule(Rule::struct_initializer, &pair)?;
 
-        let condition = Expression::from_pair(items.next().unwrap())?;
-        let block = Block::from_pair(items.next().unwrap())?;
+        let span = to_span(pair.as_span());
+        let fields = pair
+            .into_inner()
+            .map(FieldInitializer::from_pair)
+            .collect::<Result<_, _>>()?;
 
-        Ok(ConditionalBranch { condition, block })
+        Ok(StructInitializer { fields, span })
     }
 }
 
+/// The value given to one field in a [`StructInitializer`].
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(
     feature = "serde-1",
     derive(serde_derive::Serialize, serde_derive::Deserialize),
     serde(rename_all = "kebab-case")
 )]
-pub struct Conditional {
-    pub true_branch: ConditionalBranch,
-    pub else_if_branches: Vec<ConditionalBranch>,
-    pub else_branch: Option<Block>,
+pub struct FieldInitializer {
+    pub name: Identifier,
+    pub value: Initializer,
     pub span: Span,
 }
 
-impl Conditional {
-    fn from_pair(pair: Pair<'_, Rule>) -> Result<Conditional, ParseError> {
-        ParseError::expect_rule(Rule::conditional, &pair)?;
+impl FieldInitializer {
+    fn from_pair(
+        pair: Pair<'_, Rule>,
+    ) -> Result<FieldInitializer, ParseError> {
+        ParseError::expect_rule(Rule::field_initializer, &pair)?;
 
         let span = to_span(pair.as_span());
 
         let mut items = pair.into_inner();
-        let true_branch = ConditionalBranch::from_pair(items.next().unwrap())?;
+        let name = Identifier::from_pair(items.next().unwrap())?;
+        let value = Initializer::from_pair(items.nth(1).unwrap())?;
 
-        let mut else_if_branches = Vec::new();
-        let mut else_branch = None;
+        Ok(FieldInitializer { name, value, span })
+    }
+}
 
-        while let Some(branch) = items.next() {
-            match branch.as_rule() {
-                Rule::elsif_branch => {
-                    else_if_branches.push(ConditionalBranch::from_pair(branch)?)
-                },
-                Rule::else_branch => {
-                    else_branch = Some(Block::from_pair(branch)?);
-                    break;
-                },
-                _ => {
-                    return Err(ParseError::expected_one_of(
+/// An expression.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Expression {
+    Variable(Identifier),
+    Literal(Literal),
+    BinaryExpression(BinaryExpression),
+    UnaryExpression(UnaryExpression),
+    Call(Call),
+    MemberAccess(MemberAccess),
+}
+
+impl Expression {
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Variable(ident) => ident.span,
+            Expression::Literal(lit) => lit.span(),
+            Expression::BinaryExpression(bin) => bin.span,
+            Expression::UnaryExpression(unary) => unary.span,
+            Expression::Call(call) => call.span,
+            Expression::MemberAccess(access) => access.span,
+        }
+    }
+
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<Expression, ParseError> {
+        match pair.as_rule() {
+            Rule::infix => Ok(Expression::BinaryExpression(
+                BinaryExpression::from_pair(pair)?,
+            )),
+            Rule::unary => Ok(Expression::UnaryExpression(
+                UnaryExpression::from_pair(pair)?,
+            )),
+            Rule::call => Ok(Expression::Call(Call::from_pair(pair)?)),
+            Rule::member_access => {
+                Ok(Expression::MemberAccess(MemberAccess::from_pair(pair)?))
+            },
+            Rule::identifier => {
+                Ok(Expression::Variable(Identifier::from_pair(pair)?))
+            },
+            Rule::braced_expression => {
+                Expression::from_pair(pair.into_inner().next().unwrap())
+            },
+            Rule::boolean
+            | Rule::float
+            | Rule::integer
+            | Rule::string
+            | Rule::wstring
+            | Rule::typed_literal
+            | Rule::duration
+            | Rule::time_of_day
+            | Rule::date
+            | Rule::date_and_time => {
+                Ok(Expression::Literal(Literal::from_pair(pair)?))
+            },
+            _ => Err(ParseError::expected_one_of(
+                &[
+                    Rule::infix,
+                    Rule::unary,
+                    Rule::call,
+                    Rule::member_access,
+                    Rule::identifier,
+                    Rule::boolean,
+                    Rule::float,
+                    Rule::integer,
+                    Rule::string,
+                ],
+                pair.as_span(),
+            )),
+        }
+    }
+}
+
+/// Invoking a function or function block (e.g. `ADD(a, b)` or
+/// `timer(IN := start, Q => done)`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct Call {
+    pub function: Identifier,
+    pub arguments: Vec<Argument>,
+    pub span: Span,
+}
+
+impl Call {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<Call, ParseError> {
+        ParseError::expect_rule(Rule::call, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut items = pair.into_inner();
+        let function = Identifier::from_pair(items.next().unwrap())?;
+        let arguments = items
+            .map(Argument::from_pair)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Call {
+            function,
+            arguments,
+            span,
+        })
+    }
+}
+
+/// A single argument passed to a [`Call`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Argument {
+    Positional(Expression),
+    Input(InputArgument),
+    Output(OutputArgument),
+}
+
+impl Argument {
+    pub fn span(&self) -> Span {
+        match self {
+            Argument::Positional(value) => value.span(),
+            Argument::Input(input) => input.span,
+            Argument::Output(output) => output.span,
+        }
+    }
+
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<Argument, ParseError> {
+        match pair.as_rule() {
+            Rule::input_argument => {
+                Ok(Argument::Input(InputArgument::from_pair(pair)?))
+            },
+            Rule::output_argument => {
+                Ok(Argument::Output(OutputArgument::from_pair(pair)?))
+            },
+            _ => Ok(Argument::Positional(Expression::from_pair(pair)?)),
+        }
+    }
+}
+
+/// Binding an expression to one of a function's inputs (e.g. `IN := x`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct InputArgument {
+    pub name: Identifier,
+    pub value: Expression,
+    pub span: Span,
+}
+
+impl InputArgument {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<InputArgument, ParseError> {
+        ParseError::expect_rule(Rule::input_argument, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut items = pair.into_inner();
+        let name = Identifier::from_pair(items.next().unwrap())?;
+        ParseError::expect_rule(Rule::assign, &items.next().unwrap())?;
+        let value = Expression::from_pair(items.next().unwrap())?;
+
+        Ok(InputArgument { name, value, span })
+    }
+}
+
+/// Storing one of a function's outputs in a variable (e.g. `Q => done`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct OutputArgument {
+    pub name: Identifier,
+    pub variable: Identifier,
+    pub span: Span,
+}
+
+impl OutputArgument {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<OutputArgument, ParseError> {
+        ParseError::expect_rule(Rule::output_argument, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut items = pair.into_inner();
+        let name = Identifier::from_pair(items.next().unwrap())?;
+        let variable = Identifier::from_pair(items.next().unwrap())?;
+
+        Ok(OutputArgument {
+            name,
+            variable,
+            span,
+        })
+    }
+}
+
+/// Reading a field from a struct or function block instance (e.g.
+/// `timer.Q`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct MemberAccess {
+    pub value: Box<Expression>,
+    pub member: Identifier,
+    pub span: Span,
+}
+
+impl MemberAccess {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<MemberAccess, ParseError> {
+        ParseError::expect_rule(Rule::member_access, &pair)?;
+
+        let mut items = pair.into_inner();
+        let mut value =
+            Expression::Variable(Identifier::from_pair(items.next().unwrap())?);
+
+        // "a.b.c" is parsed as "(a.b).c"
+        for item in items {
+            let member = Identifier::from_pair(item)?;
+            let span = Span::new(value.span().start(), member.span.end());
+
+            value = Expression::MemberAccess(MemberAccess {
+                value: Box::new(value),
+                member,
+                span,
+            });
+        }
+
+        match value {
+            Expression::MemberAccess(access) => Ok(access),
+            _ => unreachable!("The grammar requires at least one member"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct Repeat {
+    pub block: Block,
+    pub condition: Assignment,
+    pub span: Span,
+}
+
+impl Repeat {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<Repeat, ParseError> {
+        ParseError::expect_rule(Rule::repeat, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut items = pair.into_inner();
+        let block = Block::from_pair(items.next().unwrap())?;
+        let condition = Assignment::from_pair(items.next().unwrap())?;
+
+        Ok(Repeat {
+            block,
+            condition,
+            span,
+        })
+    }
+}
+
+/// A `WHILE` loop (e.g. `WHILE x < 10 DO ... END_WHILE;`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct While {
+    pub condition: Expression,
+    pub body: Block,
+    pub span: Span,
+}
+
+impl While {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<While, ParseError> {
+        ParseError::expect_rule(Rule::while_loop, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut items = pair.into_inner();
+        let condition = Expression::from_pair(items.next().unwrap())?;
+        let body = Block::from_pair(items.next().unwrap())?;
+
+        Ok(While {
+            condition,
+            body,
+            span,
+        })
+    }
+}
+
+/// A counting loop (e.g. `FOR i := 1 TO 10 BY 2 DO ... END_FOR;`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct For {
+    pub variable: Identifier,
+    pub start: Expression,
+    pub end: Expression,
+    pub step: Option<Expression>,
+    pub body: Block,
+    pub span: Span,
+}
+
+impl For {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<For, ParseError> {
+        ParseError::expect_rule(Rule::for_loop, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut items = pair.into_inner();
+        let variable = Identifier::from_pair(items.next().unwrap())?;
+        ParseError::expect_rule(Rule::assign, &items.next().unwrap())?;
+        let start = Expression::from_pair(items.next().unwrap())?;
+        let end = Expression::from_pair(items.next().unwrap())?;
+
+        // the "BY" clause is optional
+        let mut next = items.next().unwrap();
+        let step = if next.as_rule() == Rule::block {
+            None
+        } else {
+            let step = Expression::from_pair(next)?;
+            next = items.next().unwrap();
+            Some(step)
+        };
+
+        let body = Block::from_pair(next)?;
+
+        Ok(For {
+            variable,
+            start,
+            end,
+            step,
+            body,
+            span,
+        })
+    }
+}
+
+/// Break out of the innermost loop.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct Exit {
+    pub span: Span,
+}
+
+impl Exit {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<Exit, ParseError> {
+        ParseError::expect_rule(Rule::exit, &pair)?;
+
+        Ok(Exit {
+            span: to_span(pair.as_span()),
+        })
+    }
+}
+
+/// A `CASE` statement (e.g. `CASE state OF 1, 2: ... ELSE ... END_CASE;`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct Case {
+    pub selector: Expression,
+    pub branches: Vec<CaseBranch>,
+    pub else_branch: Option<Block>,
+    pub span: Span,
+}
+
+impl Case {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<Case, ParseError> {
+        ParseError::expect_rule(Rule::case, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut items = pair.into_inner();
+        let selector = Expression::from_pair(items.next().unwrap())?;
+
+        let mut branches = Vec::new();
+        let mut else_branch = None;
+
+        for item in items {
+            match item.as_rule() {
+                Rule::case_branch => {
+                    branches.push(CaseBranch::from_pair(item)?)
+                },
+                Rule::case_else => {
+                    let block = item.into_inner().next().unwrap();
+                    else_branch = Some(Block::from_pair(block)?);
+                },
+                _ => {
+                    return Err(ParseError::expected_one_of(
+                        &[Rule::case_branch, Rule::case_else],
+                        item.as_span(),
+                    ))
+                },
+            }
+        }
+
+        Ok(Case {
+            selector,
+            branches,
+            else_branch,
+            span,
+        })
+    }
+}
+
+/// The statements to execute when a [`Case`] selector matches one of the
+/// `labels`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct CaseBranch {
+    pub labels: Vec<CaseLabel>,
+    pub block: Block,
+    pub span: Span,
+}
+
+impl CaseBranch {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<CaseBranch, ParseError> {
+        ParseError::expect_rule(Rule::case_branch, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut labels = Vec::new();
+        let mut block = None;
+
+        for item in pair.into_inner() {
+            if item.as_rule() == Rule::block {
+                block = Some(Block::from_pair(item)?);
+            } else {
+                labels.push(CaseLabel::from_pair(item)?);
+            }
+        }
+
+        Ok(CaseBranch {
+            labels,
+            block: block.unwrap(),
+            span,
+        })
+    }
+}
+
+/// A single label in a [`CaseBranch`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum CaseLabel {
+    Integer(SignedInteger),
+    Range(Subrange),
+    /// A value from an enumerated type (e.g. `Idle`).
+    Enumerated(Identifier),
+}
+
+impl CaseLabel {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<CaseLabel, ParseError> {
+        match pair.as_rule() {
+            Rule::signed_integer => {
+                Ok(CaseLabel::Integer(SignedInteger::from_pair(pair)?))
+            },
+            Rule::subrange => Ok(CaseLabel::Range(Subrange::from_pair(pair)?)),
+            Rule::identifier => {
+                Ok(CaseLabel::Enumerated(Identifier::from_pair(pair)?))
+            },
+            _ => Err(ParseError::expected_one_of(
+                &[Rule::signed_integer, Rule::subrange, Rule::identifier],
+                pair.as_span(),
+            )),
+        }
+    }
+}
+
+/// An inclusive range of integers (e.g. `3..10`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct Subrange {
+    pub start: SignedInteger,
+    pub end: SignedInteger,
+    pub span: Span,
+}
+
+impl Subrange {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<Subrange, ParseError> {
+        ParseError::expect_rule(Rule::subrange, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut items = pair.into_inner();
+        let start = SignedInteger::from_pair(items.next().unwrap())?;
+        let end = SignedInteger::from_pair(items.next().unwrap())?;
+
+        Ok(Subrange { start, end, span })
+    }
+}
+
+/// An integer constant which may be negative (e.g. `-42`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct SignedInteger {
+    pub value: i64,
+    pub span: Span,
+}
+
+impl SignedInteger {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<SignedInteger, ParseError> {
+        ParseError::expect_rule(Rule::signed_integer, &pair)?;
+
+        let pest_span = pair.as_span();
+        let mut items = pair.into_inner();
+
+        let mut next = items.next().unwrap();
+        let negative = next.as_rule() == Rule::minus;
+        if negative {
+            next = items.next().unwrap();
+        }
+
+        let magnitude = i128::from(IntegerLiteral::from_pair(next)?.value);
+        let value = if negative { -magnitude } else { magnitude };
+
+        match i64::try_from(value) {
+            Ok(value) => Ok(SignedInteger {
+                value,
+                span: to_span(pest_span),
+            }),
+            Err(_) => Err(ParseError::custom(
+                "The integer is out of range",
+                pest_span,
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct Block {
+    pub statements: Vec<Statement>,
+    pub span: Span,
+}
+
+impl Block {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<Block, ParseError> {
+        ParseError::expect_rule(Rule::block, &pair)?;
+
+        let span = to_span(pair.as_span());
+        let statements = pair
+            .into_inner()
+            .map(Statement::from_pair)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Block { statements, span })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Statement {
+    Assignment(Assignment),
+    Repeat(Repeat),
+    Conditional(Conditional),
+    While(While),
+    For(For),
+    Case(Case),
+    Call(Call),
+    Exit(Exit),
+}
+
+impl Statement {
+    pub(crate) fn from_pair(
+        pair: Pair<'_, Rule>,
+    ) -> Result<Statement, ParseError> {
+        match pair.as_rule() {
+            Rule::statement => {
+                Statement::from_pair(pair.into_inner().next().unwrap())
+            },
+            Rule::assignment => {
+                Ok(Statement::Assignment(Assignment::from_pair(pair)?))
+            },
+            Rule::repeat => Ok(Statement::Repeat(Repeat::from_pair(pair)?)),
+            Rule::conditional => {
+                Ok(Statement::Conditional(Conditional::from_pair(pair)?))
+            },
+            Rule::while_loop => Ok(Statement::While(While::from_pair(pair)?)),
+            Rule::for_loop => Ok(Statement::For(For::from_pair(pair)?)),
+            Rule::case => Ok(Statement::Case(Case::from_pair(pair)?)),
+            Rule::call => Ok(Statement::Call(Call::from_pair(pair)?)),
+            Rule::exit => Ok(Statement::Exit(Exit::from_pair(pair)?)),
+            _ => Err(ParseError::expected_one_of(
+                &[
+                    Rule::assignment,
+                    Rule::repeat,
+                    Rule::conditional,
+                    Rule::while_loop,
+                    Rule::for_loop,
+                    Rule::case,
+                    Rule::call,
+                    Rule::exit,
+                ],
+                pair.as_span(),
+            )),
+        }
+    }
+}
+
+/// An assignment statement (e.g. `x := 42`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct Assignment {
+    pub variable: Identifier,
+    pub value: Expression,
+    pub span: Span,
+}
+
+impl Assignment {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<Assignment, ParseError> {
+        ParseError::expect_rule(Rule::assignment, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut items = pair.into_inner();
+        let variable = Identifier::from_pair(items.next().unwrap())?;
+        ParseError::expect_rule(Rule::assign, &items.next().unwrap())?;
+        let value = Expression::from_pair(items.next().unwrap())?;
+
+        Ok(Assignment {
+            variable,
+            span,
+            value,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct BinaryExpression {
+    pub left: Box<Expression>,
+    pub right: Box<Expression>,
+    pub op: BinaryOp,
+    pub span: Span,
+}
+
+impl BinaryExpression {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<BinaryExpression, ParseError> {
+        ParseError::expect_rule(Rule::infix, &pair)?;
+
+        let span = pair.as_span();
+        // operands are tracked alongside the span of their pair so a
+        // parenthesised operand's parentheses are part of the parent's span
+        let (expr, _) = operator_precedence().climb(
+            pair.into_inner(),
+            |operand| {
+                let span = to_span(operand.as_span());
+                Ok::<_, ParseError>((Expression::from_pair(operand)?, span))
+            },
+            |left, op, right| {
+                let (left, left_span) = left?;
+                let (right, right_span) = right?;
+                let op = BinaryOp::from_pair(op)?;
+                let span = Span::new(left_span.start(), right_span.end());
+
+                let expr = Expression::BinaryExpression(BinaryExpression {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    op,
+                    span,
+                });
+                Ok((expr, span))
+            },
+        )?;
+
+        match expr {
+            Expression::BinaryExpression(mut bin) => {
+                bin.span = to_span(span);
+                Ok(bin)
+            },
+            _ => Err(ParseError::custom("Expected a binary expression", span)),
+        }
+    }
+}
+
+/// The IEC 61131-3 operator table, from lowest to highest precedence. Operators
+/// with equal precedence are evaluated from left to right.
+fn operator_precedence() -> PrecClimber<Rule> {
+    use pest::prec_climber::{Assoc::Left, Operator};
+
+    PrecClimber::new(vec![
+        Operator::new(Rule::or, Left),
+        Operator::new(Rule::xor, Left),
+        Operator::new(Rule::and, Left),
+        Operator::new(Rule::equal, Left) | Operator::new(Rule::not_equal, Left),
+        Operator::new(Rule::less_than, Left)
+            | Operator::new(Rule::less_than_or_equal, Left)
+            | Operator::new(Rule::greater_than, Left)
+            | Operator::new(Rule::greater_than_or_equal, Left),
+        Operator::new(Rule::plus, Left) | Operator::new(Rule::minus, Left),
+        Operator::new(Rule::multiply, Left)
+            | Operator::new(Rule::divide, Left)
+            | Operator::new(Rule::modulus, Left),
+        Operator::new(Rule::power, Left),
+    ])
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Hash, Eq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum BinaryOp {
+    Equals,
+    NotEquals,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Exponent,
+    And,
+    Xor,
+    Or,
+}
+
+impl BinaryOp {
+    /// How this operator is written in source code.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Equals => "=",
+            BinaryOp::NotEquals => "<>",
+            BinaryOp::LessThan => "<",
+            BinaryOp::LessThanOrEqual => "<=",
+            BinaryOp::GreaterThan => ">",
+            BinaryOp::GreaterThanOrEqual => ">=",
+            BinaryOp::Add => "+",
+            BinaryOp::Subtract => "-",
+            BinaryOp::Multiply => "*",
+            BinaryOp::Divide => "/",
+            BinaryOp::Modulo => "MOD",
+            BinaryOp::Exponent => "**",
+            BinaryOp::And => "AND",
+            BinaryOp::Xor => "XOR",
+            BinaryOp::Or => "OR",
+        }
+    }
+
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<BinaryOp, ParseError> {
+        match pair.as_rule() {
+            Rule::plus => Ok(BinaryOp::Add),
+            Rule::minus => Ok(BinaryOp::Subtract),
+            Rule::multiply => Ok(BinaryOp::Multiply),
+            Rule::divide => Ok(BinaryOp::Divide),
+            Rule::modulus => Ok(BinaryOp::Modulo),
+            Rule::power => Ok(BinaryOp::Exponent),
+            Rule::equal => Ok(BinaryOp::Equals),
+            Rule::not_equal => Ok(BinaryOp::NotEquals),
+            Rule::less_than => Ok(BinaryOp::LessThan),
+            Rule::less_than_or_equal => Ok(BinaryOp::LessThanOrEqual),
+            Rule::greater_than => Ok(BinaryOp::GreaterThan),
+            Rule::greater_than_or_equal => Ok(BinaryOp::GreaterThanOrEqual),
+            Rule::and => Ok(BinaryOp::And),
+            Rule::xor => Ok(BinaryOp::Xor),
+            Rule::or => Ok(BinaryOp::Or),
+            _ => Err(ParseError::custom(
+                "Unknown binary operator",
+                pair.as_span(),
+            )),
+        }
+    }
+}
+
+/// An operator applied to a single operand (e.g. `NOT x` or `-y`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct UnaryExpression {
+    pub op: UnaryOp,
+    pub value: Box<Expression>,
+    pub span: Span,
+}
+
+impl UnaryExpression {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<UnaryExpression, ParseError> {
+        ParseError::expect_rule(Rule::unary, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut items = pair.into_inner();
+        let op = UnaryOp::from_pair(items.next().unwrap())?;
+        let value = Box::new(Expression::from_pair(items.next().unwrap())?);
+
+        Ok(UnaryExpression { op, value, span })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Hash, Eq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum UnaryOp {
+    Not,
+    Negate,
+    Plus,
+}
+
+impl UnaryOp {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<UnaryOp, ParseError> {
+        match pair.as_rule() {
+            Rule::not => Ok(UnaryOp::Not),
+            Rule::minus => Ok(UnaryOp::Negate),
+            Rule::plus => Ok(UnaryOp::Plus),
+            _ => Err(ParseError::custom(
+                "Unknown unary operator",
+                pair.as_span(),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Literal {
+    Integer(IntegerLiteral),
+    Float(FloatLiteral),
+    String(StringLiteral),
+    WString(StringLiteral),
+    Boolean(BooleanLiteral),
+    Typed(TypedLiteral),
+    Duration(DurationLiteral),
+    TimeOfDay(TimeOfDayLiteral),
+    Date(DateLiteral),
+    DateAndTime(DateAndTimeLiteral),
+}
+
+impl Literal {
+    pub fn span(&self) -> Span {
+        match self {
+            Literal::Integer(int) => int.span,
+            Literal::Float(float) => float.span,
+            Literal::String(string) | Literal::WString(string) => string.span,
+            Literal::Boolean(boolean) => boolean.span,
+            Literal::Typed(typed) => typed.span,
+            Literal::Duration(duration) => duration.span,
+            Literal::TimeOfDay(tod) => tod.span,
+            Literal::Date(date) => date.span,
+            Literal::DateAndTime(dt) => dt.span,
+        }
+    }
+
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<Literal, ParseError> {
+        match pair.as_rule() {
+            Rule::boolean => {
+                Ok(Literal::Boolean(BooleanLiteral::from_pair(pair)?))
+            },
+            Rule::float => Ok(Literal::Float(FloatLiteral::from_pair(pair)?)),
+            Rule::integer => {
+                Ok(Literal::Integer(IntegerLiteral::from_pair(pair)?))
+            },
+            Rule::string => {
+                Ok(Literal::String(StringLiteral::from_pair(pair)?))
+            },
+            Rule::wstring => {
+                Ok(Literal::WString(StringLiteral::from_pair(pair)?))
+            },
+            Rule::typed_literal => {
+                Ok(Literal::Typed(TypedLiteral::from_pair(pair)?))
+            },
+            Rule::duration => {
+                Ok(Literal::Duration(DurationLiteral::from_pair(pair)?))
+            },
+            Rule::time_of_day => {
+                Ok(Literal::TimeOfDay(TimeOfDayLiteral::from_pair(pair)?))
+            },
+            Rule::date => Ok(Literal::Date(DateLiteral::from_pair(pair)?)),
+            Rule::date_and_time => {
+                Ok(Literal::DateAndTime(DateAndTimeLiteral::from_pair(pair)?))
+            },
+            _ => Err(ParseError::expected_one_of(
+                &[
+                    Rule::boolean,
+                    Rule::float,
+                    Rule::integer,
+                    Rule::string,
+                    Rule::wstring,
+                    Rule::typed_literal,
+                    Rule::duration,
+                    Rule::time_of_day,
+                    Rule::date,
+                    Rule::date_and_time,
+                ],
+                pair.as_span(),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct IntegerLiteral {
+    pub value: u64,
+    pub span: Span,
+}
+
+impl IntegerLiteral {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<IntegerLiteral, ParseError> {
+        let span = to_span(pair.as_span());
+
+        if pair.as_rule() == Rule::integer {
+            // we need to re-wrap the IntegerLiteral so the span includes
+            // the leading 0x or 0b sigil
+            let lit =
+                IntegerLiteral::from_pair(pair.into_inner().next().unwrap())?;
+
+            return Ok(IntegerLiteral {
+                value: lit.value,
+                span,
+            });
+        }
+
+        let radix = match pair.as_rule() {
+            Rule::integer_decimal | Rule::integer_zero => 10,
+            Rule::integer_hexadecimal => 16,
+            Rule::integer_octal => 8,
+            Rule::integer_binary => 2,
+            _ => {
+                return Err(ParseError::expected_one_of(
+                    &[
+                        Rule::integer_decimal,
+                        Rule::integer_hexadecimal,
+                        Rule::integer_octal,
+                        Rule::integer_binary,
+                    ],
+                    pair.as_span(),
+                ));
+            },
+        };
+
+        // underscores are only there for readability
+        let digits = pair.as_str().replace('_', "");
+
+        match u64::from_str_radix(&digits, radix) {
+            Ok(value) => Ok(IntegerLiteral { value, span }),
+            Err(_) => Err(ParseError::custom(
+                "The integer is too large",
+                pair.as_span(),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct StringLiteral {
+    pub value: String,
+    pub span: Span,
+}
+
+impl StringLiteral {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<StringLiteral, ParseError> {
+        let hex_digits = match pair.as_rule() {
+            Rule::string => 2,
+            Rule::wstring => 4,
+            _ => {
+                return Err(ParseError::expected_one_of(
+                    &[Rule::string, Rule::wstring],
+                    pair.as_span(),
+                ))
+            },
+        };
+
+        let span = to_span(pair.as_span());
+        let content = pair.into_inner().next().unwrap();
+        let value = unescape(content.as_str(), hex_digits).ok_or_else(|| {
+            ParseError::custom("Invalid character code", content.as_span())
+        })?;
+
+        Ok(StringLiteral { value, span })
+    }
+}
+
+/// Expand the `$` escapes in a string, where `hex_digits` is the number of
+/// digits used by a `$hh` character code (2 for `STRING`, 4 for `WSTRING`).
+fn unescape(src: &str, hex_digits: usize) -> Option<String> {
+    let mut value = String::with_capacity(src.len());
+    let mut chars = src.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            value.push(c);
+            continue;
+        }
+
+        // the grammar guarantees every escape is well-formed
+        let escaped = match chars.next().unwrap() {
+            '$' => '$',
+            '\'' => '\'',
+            '"' => '"',
+            'L' | 'l' | 'N' | 'n' => '\n',
+            'P' | 'p' => '\x0C',
+            'R' | 'r' => '\r',
+            'T' | 't' => '\t',
+            first => {
+                let mut code = first.to_digit(16).unwrap();
+                for _ in 1..hex_digits {
+                    let digit = chars.next().unwrap().to_digit(16).unwrap();
+                    code = code * 16 + digit;
+                }
+                std::char::from_u32(code)?
+            },
+        };
+        value.push(escaped);
+    }
+
+    Some(value)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct FloatLiteral {
+    pub value: f64,
+    pub span: Span,
+}
+
+impl FloatLiteral {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<FloatLiteral, ParseError> {
+        ParseError::expect_rule(Rule::float, &pair)?;
+
+        let value: f64 = pair.as_str().replace('_', "").parse().unwrap();
+
+        if !value.is_finite() {
+            return Err(ParseError::custom(
+                "The number is too large",
+                pair.as_span(),
+            ));
+        }
+
+        Ok(FloatLiteral {
+            value,
+            span: to_span(pair.as_span()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct BooleanLiteral {
+    pub value: bool,
+    pub span: Span,
+}
+
+impl BooleanLiteral {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<BooleanLiteral, ParseError> {
+        ParseError::expect_rule(Rule::boolean, &pair)?;
+
+        let span = pair.as_span();
+        let inner = pair.into_inner().next().unwrap();
+
+        let value = match inner.as_rule() {
+            Rule::boolean_true => true,
+            Rule::boolean_false => false,
+            _ => {
+                return Err(ParseError::expected_one_of(
+                    &[Rule::boolean_true, Rule::boolean_false],
+                    span,
+                ));
+            },
+        };
+
+        Ok(BooleanLiteral {
+            value,
+            span: to_span(span),
+        })
+    }
+}
+
+/// A literal with an explicit elementary type (e.g. `INT#-5`, `UINT#16#FF`
+/// or `REAL#1.5`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct TypedLiteral {
+    pub declared_type: Identifier,
+    /// Was the value preceded by a `-` sign?
+    pub negative: bool,
+    /// The value itself, either a [`Literal::Integer`], [`Literal::Float`]
+    /// or [`Literal::Boolean`].
+    pub value: Box<Literal>,
+    pub span: Span,
+}
+
+impl TypedLiteral {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<TypedLiteral, ParseError> {
+        ParseError::expect_rule(Rule::typed_literal, &pair)?;
+
+        let pest_span = pair.as_span();
+        let mut items = pair.into_inner();
+
+        let type_name = items.next().unwrap();
+        let declared_type = Identifier {
+            value: type_name.as_str().to_string(),
+            span: to_span(type_name.as_span()),
+        };
+
+        let mut next = items.next().unwrap();
+        let negative = next.as_rule() == Rule::minus;
+        if next.as_rule() == Rule::minus || next.as_rule() == Rule::plus {
+            next = items.next().unwrap();
+        }
+        let value = Literal::from_pair(next)?;
+
+        check_literal_range(&declared_type.value, negative, &value)
+            .map_err(|msg| ParseError::custom(msg, pest_span.clone()))?;
+
+        Ok(TypedLiteral {
+            declared_type,
+            negative,
+            value: Box::new(value),
+            span: to_span(pest_span),
+        })
+    }
+}
+
+/// Make sure a literal's value can be represented by its declared type.
+fn check_literal_range(
+    type_name: &str,
+    negative: bool,
+    value: &Literal,
+) -> Result<(), String> {
+    let type_name = type_name.to_uppercase();
+
+    let (min, max): (i128, i128) = match type_name.as_str() {
+        "SINT" => (i8::min_value().into(), i8::max_value().into()),
+        "INT" => (i16::min_value().into(), i16::max_value().into()),
+        "DINT" => (i32::min_value().into(), i32::max_value().into()),
+        "LINT" => (i64::min_value().into(), i64::max_value().into()),
+        "USINT" | "BYTE" => (0, u8::max_value().into()),
+        "UINT" | "WORD" => (0, u16::max_value().into()),
+        "UDINT" | "DWORD" => (0, u32::max_value().into()),
+        "ULINT" | "LWORD" => (0, u64::max_value().into()),
+        "BOOL" => (0, 1),
+        "REAL" | "LREAL" => {
+            return match value {
+                Literal::Integer(_) => Ok(()),
+                Literal::Float(f)
+                    if type_name == "REAL"
+                        && f.value > f64::from(std::f32::MAX) =>
+                {
+                    Err(format!("{} is out of range for a REAL", f.value))
+                },
+                Literal::Float(_) => Ok(()),
+                _ => Err(format!("Expected a number for a {}", type_name)),
+            };
+        },
+        _ => return Err(format!("Unknown type, {}", type_name)),
+    };
+
+    let magnitude = match value {
+        Literal::Integer(int) => i128::from(int.value),
+        Literal::Boolean(b) if type_name == "BOOL" && !negative => {
+            return Ok(())
+        },
+        _ => return Err(format!("Expected an integer for a {}", type_name)),
+    };
+    let value = if negative { -magnitude } else { magnitude };
+
+    if min <= value && value <= max {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} is out of range for a {} (expected {} to {})",
+            value, type_name, min, max
+        ))
+    }
+}
+
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+
+/// A `TIME` literal (e.g. `T#1h_30m` or `TIME#1.5s`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct DurationLiteral {
+    pub value: Duration,
+    pub span: Span,
+}
+
+impl DurationLiteral {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<DurationLiteral, ParseError> {
+        ParseError::expect_rule(Rule::duration, &pair)?;
+
+        let pest_span = pair.as_span();
+        let mut components = pair.into_inner().peekable();
+        let mut total_nanos: u128 = 0;
+        let mut previous_unit = None;
+
+        while let Some(component) = components.next() {
+            let component_span = component.as_span();
+            let mut items = component.into_inner();
+            let value = items.next().unwrap();
+            let unit = duration_unit_in_nanos(items.next().unwrap())?;
+
+            match previous_unit {
+                Some(previous) if unit >= previous => {
+                    return Err(ParseError::custom(
+                        "Duration units must go from largest to smallest",
+                        component_span,
+                    ));
+                },
+                _ => previous_unit = Some(unit),
+            }
+
+            let digits = value.as_str().replace('_', "");
+
+            let nanos = if digits.contains('.') {
+                if components.peek().is_some() {
+                    return Err(ParseError::custom(
+                        "Only the last unit in a duration may be fractional",
+                        value.as_span(),
+                    ));
+                }
+
+                let value: f64 = digits.parse().unwrap();
+                Some((value * unit as f64).round() as u128)
+            } else {
+                digits
+                    .parse::<u128>()
+                    .ok()
+                    .and_then(|value| value.checked_mul(unit))
+            };
+
+            total_nanos = nanos
+                .and_then(|nanos| total_nanos.checked_add(nanos))
+                .ok_or_else(|| {
+                    ParseError::custom(
+                        "The duration is too long",
+                        pest_span.clone(),
+                    )
+                })?;
+        }
+
+        let secs = u64::try_from(total_nanos / NANOS_PER_SECOND).map_err(|_| {
+            ParseError::custom("The duration is too long", pest_span.clone())
+        })?;
+        let nanos = (total_nanos % NANOS_PER_SECOND) as u32;
+
+        Ok(DurationLiteral {
+            value: Duration::new(secs, nanos),
+            span: to_span(pest_span),
+        })
+    }
+}
+
+fn duration_unit_in_nanos(pair: Pair<'_, Rule>) -> Result<u128, ParseError> {
+    match pair.as_rule() {
+        Rule::days => Ok(24 * 60 * 60 * NANOS_PER_SECOND),
+        Rule::hours => Ok(60 * 60 * NANOS_PER_SECOND),
+        Rule::minutes => Ok(60 * NANOS_PER_SECOND),
+        Rule::seconds => Ok(NANOS_PER_SECOND),
+        Rule::milliseconds => Ok(1_000_000),
+        Rule::microseconds => Ok(1_000),
+        Rule::nanoseconds => Ok(1),
+        _ => Err(ParseError::expected_one_of(
+            &[
+                Rule::days,
+                Rule::hours,
+                Rule::minutes,
+                Rule::seconds,
+                Rule::milliseconds,
+                Rule::microseconds,
+                Rule::nanoseconds,
+            ],
+            pair.as_span(),
+        )),
+    }
+}
+
+/// A `TIME_OF_DAY` literal (e.g. `TOD#12:30:15.5`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct TimeOfDayLiteral {
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub nanosecond: u32,
+    pub span: Span,
+}
+
+impl TimeOfDayLiteral {
+    fn from_pair(
+        pair: Pair<'_, Rule>,
+    ) -> Result<TimeOfDayLiteral, ParseError> {
+        ParseError::expect_rule(Rule::time_of_day, &pair)?;
+
+        let span = to_span(pair.as_span());
+        let payload = pair.into_inner().next().unwrap();
+
+        TimeOfDayLiteral::from_payload(payload, span)
+    }
+
+    fn from_payload(
+        pair: Pair<'_, Rule>,
+        span: Span,
+    ) -> Result<TimeOfDayLiteral, ParseError> {
+        ParseError::expect_rule(Rule::tod_payload, &pair)?;
+
+        let mut items = pair.into_inner();
+        let hour = items.next().unwrap();
+        let minute = items.next().unwrap();
+        let second = items.next().unwrap();
+
+        let hour_value: u8 = hour.as_str().parse().unwrap();
+        if hour_value >= 24 {
+            return Err(ParseError::custom(
+                "Hours must be between 0 and 23",
+                hour.as_span(),
+            ));
+        }
+
+        let minute_value: u8 = minute.as_str().parse().unwrap();
+        if minute_value >= 60 {
+            return Err(ParseError::custom(
+                "Minutes must be between 0 and 59",
+                minute.as_span(),
+            ));
+        }
+
+        let mut second_parts = second.as_str().splitn(2, '.');
+        let second_value: u8 = second_parts.next().unwrap().parse().unwrap();
+        if second_value >= 60 {
+            return Err(ParseError::custom(
+                "Seconds must be between 0 and 59",
+                second.as_span(),
+            ));
+        }
+
+        let nanosecond = match second_parts.next() {
+            Some(fraction) if fraction.len() > 9 => {
+                return Err(ParseError::custom(
+                    "Fractional seconds can't be more precise than a nanosecond",
+                    second.as_span(),
+                ));
+            },
+            Some(fraction) => {
+                let padded = format!("{:0<9}", fraction);
+                padded.parse().unwrap()
+            },
+            None => 0,
+        };
+
+        Ok(TimeOfDayLiteral {
+            hour: hour_value,
+            minute: minute_value,
+            second: second_value,
+            nanosecond,
+            span,
+        })
+    }
+}
+
+/// A `DATE` literal (e.g. `D#2024-01-01`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct DateLiteral {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub span: Span,
+}
+
+impl DateLiteral {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<DateLiteral, ParseError> {
+        ParseError::expect_rule(Rule::date, &pair)?;
+
+        let span = to_span(pair.as_span());
+        let payload = pair.into_inner().next().unwrap();
+
+        DateLiteral::from_payload(payload, span)
+    }
+
+    fn from_payload(
+        pair: Pair<'_, Rule>,
+        span: Span,
+    ) -> Result<DateLiteral, ParseError> {
+        ParseError::expect_rule(Rule::date_payload, &pair)?;
+
+        let mut items = pair.into_inner();
+        let year = items.next().unwrap();
+        let month = items.next().unwrap();
+        let day = items.next().unwrap();
+
+        let year_value: u16 = year.as_str().parse().unwrap();
+
+        let month_value: u8 = month.as_str().parse().unwrap();
+        if !(1..=12).contains(&month_value) {
+            return Err(ParseError::custom(
+                "Months must be between 1 and 12",
+                month.as_span(),
+            ));
+        }
+
+        let day_value: u8 = day.as_str().parse().unwrap();
+        let days_in_month = days_in_month(year_value, month_value);
+        if !(1..=days_in_month).contains(&day_value) {
+            return Err(ParseError::custom(
+                format!("Days must be between 1 and {}", days_in_month),
+                day.as_span(),
+            ));
+        }
+
+        Ok(DateLiteral {
+            year: year_value,
+            month: month_value,
+            day: day_value,
+            span,
+        })
+    }
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    let is_leap_year =
+        (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+
+    match month {
+        2 if is_leap_year => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// A `DATE_AND_TIME` literal (e.g. `DT#2024-01-01-12:00:00`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct DateAndTimeLiteral {
+    pub date: DateLiteral,
+    pub time: TimeOfDayLiteral,
+    pub span: Span,
+}
+
+impl DateAndTimeLiteral {
+    fn from_pair(
+        pair: Pair<'_, Rule>,
+    ) -> Result<DateAndTimeLiteral, ParseError> {
+        ParseError::expect_rule(Rule::date_and_time, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut items = pair.into_inner();
+        let date_payload = items.next().unwrap();
+        let date_span = to_span(date_payload.as_span());
+        let date = DateLiteral::from_payload(date_payload, date_span)?;
+        let tod_payload = items.next().unwrap();
+        let tod_span = to_span(tod_payload.as_span());
+        let time = TimeOfDayLiteral::from_payload(tod_payload, tod_span)?;
+
+        Ok(DateAndTimeLiteral { date, time, span })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct ConditionalBranch {
+    pub condition: Expression,
+    pub block: Block,
+}
+
+impl ConditionalBranch {
+    fn from_pair(
+        pair: Pair<'_, Rule>,
+    ) -> Result<ConditionalBranch, ParseError> {
+        let mut items = pair.into_inner();
+
+        let condition = Expression::from_pair(items.next().unwrap())?;
+        let block = Block::from_pair(items.next().unwrap())?;
+
+        Ok(ConditionalBranch { condition, block })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-1",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct Conditional {
+    pub true_branch: ConditionalBranch,
+    pub else_if_branches: Vec<ConditionalBranch>,
+    pub else_branch: Option<Block>,
+    pub span: Span,
+}
+
+impl Conditional {
+    fn from_pair(pair: Pair<'_, Rule>) -> Result<Conditional, ParseError> {
+        ParseError::expect_rule(Rule::conditional, &pair)?;
+
+        let span = to_span(pair.as_span());
+
+        let mut items = pair.into_inner();
+        let true_branch = ConditionalBranch::from_pair(items.next().unwrap())?;
+
+        let mut else_if_branches = Vec::new();
+        let mut else_branch = None;
+
+        while let Some(branch) = items.next() {
+            match branch.as_rule() {
+                Rule::elsif_branch => {
+                    else_if_branches.push(ConditionalBranch::from_pair(branch)?)
+                },
+                Rule::else_branch => {
+                    let block = branch.into_inner().next().unwrap();
+                    else_branch = Some(Block::from_pair(block)?);
+                    break;
+                },
+                _ => {
+                    return Err(ParseError::expected_one_of(
                         &[Rule::elsif_branch, Rule::else_branch],
                         branch.as_span(),
                     ))
@@ -713,820 +2802,1928 @@ impl Conditional {
             }
         }
 
-        Ok(Conditional {
-            true_branch,
-            else_if_branches,
-            else_branch,
-            span,
-        })
+        Ok(Conditional {
+            true_branch,
+            else_if_branches,
+            else_branch,
+            span,
+        })
+    }
+}
+
+macro_rules! impl_from_str {
+    ($( $name:ty => $rule:ident, )*) => {
+        $(
+            impl FromStr for $name {
+                type Err = ParseError;
+
+                fn from_str(src: &str) -> Result<$name, ParseError> {
+                    let mut pairs = RawParser::parse(Rule::$rule, src)?;
+
+                    <$name>::from_pair(pairs.next().unwrap())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_str! {
+    Identifier => identifier,
+    Assignment => assignment,
+    VariableDeclaration => variable_decl,
+    DirectAddress => direct_address,
+    TypeDeclaration => type_declaration,
+    StructType => struct_type,
+    EnumeratedType => enumerated_type,
+    SubrangeType => subrange_type,
+    ArrayType => array_type,
+    ArrayInitializer => array_initializer,
+    StructInitializer => struct_initializer,
+    Expression => expression,
+    BinaryOp => binary_operator,
+    BinaryExpression => infix,
+    UnaryExpression => unary,
+    UnaryOp => unary_operator,
+    Call => call,
+    MemberAccess => member_access,
+    FloatLiteral => float,
+    IntegerLiteral => integer,
+    BooleanLiteral => boolean,
+    StringLiteral => string,
+    TypedLiteral => typed_literal,
+    DurationLiteral => duration,
+    TimeOfDayLiteral => time_of_day,
+    DateLiteral => date,
+    DateAndTimeLiteral => date_and_time,
+    Statement => statement,
+    Repeat => repeat,
+    While => while_loop,
+    For => for_loop,
+    Case => case,
+    SignedInteger => signed_integer,
+    Subrange => subrange,
+    VarBlock => var_block,
+    VarBlockKind => var_block_kind,
+    Program => program,
+    Configuration => configuration,
+    Resource => resource,
+    Task => task,
+    ProgramConfiguration => program_configuration,
+    Function => function,
+    FunctionBlock => function_block,
+    Conditional => conditional,
+    File => file,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_identifier() {
+        let src = "x_32";
+        let expected = Identifier {
+            value: String::from("x_32"),
+            span: Span::new(0, 4),
+        };
+
+        parses_to! {
+            parser: RawParser,
+            input: src,
+            rule: Rule::identifier,
+            tokens: [
+                    identifier(0, 4)
+            ]
+        }
+
+        let got = Identifier::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn parse_a_variable_declaration() {
+        let src = "x : u32";
+        let expected = VariableDeclaration {
+            name: Identifier {
+                value: String::from("x"),
+                span: Span::new(0, 1),
+            },
+            address: None,
+            declared_type: DataType::Named(Identifier {
+                value: String::from("u32"),
+                span: Span::new(4, 7),
+            }),
+            initial_value: None,
+            span: Span::new(0, 7),
+        };
+
+        parses_to! {
+            parser: RawParser,
+            input: src,
+            rule: Rule::variable_decl,
+            tokens: [
+                variable_decl(0, 7, [
+                    identifier(0, 1),
+                    identifier(4, 7),
+                ])
+            ]
+        }
+
+        let got = VariableDeclaration::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn variable_decl_with_default_value() {
+        let src = "x: INT := 42";
+        let expected = VariableDeclaration {
+            name: Identifier {
+                value: String::from("x"),
+                span: Span::new(0, 1),
+            },
+            address: None,
+            declared_type: DataType::Named(Identifier {
+                value: String::from("INT"),
+                span: Span::new(3, 6),
+            }),
+            initial_value: Some(Initializer::Expression(Expression::Literal(
+                Literal::Integer(IntegerLiteral {
+                    value: 42,
+                    span: Span::new(10, 12),
+                }),
+            ))),
+            span: Span::new(0, 12),
+        };
+
+        parses_to! {
+            parser: RawParser,
+            input: src,
+            rule: Rule::variable_decl,
+            tokens: [
+                variable_decl(0, 12, [
+                    identifier(0, 1),
+                    identifier(3, 6),
+                    assign(7, 9),
+                    integer(10, 12, [integer_decimal(10, 12)]),
+                ])
+            ]
+        }
+
+        let got = VariableDeclaration::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn simple_assignment() {
+        let src = "x := y";
+        let expected = Assignment {
+            variable: Identifier {
+                value: String::from("x"),
+                span: Span::new(0, 1),
+            },
+            value: Expression::Variable(Identifier {
+                value: String::from("y"),
+                span: Span::new(5, 6),
+            }),
+            span: Span::new(0, 6),
+        };
+
+        parses_to! {
+            parser: RawParser,
+            input: src,
+            rule: Rule::assignment,
+            tokens: [
+                assignment(0, 6, [
+                    identifier(0, 1),
+                    assign(2, 4),
+                    identifier(5, 6),
+                ])
+            ]
+        }
+
+        let got = Assignment::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn a_plus_b() {
+        let src = "a + b";
+        let expected = BinaryExpression {
+            left: Box::new(Expression::Variable(Identifier {
+                value: String::from("a"),
+                span: Span::new(0, 1),
+            })),
+            right: Box::new(Expression::Variable(Identifier {
+                value: String::from("b"),
+                span: Span::new(4, 5),
+            })),
+            op: BinaryOp::Add,
+            span: Span::new(0, 5),
+        };
+
+        parses_to! {
+            parser: RawParser,
+            input: src,
+            rule: Rule::infix,
+            tokens: [
+                infix(0, 5, [
+                    identifier(0, 1),
+                    plus(2, 3),
+                    identifier(4, 5),
+                ])
+            ]
+        }
+
+        let got = BinaryExpression::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn parse_false() {
+        let src = "FALSE";
+        let expected = BooleanLiteral {
+            value: false,
+            span: Span::new(0, 5),
+        };
+
+        parses_to! {
+            parser: RawParser,
+            input: src,
+            rule: Rule::boolean,
+            tokens: [
+                boolean(0, 5, [boolean_false(0, 5)]),
+            ]
+        }
+
+        let got = BooleanLiteral::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn parse_true() {
+        let src = "TRue";
+        let expected = BooleanLiteral {
+            value: true,
+            span: Span::new(0, 4),
+        };
+
+        parses_to! {
+            parser: RawParser,
+            input: src,
+            rule: Rule::boolean,
+            tokens: [
+                boolean(0, 4, [boolean_true(0, 4)]),
+            ]
+        }
+
+        let got = BooleanLiteral::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn parse_basic_float() {
+        let src = "3.14";
+        let expected = FloatLiteral {
+            value: 3.14,
+            span: Span::new(0, 4),
+        };
+
+        parses_to! {
+            parser: RawParser,
+            input: src,
+            rule: Rule::float,
+            tokens: [float(0, 4)]
+        }
+
+        let got = FloatLiteral::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn parse_an_integer() {
+        let src = "42";
+        let expected = IntegerLiteral {
+            value: 42,
+            span: Span::new(0, 2),
+        };
+
+        parses_to! {
+            parser: RawParser,
+            input: src,
+            rule: Rule::integer,
+            tokens: [
+                integer(0, 2, [integer_decimal(0, 2)]),
+            ]
+        }
+
+        let got = IntegerLiteral::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
     }
-}
 
-macro_rules! impl_from_str {
-    ($( $name:ty => $rule:ident, )*) => {
-        $(
-            impl FromStr for $name {
-                type Err = ParseError;
+    #[test]
+    fn parse_a_hex_integer() {
+        let src = "16#deadbeef";
+        let expected = IntegerLiteral {
+            value: 0xdeadbeef,
+            span: Span::new(0, 11),
+        };
 
-                fn from_str(src: &str) -> Result<$name, ParseError> {
-                    let mut pairs = RawParser::parse(Rule::$rule, src)?;
+        parses_to! {
+            parser: RawParser,
+            input: src,
+            rule: Rule::integer,
+            tokens: [
+                integer(0, 11, [integer_hexadecimal(3, 11)]),
+            ]
+        }
 
-                    <$name>::from_pair(pairs.next().unwrap())
-                }
-            }
-        )*
-    };
-}
+        let got = IntegerLiteral::from_str(src).unwrap();
 
-impl_from_str! {
-    Identifier => identifier,
-    Assignment => assignment,
-    VariableDeclaration => variable_decl,
-    Expression => expression,
-    BinaryOp => binary_operator,
-    BinaryExpression => infix,
-    FloatLiteral => float,
-    IntegerLiteral => integer,
-    BooleanLiteral => boolean,
-    Statement => statement,
-    Repeat => repeat,
-    VarBlock => var_block,
-    VarBlockKind => var_block_kind,
-    Program => program,
-    Function => function,
-    FunctionBlock => function_block,
-    Conditional => conditional,
-}
+        assert_eq!(got, expected);
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn simple_repeat() {
+        let src = "REPEAT \nx := FALSE;\nUNTIL x := FALSE;\nEND_REPEAT;";
+        let expected = Repeat {
+            block: Block {
+                statements: vec![Statement::Assignment(Assignment {
+                    variable: Identifier::new("x", 8, 9),
+                    value: Expression::Literal(Literal::Boolean(
+                        BooleanLiteral {
+                            value: false,
+                            span: Span::new(13, 18),
+                        },
+                    )),
+                    span: Span::new(8, 18),
+                })],
+                span: Span::new(8, 19),
+            },
+            condition: Assignment {
+                variable: Identifier::new("x", 26, 27),
+                value: Expression::Literal(Literal::Boolean(BooleanLiteral {
+                    value: false,
+                    span: Span::new(31, 36),
+                })),
+                span: Span::new(26, 36),
+            },
+            span: Span::new(0, 48),
+        };
+
+        parses_to! {
+            parser: RawParser,
+            input: src,
+            rule: Rule::repeat,
+            tokens: [
+                repeat(0, 48, [
+                        block(8, 19, [
+                            statement(8, 19, [
+                                assignment(8, 18, [
+                                    identifier(8, 9),
+                                    assign(10, 12),
+                                    boolean(13, 18, [boolean_false(13, 18)]),
+                                ])
+                            ])
+                        ]),
+                        assignment(26, 36, [
+                            identifier(26, 27),
+                            assign(28, 30),
+                            boolean(31, 36, [boolean_false(31, 36)]),
+                        ])
+                ]),
+            ]
+        }
+
+        let got = Repeat::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
+    }
 
     #[test]
-    fn parse_identifier() {
-        let src = "x_32";
-        let expected = Identifier {
-            value: String::from("x_32"),
-            span: Span::new(0, 4),
+    fn var_block_with_two_decls() {
+        let src = "VAR\nx : BOOL;\nfourty_two : INT;\nEND_VAR";
+        let expected = VarBlock {
+            declarations: vec![
+                VariableDeclaration {
+                    name: Identifier::new("x", 4, 5),
+                    address: None,
+                    declared_type: DataType::Named(Identifier::new(
+                        "BOOL", 8, 12,
+                    )),
+                    initial_value: None,
+                    span: Span::new(4, 12),
+                },
+                VariableDeclaration {
+                    name: Identifier::new("fourty_two", 14, 24),
+                    address: None,
+                    declared_type: DataType::Named(Identifier::new(
+                        "INT", 27, 30,
+                    )),
+                    initial_value: None,
+                    span: Span::new(14, 30),
+                },
+            ],
+            kind: VarBlockKind::Normal,
+            span: Span::new(0, 39),
         };
 
         parses_to! {
             parser: RawParser,
             input: src,
-            rule: Rule::identifier,
+            rule: Rule::var_block,
             tokens: [
-                    identifier(0, 4)
+                var_block(0, 39, [
+                    normal_var_block(0, 3),
+                    variable_decl(4, 12, [
+                        identifier(4, 5),
+                        identifier(8, 12),
+                    ]),
+                    variable_decl(14, 30, [
+                        identifier(14, 24),
+                        identifier(27, 30),
+                    ]),
+                ]),
             ]
         }
 
-        let got = Identifier::from_str(src).unwrap();
+        let got = VarBlock::from_str(src).unwrap();
 
         assert_eq!(got, expected);
     }
 
     #[test]
-    fn parse_a_variable_declaration() {
-        let src = "x : u32";
-        let expected = VariableDeclaration {
-            name: Identifier {
-                value: String::from("x"),
-                span: Span::new(0, 1),
+    fn parse_a_program() {
+        let src = r#"PROGRAM foo
+            VAR_GLOBAL
+                fourty_two: INT;
+            END_VAR
+
+            fourty_two := 42;
+        END_PROGRAM
+        "#;
+        let expected = Program {
+            name: Identifier::new("foo", 8, 11),
+            var_blocks: vec![VarBlock {
+                declarations: vec![VariableDeclaration {
+                    name: Identifier::new("fourty_two", 51, 61),
+                    address: None,
+                    declared_type: DataType::Named(Identifier::new(
+                        "INT", 63, 66,
+                    )),
+                    initial_value: None,
+                    span: Span::new(51, 66),
+                }],
+                kind: VarBlockKind::Global,
+                span: Span::new(24, 87),
+            }],
+            body: Block {
+                statements: vec![Statement::Assignment(Assignment {
+                    variable: Identifier::new("fourty_two", 101, 111),
+                    value: Expression::Literal(Literal::Integer(
+                        IntegerLiteral {
+                            value: 42,
+                            span: Span::new(115, 117),
+                        },
+                    )),
+                    span: Span::new(101, 117),
+                })],
+                span: Span::new(101, 118),
             },
-            declared_type: Identifier {
-                value: String::from("u32"),
-                span: Span::new(4, 7),
+            span: Span::new(0, 147),
+        };
+
+        parses_to! {
+            parser: RawParser,
+            input: src,
+            rule: Rule::program,
+            tokens: [
+                program(0, 147, [
+                    identifier(8, 11),
+                    preamble(24, 87, [
+                        var_block(24, 87, [
+                            global_var_block(24, 34),
+                            variable_decl(51, 66, [
+                                identifier(51, 61),
+                                identifier(63, 66),
+                            ]),
+                        ]),
+                    ]),
+                    block(101, 118, [
+                        statement(101, 118, [
+                            assignment(101, 117, [
+                                identifier(101, 111),
+                                assign(112, 114),
+                                integer(115, 117, [integer_decimal(115, 117)]),
+                            ])
+                        ])
+                    ]),
+                ]),
+            ]
+        }
+
+        let got = Program::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn parse_a_function() {
+        let src = r#"FUNCTION ReturnFive : INT
+            VAR_INPUT
+                input: INT;
+            END_VAR
+
+            ReturnFive := 5;
+
+        END_FUNCTION
+        "#;
+        let expected = Function {
+            name: Identifier::new("ReturnFive", 9, 19),
+            return_type: Identifier::new("INT", 22, 25),
+            var_blocks: vec![VarBlock {
+                declarations: vec![VariableDeclaration {
+                    name: Identifier::new("input", 64, 69),
+                    address: None,
+                    declared_type: DataType::Named(Identifier::new(
+                        "INT", 71, 74,
+                    )),
+                    initial_value: None,
+                    span: Span::new(64, 74),
+                }],
+                kind: VarBlockKind::Input,
+                span: Span::new(38, 95),
+            }],
+            body: Block {
+                statements: vec![Statement::Assignment(Assignment {
+                    variable: Identifier::new("ReturnFive", 109, 119),
+                    value: Expression::Literal(Literal::Integer(
+                        IntegerLiteral {
+                            value: 5,
+                            span: Span::new(123, 124),
+                        },
+                    )),
+                    span: Span::new(109, 124),
+                })],
+                span: Span::new(109, 125),
             },
-            initial_value: None,
-            span: Span::new(0, 7),
+            span: Span::new(0, 156),
         };
 
         parses_to! {
             parser: RawParser,
             input: src,
-            rule: Rule::variable_decl,
+            rule: Rule::function,
             tokens: [
-                variable_decl(0, 7, [
-                    identifier(0, 1),
-                    identifier(4, 7),
+                function(0, 156, [
+                    identifier(9, 19),
+                    identifier(22, 25),
+                    preamble(38, 95, [
+                        var_block(38, 95, [
+                            input_var_block(38, 47),
+                            variable_decl(64, 74, [
+                                identifier(64, 69),
+                                identifier(71, 74),
+                            ]),
+                        ]),
+                    ]),
+                    block(109, 125, [
+                        statement(109, 125, [
+                            assignment(109, 124, [
+                                identifier(109, 119),
+                                assign(120, 122),
+                                integer(123, 124, [
+                                    integer_decimal(123, 124),
+                                ]),
+                            ]),
+                        ]),
+                    ]),
                 ])
             ]
         }
 
-        let got = VariableDeclaration::from_str(src).unwrap();
+        let got = Function::from_str(src).unwrap();
 
         assert_eq!(got, expected);
     }
 
     #[test]
-    fn variable_decl_with_default_value() {
-        let src = "x: INT := 42";
-        let expected = VariableDeclaration {
-            name: Identifier {
-                value: String::from("x"),
-                span: Span::new(0, 1),
-            },
-            declared_type: Identifier {
-                value: String::from("INT"),
-                span: Span::new(3, 6),
-            },
-            initial_value: Some(Expression::Literal(Literal::Integer(
-                IntegerLiteral {
-                    value: 42,
-                    span: Span::new(10, 12),
+    fn parse_a_function_block() {
+        let src = r#"FUNCTION_BLOCK FB_Timed_Counter
+        //=======================================================================
+        // Function Block Timed Counter :  Incremental count of the timed interval
+        //=======================================================================
+            VAR_INPUT
+                Execute         : BOOL := FALSE;        // Trigger signal to begin Timed Counting
+                Time_Increment  : REAL := 1.25;         // Enter Cycle Time (Seconds) between counts
+            END_VAR
+
+            VAR
+                CycleTimer      : TON;                  // Timer FB from Command Library
+                CycleCounter    : CTU;                  // Counter FB from Command Library
+                TimerPreset     : TIME;                 // Converted Time_Increment in Seconds to MS
+            END_VAR
+
+            Current_Count := CycleCounter;
+            Count_Complete := CycleCounter;
+        END_FUNCTION_BLOCK"#;
+        let expected = FunctionBlock {
+            name: Identifier::new("FB_Timed_Counter", 15, 31),
+            var_blocks: vec![
+                VarBlock {
+                    declarations: vec![
+                        VariableDeclaration {
+                            name: Identifier::new("Execute", 317, 324),
+                            address: None,
+                            declared_type: DataType::Named(Identifier::new(
+                                "BOOL", 335, 339,
+                            )),
+                            initial_value: Some(Initializer::Expression(
+                                Expression::Literal(Literal::Boolean(
+                                    BooleanLiteral {
+                                        value: false,
+                                        span: Span::new(343, 348),
+                                    },
+                                )),
+                            )),
+                            span: Span::new(317, 348),
+                        },
+                        VariableDeclaration {
+                            name: Identifier::new("Time_Increment", 415, 429),
+                            address: None,
+                            declared_type: DataType::Named(Identifier::new(
+                                "REAL", 433, 437,
+                            )),
+                            initial_value: Some(Initializer::Expression(
+                                Expression::Literal(Literal::Float(
+                                    FloatLiteral {
+                                        value: 1.25,
+                                        span: Span::new(441, 445),
+                                    },
+                                )),
+                            )),
+                            span: Span::new(415, 445),
+                        },
+                    ],
+                    kind: VarBlockKind::Input,
+                    span: Span::new(291, 519),
                 },
-            ))),
-            span: Span::new(0, 12),
+                VarBlock {
+                    declarations: vec![
+                        VariableDeclaration {
+                            name: Identifier::new("CycleTimer", 553, 563),
+                            address: None,
+                            declared_type: DataType::Named(Identifier::new(
+                                "TON", 571, 574,
+                            )),
+                            initial_value: None,
+                            span: Span::new(553, 574),
+                        },
+                        VariableDeclaration {
+                            name: Identifier::new("CycleCounter", 642, 654),
+                            address: None,
+                            declared_type: DataType::Named(Identifier::new(
+                                "CTU", 660, 663,
+                            )),
+                            initial_value: None,
+                            span: Span::new(642, 663),
+                        },
+                        VariableDeclaration {
+                            name: Identifier::new("TimerPreset", 733, 744),
+                            address: None,
+                            declared_type: DataType::Named(Identifier::new(
+                                "TIME", 751, 755,
+                            )),
+                            initial_value: None,
+                            span: Span::new(733, 755),
+                        },
+                    ],
+                    kind: VarBlockKind::Normal,
+                    span: Span::new(533, 837),
+                },
+            ],
+            body: Block {
+                statements: vec![
+                    Statement::Assignment(Assignment {
+                        variable: Identifier::new("Current_Count", 851, 864),
+                        value: Expression::Variable(Identifier::new(
+                            "CycleCounter",
+                            868,
+                            880,
+                        )),
+                        span: Span::new(851, 880),
+                    }),
+                    Statement::Assignment(Assignment {
+                        variable: Identifier::new("Count_Complete", 894, 908),
+                        value: Expression::Variable(Identifier::new(
+                            "CycleCounter",
+                            912,
+                            924,
+                        )),
+                        span: Span::new(894, 924),
+                    }),
+                ],
+                span: Span::new(851, 925),
+            },
+            span: Span::new(0, 952),
         };
 
         parses_to! {
             parser: RawParser,
             input: src,
-            rule: Rule::variable_decl,
+            rule: Rule::function_block,
             tokens: [
-                variable_decl(0, 12, [
-                    identifier(0, 1),
-                    identifier(3, 6),
-                    assign(7, 9),
-                    integer(10, 12, [integer_decimal(10, 12)]),
-                ])
+              function_block(0, 952, [
+                identifier(15, 31),
+                preamble(291, 837, [
+                  var_block(291, 519, [
+                    input_var_block(291, 300),
+                    variable_decl(317, 348, [
+                      identifier(317, 324),
+                      identifier(335, 339),
+                      assign(340, 342),
+                      boolean(343, 348, [
+                        boolean_false(343, 348),
+                      ]),
+                    ]),
+                    variable_decl(415, 445, [
+                      identifier(415, 429),
+                      identifier(433, 437),
+                      assign(438, 440),
+                      float(441, 445),
+                    ]),
+                  ]),
+                  var_block(533, 837, [
+                    normal_var_block(533, 536),
+                    variable_decl(553, 574, [
+                      identifier(553, 563),
+                      identifier(571, 574),
+                    ]),
+                    variable_decl(642, 663, [
+                      identifier(642, 654),
+                      identifier(660, 663),
+                    ]),
+                    variable_decl(733, 755, [
+                      identifier(733, 744),
+                      identifier(751, 755),
+                    ]),
+                  ]),
+                ]),
+                block(851, 925, [
+                  statement(851, 881, [
+                    assignment(851, 880, [
+                      identifier(851, 864),
+                      assign(865, 867),
+                      identifier(868, 880),
+                    ]),
+                  ]),
+                  statement(894, 925, [
+                    assignment(894, 924, [
+                      identifier(894, 908),
+                      assign(909, 911),
+                      identifier(912, 924),
+                    ]),
+                  ]),
+                ]),
+              ]),
             ]
         }
 
-        let got = VariableDeclaration::from_str(src).unwrap();
+        let got = FunctionBlock::from_str(src).unwrap();
 
         assert_eq!(got, expected);
+
+        // ... my god that was a pain to type out
     }
 
     #[test]
-    fn simple_assignment() {
-        let src = "x := y";
-        let expected = Assignment {
-            variable: Identifier {
-                value: String::from("x"),
-                span: Span::new(0, 1),
+    fn solitary_if() {
+        let src = "if x = true THEN\nx := false;\nend_if";
+        let expected = Conditional {
+            true_branch: ConditionalBranch {
+                condition: Expression::BinaryExpression(BinaryExpression {
+                    left: Box::new(Expression::Variable(Identifier::new(
+                        "x", 3, 4,
+                    ))),
+                    right: Box::new(Expression::Literal(Literal::Boolean(
+                        BooleanLiteral {
+                            value: true,
+                            span: Span::new(7, 11),
+                        },
+                    ))),
+                    op: BinaryOp::Equals,
+                    span: Span::new(3, 12),
+                }),
+                block: Block {
+                    statements: vec![Statement::Assignment(Assignment {
+                        variable: Identifier::new("x", 17, 18),
+                        value: Expression::Literal(Literal::Boolean(
+                            BooleanLiteral {
+                                value: false,
+                                span: Span::new(22, 27),
+                            },
+                        )),
+                        span: Span::new(17, 27),
+                    })],
+                    span: Span::new(17, 28),
+                },
             },
-            value: Expression::Variable(Identifier {
-                value: String::from("y"),
-                span: Span::new(5, 6),
-            }),
-            span: Span::new(0, 6),
+            else_if_branches: vec![],
+            else_branch: None,
+            span: Span::new(0, 35),
         };
 
         parses_to! {
             parser: RawParser,
             input: src,
-            rule: Rule::assignment,
+            rule: Rule::conditional,
             tokens: [
-                assignment(0, 6, [
-                    identifier(0, 1),
-                    assign(2, 4),
-                    identifier(5, 6),
-                ])
+                conditional(0, 35, [
+                  if_branch(0, 28, [
+                    infix(3, 12, [
+                      identifier(3, 4),
+                      equal(5, 6),
+                      boolean(7, 11, [
+                        boolean_true(7, 11),
+                      ]),
+                    ]),
+                    block(17, 28, [
+                      statement(17, 28, [
+                        assignment(17, 27, [
+                          identifier(17, 18),
+                          assign(19, 21),
+                          boolean(22, 27, [
+                            boolean_false(22, 27),
+                          ]),
+                        ]),
+                      ]),
+                    ]),
+                  ]),
+                ]),
             ]
         }
 
-        let got = Assignment::from_str(src).unwrap();
+        let got = Conditional::from_str(src).unwrap();
+        println!("{:#?}", got);
 
         assert_eq!(got, expected);
     }
 
     #[test]
-    fn a_plus_b() {
-        let src = "a + b";
-        let expected = BinaryExpression {
-            left: Box::new(Expression::Variable(Identifier {
-                value: String::from("a"),
-                span: Span::new(0, 1),
-            })),
-            right: Box::new(Expression::Variable(Identifier {
-                value: String::from("b"),
-                span: Span::new(4, 5),
-            })),
-            op: BinaryOp::Add,
-            span: Span::new(0, 5),
-        };
+    fn parse_a_file() {
+        let src = r#"
+            PROGRAM main
+                x := 1;
+            END_PROGRAM
+
+            FUNCTION_BLOCK counter
+                count := count + 1;
+            END_FUNCTION_BLOCK
+        "#;
 
-        parses_to! {
-            parser: RawParser,
-            input: src,
-            rule: Rule::infix,
-            tokens: [
-                infix(0, 5, [
-                    identifier(0, 1),
-                    plus(2, 3),
-                    identifier(4, 5),
-                ])
-            ]
-        }
+        let got = File::from_str(src).unwrap();
 
-        let got = BinaryExpression::from_str(src).unwrap();
+        assert_eq!(got.programs.len(), 1);
+        assert_eq!(got.programs[0].name.value, "main");
+        assert_eq!(got.function_blocks.len(), 1);
+        assert_eq!(got.function_blocks[0].name.value, "counter");
+        assert!(got.functions.is_empty());
+        assert_eq!(got.span, Span::new(0, src.len() as u32));
+    }
+
+    #[test]
+    fn if_elsif_else_statement() {
+        let src = "IF a THEN\nx := 1;\nELSIF b THEN\nx := 2;\nELSE\nx := 3;\nEND_IF;";
+        let expected = Statement::Conditional(Conditional {
+            true_branch: ConditionalBranch {
+                condition: Expression::Variable(Identifier::new("a", 3, 4)),
+                block: Block {
+                    statements: vec![Statement::Assignment(Assignment {
+                        variable: Identifier::new("x", 10, 11),
+                        value: Expression::Literal(Literal::Integer(
+                            IntegerLiteral {
+                                value: 1,
+                                span: Span::new(15, 16),
+                            },
+                        )),
+                        span: Span::new(10, 16),
+                    })],
+                    span: Span::new(10, 17),
+                },
+            },
+            else_if_branches: vec![ConditionalBranch {
+                condition: Expression::Variable(Identifier::new("b", 24, 25)),
+                block: Block {
+                    statements: vec![Statement::Assignment(Assignment {
+                        variable: Identifier::new("x", 31, 32),
+                        value: Expression::Literal(Literal::Integer(
+                            IntegerLiteral {
+                                value: 2,
+                                span: Span::new(36, 37),
+                            },
+                        )),
+                        span: Span::new(31, 37),
+                    })],
+                    span: Span::new(31, 38),
+                },
+            }],
+            else_branch: Some(Block {
+                statements: vec![Statement::Assignment(Assignment {
+                    variable: Identifier::new("x", 44, 45),
+                    value: Expression::Literal(Literal::Integer(
+                        IntegerLiteral {
+                            value: 3,
+                            span: Span::new(49, 50),
+                        },
+                    )),
+                    span: Span::new(44, 50),
+                })],
+                span: Span::new(44, 51),
+            }),
+            span: Span::new(0, 58),
+        });
+
+        let got = Statement::from_str(src).unwrap();
 
         assert_eq!(got, expected);
     }
 
     #[test]
-    fn parse_false() {
-        let src = "FALSE";
-        let expected = BooleanLiteral {
-            value: false,
-            span: Span::new(0, 5),
+    fn simple_while() {
+        let src = "WHILE x DO\nx := FALSE;\nEND_WHILE";
+        let expected = While {
+            condition: Expression::Variable(Identifier::new("x", 6, 7)),
+            body: Block {
+                statements: vec![Statement::Assignment(Assignment {
+                    variable: Identifier::new("x", 11, 12),
+                    value: Expression::Literal(Literal::Boolean(
+                        BooleanLiteral {
+                            value: false,
+                            span: Span::new(16, 21),
+                        },
+                    )),
+                    span: Span::new(11, 21),
+                })],
+                span: Span::new(11, 22),
+            },
+            span: Span::new(0, 32),
         };
 
         parses_to! {
             parser: RawParser,
             input: src,
-            rule: Rule::boolean,
+            rule: Rule::while_loop,
             tokens: [
-                boolean(0, 5, [boolean_false(0, 5)]),
+                while_loop(0, 32, [
+                    identifier(6, 7),
+                    block(11, 22, [
+                        statement(11, 22, [
+                            assignment(11, 21, [
+                                identifier(11, 12),
+                                assign(13, 15),
+                                boolean(16, 21, [boolean_false(16, 21)]),
+                            ]),
+                        ]),
+                    ]),
+                ]),
             ]
         }
 
-        let got = BooleanLiteral::from_str(src).unwrap();
+        let got = While::from_str(src).unwrap();
 
         assert_eq!(got, expected);
     }
 
     #[test]
-    fn parse_true() {
-        let src = "TRue";
-        let expected = BooleanLiteral {
-            value: true,
-            span: Span::new(0, 4),
+    fn for_loop_with_step() {
+        let src = "FOR i := 1 TO 10 BY 2 DO\nx := i;\nEND_FOR";
+        let expected = For {
+            variable: Identifier::new("i", 4, 5),
+            start: Expression::Literal(Literal::Integer(IntegerLiteral {
+                value: 1,
+                span: Span::new(9, 10),
+            })),
+            end: Expression::Literal(Literal::Integer(IntegerLiteral {
+                value: 10,
+                span: Span::new(14, 16),
+            })),
+            step: Some(Expression::Literal(Literal::Integer(
+                IntegerLiteral {
+                    value: 2,
+                    span: Span::new(20, 21),
+                },
+            ))),
+            body: Block {
+                statements: vec![Statement::Assignment(Assignment {
+                    variable: Identifier::new("x", 25, 26),
+                    value: Expression::Variable(Identifier::new("i", 30, 31)),
+                    span: Span::new(25, 31),
+                })],
+                span: Span::new(25, 32),
+            },
+            span: Span::new(0, 40),
         };
 
-        parses_to! {
-            parser: RawParser,
-            input: src,
-            rule: Rule::boolean,
-            tokens: [
-                boolean(0, 4, [boolean_true(0, 4)]),
-            ]
-        }
-
-        let got = BooleanLiteral::from_str(src).unwrap();
+        let got = For::from_str(src).unwrap();
 
         assert_eq!(got, expected);
     }
 
     #[test]
-    fn parse_basic_float() {
-        let src = "3.14";
-        let expected = FloatLiteral {
-            value: 3.14,
-            span: Span::new(0, 4),
-        };
-
-        parses_to! {
-            parser: RawParser,
-            input: src,
-            rule: Rule::float,
-            tokens: [float(0, 4)]
-        }
+    fn for_loop_without_step() {
+        let src = "FOR i := 0 TO limit DO\nEXIT;\nEND_FOR";
 
-        let got = FloatLiteral::from_str(src).unwrap();
+        let got = For::from_str(src).unwrap();
 
-        assert_eq!(got, expected);
+        assert_eq!(got.step, None);
+        assert_eq!(
+            got.body.statements,
+            vec![Statement::Exit(Exit {
+                span: Span::new(23, 27)
+            })]
+        );
     }
 
     #[test]
-    fn parse_an_integer() {
-        let src = "42";
-        let expected = IntegerLiteral {
-            value: 42,
-            span: Span::new(0, 2),
-        };
+    fn keywords_can_prefix_identifiers() {
+        for src in &["TON", "total", "done", "variable", "format", "iffy"] {
+            let got = Identifier::from_str(src).unwrap();
 
-        parses_to! {
-            parser: RawParser,
-            input: src,
-            rule: Rule::integer,
-            tokens: [
-                integer(0, 2, [integer_decimal(0, 2)]),
-            ]
+            assert_eq!(&got.value, src);
         }
 
-        let got = IntegerLiteral::from_str(src).unwrap();
-
-        assert_eq!(got, expected);
+        assert!(Identifier::from_str("END_FOR").is_err());
+        assert!(Identifier::from_str("function_block").is_err());
     }
 
     #[test]
-    fn parse_a_hex_integer() {
-        let src = "16#deadbeef";
-        let expected = IntegerLiteral {
-            value: 0xdeadbeef,
-            span: Span::new(0, 11),
-        };
+    fn case_with_ranges_and_multiple_labels() {
+        let src = "CASE x OF\n1, 2: y := 1;\n3..10, -1: y := 2;\nIdle: y := 3;\nELSE\ny := 4;\nEND_CASE";
 
-        parses_to! {
-            parser: RawParser,
-            input: src,
-            rule: Rule::integer,
-            tokens: [
-                integer(0, 11, [integer_hexadecimal(3, 11)]),
-            ]
-        }
+        let got = Case::from_str(src).unwrap();
 
-        let got = IntegerLiteral::from_str(src).unwrap();
+        assert_eq!(
+            got.selector,
+            Expression::Variable(Identifier::new("x", 5, 6))
+        );
+        assert_eq!(got.branches.len(), 3);
+        assert_eq!(
+            got.branches[0].labels,
+            vec![
+                CaseLabel::Integer(SignedInteger {
+                    value: 1,
+                    span: Span::new(10, 11),
+                }),
+                CaseLabel::Integer(SignedInteger {
+                    value: 2,
+                    span: Span::new(13, 14),
+                }),
+            ]
+        );
+        assert_eq!(got.branches[0].span, Span::new(10, 23));
+        assert_eq!(
+            got.branches[1].labels,
+            vec![
+                CaseLabel::Range(Subrange {
+                    start: SignedInteger {
+                        value: 3,
+                        span: Span::new(24, 25),
+                    },
+                    end: SignedInteger {
+                        value: 10,
+                        span: Span::new(27, 29),
+                    },
+                    span: Span::new(24, 29),
+                }),
+                CaseLabel::Integer(SignedInteger {
+                    value: -1,
+                    span: Span::new(31, 33),
+                }),
+            ]
+        );
+        assert_eq!(
+            got.branches[2].labels,
+            vec![CaseLabel::Enumerated(Identifier::new("Idle", 43, 47))]
+        );
+        assert_eq!(got.else_branch.unwrap().span, Span::new(62, 69));
+        assert_eq!(got.span, Span::new(0, 78));
+    }
 
-        assert_eq!(got, expected);
+    #[test]
+    fn out_of_range_signed_integer() {
+        assert_eq!(
+            SignedInteger::from_str("-9223372036854775808")
+                .unwrap()
+                .value,
+            std::i64::MIN
+        );
+        assert!(SignedInteger::from_str("9223372036854775808").is_err());
     }
 
     #[test]
-    fn simple_repeat() {
-        let src = "REPEAT \nx := FALSE;\nUNTIL x := FALSE;\nEND_REPEAT;";
-        let expected = Repeat {
-            block: Block {
-                statements: vec![Statement::Assignment(Assignment {
-                    variable: Identifier::new("x", 8, 9),
-                    value: Expression::Literal(Literal::Boolean(
-                        BooleanLiteral {
-                            value: false,
-                            span: Span::new(13, 18),
-                        },
-                    )),
-                    span: Span::new(8, 18),
-                })],
-                span: Span::new(8, 19),
-            },
-            condition: Assignment {
-                variable: Identifier::new("x", 26, 27),
-                value: Expression::Literal(Literal::Boolean(BooleanLiteral {
-                    value: false,
-                    span: Span::new(31, 36),
-                })),
-                span: Span::new(26, 36),
-            },
-            span: Span::new(0, 48),
-        };
+    fn boolean_operator_precedence() {
+        let src = "a OR b AND NOT c";
+        let expected = Expression::BinaryExpression(BinaryExpression {
+            left: Box::new(Expression::Variable(Identifier::new("a", 0, 1))),
+            right: Box::new(Expression::BinaryExpression(BinaryExpression {
+                left: Box::new(Expression::Variable(Identifier::new(
+                    "b", 5, 6,
+                ))),
+                right: Box::new(Expression::UnaryExpression(
+                    UnaryExpression {
+                        op: UnaryOp::Not,
+                        value: Box::new(Expression::Variable(
+                            Identifier::new("c", 15, 16),
+                        )),
+                        span: Span::new(11, 16),
+                    },
+                )),
+                op: BinaryOp::And,
+                span: Span::new(5, 16),
+            })),
+            op: BinaryOp::Or,
+            span: Span::new(0, 16),
+        });
 
         parses_to! {
             parser: RawParser,
             input: src,
-            rule: Rule::repeat,
+            rule: Rule::infix,
             tokens: [
-                repeat(0, 48, [
-                        block(8, 19, [
-                            statement(8, 19, [
-                                assignment(8, 18, [
-                                    identifier(8, 9),
-                                    assign(10, 12),
-                                    boolean(13, 18, [boolean_false(13, 18)]),
-                                ])
-                            ])
-                        ]),
-                        assignment(26, 36, [
-                            identifier(26, 27),
-                            assign(28, 30),
-                            boolean(31, 36, [boolean_false(31, 36)]),
-                        ])
-                ]),
+                infix(0, 16, [
+                    identifier(0, 1),
+                    or(2, 4),
+                    identifier(5, 6),
+                    and(7, 10),
+                    unary(11, 16, [
+                        not(11, 14),
+                        identifier(15, 16),
+                    ]),
+                ])
             ]
         }
 
-        let got = Repeat::from_str(src).unwrap();
+        let got = Expression::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn parenthesised_operands_keep_their_brackets() {
+        let inputs = vec![
+            ("d - (a + b) * c", Span::new(5, 10)),
+            ("d - a * (b + c)", Span::new(9, 14)),
+        ];
+
+        for (src, brackets) in inputs {
+            let got = BinaryExpression::from_str(src).unwrap();
+
+            let product = match *got.right {
+                Expression::BinaryExpression(ref bin) => bin,
+                ref other => panic!("Expected a product, found {:?}", other),
+            };
+            assert_eq!(product.span, Span::new(4, 15));
+            let operands = vec![product.left.span(), product.right.span()];
+            assert!(operands.contains(&brackets), "{:?}", operands);
+        }
+    }
+
+    /// Render an [`Expression`] as a fully parenthesized string so operator
+    /// precedence is easy to check.
+    fn parenthesize(expr: &Expression) -> String {
+        match expr {
+            Expression::Variable(ident) => ident.value.clone(),
+            Expression::Literal(Literal::Integer(int)) => int.value.to_string(),
+            Expression::Literal(other) => format!("{:?}", other),
+            Expression::BinaryExpression(bin) => format!(
+                "({} {:?} {})",
+                parenthesize(&bin.left),
+                bin.op,
+                parenthesize(&bin.right)
+            ),
+            Expression::UnaryExpression(unary) => {
+                format!("({:?} {})", unary.op, parenthesize(&unary.value))
+            },
+            other => format!("{:?}", other),
+        }
+    }
 
-        assert_eq!(got, expected);
+    #[test]
+    fn operator_precedence_and_associativity() {
+        let inputs = vec![
+            ("1 + 2 * 3", "(1 Add (2 Multiply 3))"),
+            ("(1 + 2) * 3", "((1 Add 2) Multiply 3)"),
+            ("a - b - c", "((a Subtract b) Subtract c)"),
+            ("a / b MOD c", "((a Divide b) Modulo c)"),
+            ("a ** 2 * b", "((a Exponent 2) Multiply b)"),
+            ("-a * b", "((Negate a) Multiply b)"),
+            ("a + b < c", "((a Add b) LessThan c)"),
+            ("a < b = c >= d", "((a LessThan b) Equals (c GreaterThanOrEqual d))"),
+            ("a <> b", "(a NotEquals b)"),
+            ("a != b", "(a NotEquals b)"),
+            ("a <= b", "(a LessThanOrEqual b)"),
+            ("a = b AND c", "((a Equals b) And c)"),
+            ("a & b XOR c", "((a And b) Xor c)"),
+            ("a XOR b OR c", "((a Xor b) Or c)"),
+            ("a OR b XOR c AND d", "(a Or (b Xor (c And d)))"),
+            ("NOT (a OR b)", "(Not (a Or b))"),
+            ("NOT a = b", "((Not a) Equals b)"),
+        ];
+
+        for (src, should_be) in inputs {
+            let got = Expression::from_str(src).unwrap();
+
+            assert_eq!(parenthesize(&got), should_be, "{}", src);
+        }
     }
 
     #[test]
-    fn var_block_with_two_decls() {
-        let src = "VAR\nx : BOOL;\nfourty_two : INT;\nEND_VAR";
-        let expected = VarBlock {
-            declarations: vec![
-                VariableDeclaration {
-                    name: Identifier::new("x", 4, 5),
-                    declared_type: Identifier::new("BOOL", 8, 12),
-                    initial_value: None,
-                    span: Span::new(4, 12),
-                },
-                VariableDeclaration {
-                    name: Identifier::new("fourty_two", 14, 24),
-                    declared_type: Identifier::new("INT", 27, 30),
-                    initial_value: None,
-                    span: Span::new(14, 30),
-                },
+    fn operators_dont_swallow_identifiers() {
+        let got = Expression::from_str("notify").unwrap();
+        assert_eq!(got, Expression::Variable(Identifier::new("notify", 0, 6)));
+
+        let got = Expression::from_str("a AND order").unwrap();
+        assert_eq!(parenthesize(&got), "(a And order)");
+    }
+
+    #[test]
+    fn call_with_positional_and_formal_arguments() {
+        let src = "MyFunc(x, IN := 5, OUT => y)";
+        let expected = Call {
+            function: Identifier::new("MyFunc", 0, 6),
+            arguments: vec![
+                Argument::Positional(Expression::Variable(Identifier::new(
+                    "x", 7, 8,
+                ))),
+                Argument::Input(InputArgument {
+                    name: Identifier::new("IN", 10, 12),
+                    value: Expression::Literal(Literal::Integer(
+                        IntegerLiteral {
+                            value: 5,
+                            span: Span::new(16, 17),
+                        },
+                    )),
+                    span: Span::new(10, 17),
+                }),
+                Argument::Output(OutputArgument {
+                    name: Identifier::new("OUT", 19, 22),
+                    variable: Identifier::new("y", 26, 27),
+                    span: Span::new(19, 27),
+                }),
             ],
-            kind: VarBlockKind::Normal,
-            span: Span::new(0, 39),
+            span: Span::new(0, 28),
         };
 
         parses_to! {
             parser: RawParser,
             input: src,
-            rule: Rule::var_block,
+            rule: Rule::call,
             tokens: [
-                var_block(0, 39, [
-                    normal_var_block(0, 3),
-                    variable_decl(4, 12, [
-                        identifier(4, 5),
-                        identifier(8, 12),
+                call(0, 28, [
+                    identifier(0, 6),
+                    identifier(7, 8),
+                    input_argument(10, 17, [
+                        identifier(10, 12),
+                        assign(13, 15),
+                        integer(16, 17, [integer_decimal(16, 17)]),
                     ]),
-                    variable_decl(14, 30, [
-                        identifier(14, 24),
-                        identifier(27, 30),
+                    output_argument(19, 27, [
+                        identifier(19, 22),
+                        identifier(26, 27),
                     ]),
-                ]),
+                ])
             ]
         }
 
-        let got = VarBlock::from_str(src).unwrap();
+        let got = Call::from_str(src).unwrap();
 
         assert_eq!(got, expected);
     }
 
     #[test]
-    fn parse_a_program() {
-        let src = r#"PROGRAM foo
-            VAR_GLOBAL
-                fourty_two: INT;
-            END_VAR
-
-            fourty_two := 42;
-        END_PROGRAM
-        "#;
-        let expected = Program {
-            name: Identifier::new("foo", 8, 11),
-            var_blocks: vec![VarBlock {
-                declarations: vec![VariableDeclaration {
-                    name: Identifier::new("fourty_two", 51, 61),
-                    declared_type: Identifier::new("INT", 63, 66),
-                    initial_value: None,
-                    span: Span::new(51, 66),
-                }],
-                kind: VarBlockKind::Global,
-                span: Span::new(24, 87),
-            }],
-            body: Block {
-                statements: vec![Statement::Assignment(Assignment {
-                    variable: Identifier::new("fourty_two", 101, 111),
-                    value: Expression::Literal(Literal::Integer(
-                        IntegerLiteral {
-                            value: 42,
-                            span: Span::new(115, 117),
-                        },
-                    )),
-                    span: Span::new(101, 117),
-                })],
-                span: Span::new(101, 118),
+    fn call_without_arguments_inside_an_expression() {
+        let got = Expression::from_str("Now() - start").unwrap();
+
+        match got {
+            Expression::BinaryExpression(bin) => {
+                assert_eq!(
+                    *bin.left,
+                    Expression::Call(Call {
+                        function: Identifier::new("Now", 0, 3),
+                        arguments: Vec::new(),
+                        span: Span::new(0, 5),
+                    })
+                );
+                assert_eq!(bin.op, BinaryOp::Subtract);
             },
-            span: Span::new(0, 147),
+            other => panic!("Expected a binary expression, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn nested_member_access() {
+        let src = "a.b.c";
+        let expected = MemberAccess {
+            value: Box::new(Expression::MemberAccess(MemberAccess {
+                value: Box::new(Expression::Variable(Identifier::new(
+                    "a", 0, 1,
+                ))),
+                member: Identifier::new("b", 2, 3),
+                span: Span::new(0, 3),
+            })),
+            member: Identifier::new("c", 4, 5),
+            span: Span::new(0, 5),
         };
 
+        let got = MemberAccess::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn function_block_call_statement() {
+        let src = "timer1(IN := start AND NOT timer1.Q, PT := preset);";
+
+        let got = Statement::from_str(src).unwrap();
+
+        match got {
+            Statement::Call(call) => {
+                assert_eq!(call.function, Identifier::new("timer1", 0, 6));
+                assert_eq!(call.arguments.len(), 2);
+                assert_eq!(call.span, Span::new(0, 50));
+            },
+            other => panic!("Expected a call, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_durations() {
+        let inputs = vec![
+            ("T#5s", Duration::from_secs(5)),
+            ("t#20ms", Duration::from_millis(20)),
+            ("TIME#1h_30m", Duration::from_secs(90 * 60)),
+            ("T#1d2h3m4s5ms", Duration::new(93_784, 5_000_000)),
+            ("T#1.5s", Duration::from_millis(1500)),
+            ("T#1_000ms", Duration::from_secs(1)),
+            ("T#25h", Duration::from_secs(25 * 60 * 60)),
+            ("T#10us_5ns", Duration::from_nanos(10_005)),
+        ];
+
+        for (src, should_be) in inputs {
+            let got = DurationLiteral::from_str(src).unwrap();
+
+            assert_eq!(got.value, should_be, "{}", src);
+            assert_eq!(got.span, Span::new(0, src.len() as u32));
+        }
+    }
+
+    #[test]
+    fn invalid_durations() {
+        let inputs = vec!["T#5s1h", "T#1.5h30m", "T#1s1s", "T#"];
+
+        for src in inputs {
+            assert!(DurationLiteral::from_str(src).is_err(), "{}", src);
+        }
+    }
+
+    #[test]
+    fn duration_in_an_expression() {
+        let src = "T#5s";
+
         parses_to! {
             parser: RawParser,
             input: src,
-            rule: Rule::program,
+            rule: Rule::duration,
             tokens: [
-                program(0, 147, [
-                    identifier(8, 11),
-                    preamble(24, 87, [
-                        var_block(24, 87, [
-                            global_var_block(24, 34),
-                            variable_decl(51, 66, [
-                                identifier(51, 61),
-                                identifier(63, 66),
-                            ]),
-                        ]),
-                    ]),
-                    block(101, 118, [
-                        statement(101, 118, [
-                            assignment(101, 117, [
-                                identifier(101, 111),
-                                assign(112, 114),
-                                integer(115, 117, [integer_decimal(115, 117)]),
-                            ])
-                        ])
+                duration(0, 4, [
+                    duration_component(2, 4, [
+                        duration_value(2, 3),
+                        seconds(3, 4),
                     ]),
                 ]),
             ]
         }
 
-        let got = Program::from_str(src).unwrap();
+        let got = Expression::from_str(src).unwrap();
 
-        assert_eq!(got, expected);
+        assert_eq!(
+            got,
+            Expression::Literal(Literal::Duration(DurationLiteral {
+                value: Duration::from_secs(5),
+                span: Span::new(0, 4),
+            }))
+        );
     }
 
     #[test]
-    fn parse_a_function() {
-        let src = r#"FUNCTION ReturnFive : INT
-            VAR_INPUT
-                input: INT;
-            END_VAR
+    fn parse_time_of_day() {
+        let got = TimeOfDayLiteral::from_str("TOD#12:30:15.25").unwrap();
+
+        assert_eq!(
+            got,
+            TimeOfDayLiteral {
+                hour: 12,
+                minute: 30,
+                second: 15,
+                nanosecond: 250_000_000,
+                span: Span::new(0, 15),
+            }
+        );
 
-            ReturnFive := 5;
+        assert!(TimeOfDayLiteral::from_str("TIME_OF_DAY#00:00:00").is_ok());
+        assert!(TimeOfDayLiteral::from_str("TOD#24:00:00").is_err());
+        assert!(TimeOfDayLiteral::from_str("TOD#12:60:00").is_err());
+    }
 
-        END_FUNCTION
-        "#;
-        let expected = Function {
-            name: Identifier::new("ReturnFive", 9, 19),
-            return_type: Identifier::new("INT", 22, 25),
-            var_blocks: vec![VarBlock {
-                declarations: vec![VariableDeclaration {
-                    name: Identifier::new("input", 64, 69),
-                    declared_type: Identifier::new("INT", 71, 74),
-                    initial_value: None,
-                    span: Span::new(64, 74),
-                }],
-                kind: VarBlockKind::Input,
-                span: Span::new(38, 95),
-            }],
-            body: Block {
-                statements: vec![Statement::Assignment(Assignment {
-                    variable: Identifier::new("ReturnFive", 109, 119),
-                    value: Expression::Literal(Literal::Integer(
-                        IntegerLiteral {
-                            value: 5,
-                            span: Span::new(123, 124),
-                        },
-                    )),
-                    span: Span::new(109, 124),
-                })],
-                span: Span::new(109, 125),
+    #[test]
+    fn parse_dates() {
+        let got = DateLiteral::from_str("D#2024-01-01").unwrap();
+
+        assert_eq!(
+            got,
+            DateLiteral {
+                year: 2024,
+                month: 1,
+                day: 1,
+                span: Span::new(0, 12),
+            }
+        );
+
+        assert!(DateLiteral::from_str("DATE#2024-02-29").is_ok());
+        assert!(DateLiteral::from_str("D#2023-02-29").is_err());
+        assert!(DateLiteral::from_str("D#2000-02-29").is_ok());
+        assert!(DateLiteral::from_str("D#1900-02-29").is_err());
+        assert!(DateLiteral::from_str("D#2024-13-01").is_err());
+        assert!(DateLiteral::from_str("D#2024-04-31").is_err());
+    }
+
+    #[test]
+    fn parse_date_and_time() {
+        let src = "DT#2024-01-01-12:00:00";
+        let expected = DateAndTimeLiteral {
+            date: DateLiteral {
+                year: 2024,
+                month: 1,
+                day: 1,
+                span: Span::new(3, 13),
             },
-            span: Span::new(0, 156),
+            time: TimeOfDayLiteral {
+                hour: 12,
+                minute: 0,
+                second: 0,
+                nanosecond: 0,
+                span: Span::new(14, 22),
+            },
+            span: Span::new(0, 22),
+        };
+
+        let got = DateAndTimeLiteral::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn parse_octal_and_binary_integers() {
+        let inputs = vec![
+            ("8#777", 0o777),
+            ("2#1010_1010", 0b1010_1010),
+            ("16#FF_FF", 0xFFFF),
+            ("1_000_000", 1_000_000),
+            ("0", 0),
+        ];
+
+        for (src, should_be) in inputs {
+            let got = IntegerLiteral::from_str(src).unwrap();
+
+            assert_eq!(got.value, should_be, "{}", src);
+            assert_eq!(got.span, Span::new(0, src.len() as u32));
+        }
+
+        assert!(IntegerLiteral::from_str("18446744073709551616").is_err());
+    }
+
+    #[test]
+    fn parse_floats_with_exponents() {
+        let inputs = vec![
+            ("1.0E-3", 1.0e-3),
+            ("2.5e6", 2.5e6),
+            ("1_000.0", 1000.0),
+            ("0.5E+2", 50.0),
+        ];
+
+        for (src, should_be) in inputs {
+            let got = FloatLiteral::from_str(src).unwrap();
+
+            assert_eq!(got.value, should_be, "{}", src);
+        }
+    }
+
+    #[test]
+    fn typed_integer_literal() {
+        let src = "INT#-5";
+        let expected = TypedLiteral {
+            declared_type: Identifier::new("INT", 0, 3),
+            negative: true,
+            value: Box::new(Literal::Integer(IntegerLiteral {
+                value: 5,
+                span: Span::new(5, 6),
+            })),
+            span: Span::new(0, 6),
         };
 
         parses_to! {
             parser: RawParser,
             input: src,
-            rule: Rule::function,
+            rule: Rule::typed_literal,
             tokens: [
-                function(0, 156, [
-                    identifier(9, 19),
-                    identifier(22, 25),
-                    preamble(38, 95, [
-                        var_block(38, 95, [
-                            input_var_block(38, 47),
-                            variable_decl(64, 74, [
-                                identifier(64, 69),
-                                identifier(71, 74),
-                            ]),
-                        ]),
-                    ]),
-                    block(109, 125, [
-                        statement(109, 125, [
-                            assignment(109, 124, [
-                                identifier(109, 119),
-                                assign(120, 122),
-                                integer(123, 124, [
-                                    integer_decimal(123, 124),
-                                ]),
-                            ]),
-                        ]),
-                    ]),
-                ])
+                typed_literal(0, 6, [
+                    elementary_type_name(0, 3),
+                    minus(4, 5),
+                    integer(5, 6, [integer_decimal(5, 6)]),
+                ]),
             ]
         }
 
-        let got = Function::from_str(src).unwrap();
+        let got = TypedLiteral::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn typed_literals_in_expressions() {
+        let got = Expression::from_str("UINT#16#FF + REAL#1.5").unwrap();
+
+        match got {
+            Expression::BinaryExpression(bin) => {
+                match *bin.left {
+                    Expression::Literal(Literal::Typed(ref typed)) => {
+                        assert_eq!(typed.declared_type.value, "UINT");
+                        assert_eq!(
+                            *typed.value,
+                            Literal::Integer(IntegerLiteral {
+                                value: 0xFF,
+                                span: Span::new(5, 10),
+                            })
+                        );
+                    },
+                    ref other => panic!("Expected UINT#16#FF, not {:?}", other),
+                }
+                match *bin.right {
+                    Expression::Literal(Literal::Typed(ref typed)) => {
+                        assert_eq!(typed.declared_type.value, "REAL");
+                    },
+                    ref other => panic!("Expected REAL#1.5, not {:?}", other),
+                }
+            },
+            other => panic!("Expected a binary expression, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn typed_literal_range_checks() {
+        let valid = vec![
+            "SINT#-128",
+            "SINT#127",
+            "USINT#255",
+            "BYTE#2#1111_0000",
+            "ULINT#18446744073709551615",
+            "LINT#-9223372036854775808",
+            "BOOL#1",
+            "BOOL#FALSE",
+            "REAL#1",
+            "LREAL#1.0E300",
+        ];
+        let invalid = vec![
+            "SINT#128",
+            "SINT#-129",
+            "USINT#256",
+            "UINT#-1",
+            "INT#1.5",
+            "BOOL#2",
+            "REAL#1.0E300",
+            "WORD#TRUE",
+        ];
+
+        for src in valid {
+            assert!(TypedLiteral::from_str(src).is_ok(), "{}", src);
+        }
+        for src in invalid {
+            assert!(TypedLiteral::from_str(src).is_err(), "{}", src);
+        }
+    }
+
+    #[test]
+    fn string_literals_with_escapes() {
+        let inputs = vec![
+            ("'hello'", Literal::String(StringLiteral {
+                value: String::from("hello"),
+                span: Span::new(0, 7),
+            })),
+            ("'It$'s $$5$N'", Literal::String(StringLiteral {
+                value: String::from("It's $5\n"),
+                span: Span::new(0, 13),
+            })),
+            ("'$41$t'", Literal::String(StringLiteral {
+                value: String::from("A\t"),
+                span: Span::new(0, 7),
+            })),
+            ("\"$00C4$\"\"", Literal::WString(StringLiteral {
+                value: String::from("\u{C4}\""),
+                span: Span::new(0, 9),
+            })),
+        ];
+
+        for (src, should_be) in inputs {
+            let got = match Expression::from_str(src).unwrap() {
+                Expression::Literal(lit) => lit,
+                other => panic!("Expected a literal, found {:?}", other),
+            };
+
+            assert_eq!(got, should_be, "{}", src);
+        }
+    }
+
+    #[test]
+    fn multi_dimensional_array_type() {
+        let src = "ARRAY [1..10, 0..3] OF REAL";
+        let expected = ArrayType {
+            dimensions: vec![
+                Subrange {
+                    start: SignedInteger {
+                        value: 1,
+                        span: Span::new(7, 8),
+                    },
+                    end: SignedInteger {
+                        value: 10,
+                        span: Span::new(10, 12),
+                    },
+                    span: Span::new(7, 12),
+                },
+                Subrange {
+                    start: SignedInteger {
+                        value: 0,
+                        span: Span::new(14, 15),
+                    },
+                    end: SignedInteger {
+                        value: 3,
+                        span: Span::new(17, 18),
+                    },
+                    span: Span::new(14, 18),
+                },
+            ],
+            element_type: Box::new(DataType::Named(Identifier::new(
+                "REAL", 23, 27,
+            ))),
+            span: Span::new(0, 27),
+        };
+
+        let got = ArrayType::from_str(src).unwrap();
 
         assert_eq!(got, expected);
     }
 
     #[test]
-    fn parse_a_function_block() {
-        let src = r#"FUNCTION_BLOCK FB_Timed_Counter
-        //=======================================================================
-        // Function Block Timed Counter :  Incremental count of the timed interval
-        //=======================================================================
-            VAR_INPUT
-                Execute         : BOOL := FALSE;        // Trigger signal to begin Timed Counting
-                Time_Increment  : REAL := 1.25;         // Enter Cycle Time (Seconds) between counts
-            END_VAR
+    fn subrange_type_with_initial_value() {
+        let src = "Percent : INT (0..100) := 50";
+        let expected = TypeDeclaration {
+            name: Identifier::new("Percent", 0, 7),
+            data_type: DataType::Subrange(SubrangeType {
+                base_type: Identifier::new("INT", 10, 13),
+                range: Subrange {
+                    start: SignedInteger {
+                        value: 0,
+                        span: Span::new(15, 16),
+                    },
+                    end: SignedInteger {
+                        value: 100,
+                        span: Span::new(18, 21),
+                    },
+                    span: Span::new(15, 21),
+                },
+                span: Span::new(10, 22),
+            }),
+            initial_value: Some(Initializer::Expression(Expression::Literal(
+                Literal::Integer(IntegerLiteral {
+                    value: 50,
+                    span: Span::new(26, 28),
+                }),
+            ))),
+            span: Span::new(0, 28),
+        };
 
-            VAR
-                CycleTimer      : TON;                  // Timer FB from Command Library
-                CycleCounter    : CTU;                  // Counter FB from Command Library
-                TimerPreset     : TIME;                 // Converted Time_Increment in Seconds to MS
-            END_VAR
+        let got = TypeDeclaration::from_str(src).unwrap();
 
-            Current_Count := CycleCounter;
-            Count_Complete := CycleCounter;
-        END_FUNCTION_BLOCK"#;
-        let expected = FunctionBlock {
-            name: Identifier::new("FB_Timed_Counter", 15, 31),
-            var_blocks: vec![
-                VarBlock {
-                    declarations: vec![
-                        VariableDeclaration {
-                            name: Identifier::new("Execute", 317, 324),
-                            declared_type: Identifier::new("BOOL", 335, 339),
-                            initial_value: Some(Expression::Literal(
-                                Literal::Boolean(BooleanLiteral {
-                                    value: false,
-                                    span: Span::new(343, 348),
-                                }),
-                            )),
-                            span: Span::new(317, 348),
-                        },
-                        VariableDeclaration {
-                            name: Identifier::new("Time_Increment", 415, 429),
-                            declared_type: Identifier::new("REAL", 433, 437),
-                            initial_value: Some(Expression::Literal(
-                                Literal::Float(FloatLiteral {
-                                    value: 1.25,
-                                    span: Span::new(441, 445),
-                                }),
-                            )),
-                            span: Span::new(415, 445),
-                        },
-                    ],
-                    kind: VarBlockKind::Input,
-                    span: Span::new(291, 519),
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn enumerated_type_with_explicit_values() {
+        let src = "(Idle, Running := 5)";
+        let expected = EnumeratedType {
+            values: vec![
+                EnumeratedValue {
+                    name: Identifier::new("Idle", 1, 5),
+                    value: None,
+                    span: Span::new(1, 5),
                 },
-                VarBlock {
-                    declarations: vec![
-                        VariableDeclaration {
-                            name: Identifier::new("CycleTimer", 553, 563),
-                            declared_type: Identifier::new("TON", 571, 574),
-                            initial_value: None,
-                            span: Span::new(553, 574),
-                        },
-                        VariableDeclaration {
-                            name: Identifier::new("CycleCounter", 642, 654),
-                            declared_type: Identifier::new("CTU", 660, 663),
-                            initial_value: None,
-                            span: Span::new(642, 663),
-                        },
-                        VariableDeclaration {
-                            name: Identifier::new("TimerPreset", 733, 744),
-                            declared_type: Identifier::new("TIME", 751, 755),
-                            initial_value: None,
-                            span: Span::new(733, 755),
-                        },
-                    ],
-                    kind: VarBlockKind::Normal,
-                    span: Span::new(533, 837),
+                EnumeratedValue {
+                    name: Identifier::new("Running", 7, 14),
+                    value: Some(SignedInteger {
+                        value: 5,
+                        span: Span::new(18, 19),
+                    }),
+                    span: Span::new(7, 19),
                 },
             ],
-            body: Block {
-                statements: vec![
-                    Statement::Assignment(Assignment {
-                        variable: Identifier::new("Current_Count", 851, 864),
-                        value: Expression::Variable(Identifier::new(
-                            "CycleCounter",
-                            868,
-                            880,
-                        )),
-                        span: Span::new(851, 880),
-                    }),
-                    Statement::Assignment(Assignment {
-                        variable: Identifier::new("Count_Complete", 894, 908),
-                        value: Expression::Variable(Identifier::new(
-                            "CycleCounter",
-                            912,
-                            924,
-                        )),
-                        span: Span::new(894, 924),
-                    }),
-                ],
-                span: Span::new(851, 925),
-            },
-            span: Span::new(0, 952),
+            span: Span::new(0, 20),
         };
 
-        parses_to! {
-            parser: RawParser,
-            input: src,
-            rule: Rule::function_block,
-            tokens: [
-              function_block(0, 952, [
-                identifier(15, 31),
-                preamble(291, 837, [
-                  var_block(291, 519, [
-                    input_var_block(291, 300),
-                    variable_decl(317, 348, [
-                      identifier(317, 324),
-                      identifier(335, 339),
-                      assign(340, 342),
-                      boolean(343, 348, [
-                        boolean_false(343, 348),
-                      ]),
-                    ]),
-                    variable_decl(415, 445, [
-                      identifier(415, 429),
-                      identifier(433, 437),
-                      assign(438, 440),
-                      float(441, 445),
-                    ]),
-                  ]),
-                  var_block(533, 837, [
-                    normal_var_block(533, 536),
-                    variable_decl(553, 574, [
-                      identifier(553, 563),
-                      identifier(571, 574),
-                    ]),
-                    variable_decl(642, 663, [
-                      identifier(642, 654),
-                      identifier(660, 663),
-                    ]),
-                    variable_decl(733, 755, [
-                      identifier(733, 744),
-                      identifier(751, 755),
-                    ]),
-                  ]),
-                ]),
-                block(851, 925, [
-                  statement(851, 881, [
-                    assignment(851, 880, [
-                      identifier(851, 864),
-                      assign(865, 867),
-                      identifier(868, 880),
-                    ]),
-                  ]),
-                  statement(894, 925, [
-                    assignment(894, 924, [
-                      identifier(894, 908),
-                      assign(909, 911),
-                      identifier(912, 924),
-                    ]),
-                  ]),
-                ]),
-              ]),
-            ]
+        let got = EnumeratedType::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn struct_type_declaration() {
+        let src = "Point : STRUCT x : REAL; y : REAL := 1.0; END_STRUCT";
+
+        let got = TypeDeclaration::from_str(src).unwrap();
+
+        match got.data_type {
+            DataType::Struct(s) => {
+                let names: Vec<_> =
+                    s.fields.iter().map(|f| f.name.value.as_str()).collect();
+                assert_eq!(names, &["x", "y"]);
+                assert!(s.fields[1].initial_value.is_some());
+            },
+            other => panic!("Expected a struct, found {:?}", other),
         }
+    }
 
-        let got = FunctionBlock::from_str(src).unwrap();
+    #[test]
+    fn nested_array_and_struct_initializers() {
+        let src = "(origin := (x := 0, y := 0), path := [2(1), -1])";
 
-        assert_eq!(got, expected);
+        let got = StructInitializer::from_str(src).unwrap();
 
-        // ... my god that was a pain to type out
+        assert_eq!(got.fields.len(), 2);
+        assert_eq!(got.fields[0].name.value, "origin");
+        match got.fields[0].value {
+            Initializer::Struct(ref origin) => {
+                assert_eq!(origin.fields.len(), 2)
+            },
+            ref other => panic!("Expected a struct, found {:?}", other),
+        }
+        match got.fields[1].value {
+            Initializer::Array(ref path) => {
+                assert_eq!(path.elements.len(), 2);
+                assert_eq!(
+                    path.elements[0].repetitions,
+                    Some(IntegerLiteral {
+                        value: 2,
+                        span: Span::new(38, 39),
+                    })
+                );
+                assert!(path.elements[1].repetitions.is_none());
+            },
+            ref other => panic!("Expected an array, found {:?}", other),
+        }
     }
 
     #[test]
-    fn solitary_if() {
-        let src = "if x = true THEN\nx := false;\nend_if";
-        let expected = Conditional {
-            true_branch: ConditionalBranch {
-                condition: Expression::BinaryExpression(BinaryExpression {
-                    left: Box::new(Expression::Variable(Identifier::new(
-                        "x", 3, 4,
-                    ))),
-                    right: Box::new(Expression::Literal(Literal::Boolean(
-                        BooleanLiteral {
-                            value: true,
-                            span: Span::new(7, 11),
-                        },
-                    ))),
-                    op: BinaryOp::Equals,
-                    span: Span::new(3, 12),
-                }),
-                block: Block {
-                    statements: vec![Statement::Assignment(Assignment {
-                        variable: Identifier::new("x", 17, 18),
-                        value: Expression::Literal(Literal::Boolean(
-                            BooleanLiteral {
-                                value: false,
-                                span: Span::new(22, 27),
-                            },
-                        )),
-                        span: Span::new(17, 27),
-                    })],
-                    span: Span::new(17, 28),
+    fn periodic_task() {
+        let src = "TASK Fast(INTERVAL := T#10ms, PRIORITY := 1)";
+        let expected = Task {
+            name: Identifier::new("Fast", 5, 9),
+            single: None,
+            interval: Some(Expression::Literal(Literal::Duration(
+                DurationLiteral {
+                    value: Duration::from_millis(10),
+                    span: Span::new(22, 28),
                 },
-            },
-            else_if_branches: vec![],
-            else_branch: None,
-            span: Span::new(0, 35),
+            ))),
+            priority: Some(IntegerLiteral {
+                value: 1,
+                span: Span::new(42, 43),
+            }),
+            span: Span::new(0, 44),
+        };
+
+        let got = Task::from_str(src).unwrap();
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn task_properties_can_only_be_set_once() {
+        let src = "TASK Fast(PRIORITY := 1, PRIORITY := 2)";
+
+        assert!(Task::from_str(src).is_err());
+    }
+
+    #[test]
+    fn single_resource_configuration() {
+        let src = "CONFIGURATION Cell
+            TASK Fast(INTERVAL := T#10ms, PRIORITY := 1);
+            PROGRAM Main WITH Fast : MainProg;
+        END_CONFIGURATION";
+
+        let got = Configuration::from_str(src).unwrap();
+
+        assert_eq!(got.resources.len(), 1);
+        let resource = &got.resources[0];
+        assert!(resource.name.is_none());
+        assert_eq!(resource.tasks[0].name.value, "Fast");
+        assert_eq!(
+            resource.programs,
+            vec![ProgramConfiguration {
+                name: Identifier::new("Main", 97, 101),
+                task: Some(Identifier::new("Fast", 107, 111)),
+                program_type: Identifier::new("MainProg", 114, 122),
+                span: Span::new(89, 122),
+            }]
+        );
+    }
+
+    #[test]
+    fn located_variable() {
+        let src = "Start AT %IX4.2 : BOOL";
+        let expected = VariableDeclaration {
+            name: Identifier::new("Start", 0, 5),
+            address: Some(DirectAddress {
+                area: AddressArea::Input,
+                size: AddressSize::Bit,
+                byte: 4,
+                bit: Some(2),
+                span: Span::new(9, 15),
+            }),
+            declared_type: DataType::Named(Identifier::new("BOOL", 18, 22)),
+            initial_value: None,
+            span: Span::new(0, 22),
         };
 
         parses_to! {
             parser: RawParser,
             input: src,
-            rule: Rule::conditional,
+            rule: Rule::variable_decl,
             tokens: [
-                conditional(0, 35, [
-                  if_branch(0, 28, [
-                    infix(3, 12, [
-                      identifier(3, 4),
-                      equal(5, 6),
-                      boolean(7, 11, [
-                        boolean_true(7, 11),
-                      ]),
-                    ]),
-                    block(17, 28, [
-                      statement(17, 28, [
-                        assignment(17, 27, [
-                          identifier(17, 18),
-                          assign(19, 21),
-                          boolean(22, 27, [
-                            boolean_false(22, 27),
-                          ]),
-                        ]),
-                      ]),
+                variable_decl(0, 22, [
+                    identifier(0, 5),
+                    direct_address(9, 15, [
+                        input_area(10, 11),
+                        bit_size(11, 12),
+                        address_byte(12, 13),
+                        address_bit(14, 15),
                     ]),
-                  ]),
-                ]),
+                    identifier(18, 22),
+                ])
             ]
         }
 
-        let got = Conditional::from_str(src).unwrap();
-        println!("{:#?}", got);
+        let got = VariableDeclaration::from_str(src).unwrap();
 
         assert_eq!(got, expected);
     }
 
+    #[test]
+    fn parse_direct_addresses() {
+        let inputs = vec![
+            ("%QW2", AddressArea::Output, AddressSize::Word, 2, None),
+            ("%MD10", AddressArea::Memory, AddressSize::DoubleWord, 10, None),
+            ("%ib7", AddressArea::Input, AddressSize::Byte, 7, None),
+            ("%QL16", AddressArea::Output, AddressSize::LongWord, 16, None),
+            ("%I4.0", AddressArea::Input, AddressSize::Bit, 4, Some(0)),
+            ("%MX1.7", AddressArea::Memory, AddressSize::Bit, 1, Some(7)),
+        ];
+
+        for (src, area, size, byte, bit) in inputs {
+            let got = DirectAddress::from_str(src).unwrap();
+
+            assert_eq!(got.area, area, "{}", src);
+            assert_eq!(got.size, size, "{}", src);
+            assert_eq!(got.byte, byte, "{}", src);
+            assert_eq!(got.bit, bit, "{}", src);
+        }
+    }
+
+    #[test]
+    fn invalid_direct_addresses() {
+        let inputs =
+            vec!["%IX4", "%IX4.8", "%QW2.1", "%Z4", "%IX99999999999.0"];
+
+        for src in inputs {
+            assert!(DirectAddress::from_str(src).is_err(), "{}", src);
+        }
+    }
+
     /// A way to cheat [`parses_to!()`] when you want to see what a parse tree
     /// would look like.
     fn _pretty_print(pair: Pair<'_, Rule>, indent_level: usize) {
@@ -0,0 +1,408 @@
+//! A lossless *Concrete Syntax Tree* which keeps comments, pragmas and
+//! whitespace.
+//!
+//! The tree is split into two layers, similar to [`rowan`][rowan]. The
+//! *green* tree ([`GreenNode`] and [`GreenToken`]) owns the text and knows
+//! nothing about where it is located, while the *red* tree ([`SyntaxNode`]
+//! and [`SyntaxToken`]) is a cheap view over the green tree which tracks
+//! each element's offset into the original source.
+//!
+//! Concatenating the text of every token in a tree gives back the exact text
+//! it was parsed from.
+//!
+//! [rowan]: https://github.com/rust-analyzer/rowan
+
+use crate::{
+    parser::{RawParser, Rule},
+    ParseError,
+};
+use codespan::Span;
+use pest::{iterators::Pair, Parser};
+use std::fmt::{self, Display, Formatter};
+
+/// Parse a string of *Structured Text* into a lossless syntax tree.
+pub fn parse(src: &str) -> Result<GreenNode, ParseError> {
+    let file = RawParser::parse(Rule::file, src)?.next().unwrap();
+
+    // the file rule starts at SOI and ends at EOI, so this covers everything
+    Ok(build_node(file, src))
+}
+
+/// The kind of item in a syntax tree.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    /// Something matched by a rule in the grammar.
+    Rule(Rule),
+    Whitespace,
+    /// A `(* block *)` or `// line` comment.
+    Comment,
+    /// A vendor-specific pragma (e.g. `{attribute 'hide'}`).
+    Pragma,
+    /// A keyword which isn't part of a more specific rule (e.g. `IF`).
+    Keyword,
+    /// A single punctuation character (e.g. `;` or `(`).
+    Punctuation,
+}
+
+impl SyntaxKind {
+    /// Is this something which doesn't affect the meaning of a program?
+    pub fn is_trivia(self) -> bool {
+        match self {
+            SyntaxKind::Whitespace
+            | SyntaxKind::Comment
+            | SyntaxKind::Pragma => true,
+            _ => false,
+        }
+    }
+}
+
+/// An interior node in the green tree.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenNode {
+    kind: SyntaxKind,
+    text_len: usize,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> GreenNode {
+        let text_len = children.iter().map(GreenElement::text_len).sum();
+
+        GreenNode {
+            kind,
+            text_len,
+            children,
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind { self.kind }
+
+    /// The length of this node's text, in bytes.
+    pub fn text_len(&self) -> usize { self.text_len }
+
+    pub fn children(&self) -> &[GreenElement] { &self.children }
+}
+
+impl Display for GreenNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for child in &self.children {
+            match child {
+                GreenElement::Node(node) => write!(f, "{}", node)?,
+                GreenElement::Token(token) => write!(f, "{}", token.text)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A leaf in the green tree.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenToken {
+    kind: SyntaxKind,
+    text: String,
+}
+
+impl GreenToken {
+    pub fn new<S: Into<String>>(kind: SyntaxKind, text: S) -> GreenToken {
+        GreenToken {
+            kind,
+            text: text.into(),
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind { self.kind }
+
+    pub fn text(&self) -> &str { &self.text }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GreenElement {
+    Node(GreenNode),
+    Token(GreenToken),
+}
+
+impl GreenElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            GreenElement::Node(node) => node.kind,
+            GreenElement::Token(token) => token.kind,
+        }
+    }
+
+    pub fn text_len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.text_len,
+            GreenElement::Token(token) => token.text.len(),
+        }
+    }
+}
+
+/// A view over a [`GreenNode`] which knows where it is in the source text.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SyntaxNode<'a> {
+    green: &'a GreenNode,
+    offset: usize,
+}
+
+impl<'a> SyntaxNode<'a> {
+    pub fn new_root(green: &'a GreenNode) -> SyntaxNode<'a> {
+        SyntaxNode { green, offset: 0 }
+    }
+
+    pub fn kind(self) -> SyntaxKind { self.green.kind }
+
+    pub fn green(self) -> &'a GreenNode { self.green }
+
+    pub fn span(self) -> Span {
+        let end = self.offset + self.green.text_len;
+        Span::new(self.offset as u32, end as u32)
+    }
+
+    /// The node's direct children, including tokens.
+    pub fn children_with_tokens(
+        self,
+    ) -> impl Iterator<Item = SyntaxElement<'a>> + 'a {
+        let mut offset = self.offset;
+
+        self.green.children.iter().map(move |child| {
+            let element = match child {
+                GreenElement::Node(green) => {
+                    SyntaxElement::Node(SyntaxNode { green, offset })
+                },
+                GreenElement::Token(green) => {
+                    SyntaxElement::Token(SyntaxToken { green, offset })
+                },
+            };
+            offset += child.text_len();
+            element
+        })
+    }
+
+    /// The node's direct children, skipping over tokens.
+    pub fn children(self) -> impl Iterator<Item = SyntaxNode<'a>> + 'a {
+        self.children_with_tokens().filter_map(|element| match element {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// Every token underneath this node, in the order they appear in the
+    /// source text.
+    pub fn tokens(self) -> Vec<SyntaxToken<'a>> {
+        let mut tokens = Vec::new();
+
+        for child in self.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+
+        tokens
+    }
+}
+
+impl<'a> Display for SyntaxNode<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(self.green, f)
+    }
+}
+
+/// A view over a [`GreenToken`] which knows where it is in the source text.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SyntaxToken<'a> {
+    green: &'a GreenToken,
+    offset: usize,
+}
+
+impl<'a> SyntaxToken<'a> {
+    pub fn kind(self) -> SyntaxKind { self.green.kind }
+
+    pub fn text(self) -> &'a str { &self.green.text }
+
+    pub fn span(self) -> Span {
+        let end = self.offset + self.green.text.len();
+        Span::new(self.offset as u32, end as u32)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SyntaxElement<'a> {
+    Node(SyntaxNode<'a>),
+    Token(SyntaxToken<'a>),
+}
+
+impl<'a> SyntaxElement<'a> {
+    pub fn kind(self) -> SyntaxKind {
+        match self {
+            SyntaxElement::Node(node) => node.kind(),
+            SyntaxElement::Token(token) => token.kind(),
+        }
+    }
+
+    pub fn span(self) -> Span {
+        match self {
+            SyntaxElement::Node(node) => node.span(),
+            SyntaxElement::Token(token) => token.span(),
+        }
+    }
+}
+
+fn build_element(pair: Pair<'_, Rule>, src: &str) -> GreenElement {
+    let is_leaf = pair.clone().into_inner().next().is_none();
+
+    if is_leaf && !pair.as_str().is_empty() {
+        GreenElement::Token(GreenToken::new(
+            SyntaxKind::Rule(pair.as_rule()),
+            pair.as_str(),
+        ))
+    } else {
+        GreenElement::Node(build_node(pair, src))
+    }
+}
+
+fn build_node(pair: Pair<'_, Rule>, src: &str) -> GreenNode {
+    let kind = SyntaxKind::Rule(pair.as_rule());
+    let span = pair.as_span();
+
+    let mut children = Vec::new();
+    let mut cursor = span.start();
+
+    for child in pair.into_inner() {
+        if child.as_rule() == Rule::EOI {
+            continue;
+        }
+
+        // anything between child rules was skipped by the grammar
+        let child_span = child.as_span();
+        lex_gap(&src[cursor..child_span.start()], &mut children);
+        children.push(build_element(child, src));
+        cursor = child_span.end();
+    }
+
+    lex_gap(&src[cursor..span.end()], &mut children);
+
+    GreenNode::new(kind, children)
+}
+
+/// Split the text which wasn't captured by a rule into trivia, keywords and
+/// punctuation.
+fn lex_gap(mut gap: &str, tokens: &mut Vec<GreenElement>) {
+    while let Some(first) = gap.chars().next() {
+        let (kind, len) = if first.is_whitespace() {
+            let len = gap
+                .find(|c: char| !c.is_whitespace())
+                .unwrap_or(gap.len());
+            (SyntaxKind::Whitespace, len)
+        } else if gap.starts_with("(*") {
+            let len = gap.find("*)").map(|ix| ix + 2).unwrap_or(gap.len());
+            (SyntaxKind::Comment, len)
+        } else if gap.starts_with("//") {
+            let len = gap
+                .find(|c: char| c == '\r' || c == '\n')
+                .unwrap_or(gap.len());
+            (SyntaxKind::Comment, len)
+        } else if first == '{' {
+            let len = gap.find('}').map(|ix| ix + 1).unwrap_or(gap.len());
+            (SyntaxKind::Pragma, len)
+        } else if first.is_alphanumeric() || first == '_' {
+            let len = gap
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(gap.len());
+            (SyntaxKind::Keyword, len)
+        } else {
+            (SyntaxKind::Punctuation, first.len_utf8())
+        };
+
+        tokens.push(GreenElement::Token(GreenToken::new(kind, &gap[..len])));
+        gap = &gap[len..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC: &str = "(* header *)
+PROGRAM main // the entry point
+    VAR
+        {attribute 'hide'}
+        x : INT := 5; (* counter *)
+    END_VAR
+
+    x := x + 1;
+END_PROGRAM
+";
+
+    #[test]
+    fn round_trip_is_byte_for_byte() {
+        let green = parse(SRC).unwrap();
+
+        assert_eq!(green.to_string(), SRC);
+        assert_eq!(green.text_len(), SRC.len());
+        assert_eq!(green.kind(), SyntaxKind::Rule(Rule::file));
+    }
+
+    #[test]
+    fn trivia_is_kept() {
+        let green = parse(SRC).unwrap();
+        let root = SyntaxNode::new_root(&green);
+
+        let tokens = root.tokens();
+        let comments: Vec<_> = tokens
+            .iter()
+            .filter(|t| t.kind() == SyntaxKind::Comment)
+            .map(|t| t.text())
+            .collect();
+        assert_eq!(
+            comments,
+            &["(* header *)", "// the entry point", "(* counter *)"]
+        );
+
+        let pragma = tokens
+            .iter()
+            .find(|t| t.kind() == SyntaxKind::Pragma)
+            .unwrap();
+        assert_eq!(pragma.text(), "{attribute 'hide'}");
+        let start = SRC.find('{').unwrap() as u32;
+        assert_eq!(pragma.span(), Span::new(start, start + 18));
+    }
+
+    #[test]
+    fn tokens_cover_the_whole_source() {
+        let green = parse(SRC).unwrap();
+        let root = SyntaxNode::new_root(&green);
+
+        let mut offset = 0;
+        for token in root.tokens() {
+            assert_eq!(token.span().start().to_usize(), offset);
+            let end = token.span().end().to_usize();
+            assert_eq!(&SRC[offset..end], token.text());
+            offset = end;
+        }
+
+        assert_eq!(offset, SRC.len());
+    }
+
+    #[test]
+    fn nodes_know_their_location() {
+        let green = parse(SRC).unwrap();
+        let root = SyntaxNode::new_root(&green);
+
+        let program = root.children().next().unwrap();
+        assert_eq!(program.kind(), SyntaxKind::Rule(Rule::program));
+        let span = program.span();
+        assert_eq!(span.start().to_usize(), SRC.find("PROGRAM").unwrap());
+        let text = &SRC[span.start().to_usize()..span.end().to_usize()];
+        assert_eq!(program.to_string(), text);
+        assert!(text.trim_end().ends_with("END_PROGRAM"));
+
+        let keywords: Vec<_> = program
+            .children_with_tokens()
+            .filter(|e| e.kind() == SyntaxKind::Keyword)
+            .map(|e| e.span())
+            .collect();
+        assert_eq!(keywords.len(), 2);
+    }
+}
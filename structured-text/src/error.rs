@@ -1,6 +1,11 @@
 use crate::parser::Rule;
+use codespan::Span;
+use codespan_reporting::diagnostic::{
+    Diagnostic as CodespanDiagnostic, Label as CodespanLabel, LabelStyle,
+    Severity as CodespanSeverity,
+};
 use pest::{
-    error::{Error as PestError, ErrorVariant},
+    error::{Error as PestError, ErrorVariant, InputLocation},
     iterators::Pair,
 };
 use std::{
@@ -15,6 +20,39 @@ pub struct ParseError {
 }
 
 impl ParseError {
+    /// The location in the source text this error refers to.
+    pub fn span(&self) -> Span {
+        match self.inner.location {
+            InputLocation::Pos(pos) => Span::new(pos as u32, pos as u32),
+            InputLocation::Span((start, end)) => {
+                Span::new(start as u32, end as u32)
+            },
+        }
+    }
+
+    pub(crate) fn into_diagnostic(self, span: Span) -> Diagnostic {
+        match self.inner.variant {
+            ErrorVariant::CustomError { message } => {
+                Diagnostic::error(message, Label::new(span, ""))
+            },
+            ErrorVariant::ParsingError {
+                positives,
+                negatives,
+            } => {
+                let mut label = Vec::new();
+                if !positives.is_empty() {
+                    label.push(format!("expected {}", describe(&positives)));
+                }
+                if !negatives.is_empty() {
+                    label.push(format!("unexpected {}", describe(&negatives)));
+                }
+
+                let label = Label::new(span, label.join(", "));
+                Diagnostic::error("Syntax error", label)
+            },
+        }
+    }
+
     pub(crate) fn custom<S: Into<String>>(
         message: S,
         span: pest::Span<'_>,
@@ -63,6 +101,21 @@ impl ParseError {
     }
 }
 
+/// Turn a list of rules into something like "identifier, integer or float".
+fn describe(rules: &[Rule]) -> String {
+    let names: Vec<_> = rules
+        .iter()
+        .map(|rule| format!("{:?}", rule).replace('_', " "))
+        .collect();
+
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => {
+            format!("{} or {}", rest.join(", "), last)
+        },
+        _ => names.join(""),
+    }
+}
+
 // this is just an implementation detail to make `?` more useful
 #[doc(hidden)]
 impl From<PestError<Rule>> for ParseError {
@@ -80,3 +133,149 @@ impl Display for ParseError {
         write!(f, "Unable to parse the input text")
     }
 }
+
+/// A problem encountered while parsing or analysing a program, laid out the
+/// same way as a `codespan_reporting` diagnostic so it can be rendered
+/// alongside other compiler errors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub primary_label: Label,
+    /// Other locations which help explain the problem (e.g. where a
+    /// duplicate name was first declared).
+    pub secondary_labels: Vec<Label>,
+}
+
+impl Diagnostic {
+    pub fn error<S: Into<String>>(message: S, primary_label: Label) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message: message.into(),
+            primary_label,
+            secondary_labels: Vec::new(),
+        }
+    }
+
+    pub fn warning<S: Into<String>>(message: S, primary_label: Label) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            ..Diagnostic::error(message, primary_label)
+        }
+    }
+
+    /// Extra information about another diagnostic (e.g. where a duplicate
+    /// name was first declared, when that is in a different file).
+    pub fn note<S: Into<String>>(message: S, primary_label: Label) -> Self {
+        Diagnostic {
+            severity: Severity::Note,
+            ..Diagnostic::error(message, primary_label)
+        }
+    }
+
+    pub fn with_secondary_label(mut self, label: Label) -> Self {
+        self.secondary_labels.push(label);
+        self
+    }
+
+    /// Convert to a [`codespan_reporting`] diagnostic so it can be printed
+    /// using `codespan_reporting::term::emit()`.
+    pub fn to_codespan<FileId: Copy>(
+        &self,
+        file_id: FileId,
+    ) -> CodespanDiagnostic<FileId> {
+        let mut labels =
+            vec![self.primary_label.to_codespan(LabelStyle::Primary, file_id)];
+        labels.extend(
+            self.secondary_labels
+                .iter()
+                .map(|label| label.to_codespan(LabelStyle::Secondary, file_id)),
+        );
+
+        CodespanDiagnostic::new(self.severity.into())
+            .with_message(self.message.clone())
+            .with_labels(labels)
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+
+        if !self.primary_label.message.is_empty() {
+            write!(f, ": {}", self.primary_label.message)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+impl From<Severity> for CodespanSeverity {
+    fn from(other: Severity) -> CodespanSeverity {
+        match other {
+            Severity::Note => CodespanSeverity::Note,
+            Severity::Warning => CodespanSeverity::Warning,
+            Severity::Error => CodespanSeverity::Error,
+        }
+    }
+}
+
+/// A message attached to a particular part of the source text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+impl Label {
+    pub fn new<S: Into<String>>(span: Span, message: S) -> Self {
+        Label {
+            span,
+            message: message.into(),
+        }
+    }
+
+    pub fn to_codespan<FileId>(
+        &self,
+        style: LabelStyle,
+        file_id: FileId,
+    ) -> CodespanLabel<FileId> {
+        let range = self.span.start().to_usize()..self.span.end().to_usize();
+
+        CodespanLabel::new(style, file_id, range)
+            .with_message(self.message.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_to_a_codespan_diagnostic() {
+        let diagnostic = Diagnostic::warning(
+            "`x` shadows a declaration in an outer scope",
+            Label::new(Span::new(10, 11), "declared here"),
+        )
+        .with_secondary_label(Label::new(Span::new(2, 3), "outer"));
+
+        let got = diagnostic.to_codespan(());
+
+        assert_eq!(got.severity, CodespanSeverity::Warning);
+        assert_eq!(got.message, diagnostic.message);
+        assert_eq!(got.labels.len(), 2);
+        assert_eq!(got.labels[0].style, LabelStyle::Primary);
+        assert_eq!(got.labels[0].range, 10..11);
+        assert_eq!(got.labels[0].message, "declared here");
+        assert_eq!(got.labels[1].style, LabelStyle::Secondary);
+        assert_eq!(got.labels[1].range, 2..3);
+    }
+}
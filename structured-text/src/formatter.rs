@@ -0,0 +1,917 @@
+//! A pretty-printer which turns a [`File`] back into *Structured Text* using a
+//! canonical style.
+
+use crate::ast::*;
+use std::time::Duration;
+
+/// Settings used when formatting code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatOptions {
+    /// The number of spaces used for each level of indentation.
+    pub indent_width: usize,
+    pub keyword_case: KeywordCase,
+    /// Line up the `:` in each `VAR` block.
+    pub align_declarations: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> FormatOptions {
+        FormatOptions {
+            indent_width: 4,
+            keyword_case: KeywordCase::Upper,
+            align_declarations: true,
+        }
+    }
+}
+
+/// How keywords (e.g. `IF` and `END_VAR`) should be written.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum KeywordCase {
+    Upper,
+    Lower,
+}
+
+/// Turn a [`File`] back into source code.
+pub fn format(file: &File, options: &FormatOptions) -> String {
+    let mut formatter = Formatter {
+        options,
+        buffer: String::new(),
+        indent: 0,
+    };
+    formatter.file(file);
+
+    formatter.buffer
+}
+
+struct Formatter<'a> {
+    options: &'a FormatOptions,
+    buffer: String,
+    indent: usize,
+}
+
+impl<'a> Formatter<'a> {
+    fn kw(&self, keyword: &str) -> String {
+        match self.options.keyword_case {
+            KeywordCase::Upper => keyword.to_uppercase(),
+            KeywordCase::Lower => keyword.to_lowercase(),
+        }
+    }
+
+    fn line(&mut self, text: &str) {
+        if !text.is_empty() {
+            let indent = self.indent * self.options.indent_width;
+            self.buffer.extend(std::iter::repeat(' ').take(indent));
+            self.buffer.push_str(text);
+        }
+        self.buffer.push('\n');
+    }
+
+    fn indented<F: FnOnce(&mut Self)>(&mut self, body: F) {
+        self.indent += 1;
+        body(self);
+        self.indent -= 1;
+    }
+
+    fn file(&mut self, file: &File) {
+        // each item is separated by a blank line
+        let mut first = true;
+        let mut separator = |f: &mut Self| {
+            if !first {
+                f.line("");
+            }
+            first = false;
+        };
+
+        if !file.types.is_empty() {
+            separator(self);
+            self.types(&file.types);
+        }
+        for function in &file.functions {
+            separator(self);
+            self.function(function);
+        }
+        for function_block in &file.function_blocks {
+            separator(self);
+            self.function_block(function_block);
+        }
+        for program in &file.programs {
+            separator(self);
+            self.program(program);
+        }
+        for configuration in &file.configurations {
+            separator(self);
+            self.configuration(configuration);
+        }
+    }
+
+    fn types(&mut self, types: &[TypeDeclaration]) {
+        self.line(&self.kw("TYPE"));
+        self.indented(|f| {
+            for decl in types {
+                f.type_declaration(decl);
+            }
+        });
+        self.line(&self.kw("END_TYPE"));
+    }
+
+    fn type_declaration(&mut self, decl: &TypeDeclaration) {
+        let init = self.initial_value(&decl.initial_value);
+
+        match decl.data_type {
+            DataType::Struct(ref s) => {
+                let header =
+                    format!("{} : {}", decl.name.value, self.kw("STRUCT"));
+                self.line(&header);
+                self.indented(|f| f.declarations(&s.fields));
+                let footer = format!("{}{};", self.kw("END_STRUCT"), init);
+                self.line(&footer);
+            },
+            ref other => {
+                let data_type = self.data_type(other);
+                self.line(&format!(
+                    "{} : {}{};",
+                    decl.name.value, data_type, init
+                ));
+            },
+        }
+    }
+
+    fn function(&mut self, function: &Function) {
+        let header = format!(
+            "{} {} : {}",
+            self.kw("FUNCTION"),
+            function.name.value,
+            function.return_type.value
+        );
+        self.pou(&header, &function.var_blocks, &function.body);
+        self.line(&self.kw("END_FUNCTION"));
+    }
+
+    fn function_block(&mut self, function_block: &FunctionBlock) {
+        let header = format!(
+            "{} {}",
+            self.kw("FUNCTION_BLOCK"),
+            function_block.name.value
+        );
+        self.pou(&header, &function_block.var_blocks, &function_block.body);
+        self.line(&self.kw("END_FUNCTION_BLOCK"));
+    }
+
+    fn program(&mut self, program: &Program) {
+        let header = format!("{} {}", self.kw("PROGRAM"), program.name.value);
+        self.pou(&header, &program.var_blocks, &program.body);
+        self.line(&self.kw("END_PROGRAM"));
+    }
+
+    /// The shared parts of a *Program Organisation Unit*.
+    fn pou(&mut self, header: &str, var_blocks: &[VarBlock], body: &Block) {
+        self.line(header);
+        self.indented(|f| {
+            for var_block in var_blocks {
+                f.var_block(var_block);
+            }
+            if !var_blocks.is_empty() && !body.statements.is_empty() {
+                f.line("");
+            }
+            f.block(body);
+        });
+    }
+
+    fn configuration(&mut self, configuration: &Configuration) {
+        let header = format!(
+            "{} {}",
+            self.kw("CONFIGURATION"),
+            configuration.name.value
+        );
+        self.line(&header);
+        self.indented(|f| {
+            for var_block in &configuration.var_blocks {
+                f.var_block(var_block);
+            }
+            for resource in &configuration.resources {
+                f.resource(resource);
+            }
+        });
+        self.line(&self.kw("END_CONFIGURATION"));
+    }
+
+    fn resource(&mut self, resource: &Resource) {
+        let name = match resource.name {
+            Some(ref name) => name,
+            // an anonymous resource is declared directly inside the
+            // configuration
+            None => return self.resource_body(resource),
+        };
+
+        let mut header = format!("{} {}", self.kw("RESOURCE"), name.value);
+        if let Some(ref processor) = resource.processor {
+            header.push_str(&format!(" {} {}", self.kw("ON"), processor.value));
+        }
+
+        self.line(&header);
+        self.indented(|f| {
+            for var_block in &resource.var_blocks {
+                f.var_block(var_block);
+            }
+            f.resource_body(resource);
+        });
+        self.line(&self.kw("END_RESOURCE"));
+    }
+
+    fn resource_body(&mut self, resource: &Resource) {
+        for task in &resource.tasks {
+            let mut properties = Vec::new();
+            if let Some(ref single) = task.single {
+                let value = self.expression(single);
+                properties.push(format!("{} := {}", self.kw("SINGLE"), value));
+            }
+            if let Some(ref interval) = task.interval {
+                let value = self.expression(interval);
+                properties
+                    .push(format!("{} := {}", self.kw("INTERVAL"), value));
+            }
+            if let Some(ref priority) = task.priority {
+                properties.push(format!(
+                    "{} := {}",
+                    self.kw("PRIORITY"),
+                    priority.value
+                ));
+            }
+
+            let line = format!(
+                "{} {}({});",
+                self.kw("TASK"),
+                task.name.value,
+                properties.join(", ")
+            );
+            self.line(&line);
+        }
+
+        for program in &resource.programs {
+            let mut line =
+                format!("{} {}", self.kw("PROGRAM"), program.name.value);
+            if let Some(ref task) = program.task {
+                line.push_str(&format!(" {} {}", self.kw("WITH"), task.value));
+            }
+            line.push_str(&format!(" : {};", program.program_type.value));
+            self.line(&line);
+        }
+    }
+
+    fn var_block(&mut self, var_block: &VarBlock) {
+        let keyword = match var_block.kind {
+            VarBlockKind::Normal => "VAR",
+            VarBlockKind::Global => "VAR_GLOBAL",
+            VarBlockKind::External => "VAR_EXTERNAL",
+            VarBlockKind::Input => "VAR_INPUT",
+            VarBlockKind::Output => "VAR_OUTPUT",
+        };
+
+        self.line(&self.kw(keyword));
+        self.indented(|f| f.declarations(&var_block.declarations));
+        self.line(&self.kw("END_VAR"));
+    }
+
+    fn declarations(&mut self, declarations: &[VariableDeclaration]) {
+        let names: Vec<_> = declarations
+            .iter()
+            .map(|decl| self.declaration_name(decl))
+            .collect();
+
+        let width = if self.options.align_declarations {
+            names.iter().map(|name| name.chars().count()).max().unwrap_or(0)
+        } else {
+            0
+        };
+
+        for (name, decl) in names.iter().zip(declarations) {
+            let line = format!(
+                "{:width$} : {}{};",
+                name,
+                self.data_type(&decl.declared_type),
+                self.initial_value(&decl.initial_value),
+                width = width
+            );
+            self.line(&line);
+        }
+    }
+
+    fn declaration_name(&self, decl: &VariableDeclaration) -> String {
+        match decl.address {
+            Some(address) => format!(
+                "{} {} {}",
+                decl.name.value,
+                self.kw("AT"),
+                direct_address(address)
+            ),
+            None => decl.name.value.clone(),
+        }
+    }
+
+    fn initial_value(&self, initial_value: &Option<Initializer>) -> String {
+        match initial_value {
+            Some(value) => format!(" := {}", self.initializer(value)),
+            None => String::new(),
+        }
+    }
+
+    fn data_type(&self, data_type: &DataType) -> String {
+        match data_type {
+            DataType::Named(name) => name.value.clone(),
+            DataType::Enumerated(e) => {
+                let values: Vec<_> = e
+                    .values
+                    .iter()
+                    .map(|v| match v.value {
+                        Some(ref value) => {
+                            format!("{} := {}", v.name.value, value.value)
+                        },
+                        None => v.name.value.clone(),
+                    })
+                    .collect();
+                format!("({})", values.join(", "))
+            },
+            DataType::Subrange(s) => {
+                format!("{} ({})", s.base_type.value, subrange(&s.range))
+            },
+            DataType::Array(a) => {
+                let dimensions: Vec<_> =
+                    a.dimensions.iter().map(subrange).collect();
+                format!(
+                    "{} [{}] {} {}",
+                    self.kw("ARRAY"),
+                    dimensions.join(", "),
+                    self.kw("OF"),
+                    self.data_type(&a.element_type)
+                )
+            },
+            // the parser only accepts structs in a TYPE declaration, but
+            // anything else can still be written on a single line
+            DataType::Struct(s) => {
+                let mut text = self.kw("STRUCT");
+                for field in &s.fields {
+                    text.push_str(&format!(
+                        " {} : {}{};",
+                        self.declaration_name(field),
+                        self.data_type(&field.declared_type),
+                        self.initial_value(&field.initial_value)
+                    ));
+                }
+                format!("{} {}", text, self.kw("END_STRUCT"))
+            },
+        }
+    }
+
+    fn initializer(&self, initializer: &Initializer) -> String {
+        match initializer {
+            Initializer::Expression(e) => self.expression(e),
+            Initializer::Array(a) => {
+                let elements: Vec<_> = a
+                    .elements
+                    .iter()
+                    .map(|element| {
+                        let value = self.initializer(&element.value);
+                        match element.repetitions {
+                            Some(ref count) => {
+                                format!("{}({})", count.value, value)
+                            },
+                            None => value,
+                        }
+                    })
+                    .collect();
+                format!("[{}]", elements.join(", "))
+            },
+            Initializer::Struct(s) => {
+                let fields: Vec<_> = s
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        format!(
+                            "{} := {}",
+                            field.name.value,
+                            self.initializer(&field.value)
+                        )
+                    })
+                    .collect();
+                format!("({})", fields.join(", "))
+            },
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        for statement in &block.statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Assignment(a) => {
+                let line = format!("{};", self.assignment(a));
+                self.line(&line);
+            },
+            Statement::Call(c) => {
+                let line = format!("{};", self.call(c));
+                self.line(&line);
+            },
+            Statement::Exit(_) => {
+                let line = format!("{};", self.kw("EXIT"));
+                self.line(&line);
+            },
+            Statement::Conditional(c) => self.conditional(c),
+            Statement::Repeat(r) => {
+                self.line(&self.kw("REPEAT"));
+                self.indented(|f| f.block(&r.block));
+                let condition = self.assignment(&r.condition);
+                let until = format!("{} {};", self.kw("UNTIL"), condition);
+                self.line(&until);
+                let footer = format!("{};", self.kw("END_REPEAT"));
+                self.line(&footer);
+            },
+            Statement::While(w) => {
+                let header = format!(
+                    "{} {} {}",
+                    self.kw("WHILE"),
+                    self.expression(&w.condition),
+                    self.kw("DO")
+                );
+                self.line(&header);
+                self.indented(|f| f.block(&w.body));
+                let footer = format!("{};", self.kw("END_WHILE"));
+                self.line(&footer);
+            },
+            Statement::For(f) => self.for_loop(f),
+            Statement::Case(c) => self.case(c),
+        }
+    }
+
+    fn assignment(&self, assignment: &Assignment) -> String {
+        format!(
+            "{} := {}",
+            assignment.variable.value,
+            self.expression(&assignment.value)
+        )
+    }
+
+    fn conditional(&mut self, conditional: &Conditional) {
+        let branches = std::iter::once(&conditional.true_branch)
+            .chain(&conditional.else_if_branches);
+
+        for (i, branch) in branches.enumerate() {
+            let keyword = if i == 0 { "IF" } else { "ELSIF" };
+            let header = format!(
+                "{} {} {}",
+                self.kw(keyword),
+                self.expression(&branch.condition),
+                self.kw("THEN")
+            );
+            self.line(&header);
+            self.indented(|f| f.block(&branch.block));
+        }
+
+        if let Some(ref else_branch) = conditional.else_branch {
+            self.line(&self.kw("ELSE"));
+            self.indented(|f| f.block(else_branch));
+        }
+
+        let footer = format!("{};", self.kw("END_IF"));
+        self.line(&footer);
+    }
+
+    fn for_loop(&mut self, for_loop: &For) {
+        let mut header = format!(
+            "{} {} := {} {} {}",
+            self.kw("FOR"),
+            for_loop.variable.value,
+            self.expression(&for_loop.start),
+            self.kw("TO"),
+            self.expression(&for_loop.end)
+        );
+        if let Some(ref step) = for_loop.step {
+            header.push_str(&format!(
+                " {} {}",
+                self.kw("BY"),
+                self.expression(step)
+            ));
+        }
+        header.push_str(&format!(" {}", self.kw("DO")));
+
+        self.line(&header);
+        self.indented(|f| f.block(&for_loop.body));
+        let footer = format!("{};", self.kw("END_FOR"));
+        self.line(&footer);
+    }
+
+    fn case(&mut self, case: &Case) {
+        let header = format!(
+            "{} {} {}",
+            self.kw("CASE"),
+            self.expression(&case.selector),
+            self.kw("OF")
+        );
+        self.line(&header);
+
+        self.indented(|f| {
+            for branch in &case.branches {
+                let labels: Vec<_> = branch
+                    .labels
+                    .iter()
+                    .map(|label| match label {
+                        CaseLabel::Integer(i) => i.value.to_string(),
+                        CaseLabel::Range(r) => subrange(r),
+                        CaseLabel::Enumerated(name) => name.value.clone(),
+                    })
+                    .collect();
+                f.line(&format!("{}:", labels.join(", ")));
+                f.indented(|f| f.block(&branch.block));
+            }
+
+            if let Some(ref else_branch) = case.else_branch {
+                f.line(&f.kw("ELSE"));
+                f.indented(|f| f.block(else_branch));
+            }
+        });
+
+        let footer = format!("{};", self.kw("END_CASE"));
+        self.line(&footer);
+    }
+
+    fn call(&self, call: &Call) -> String {
+        let arguments: Vec<_> = call
+            .arguments
+            .iter()
+            .map(|arg| match arg {
+                Argument::Positional(value) => self.expression(value),
+                Argument::Input(input) => format!(
+                    "{} := {}",
+                    input.name.value,
+                    self.expression(&input.value)
+                ),
+                Argument::Output(output) => format!(
+                    "{} => {}",
+                    output.name.value, output.variable.value
+                ),
+            })
+            .collect();
+
+        format!("{}({})", call.function.value, arguments.join(", "))
+    }
+
+    fn expression(&self, expression: &Expression) -> String {
+        match expression {
+            Expression::Variable(name) => name.value.clone(),
+            Expression::Literal(literal) => self.literal(literal),
+            Expression::Call(call) => self.call(call),
+            Expression::MemberAccess(access) => format!(
+                "{}.{}",
+                self.expression(&access.value),
+                access.member.value
+            ),
+            Expression::UnaryExpression(unary) => {
+                let operand = match *unary.value {
+                    Expression::BinaryExpression(_) => {
+                        format!("({})", self.expression(&unary.value))
+                    },
+                    ref other => self.expression(other),
+                };
+
+                match unary.op {
+                    UnaryOp::Not => format!("{} {}", self.kw("NOT"), operand),
+                    UnaryOp::Negate => format!("-{}", operand),
+                    UnaryOp::Plus => format!("+{}", operand),
+                }
+            },
+            Expression::BinaryExpression(binary) => {
+                let precedence = precedence(binary.op);
+
+                // every operator is left-associative, so only the right hand
+                // side needs parentheses when precedence is equal
+                let left = self.operand(&binary.left, |p| p < precedence);
+                let right = self.operand(&binary.right, |p| p <= precedence);
+
+                format!("{} {} {}", left, self.binary_op(binary.op), right)
+            },
+        }
+    }
+
+    fn operand<F>(&self, expression: &Expression, needs_parens: F) -> String
+    where
+        F: FnOnce(u8) -> bool,
+    {
+        let text = self.expression(expression);
+
+        match expression {
+            Expression::BinaryExpression(inner)
+                if needs_parens(precedence(inner.op)) =>
+            {
+                format!("({})", text)
+            },
+            _ => text,
+        }
+    }
+
+    fn binary_op(&self, op: BinaryOp) -> String {
+        match op {
+            BinaryOp::Modulo | BinaryOp::And | BinaryOp::Xor | BinaryOp::Or => {
+                self.kw(op.symbol())
+            },
+            _ => op.symbol().to_string(),
+        }
+    }
+
+    fn literal(&self, literal: &Literal) -> String {
+        match literal {
+            Literal::Integer(i) => i.value.to_string(),
+            Literal::Float(f) => float(f.value),
+            Literal::Boolean(b) => {
+                self.kw(if b.value { "TRUE" } else { "FALSE" })
+            },
+            Literal::String(s) => string(&s.value, '\''),
+            Literal::WString(s) => string(&s.value, '"'),
+            Literal::Typed(t) => format!(
+                "{}#{}{}",
+                t.declared_type.value,
+                if t.negative { "-" } else { "" },
+                self.literal(&t.value)
+            ),
+            Literal::Duration(d) => format!("T#{}", duration(d.value)),
+            Literal::TimeOfDay(t) => format!("TOD#{}", time_of_day(t)),
+            Literal::Date(d) => format!("D#{}", date(d)),
+            Literal::DateAndTime(dt) => {
+                format!("DT#{}-{}", date(&dt.date), time_of_day(&dt.time))
+            },
+        }
+    }
+}
+
+/// How tightly an operator binds, mirroring the `PrecClimber` used by the
+/// parser.
+fn precedence(op: BinaryOp) -> u8 {
+    match op {
+        BinaryOp::Or => 1,
+        BinaryOp::Xor => 2,
+        BinaryOp::And => 3,
+        BinaryOp::Equals | BinaryOp::NotEquals => 4,
+        BinaryOp::LessThan
+        | BinaryOp::LessThanOrEqual
+        | BinaryOp::GreaterThan
+        | BinaryOp::GreaterThanOrEqual => 5,
+        BinaryOp::Add | BinaryOp::Subtract => 6,
+        BinaryOp::Multiply | BinaryOp::Divide | BinaryOp::Modulo => 7,
+        BinaryOp::Exponent => 8,
+    }
+}
+
+fn subrange(range: &Subrange) -> String {
+    format!("{}..{}", range.start.value, range.end.value)
+}
+
+fn direct_address(address: DirectAddress) -> String {
+    let area = match address.area {
+        AddressArea::Input => 'I',
+        AddressArea::Output => 'Q',
+        AddressArea::Memory => 'M',
+    };
+    let size = match address.size {
+        AddressSize::Bit => 'X',
+        AddressSize::Byte => 'B',
+        AddressSize::Word => 'W',
+        AddressSize::DoubleWord => 'D',
+        AddressSize::LongWord => 'L',
+    };
+
+    match address.bit {
+        Some(bit) => format!("%{}{}{}.{}", area, size, address.byte, bit),
+        None => format!("%{}{}{}", area, size, address.byte),
+    }
+}
+
+/// Floats always need a decimal point, otherwise they'd be parsed as integers.
+fn float(value: f64) -> String {
+    let text = format!("{:?}", value);
+
+    if text.contains('.') {
+        return text;
+    }
+
+    match text.find('e') {
+        Some(ix) => format!("{}.0{}", &text[..ix], &text[ix..]),
+        None => format!("{}.0", text),
+    }
+}
+
+fn string(value: &str, quote: char) -> String {
+    let mut text = String::new();
+    text.push(quote);
+
+    for c in value.chars() {
+        match c {
+            '$' => text.push_str("$$"),
+            '\n' => text.push_str("$N"),
+            '\r' => text.push_str("$R"),
+            '\t' => text.push_str("$T"),
+            '\x0C' => text.push_str("$P"),
+            _ if c == quote => {
+                text.push('$');
+                text.push(c);
+            },
+            _ if c.is_control() && quote == '\'' => {
+                text.push_str(&format!("${:02X}", c as u32))
+            },
+            _ if c.is_control() => {
+                text.push_str(&format!("${:04X}", c as u32))
+            },
+            _ => text.push(c),
+        }
+    }
+
+    text.push(quote);
+    text
+}
+
+fn duration(value: Duration) -> String {
+    const NANOS_PER_UNIT: &[(&str, u128)] = &[
+        ("d", 24 * 60 * 60 * 1_000_000_000),
+        ("h", 60 * 60 * 1_000_000_000),
+        ("m", 60 * 1_000_000_000),
+        ("s", 1_000_000_000),
+        ("ms", 1_000_000),
+        ("us", 1_000),
+        ("ns", 1),
+    ];
+
+    let mut remaining = value.as_nanos();
+    if remaining == 0 {
+        return String::from("0s");
+    }
+
+    let mut text = String::new();
+    for &(unit, nanos) in NANOS_PER_UNIT {
+        let count = remaining / nanos;
+        if count > 0 {
+            text.push_str(&format!("{}{}", count, unit));
+            remaining %= nanos;
+        }
+    }
+
+    text
+}
+
+fn time_of_day(time: &TimeOfDayLiteral) -> String {
+    let mut text =
+        format!("{:02}:{:02}:{:02}", time.hour, time.minute, time.second);
+
+    if time.nanosecond > 0 {
+        let fraction = format!("{:09}", time.nanosecond);
+        text.push('.');
+        text.push_str(fraction.trim_end_matches('0'));
+    }
+
+    text
+}
+
+fn date(date: &DateLiteral) -> String {
+    format!("{:04}-{:02}-{:02}", date.year, date.month, date.day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn reformat(src: &str, options: &FormatOptions) -> String {
+        let file: File = src.parse().unwrap();
+        format(&file, options)
+    }
+
+    #[test]
+    fn canonical_program() {
+        let src = "program main var x:INT:=1; counter : ARRAY[0..9] OF REAL;
+            end_var
+            if x>1 then x:=(x+1)*2; elsif x = 0 then x := -(x - 1);
+            else exit; end_if;
+            case x of 1, 2..5: counter(IN := x, Q => x); else x := 0; end_case;
+            end_program";
+        let should_be = "PROGRAM main
+    VAR
+        x       : INT := 1;
+        counter : ARRAY [0..9] OF REAL;
+    END_VAR
+
+    IF x > 1 THEN
+        x := (x + 1) * 2;
+    ELSIF x = 0 THEN
+        x := -(x - 1);
+    ELSE
+        EXIT;
+    END_IF;
+    CASE x OF
+        1, 2..5:
+            counter(IN := x, Q => x);
+        ELSE
+            x := 0;
+    END_CASE;
+END_PROGRAM
+";
+
+        let got = reformat(src, &FormatOptions::default());
+
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn lowercase_keywords_without_alignment() {
+        let src = "FUNCTION add : INT
+            VAR_INPUT a : INT; second : INT; END_VAR
+            WHILE a < second DO a := a + 1; END_WHILE;
+        END_FUNCTION";
+        let options = FormatOptions {
+            indent_width: 2,
+            keyword_case: KeywordCase::Lower,
+            align_declarations: false,
+        };
+        let should_be = "function add : INT
+  var_input
+    a : INT;
+    second : INT;
+  end_var
+
+  while a < second do
+    a := a + 1;
+  end_while;
+end_function
+";
+
+        let got = reformat(src, &options);
+
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn only_insert_parentheses_where_needed() {
+        let inputs = vec![
+            ("a + b * c", "a + b * c"),
+            ("(a + b) * c", "(a + b) * c"),
+            ("(a - b) - c", "a - b - c"),
+            ("a - (b - c)", "a - (b - c)"),
+            ("((a))", "a"),
+            ("NOT (a AND b) OR c", "NOT (a AND b) OR c"),
+            ("-(-a)", "--a"),
+            ("a ** (b ** c)", "a ** (b ** c)"),
+        ];
+
+        for (src, should_be) in inputs {
+            let src = format!("PROGRAM main x := {}; END_PROGRAM", src);
+            let got = reformat(&src, &FormatOptions::default());
+
+            let should_be = format!(
+                "PROGRAM main\n    x := {};\nEND_PROGRAM\n",
+                should_be
+            );
+            assert_eq!(got, should_be);
+        }
+    }
+
+    #[test]
+    fn literals_use_their_canonical_form() {
+        let inputs = vec![
+            ("16#FF", "255"),
+            ("1_000.5", "1000.5"),
+            ("1.5e3", "1500.0"),
+            ("'it$'s $N'", "'it$'s $N'"),
+            ("\"wide\"", "\"wide\""),
+            ("T#90m", "T#1h30m"),
+            ("T#0ms", "T#0s"),
+            ("TIME_OF_DAY#12:30:00.500", "TOD#12:30:00.5"),
+            ("DATE_AND_TIME#2019-12-01-08:00:00", "DT#2019-12-01-08:00:00"),
+            ("INT#-5", "INT#-5"),
+        ];
+
+        for (src, should_be) in inputs {
+            let src = format!("PROGRAM main x := {}; END_PROGRAM", src);
+            let got = reformat(&src, &FormatOptions::default());
+
+            let should_be = format!(
+                "PROGRAM main\n    x := {};\nEND_PROGRAM\n",
+                should_be
+            );
+            assert_eq!(got, should_be);
+        }
+    }
+
+    #[test]
+    fn structs_outside_a_type_declaration_are_written_inline() {
+        let src = "TYPE Point : STRUCT x : INT; y : REAL := 1.5; END_STRUCT;
+            END_TYPE
+            PROGRAM main VAR p : Point; END_VAR END_PROGRAM";
+        let mut file: File = src.parse().unwrap();
+        let point = file.types[0].data_type.clone();
+        file.programs[0].var_blocks[0].declarations[0].declared_type = point;
+
+        let got = format(&file, &FormatOptions::default());
+
+        assert!(got.contains(
+            "p : STRUCT x : INT; y : REAL := 1.5; END_STRUCT;"
+        ));
+    }
+}
@@ -16,17 +16,31 @@ extern crate pest;
 extern crate pretty_assertions;
 
 mod ast;
+pub mod cst;
 mod error;
+mod formatter;
 pub mod parser;
+mod recovery;
+pub mod resolve;
+pub mod types;
+pub mod visitor;
 
-pub use crate::{ast::*, error::ParseError};
-
-use crate::parser::{RawParser, Rule};
-use pest::Parser as _;
+pub use crate::{
+    ast::*,
+    error::{Diagnostic, Label, ParseError, Severity},
+    formatter::{format, FormatOptions, KeywordCase},
+    visitor::{Visitor, VisitorMut},
+};
 
 /// Parse a string of *Structured Text*.
-pub fn parse(src: &str) -> Result<(), ParseError> {
-    let _p = RawParser::parse(Rule::program, src)?;
+pub fn parse(src: &str) -> Result<File, ParseError> { src.parse() }
 
-    unimplemented!()
+/// Parse a string of *Structured Text*, skipping over any statements or
+/// declarations which contain errors so every problem in the file can be
+/// reported at once.
+///
+/// The [`File`] only contains the items which could be parsed. Forgetting
+/// the `;` after a statement like `IF` or `WHILE` is only a warning.
+pub fn parse_with_recovery(src: &str) -> (File, Vec<Diagnostic>) {
+    recovery::parse(src)
 }
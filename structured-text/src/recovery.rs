@@ -0,0 +1,728 @@
+//! Error recovery for the parser.
+//!
+//! Instead of teaching the grammar about invalid code, we make a single pass
+//! over the source text and check each statement and declaration on its own,
+//! using the surrounding keywords (`THEN`, `VAR`, `END_IF`, etc.) to figure
+//! out where one ends and the next begins. Anything which fails to parse is
+//! blanked out, replacing it with whitespace so the spans in the final
+//! [`File`] still line up with the original source.
+//!
+//! Errors in the structure of the file itself (e.g. a missing `END_IF`) can't
+//! be found this way, so for those we fall back to parsing the whole file and
+//! blanking out the region around each error until it parses.
+//!
+//! A missing `;` after `END_IF`, `END_WHILE`, etc. is only reported as a
+//! warning, and the whitespace after the keyword is replaced with a `;` so
+//! parsing can continue.
+
+use crate::{
+    ast::{File, Statement, TypeDeclaration, VariableDeclaration},
+    error::{Diagnostic, Label},
+    parser::{RawParser, Rule},
+    ParseError,
+};
+use codespan::Span;
+use pest::Parser;
+
+pub(crate) fn parse(src: &str) -> (File, Vec<Diagnostic>) {
+    let mut text = src.to_string();
+    let mut tokens = tokenize(src);
+    let mut diagnostics = insert_missing_semicolons(&mut text, &mut tokens);
+
+    for fragment in fragments(&tokens) {
+        let (start, end) = (fragment.start, fragment.end);
+
+        if let Err(e) = fragment.check(&src[start..end]) {
+            let span = e.span();
+            let span = Span::new(
+                span.start().to_usize() as u32 + start as u32,
+                span.end().to_usize() as u32 + start as u32,
+            );
+            diagnostics.push(e.into_diagnostic(widen(span, &tokens)));
+            blank_out(&mut text, start, end);
+        }
+    }
+
+    tokens.retain(|t| !text[t.start..t.end].trim().is_empty());
+
+    loop {
+        let err = match text.parse::<File>() {
+            Ok(file) => {
+                diagnostics
+                    .sort_by_key(|d| d.primary_label.span.start().to_usize());
+                return (file, diagnostics);
+            },
+            Err(e) => e,
+        };
+
+        let span = err.span();
+        let position = span.start().to_usize();
+
+        diagnostics.push(err.into_diagnostic(widen(span, &tokens)));
+
+        // the grammar accepts an empty file, so this always terminates
+        let (start, end) = recovery_region(&tokens, position, text.len());
+        blank_out(&mut text, start, end);
+        tokens.retain(|t| t.end <= start || end <= t.start);
+    }
+}
+
+/// Statements like `IF` and `WHILE` must be followed by a `;`, but it's easy
+/// to forget so we add one and emit a warning.
+fn insert_missing_semicolons<'src>(
+    text: &mut String,
+    tokens: &mut Vec<Token<'src>>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut ix = 0;
+
+    while ix < tokens.len() {
+        let token = tokens[ix];
+        let word = token.word.to_uppercase();
+        ix += 1;
+
+        let ends_statement = match word.as_str() {
+            "END_IF" | "END_WHILE" | "END_FOR" | "END_CASE" | "END_REPEAT" => {
+                true
+            },
+            _ => false,
+        };
+        let followed_by_semicolon = tokens
+            .get(ix)
+            .map(|t| t.kind == TokenKind::Semicolon)
+            .unwrap_or(false);
+        // we need somewhere to put the semicolon without changing any
+        // byte offsets
+        let followed_by_whitespace = text.as_bytes()[token.end..]
+            .first()
+            .map(u8::is_ascii_whitespace)
+            .unwrap_or(false);
+
+        if !ends_statement || followed_by_semicolon || !followed_by_whitespace {
+            continue;
+        }
+
+        let span = Span::new(token.start as u32, token.end as u32);
+        diagnostics.push(Diagnostic::warning(
+            "Missing semicolon",
+            Label::new(span, format!("expected a \";\" after {}", word)),
+        ));
+
+        text.replace_range(token.end..=token.end, ";");
+        tokens.insert(
+            ix,
+            Token {
+                kind: TokenKind::Semicolon,
+                word: ";",
+                start: token.end,
+                end: token.end + 1,
+            },
+        );
+    }
+
+    diagnostics
+}
+
+/// What we expect to find between two semicolons.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum FragmentKind {
+    Statement,
+    Declaration,
+    TypeDeclaration,
+}
+
+/// A single statement or declaration, including its trailing semicolon.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Fragment {
+    kind: FragmentKind,
+    start: usize,
+    end: usize,
+}
+
+impl Fragment {
+    /// Parse the fragment on its own, making sure we also catch any errors
+    /// raised while building the AST (e.g. an integer literal which is out of
+    /// range).
+    fn check(self, text: &str) -> Result<(), ParseError> {
+        let rule = match self.kind {
+            FragmentKind::Statement => Rule::statement_fragment,
+            FragmentKind::Declaration => Rule::declaration_fragment,
+            FragmentKind::TypeDeclaration => Rule::type_declaration_fragment,
+        };
+
+        let pair = RawParser::parse(rule, text)?
+            .next()
+            .unwrap()
+            .into_inner()
+            .next()
+            .unwrap();
+
+        match self.kind {
+            FragmentKind::Statement => Statement::from_pair(pair).map(drop),
+            FragmentKind::Declaration => {
+                VariableDeclaration::from_pair(pair).map(drop)
+            },
+            FragmentKind::TypeDeclaration => {
+                TypeDeclaration::from_pair(pair).map(drop)
+            },
+        }
+    }
+}
+
+/// Which kind of fragment can appear directly inside the block opened by a
+/// keyword?
+fn fragment_kind(opened_by: &str) -> Option<FragmentKind> {
+    match opened_by {
+        "PROGRAM" | "FUNCTION" | "FUNCTION_BLOCK" | "THEN" | "ELSE" | "DO"
+        | "REPEAT" | "UNTIL" => Some(FragmentKind::Statement),
+        "STRUCT" => Some(FragmentKind::Declaration),
+        _ if opened_by == "VAR" || opened_by.starts_with("VAR_") => {
+            Some(FragmentKind::Declaration)
+        },
+        "TYPE" => Some(FragmentKind::TypeDeclaration),
+        // the branches of a CASE, configurations and resources
+        _ => None,
+    }
+}
+
+/// Does the `END_*` keyword close the block opened by `opened_by`?
+fn closes(end: &str, opened_by: &str) -> bool {
+    match end {
+        "END_IF" => opened_by == "THEN" || opened_by == "ELSE",
+        "END_CASE" => opened_by == "OF" || opened_by == "ELSE",
+        "END_WHILE" | "END_FOR" => opened_by == "DO",
+        "END_REPEAT" => opened_by == "UNTIL",
+        "END_VAR" => opened_by == "VAR" || opened_by.starts_with("VAR_"),
+        _ => end["END_".len()..] == *opened_by,
+    }
+}
+
+/// Split the source text into the statements and declarations which can be
+/// checked on their own, giving up as soon as we lose track of which block
+/// we are in.
+fn fragments(tokens: &[Token]) -> Vec<Fragment> {
+    let mut fragments = Vec::new();
+    // the keywords which opened each of the blocks we are currently in
+    let mut blocks: Vec<String> = Vec::new();
+    // where the current fragment starts, if it can be checked on its own
+    let mut start = None;
+    let mut can_start = false;
+
+    for token in tokens {
+        let word = token.word.to_uppercase();
+        let kind = blocks.last().and_then(|b| fragment_kind(b));
+        let in_declaration =
+            kind.is_some() && kind != Some(FragmentKind::Statement);
+
+        match token.kind {
+            _ if word == "OF" && in_declaration => {
+                // the "OF" in "ARRAY [1..2] OF INT" doesn't open a block
+                continue;
+            },
+            TokenKind::Other => {
+                if can_start {
+                    start = start.or(Some(token.start));
+                }
+                continue;
+            },
+            TokenKind::Semicolon => {
+                if let (Some(start), Some(kind)) = (start, kind) {
+                    fragments.push(Fragment {
+                        kind,
+                        start,
+                        end: token.end,
+                    });
+                }
+                can_start = true;
+            },
+            TokenKind::Opening => {
+                blocks.push(word);
+                can_start = true;
+            },
+            TokenKind::Separator => {
+                let expected = match word.as_str() {
+                    "ELSIF" => "THEN",
+                    "UNTIL" => "REPEAT",
+                    _ if blocks.last().map(String::as_str) == Some("OF") => {
+                        "OF"
+                    },
+                    _ => "THEN",
+                };
+                if blocks.pop().as_ref().map(String::as_str) != Some(expected) {
+                    break;
+                }
+                // the THEN after an ELSIF will open the next block
+                if word != "ELSIF" {
+                    blocks.push(word);
+                }
+                can_start = true;
+            },
+            TokenKind::Closing => match blocks.pop() {
+                Some(ref opened_by) if closes(&word, opened_by) => {
+                    // only statements may directly follow the end of a block
+                    can_start = blocks.last().and_then(|b| fragment_kind(b))
+                        == Some(FragmentKind::Statement);
+                },
+                _ => break,
+            },
+            TokenKind::Header => {
+                let in_configuration = blocks
+                    .last()
+                    .map(|b| b == "CONFIGURATION" || b == "RESOURCE")
+                    .unwrap_or(false);
+
+                // "PROGRAM" is also used to instantiate programs inside a
+                // resource
+                if !(in_configuration && word == "PROGRAM") {
+                    if !in_configuration {
+                        // the previous item wasn't finished
+                        blocks.clear();
+                    }
+                    blocks.push(word);
+                }
+                // skip the item's name and return type
+                can_start = false;
+            },
+        }
+
+        start = None;
+    }
+
+    fragments
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum TokenKind {
+    Semicolon,
+    /// A keyword which may be followed by a new statement or declaration.
+    Opening,
+    /// A keyword which ends the current block.
+    Closing,
+    /// A keyword which can be either (e.g. `ELSE`).
+    Separator,
+    /// A keyword which introduces a named item (e.g. `PROGRAM`).
+    Header,
+    Other,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Token<'src> {
+    kind: TokenKind,
+    word: &'src str,
+    start: usize,
+    end: usize,
+}
+
+impl<'src> Token<'src> {
+    fn ends_block(self) -> bool {
+        match self.kind {
+            TokenKind::Closing | TokenKind::Separator => true,
+            _ => false,
+        }
+    }
+
+    fn starts_statement(self) -> bool {
+        match self.kind {
+            TokenKind::Semicolon
+            | TokenKind::Opening
+            | TokenKind::Closing
+            | TokenKind::Separator => true,
+            _ => false,
+        }
+    }
+}
+
+fn keyword_kind(word: &str) -> TokenKind {
+    let word = word.to_uppercase();
+
+    match word.as_str() {
+        "ELSE" | "ELSIF" | "UNTIL" => TokenKind::Separator,
+        "THEN" | "DO" | "OF" | "REPEAT" | "STRUCT" | "TYPE" => {
+            TokenKind::Opening
+        },
+        "PROGRAM" | "FUNCTION" | "FUNCTION_BLOCK" | "CONFIGURATION"
+        | "RESOURCE" => TokenKind::Header,
+        _ if word.starts_with("END_") => TokenKind::Closing,
+        _ if word == "VAR" || word.starts_with("VAR_") => TokenKind::Opening,
+        _ => TokenKind::Other,
+    }
+}
+
+/// A very rough lexer which knows just enough to skip over comments,
+/// pragmas and strings.
+fn tokenize(src: &str) -> Vec<Token<'_>> {
+    let bytes = src.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let start = i;
+
+        let kind = match bytes[i] {
+            b if b.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            },
+            b'(' if bytes.get(i + 1) == Some(&b'*') => {
+                i = match src[i + 2..].find("*)") {
+                    Some(offset) => i + 2 + offset + 2,
+                    None => bytes.len(),
+                };
+                continue;
+            },
+            b'{' => {
+                i = match src[i..].find('}') {
+                    Some(offset) => i + offset + 1,
+                    None => bytes.len(),
+                };
+                continue;
+            },
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = match src[i..].find('\n') {
+                    Some(offset) => i + offset,
+                    None => bytes.len(),
+                };
+                continue;
+            },
+            quote @ b'\'' | quote @ b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    // skip over the character after a "$" escape
+                    i += if bytes[i] == b'$' { 2 } else { 1 };
+                }
+                i = std::cmp::min(i + 1, bytes.len());
+                TokenKind::Other
+            },
+            b';' => {
+                i += 1;
+                TokenKind::Semicolon
+            },
+            b if b.is_ascii_alphabetic() || b == b'_' => {
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_')
+                {
+                    i += 1;
+                }
+                keyword_kind(&src[start..i])
+            },
+            _ => {
+                // make sure we always step over a whole character
+                i += 1;
+                while !src.is_char_boundary(i) {
+                    i += 1;
+                }
+                TokenKind::Other
+            },
+        };
+
+        tokens.push(Token {
+            kind,
+            word: &src[start..i],
+            start,
+            end: i,
+        });
+    }
+
+    tokens
+}
+
+/// Errors are often reported as a single position, so stretch the span to
+/// cover the token it points at.
+fn widen(span: Span, tokens: &[Token]) -> Span {
+    if span.start() != span.end() {
+        return span;
+    }
+
+    let position = span.start().to_usize();
+
+    match tokens.iter().find(|t| t.end > position) {
+        Some(token) if token.start <= position => {
+            Span::new(position as u32, token.end as u32)
+        },
+        _ => span,
+    }
+}
+
+/// Find the part of the source text which should be skipped to recover from
+/// an error at `position`.
+fn recovery_region(
+    tokens: &[Token],
+    position: usize,
+    len: usize,
+) -> (usize, usize) {
+    // the first token at or after the error
+    let first = match tokens.iter().position(|t| t.end > position) {
+        Some(ix) => ix,
+        None => return end_of_file_region(tokens, len),
+    };
+
+    // a stray END_IF or similar, skip just the keyword
+    if tokens[first].start <= position && tokens[first].ends_block() {
+        let mut end = tokens[first].end;
+        if let Some(next) = tokens.get(first + 1) {
+            if next.kind == TokenKind::Semicolon {
+                end = next.end;
+            }
+        }
+        return (tokens[first].start, end);
+    }
+
+    // resume right after the previous statement or block header
+    let start = tokens[..first]
+        .iter()
+        .enumerate()
+        .rev()
+        .find_map(|(ix, t)| {
+            if t.starts_statement() {
+                Some(t.end)
+            } else if ix >= 1 && tokens[ix - 1].kind == TokenKind::Header {
+                // the name after a PROGRAM or FUNCTION_BLOCK
+                Some(t.end)
+            } else {
+                None
+            }
+        })
+        .unwrap_or(0);
+
+    // and skip everything up to the end of the current statement
+    let mut end = len;
+    for token in &tokens[first..] {
+        if token.kind == TokenKind::Semicolon {
+            end = token.end;
+            break;
+        } else if token.ends_block() && token.start > start {
+            end = token.start;
+            break;
+        }
+    }
+
+    if tokens.iter().any(|t| start <= t.start && t.end <= end) {
+        (start, end)
+    } else {
+        // make sure we always make progress
+        (tokens[first].start, tokens[first].end)
+    }
+}
+
+/// We ran out of input before an item was finished, so give up on the last
+/// item in the file.
+fn end_of_file_region(tokens: &[Token], len: usize) -> (usize, usize) {
+    let start = tokens
+        .iter()
+        .rev()
+        .find(|t| t.kind == TokenKind::Header)
+        .or_else(|| tokens.last())
+        .map(|t| t.start)
+        .unwrap_or(0);
+
+    (start, len)
+}
+
+/// Replace the text between `start` and `end` with whitespace, keeping
+/// newlines and byte offsets intact.
+fn blank_out(text: &mut String, start: usize, end: usize) {
+    let blanked: String = text[start..end]
+        .chars()
+        .flat_map(|c| {
+            let replacement = if c == '\n' { '\n' } else { ' ' };
+            std::iter::repeat(replacement).take(c.len_utf8())
+        })
+        .collect();
+
+    text.replace_range(start..end, &blanked);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Severity;
+
+    #[test]
+    fn valid_code_has_no_diagnostics() {
+        let src = "PROGRAM main x := 1; END_PROGRAM";
+
+        let (file, diagnostics) = parse(src);
+
+        assert!(diagnostics.is_empty());
+        assert_eq!(file, src.parse().unwrap());
+    }
+
+    #[test]
+    fn skip_invalid_statements() {
+        let src = "PROGRAM main
+            x := 1;
+            y := 2 +;
+            z := 3;
+            w := ;
+        END_PROGRAM";
+
+        let (file, diagnostics) = parse(src);
+
+        assert_eq!(diagnostics.len(), 2);
+        let statements = &file.programs[0].body.statements;
+        assert_eq!(statements.len(), 2);
+        match statements[1] {
+            Statement::Assignment(ref a) => {
+                assert_eq!(a.variable.value, "z");
+                // spans still point into the original text
+                let start = src.find("z := 3").unwrap();
+                assert_eq!(a.span.start().to_usize(), start);
+            },
+            ref other => panic!("Expected an assignment, found {:?}", other),
+        }
+        let second_error = src.find("w := ;").unwrap() + 5;
+        assert_eq!(
+            diagnostics[1].primary_label.span,
+            Span::new(second_error as u32, second_error as u32 + 1)
+        );
+    }
+
+    #[test]
+    fn recover_inside_nested_blocks() {
+        let src = "PROGRAM main
+            IF x THEN
+                y := ;
+            END_IF;
+            z := 3;
+        END_PROGRAM
+
+        FUNCTION_BLOCK fb
+            a := 1;
+        END_FUNCTION_BLOCK";
+
+        let (file, diagnostics) = parse(src);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(file.programs[0].body.statements.len(), 2);
+        assert_eq!(file.function_blocks.len(), 1);
+    }
+
+    #[test]
+    fn custom_errors_are_also_reported() {
+        let src = "PROGRAM main
+            VAR
+                x : SINT := SINT#300;
+                y : INT;
+            END_VAR
+            x := T#1h2d;
+        END_PROGRAM";
+
+        let (file, diagnostics) = parse(src);
+
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics[0].primary_label.message.is_empty());
+        let declarations = &file.programs[0].var_blocks[0].declarations;
+        assert_eq!(declarations.len(), 1);
+        assert_eq!(declarations[0].name.value, "y");
+    }
+
+    #[test]
+    fn give_up_on_unfinished_items() {
+        let src = "FUNCTION_BLOCK fb
+            a := 1;
+        END_FUNCTION_BLOCK
+
+        PROGRAM main
+            x := 1;";
+
+        let (file, diagnostics) = parse(src);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(file.function_blocks.len(), 1);
+        assert!(file.programs.is_empty());
+    }
+
+    #[test]
+    fn stray_closing_keywords_are_skipped() {
+        let src = "PROGRAM main
+            x := 1;
+            END_IF;
+            y := 2;
+        END_PROGRAM";
+
+        let (file, diagnostics) = parse(src);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(file.programs[0].body.statements.len(), 2);
+    }
+
+    #[test]
+    fn missing_semicolons_are_a_warning() {
+        let src = "PROGRAM main
+            IF x THEN y := 1; END_IF
+            WHILE x DO y := 2; END_WHILE
+            z := 3;
+        END_PROGRAM";
+
+        let (file, diagnostics) = parse(src);
+
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+        let end_if = src.find("END_IF").unwrap();
+        assert_eq!(
+            diagnostics[0].primary_label.span,
+            Span::new(end_if as u32, end_if as u32 + 6)
+        );
+        assert_eq!(file.programs[0].body.statements.len(), 3);
+        // but the normal parser is strict
+        assert!(src.parse::<File>().is_err());
+    }
+
+    #[test]
+    fn fragments_follow_the_block_structure() {
+        let src = "TYPE Mode : (Idle, Running); END_TYPE
+        PROGRAM main
+            VAR xs : ARRAY [1..2] OF INT; END_VAR
+            IF x THEN a := 1; ELSE b := 2; END_IF;
+            CASE x OF 1: c := 3; ELSE d := 4; END_CASE;
+            REPEAT e := 5; UNTIL f := 6; END_REPEAT;
+        END_PROGRAM";
+        let tokens = tokenize(src);
+
+        let got: Vec<_> = fragments(&tokens)
+            .into_iter()
+            .map(|f| (f.kind, &src[f.start..f.end]))
+            .collect();
+
+        let expected = vec![
+            (FragmentKind::TypeDeclaration, "Mode : (Idle, Running);"),
+            (FragmentKind::Declaration, "xs : ARRAY [1..2] OF INT;"),
+            (FragmentKind::Statement, "a := 1;"),
+            (FragmentKind::Statement, "b := 2;"),
+            (FragmentKind::Statement, "d := 4;"),
+            (FragmentKind::Statement, "e := 5;"),
+            (FragmentKind::Statement, "f := 6;"),
+        ];
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn skip_invalid_type_declarations() {
+        let src = "TYPE
+            Mode : (Idle, Running;
+            Speed : INT (0..100);
+        END_TYPE
+        PROGRAM main
+            VAR x : ; END_VAR
+            x := 1;
+        END_PROGRAM";
+
+        let (file, diagnostics) = parse(src);
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(file.types.len(), 1);
+        assert_eq!(file.types[0].name.value, "Speed");
+        assert!(file.programs[0].var_blocks[0].declarations.is_empty());
+        let first = diagnostics[0].primary_label.span.start().to_usize();
+        assert!(first < src.find("Speed").unwrap());
+    }
+
+    #[test]
+    fn blanking_keeps_byte_offsets() {
+        let mut text = String::from("a := 'é';\nb");
+
+        blank_out(&mut text, 0, 12);
+
+        assert_eq!(text, "          \n ");
+    }
+}
@@ -0,0 +1,1166 @@
+//! Name resolution.
+//!
+//! Every program, function block, function, configuration and resource
+//! introduces a [`Scope`], with the file-level scope containing the program
+//! organisation units, user-defined types and any `VAR_GLOBAL` declared by a
+//! configuration. Enumerated values are visible everywhere.
+//!
+//! Like the rest of IEC 61131-3, names are case-insensitive.
+//!
+//! A project may be split across several files, in which case
+//! [`resolve_with()`] makes the items from the other files visible too.
+
+use crate::{
+    ast::*,
+    error::{Diagnostic, Label},
+    visitor::{self, Visitor},
+};
+use codespan::Span;
+use std::collections::HashMap;
+
+/// Resolve every name in a [`File`] to the item it refers to.
+pub fn resolve(file: &File) -> Resolution { resolve_with(file, &[]) }
+
+/// Resolve every name in a [`File`] which is part of a larger project, where
+/// the items declared by the `others` can be used as well.
+///
+/// Only the problems in `file` are reported.
+pub fn resolve_with(file: &File, others: &[&File]) -> Resolution {
+    let mut resolver = Resolver::default();
+    resolver.declare_items(file);
+    for (index, other) in others.iter().enumerate() {
+        resolver.file = index + 1;
+        resolver.quietly(|r| r.declare_items(other));
+    }
+    // we need the scopes of every item (e.g. a function's inputs) to check
+    // how they are used
+    for (index, other) in others.iter().enumerate() {
+        resolver.file = index + 1;
+        resolver.quietly(|r| r.visit_file(other));
+    }
+
+    resolver.file = 0;
+    resolver.visit_file(file);
+
+    resolver.resolution
+}
+
+/// The result of name resolution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resolution {
+    pub symbols: Vec<Symbol>,
+    pub scopes: Vec<Scope>,
+    /// Problems encountered while resolving names.
+    pub diagnostics: Vec<Diagnostic>,
+    /// The symbol each identifier refers to, keyed by the index of the file
+    /// it is in and the identifier's span.
+    references: HashMap<(usize, Span), SymbolId>,
+}
+
+impl Resolution {
+    /// The scope containing everything declared at the top of a file.
+    pub const FILE_SCOPE: ScopeId = ScopeId(0);
+
+    pub fn symbol(&self, id: SymbolId) -> &Symbol { &self.symbols[id.0] }
+
+    pub fn scope(&self, id: ScopeId) -> &Scope { &self.scopes[id.0] }
+
+    /// Find the declaration an [`Identifier`] refers to.
+    ///
+    /// Names which refer to something from the standard library (e.g. `INT`
+    /// or `TON`) don't have a declaration.
+    pub fn lookup(&self, identifier: &Identifier) -> Option<SymbolId> {
+        self.lookup_in(0, identifier)
+    }
+
+    /// Find the declaration an [`Identifier`] from one of the other files
+    /// passed to [`resolve_with()`] refers to.
+    pub(crate) fn lookup_in(
+        &self,
+        file: usize,
+        identifier: &Identifier,
+    ) -> Option<SymbolId> {
+        self.references.get(&(file, identifier.span)).cloned()
+    }
+
+    /// The scope introduced by a particular item (e.g. a program).
+    pub fn scope_of(&self, owner: SymbolId) -> Option<ScopeId> {
+        self.scopes
+            .iter()
+            .position(|scope| scope.owner == Some(owner))
+            .map(ScopeId)
+    }
+
+    /// Were any errors (as opposed to warnings) encountered?
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|d| d.severity == crate::Severity::Error)
+    }
+}
+
+impl Default for Resolution {
+    fn default() -> Resolution {
+        Resolution {
+            symbols: Vec::new(),
+            scopes: vec![Scope::new(None, None)],
+            diagnostics: Vec::new(),
+            references: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SymbolId(usize);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ScopeId(usize);
+
+/// Something which has been declared.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The span of the name in the symbol's declaration.
+    pub span: Span,
+    /// The scope this symbol was declared in.
+    pub scope: ScopeId,
+    /// The declared type of a variable, the definition of a user-defined
+    /// type, or the return type of a function.
+    pub data_type: Option<DataType>,
+    /// The file this symbol was declared in, where `0` is the file being
+    /// resolved.
+    pub(crate) file: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolKind {
+    Program,
+    FunctionBlock,
+    Function,
+    Type,
+    /// A field in a `STRUCT`.
+    Field,
+    EnumeratedValue,
+    Variable(VarBlockKind),
+    Configuration,
+    Resource,
+    Task,
+    /// A program which is instantiated in a resource.
+    ProgramInstance,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scope {
+    pub parent: Option<ScopeId>,
+    /// The item which introduced this scope, or `None` for the file scope
+    /// and anonymous resources.
+    pub owner: Option<SymbolId>,
+    names: HashMap<String, SymbolId>,
+}
+
+impl Scope {
+    fn new(parent: Option<ScopeId>, owner: Option<SymbolId>) -> Scope {
+        Scope {
+            parent,
+            owner,
+            names: HashMap::new(),
+        }
+    }
+
+    /// Get the symbol declared directly in this scope with a particular
+    /// name.
+    pub fn get(&self, name: &str) -> Option<SymbolId> {
+        self.names.get(&key(name)).cloned()
+    }
+
+    pub fn symbols<'a>(&'a self) -> impl Iterator<Item = SymbolId> + 'a {
+        self.names.values().cloned()
+    }
+}
+
+const ELEMENTARY_TYPES: &[&str] = &[
+    "BOOL",
+    "SINT",
+    "INT",
+    "DINT",
+    "LINT",
+    "USINT",
+    "UINT",
+    "UDINT",
+    "ULINT",
+    "REAL",
+    "LREAL",
+    "BYTE",
+    "WORD",
+    "DWORD",
+    "LWORD",
+    "TIME",
+    "DATE",
+    "TIME_OF_DAY",
+    "TOD",
+    "DATE_AND_TIME",
+    "DT",
+    "STRING",
+    "WSTRING",
+];
+
+const STANDARD_FUNCTION_BLOCKS: &[&str] = &[
+    "TON", "TOF", "TP", "CTU", "CTD", "CTUD", "R_TRIG", "F_TRIG", "SR", "RS",
+];
+
+const STANDARD_FUNCTIONS: &[&str] = &[
+    "ABS", "SQRT", "LN", "LOG", "EXP", "SIN", "COS", "TAN", "ASIN", "ACOS",
+    "ATAN", "ADD", "MUL", "SUB", "DIV", "MOD", "EXPT", "MOVE", "SHL", "SHR",
+    "ROL", "ROR", "AND", "OR", "XOR", "NOT", "SEL", "MAX", "MIN", "LIMIT",
+    "MUX", "GT", "GE", "EQ", "LE", "LT", "NE", "LEN", "LEFT", "RIGHT", "MID",
+    "CONCAT", "INSERT", "DELETE", "REPLACE", "FIND", "TRUNC",
+];
+
+fn key(name: &str) -> String { name.to_uppercase() }
+
+fn is_builtin_type(name: &str) -> bool {
+    let name = key(name);
+    ELEMENTARY_TYPES.contains(&name.as_str())
+        || STANDARD_FUNCTION_BLOCKS.contains(&name.as_str())
+}
+
+fn is_standard_function(name: &str) -> bool {
+    let name = key(name);
+    STANDARD_FUNCTIONS.contains(&name.as_str()) || is_conversion(&name)
+}
+
+/// Is this a type conversion like `REAL_TO_INT`?
+fn is_conversion(name: &str) -> bool {
+    let mut halves = name.splitn(2, "_TO_");
+
+    match (halves.next(), halves.next()) {
+        (Some(from), Some(to)) => {
+            ELEMENTARY_TYPES.contains(&from) && ELEMENTARY_TYPES.contains(&to)
+        },
+        _ => false,
+    }
+}
+
+/// Do two declarations refer to the same type?
+fn same_type(left: &DataType, right: &DataType) -> bool {
+    match (left, right) {
+        (DataType::Named(left), DataType::Named(right)) => {
+            key(&left.value) == key(&right.value)
+        },
+        (DataType::Struct(left), DataType::Struct(right)) => {
+            left.fields.len() == right.fields.len()
+                && left.fields.iter().zip(&right.fields).all(|(l, r)| {
+                    key(&l.name.value) == key(&r.name.value)
+                        && same_type(&l.declared_type, &r.declared_type)
+                })
+        },
+        (DataType::Enumerated(left), DataType::Enumerated(right)) => {
+            left.values.len() == right.values.len()
+                && left.values.iter().zip(&right.values).all(|(l, r)| {
+                    key(&l.name.value) == key(&r.name.value)
+                        && l.value.as_ref().map(|v| v.value)
+                            == r.value.as_ref().map(|v| v.value)
+                })
+        },
+        (DataType::Subrange(left), DataType::Subrange(right)) => {
+            key(&left.base_type.value) == key(&right.base_type.value)
+                && same_range(&left.range, &right.range)
+        },
+        (DataType::Array(left), DataType::Array(right)) => {
+            left.dimensions.len() == right.dimensions.len()
+                && left
+                    .dimensions
+                    .iter()
+                    .zip(&right.dimensions)
+                    .all(|(l, r)| same_range(l, r))
+                && same_type(&left.element_type, &right.element_type)
+        },
+        _ => false,
+    }
+}
+
+fn same_range(left: &Subrange, right: &Subrange) -> bool {
+    left.start.value == right.start.value && left.end.value == right.end.value
+}
+
+fn declared_as(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Named(name) => format!("declared as {} here", name.value),
+        _ => String::from("declared here"),
+    }
+}
+
+#[derive(Default)]
+struct Resolver<'ast> {
+    resolution: Resolution,
+    current: Option<ScopeId>,
+    enumerated_values: HashMap<String, SymbolId>,
+    /// Every `VAR_GLOBAL` in the project, used to check `VAR_EXTERNAL`s.
+    globals: Vec<&'ast VariableDeclaration>,
+    /// The index of the file being visited.
+    file: usize,
+}
+
+impl<'ast> Resolver<'ast> {
+    fn current_scope(&self) -> ScopeId {
+        self.current.unwrap_or(Resolution::FILE_SCOPE)
+    }
+
+    /// Do something without reporting any problems, because they belong to
+    /// another file.
+    fn quietly<F: FnOnce(&mut Self)>(&mut self, thunk: F) {
+        let reported = self.resolution.diagnostics.len();
+        thunk(self);
+        self.resolution.diagnostics.truncate(reported);
+    }
+
+    fn refer(&mut self, name: &Identifier, id: SymbolId) {
+        self.resolution
+            .references
+            .insert((self.file, name.span), id);
+    }
+
+    /// Add everything which is visible file-wide to the file scope, so it
+    /// can be used before its declaration.
+    fn declare_items(&mut self, file: &'ast File) {
+        for ty in &file.types {
+            let definition = Some(ty.data_type.clone());
+            self.declare(&ty.name, SymbolKind::Type, definition);
+        }
+        for function in &file.functions {
+            let return_type = DataType::Named(function.return_type.clone());
+            let kind = SymbolKind::Function;
+            self.declare(&function.name, kind, Some(return_type));
+            self.find_globals(&function.var_blocks);
+        }
+        for function_block in &file.function_blocks {
+            self.declare(&function_block.name, SymbolKind::FunctionBlock, None);
+            self.find_globals(&function_block.var_blocks);
+        }
+        for program in &file.programs {
+            self.declare(&program.name, SymbolKind::Program, None);
+            self.find_globals(&program.var_blocks);
+        }
+
+        for configuration in &file.configurations {
+            self.declare(&configuration.name, SymbolKind::Configuration, None);
+
+            let resource_blocks = configuration
+                .resources
+                .iter()
+                .flat_map(|resource| &resource.var_blocks);
+
+            for block in configuration.var_blocks.iter().chain(resource_blocks)
+            {
+                if block.kind == VarBlockKind::Global {
+                    for declaration in &block.declarations {
+                        self.declare_variable(declaration, &block.kind);
+                        self.globals.push(declaration);
+                    }
+                }
+            }
+        }
+
+        let mut enumerated_types = EnumeratedTypes::default();
+        enumerated_types.visit_file(file);
+        for (name, ty) in enumerated_types.found {
+            self.declare_enumerated_values(name, ty);
+        }
+    }
+
+    fn find_globals(&mut self, var_blocks: &'ast [VarBlock]) {
+        for block in var_blocks {
+            if block.kind == VarBlockKind::Global {
+                self.globals.extend(&block.declarations);
+            }
+        }
+    }
+
+    fn declare_enumerated_values(
+        &mut self,
+        name: Option<&Identifier>,
+        ty: &EnumeratedType,
+    ) {
+        let data_type = match name {
+            Some(name) => DataType::Named(name.clone()),
+            None => DataType::Enumerated(ty.clone()),
+        };
+
+        // each enumerated type gets its own namespace, but the values are
+        // accessible from everywhere
+        let scope = ScopeId(self.resolution.scopes.len());
+        self.resolution.scopes.push(Scope::new(None, None));
+        let previous = self.current.replace(scope);
+
+        for value in &ty.values {
+            let id = self.declare(
+                &value.name,
+                SymbolKind::EnumeratedValue,
+                Some(data_type.clone()),
+            );
+            self.enumerated_values.entry(key(&value.name.value)).or_insert(id);
+        }
+
+        self.current = previous;
+    }
+
+    fn declare(
+        &mut self,
+        name: &Identifier,
+        kind: SymbolKind,
+        data_type: Option<DataType>,
+    ) -> SymbolId {
+        let id = SymbolId(self.resolution.symbols.len());
+        let scope = self.current_scope();
+        let names = &mut self.resolution.scopes[scope.0].names;
+
+        if let Some(&previous) = names.get(&key(&name.value)) {
+            let previous = &self.resolution.symbols[previous.0];
+            let diagnostic = Diagnostic::error(
+                format!("`{}` is declared more than once", name.value),
+                Label::new(name.span, "redeclared here"),
+            )
+            .with_secondary_label(Label::new(
+                previous.span,
+                "first declared here",
+            ));
+            self.resolution.diagnostics.push(diagnostic);
+        } else {
+            names.insert(key(&name.value), id);
+        }
+
+        self.resolution.symbols.push(Symbol {
+            name: name.value.clone(),
+            kind,
+            span: name.span,
+            scope,
+            data_type,
+            file: self.file,
+        });
+        // a declaration refers to itself
+        self.refer(name, id);
+
+        id
+    }
+
+    fn declare_variable(
+        &mut self,
+        declaration: &'ast VariableDeclaration,
+        kind: &VarBlockKind,
+    ) {
+        self.visit_data_type(&declaration.declared_type);
+        if let Some(ref initial_value) = declaration.initial_value {
+            self.visit_initializer(initial_value);
+        }
+
+        let data_type = Some(declaration.declared_type.clone());
+        let id = self.declare(
+            &declaration.name,
+            SymbolKind::Variable(kind.clone()),
+            data_type,
+        );
+
+        if *kind == VarBlockKind::External {
+            self.check_external(declaration);
+        } else {
+            self.check_shadowing(id);
+        }
+    }
+
+    fn check_external(&mut self, declaration: &VariableDeclaration) {
+        let name = key(&declaration.name.value);
+        let global = self
+            .globals
+            .iter()
+            .find(|global| key(&global.name.value) == name);
+
+        let global = match global {
+            Some(global) => global,
+            None => {
+                self.resolution.diagnostics.push(Diagnostic::error(
+                    format!(
+                        "No matching VAR_GLOBAL for `{}`",
+                        declaration.name.value
+                    ),
+                    Label::new(
+                        declaration.name.span,
+                        "declared as VAR_EXTERNAL here",
+                    ),
+                ));
+                return;
+            },
+        };
+
+        let external = &declaration.declared_type;
+        let global = &global.declared_type;
+
+        if !same_type(external, global) {
+            let diagnostic = Diagnostic::error(
+                format!(
+                    "`{}` has a different type to the matching VAR_GLOBAL",
+                    declaration.name.value
+                ),
+                Label::new(external.span(), declared_as(external)),
+            )
+            .with_secondary_label(Label::new(
+                global.span(),
+                declared_as(global),
+            ));
+            self.resolution.diagnostics.push(diagnostic);
+        }
+    }
+
+    fn check_shadowing(&mut self, id: SymbolId) {
+        let symbol = &self.resolution.symbols[id.0];
+        let name = &symbol.name;
+
+        let outer = self.resolution.scopes[symbol.scope.0]
+            .parent
+            .and_then(|parent| self.lookup_from(parent, name))
+            .or_else(|| self.enumerated_values.get(&key(name)).cloned());
+
+        if let Some(outer) = outer {
+            let outer = &self.resolution.symbols[outer.0];
+            let diagnostic = Diagnostic::warning(
+                format!("`{}` shadows a declaration in an outer scope", name),
+                Label::new(symbol.span, "declared here"),
+            )
+            .with_secondary_label(Label::new(
+                outer.span,
+                "shadowed declaration",
+            ));
+            self.resolution.diagnostics.push(diagnostic);
+        }
+    }
+
+    fn lookup_from(&self, scope: ScopeId, name: &str) -> Option<SymbolId> {
+        let mut scope = Some(scope);
+
+        while let Some(id) = scope {
+            let current = &self.resolution.scopes[id.0];
+            if let Some(symbol) = current.get(name) {
+                return Some(symbol);
+            }
+            scope = current.parent;
+        }
+
+        None
+    }
+
+    fn lookup(&self, name: &str) -> Option<SymbolId> {
+        self.lookup_from(self.current_scope(), name)
+            .or_else(|| self.enumerated_values.get(&key(name)).cloned())
+    }
+
+    fn error(&mut self, message: String, span: Span, label: &str) {
+        self.resolution
+            .diagnostics
+            .push(Diagnostic::error(message, Label::new(span, label)));
+    }
+
+    fn resolve_variable(&mut self, name: &Identifier) {
+        match self.lookup(&name.value) {
+            Some(id) => {
+                self.refer(name, id);
+            },
+            None => self.error(
+                format!("Undeclared variable `{}`", name.value),
+                name.span,
+                "not found in this scope",
+            ),
+        }
+    }
+
+    fn resolve_type(&mut self, name: &Identifier) {
+        let file_scope = &self.resolution.scopes[Resolution::FILE_SCOPE.0];
+
+        match file_scope.get(&name.value) {
+            Some(id) => {
+                match self.resolution.symbols[id.0].kind {
+                    SymbolKind::Type | SymbolKind::FunctionBlock => {},
+                    _ => self.error(
+                        format!("`{}` is not a type", name.value),
+                        name.span,
+                        "expected a type",
+                    ),
+                }
+                self.refer(name, id);
+            },
+            None if is_builtin_type(&name.value) => {},
+            None => self.error(
+                format!("Undeclared type `{}`", name.value),
+                name.span,
+                "not found in this scope",
+            ),
+        }
+    }
+
+    fn resolve_call(&mut self, function: &Identifier) {
+        match self.lookup(&function.value) {
+            Some(id) => {
+                self.refer(function, id);
+            },
+            None if is_standard_function(&function.value) => {},
+            None => self.error(
+                format!("Undeclared function `{}`", function.value),
+                function.span,
+                "not found in this scope",
+            ),
+        }
+    }
+
+    fn enter_scope(&mut self, owner: Option<SymbolId>) -> ScopeId {
+        let id = ScopeId(self.resolution.scopes.len());
+        let parent = self.current_scope();
+        self.resolution.scopes.push(Scope::new(Some(parent), owner));
+        self.current = Some(id);
+
+        id
+    }
+
+    fn exit_scope(&mut self) {
+        let current = self.current_scope();
+        self.current = self.resolution.scopes[current.0].parent;
+    }
+
+    fn pou(
+        &mut self,
+        name: &Identifier,
+        var_blocks: &'ast [VarBlock],
+        body: &'ast Block,
+    ) {
+        let owner = self.resolution.lookup_in(self.file, name);
+        self.enter_scope(owner);
+
+        for block in var_blocks {
+            self.visit_var_block(block);
+        }
+        self.visit_block(body);
+
+        self.exit_scope();
+    }
+
+    /// Visit the `VAR` blocks of a configuration or resource, skipping
+    /// globals because they were already declared in the file scope.
+    fn deployment_var_blocks(&mut self, var_blocks: &'ast [VarBlock]) {
+        for block in var_blocks {
+            if block.kind != VarBlockKind::Global {
+                self.visit_var_block(block);
+            }
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for Resolver<'ast> {
+    fn visit_type_declaration(&mut self, declaration: &'ast TypeDeclaration) {
+        match declaration.data_type {
+            DataType::Struct(ref s) => {
+                let owner =
+                    self.resolution.lookup_in(self.file, &declaration.name);
+                self.enter_scope(owner);
+
+                for field in &s.fields {
+                    self.visit_data_type(&field.declared_type);
+                    if let Some(ref initial_value) = field.initial_value {
+                        self.visit_initializer(initial_value);
+                    }
+                    let data_type = Some(field.declared_type.clone());
+                    self.declare(&field.name, SymbolKind::Field, data_type);
+                }
+
+                self.exit_scope();
+            },
+            ref other => self.visit_data_type(other),
+        }
+
+        if let Some(ref initial_value) = declaration.initial_value {
+            self.visit_initializer(initial_value);
+        }
+    }
+
+    fn visit_function(&mut self, function: &'ast Function) {
+        self.resolve_type(&function.return_type);
+        self.pou(&function.name, &function.var_blocks, &function.body);
+    }
+
+    fn visit_function_block(&mut self, function_block: &'ast FunctionBlock) {
+        self.pou(
+            &function_block.name,
+            &function_block.var_blocks,
+            &function_block.body,
+        );
+    }
+
+    fn visit_program(&mut self, program: &'ast Program) {
+        self.pou(&program.name, &program.var_blocks, &program.body);
+    }
+
+    fn visit_configuration(&mut self, configuration: &'ast Configuration) {
+        let owner = self.resolution.lookup_in(self.file, &configuration.name);
+        self.enter_scope(owner);
+
+        self.deployment_var_blocks(&configuration.var_blocks);
+        for resource in &configuration.resources {
+            self.visit_resource(resource);
+        }
+
+        self.exit_scope();
+    }
+
+    fn visit_resource(&mut self, resource: &'ast Resource) {
+        let owner = resource
+            .name
+            .as_ref()
+            .map(|name| self.declare(name, SymbolKind::Resource, None));
+        self.enter_scope(owner);
+
+        self.deployment_var_blocks(&resource.var_blocks);
+        for task in &resource.tasks {
+            self.visit_task(task);
+        }
+        for program in &resource.programs {
+            self.visit_program_configuration(program);
+        }
+
+        self.exit_scope();
+    }
+
+    fn visit_task(&mut self, task: &'ast Task) {
+        self.declare(&task.name, SymbolKind::Task, None);
+        visitor::walk_task(self, task);
+    }
+
+    fn visit_program_configuration(
+        &mut self,
+        program: &'ast ProgramConfiguration,
+    ) {
+        let program_type = DataType::Named(program.program_type.clone());
+        self.declare(
+            &program.name,
+            SymbolKind::ProgramInstance,
+            Some(program_type),
+        );
+
+        if let Some(ref task) = program.task {
+            match self.lookup(&task.value) {
+                Some(id)
+                    if self.resolution.symbol(id).kind == SymbolKind::Task =>
+                {
+                    self.refer(task, id);
+                },
+                _ => self.error(
+                    format!("Undeclared task `{}`", task.value),
+                    task.span,
+                    "not found in this scope",
+                ),
+            }
+        }
+
+        let file_scope = &self.resolution.scopes[Resolution::FILE_SCOPE.0];
+        match file_scope.get(&program.program_type.value) {
+            Some(id)
+                if self.resolution.symbol(id).kind == SymbolKind::Program =>
+            {
+                self.refer(&program.program_type, id);
+            },
+            _ => self.error(
+                format!("Undeclared program `{}`", program.program_type.value),
+                program.program_type.span,
+                "not found in this scope",
+            ),
+        }
+    }
+
+    fn visit_var_block(&mut self, block: &'ast VarBlock) {
+        for declaration in &block.declarations {
+            self.declare_variable(declaration, &block.kind);
+        }
+    }
+
+    fn visit_data_type(&mut self, data_type: &'ast DataType) {
+        match data_type {
+            DataType::Named(name) => self.resolve_type(name),
+            DataType::Subrange(s) => self.resolve_type(&s.base_type),
+            // the values were declared up front
+            DataType::Enumerated(_) => {},
+            other => visitor::walk_data_type(self, other),
+        }
+    }
+
+    fn visit_assignment(&mut self, assignment: &'ast Assignment) {
+        self.resolve_variable(&assignment.variable);
+        self.visit_expression(&assignment.value);
+    }
+
+    fn visit_for(&mut self, for_loop: &'ast For) {
+        self.resolve_variable(&for_loop.variable);
+        self.visit_expression(&for_loop.start);
+        self.visit_expression(&for_loop.end);
+        if let Some(ref step) = for_loop.step {
+            self.visit_expression(step);
+        }
+        self.visit_block(&for_loop.body);
+    }
+
+    fn visit_case_label(&mut self, label: &'ast CaseLabel) {
+        if let CaseLabel::Enumerated(name) = label {
+            self.resolve_variable(name);
+        }
+    }
+
+    fn visit_call(&mut self, call: &'ast Call) {
+        self.resolve_call(&call.function);
+
+        for argument in &call.arguments {
+            self.visit_argument(argument);
+        }
+    }
+
+    fn visit_argument(&mut self, argument: &'ast Argument) {
+        // we don't know the callee's parameters, so argument names can't be
+        // checked here
+        match argument {
+            Argument::Positional(value) => self.visit_expression(value),
+            Argument::Input(input) => self.visit_expression(&input.value),
+            Argument::Output(output) => self.resolve_variable(&output.variable),
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &'ast Expression) {
+        match expression {
+            Expression::Variable(name) => self.resolve_variable(name),
+            other => visitor::walk_expression(self, other),
+        }
+    }
+
+    fn visit_member_access(&mut self, access: &'ast MemberAccess) {
+        // members can only be resolved once we know the value's type
+        self.visit_expression(&access.value);
+    }
+
+    fn visit_literal(&mut self, _literal: &'ast Literal) {
+        // typed literals can only use elementary types
+    }
+}
+
+/// Find all the enumerated types in a file, along with their name if they
+/// were declared in a `TYPE` block.
+#[derive(Default)]
+struct EnumeratedTypes<'ast> {
+    found: Vec<(Option<&'ast Identifier>, &'ast EnumeratedType)>,
+}
+
+impl<'ast> Visitor<'ast> for EnumeratedTypes<'ast> {
+    fn visit_type_declaration(&mut self, declaration: &'ast TypeDeclaration) {
+        match declaration.data_type {
+            DataType::Enumerated(ref e) => {
+                self.found.push((Some(&declaration.name), e))
+            },
+            ref other => self.visit_data_type(other),
+        }
+    }
+
+    fn visit_data_type(&mut self, data_type: &'ast DataType) {
+        if let DataType::Enumerated(e) = data_type {
+            self.found.push((None, e));
+        }
+        visitor::walk_data_type(self, data_type);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Severity;
+    use pretty_assertions::assert_eq;
+
+    fn resolve_str(src: &str) -> (File, Resolution) {
+        let file: File = src.parse().unwrap();
+        let resolution = resolve(&file);
+        (file, resolution)
+    }
+
+    fn span_of(src: &str, needle: &str, nth: usize) -> Span {
+        let start = src.match_indices(needle).nth(nth).unwrap().0;
+        Span::new(start as u32, (start + needle.len()) as u32)
+    }
+
+    #[test]
+    fn items_from_other_files_are_visible() {
+        let library: File = "TYPE Mode : (Idle, Running); END_TYPE
+            FUNCTION Double : INT
+                VAR_INPUT value : INT; END_VAR
+                Double := value * 2;
+            END_FUNCTION"
+            .parse()
+            .unwrap();
+        let src = "PROGRAM main
+                VAR m : Mode; x : INT; END_VAR
+                x := Double(x);
+                m := Running;
+                x := Triple(x);
+            END_PROGRAM";
+        let file: File = src.parse().unwrap();
+
+        let resolution = resolve_with(&file, &[&library]);
+
+        let messages: Vec<_> = resolution
+            .diagnostics
+            .iter()
+            .map(|d| d.message.as_str())
+            .collect();
+        assert_eq!(messages, &["Undeclared function `Triple`"]);
+        let call = match &file.programs[0].body.statements[0] {
+            Statement::Assignment(Assignment {
+                value: Expression::Call(call),
+                ..
+            }) => call,
+            other => panic!("Expected a call, found {:?}", other),
+        };
+        let double = resolution.lookup(&call.function).unwrap();
+        assert_eq!(resolution.symbol(double).kind, SymbolKind::Function);
+    }
+
+    #[test]
+    fn resolve_variables_and_functions() {
+        let src = "FUNCTION add : INT
+                VAR_INPUT a : INT; b : INT; END_VAR
+                add := a + b;
+            END_FUNCTION
+
+            PROGRAM main
+                VAR x : INT; END_VAR
+                X := add(x, 1);
+            END_PROGRAM";
+
+        let (file, resolution) = resolve_str(src);
+
+        assert!(resolution.diagnostics.is_empty());
+        let assignment = match &file.programs[0].body.statements[0] {
+            Statement::Assignment(a) => a,
+            other => panic!("Expected an assignment, found {:?}", other),
+        };
+        let x = resolution.lookup(&assignment.variable).unwrap();
+        let x = resolution.symbol(x);
+        assert_eq!(x.name, "x");
+        assert_eq!(x.kind, SymbolKind::Variable(VarBlockKind::Normal));
+        assert_eq!(x.span, span_of(src, "x", 0));
+        match &assignment.value {
+            Expression::Call(call) => {
+                let add = resolution.lookup(&call.function).unwrap();
+                assert_eq!(resolution.symbol(add).kind, SymbolKind::Function);
+            },
+            other => panic!("Expected a call, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn each_pou_gets_its_own_scope() {
+        let src = "PROGRAM first VAR x : INT; END_VAR END_PROGRAM
+            PROGRAM second VAR x : REAL; END_VAR END_PROGRAM";
+
+        let (file, resolution) = resolve_str(src);
+
+        assert!(resolution.diagnostics.is_empty());
+        let second = resolution.lookup(&file.programs[1].name).unwrap();
+        let scope = resolution.scope_of(second).unwrap();
+        let scope = resolution.scope(scope);
+        assert_eq!(scope.parent, Some(Resolution::FILE_SCOPE));
+        let x = resolution.symbol(scope.get("X").unwrap());
+        assert_eq!(x.span, span_of(src, "x", 1));
+    }
+
+    #[test]
+    fn undeclared_names_are_reported() {
+        let src = "PROGRAM main
+                VAR x : Missing; t : TON; END_VAR
+                x := y;
+                t(IN := z);
+                REAL_TO_INT(x);
+                nothing();
+                FOO_TO_BAR(x);
+            END_PROGRAM";
+
+        let (_, resolution) = resolve_str(src);
+
+        let got: Vec<_> = resolution
+            .diagnostics
+            .iter()
+            .map(|d| (d.message.as_str(), d.primary_label.span))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                ("Undeclared type `Missing`", span_of(src, "Missing", 0)),
+                ("Undeclared variable `y`", span_of(src, "y", 0)),
+                ("Undeclared variable `z`", span_of(src, "z", 0)),
+                ("Undeclared function `nothing`", span_of(src, "nothing", 0)),
+                (
+                    "Undeclared function `FOO_TO_BAR`",
+                    span_of(src, "FOO_TO_BAR", 0)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicates_point_at_the_first_declaration() {
+        let src = "PROGRAM main
+                VAR x : INT; END_VAR
+                VAR_INPUT X : BOOL; END_VAR
+            END_PROGRAM";
+
+        let (_, resolution) = resolve_str(src);
+
+        assert_eq!(resolution.diagnostics.len(), 1);
+        let diagnostic = &resolution.diagnostics[0];
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(diagnostic.primary_label.span, span_of(src, "X", 0));
+        assert_eq!(
+            diagnostic.secondary_labels[0].span,
+            span_of(src, "x", 0)
+        );
+    }
+
+    #[test]
+    fn shadowing_is_a_warning() {
+        let src = "TYPE State : (Idle, Running); END_TYPE
+            FUNCTION_BLOCK Motor END_FUNCTION_BLOCK
+            PROGRAM main
+                VAR motor : Motor; idle : BOOL; state : State; END_VAR
+                state := Running;
+            END_PROGRAM";
+
+        let (_, resolution) = resolve_str(src);
+
+        assert!(!resolution.has_errors());
+        let got: Vec<_> = resolution
+            .diagnostics
+            .iter()
+            .map(|d| (d.severity, d.primary_label.span))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (Severity::Warning, span_of(src, "motor", 0)),
+                (Severity::Warning, span_of(src, "idle", 0)),
+                (Severity::Warning, span_of(src, "state", 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn externals_need_a_matching_global() {
+        let src = "CONFIGURATION plant
+                VAR_GLOBAL speed : REAL; running : BOOL; END_VAR
+                PROGRAM instance : main;
+            END_CONFIGURATION
+
+            PROGRAM main
+                VAR_EXTERNAL
+                    running : BOOL;
+                    speed : INT;
+                    missing : BOOL;
+                END_VAR
+                running := speed > 0;
+            END_PROGRAM";
+
+        let (_, resolution) = resolve_str(src);
+
+        let got: Vec<_> = resolution
+            .diagnostics
+            .iter()
+            .map(|d| (d.message.as_str(), d.primary_label.span))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (
+                    "`speed` has a different type to the matching VAR_GLOBAL",
+                    span_of(src, "INT", 0)
+                ),
+                (
+                    "No matching VAR_GLOBAL for `missing`",
+                    span_of(src, "missing", 0)
+                ),
+            ]
+        );
+        assert_eq!(
+            resolution.diagnostics[0].secondary_labels[0].span,
+            span_of(src, "REAL", 0)
+        );
+    }
+
+    #[test]
+    fn externals_are_compared_structurally() {
+        let src = "CONFIGURATION plant
+                VAR_GLOBAL
+                    levels : ARRAY [1..10] OF INT;
+                    limits : ARRAY [1..10] OF INT;
+                    mode : (idle, running);
+                END_VAR
+                PROGRAM instance : main;
+            END_CONFIGURATION
+
+            PROGRAM main
+                VAR_EXTERNAL
+                    levels : ARRAY [1..10] OF int;
+                    limits : ARRAY [0..9] OF INT;
+                    mode : (idle, stopped);
+                END_VAR
+            END_PROGRAM";
+
+        let (_, resolution) = resolve_str(src);
+
+        let got: Vec<_> = resolution
+            .diagnostics
+            .iter()
+            .map(|d| (d.message.as_str(), d.primary_label.span))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (
+                    "`limits` has a different type to the matching VAR_GLOBAL",
+                    span_of(src, "ARRAY [0..9] OF INT", 0)
+                ),
+                (
+                    "`mode` has a different type to the matching VAR_GLOBAL",
+                    span_of(src, "(idle, stopped)", 0)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_tasks_and_program_instances() {
+        let src = "CONFIGURATION plant
+                RESOURCE cpu ON PLC
+                    TASK fast(INTERVAL := T#10ms, PRIORITY := 1);
+                    PROGRAM a WITH fast : main;
+                    PROGRAM b WITH slow : main;
+                    PROGRAM c : other;
+                END_RESOURCE
+            END_CONFIGURATION
+
+            PROGRAM main END_PROGRAM";
+
+        let (file, resolution) = resolve_str(src);
+
+        let messages: Vec<_> = resolution
+            .diagnostics
+            .iter()
+            .map(|d| d.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            vec!["Undeclared task `slow`", "Undeclared program `other`"]
+        );
+        let instance = &file.configurations[0].resources[0].programs[0];
+        let task = resolution.lookup(instance.task.as_ref().unwrap()).unwrap();
+        assert_eq!(resolution.symbol(task).kind, SymbolKind::Task);
+        let program = resolution.lookup(&instance.program_type).unwrap();
+        assert_eq!(resolution.symbol(program).kind, SymbolKind::Program);
+    }
+}
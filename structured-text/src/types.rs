@@ -0,0 +1,1298 @@
+//! Type checking.
+//!
+//! Every expression is given a [`Type`], following the implicit conversion
+//! rules from IEC 61131-3 (3rd edition, table 11). Integers and bit strings
+//! may be widened to a larger type of the same family, unsigned integers may
+//! be widened to a larger signed integer, `SINT`/`INT` and friends may be
+//! widened to a `REAL` or `LREAL`, and `REAL` may be widened to `LREAL`.
+//! Everything else needs an explicit conversion function like `REAL_TO_INT`.
+
+use crate::{
+    ast::*,
+    error::{Diagnostic, Label},
+    resolve::{Resolution, Symbol, SymbolId, SymbolKind},
+    visitor::{self, Visitor},
+};
+use codespan::Span;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    ops::RangeInclusive,
+};
+
+/// Type check a [`File`] which has already gone through name resolution
+/// (possibly alongside other files, using [`crate::resolve::resolve_with()`]).
+pub fn check(file: &File, resolution: &Resolution) -> TypeInfo {
+    let mut checker = TypeChecker {
+        resolution,
+        info: TypeInfo::default(),
+    };
+    checker.visit_file(file);
+
+    checker.info
+}
+
+/// The result of type checking.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TypeInfo {
+    pub diagnostics: Vec<Diagnostic>,
+    /// The type of each expression, keyed by the expression's span.
+    types: HashMap<Span, Type>,
+}
+
+impl TypeInfo {
+    pub fn type_of(&self, expression: &Expression) -> Option<&Type> {
+        self.types.get(&expression.span())
+    }
+}
+
+/// The type of a value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Bool,
+    SInt,
+    Int,
+    DInt,
+    LInt,
+    USInt,
+    UInt,
+    UDInt,
+    ULInt,
+    Real,
+    LReal,
+    Byte,
+    Word,
+    DWord,
+    LWord,
+    Time,
+    Date,
+    TimeOfDay,
+    DateAndTime,
+    String,
+    WString,
+    /// An integer literal without an explicit type (e.g. `42`), which may be
+    /// used wherever an integer, bit string or real is expected.
+    AnyInt,
+    /// A float literal without an explicit type (e.g. `3.14`).
+    AnyReal,
+    /// An enumerated type, identified by its name or, for anonymous types,
+    /// its list of values.
+    Enumerated(String),
+    Struct(String),
+    FunctionBlock(String),
+    Array {
+        element: Box<Type>,
+        dimensions: Vec<RangeInclusive<i64>>,
+    },
+    /// The type couldn't be determined, usually because an error was already
+    /// reported.
+    Unknown,
+}
+
+impl Type {
+    /// Look up one of the elementary types by name.
+    pub fn elementary(name: &str) -> Option<Type> {
+        let ty = match name.to_uppercase().as_str() {
+            "BOOL" => Type::Bool,
+            "SINT" => Type::SInt,
+            "INT" => Type::Int,
+            "DINT" => Type::DInt,
+            "LINT" => Type::LInt,
+            "USINT" => Type::USInt,
+            "UINT" => Type::UInt,
+            "UDINT" => Type::UDInt,
+            "ULINT" => Type::ULInt,
+            "REAL" => Type::Real,
+            "LREAL" => Type::LReal,
+            "BYTE" => Type::Byte,
+            "WORD" => Type::Word,
+            "DWORD" => Type::DWord,
+            "LWORD" => Type::LWord,
+            "TIME" => Type::Time,
+            "DATE" => Type::Date,
+            "TIME_OF_DAY" | "TOD" => Type::TimeOfDay,
+            "DATE_AND_TIME" | "DT" => Type::DateAndTime,
+            "STRING" => Type::String,
+            "WSTRING" => Type::WString,
+            _ => return None,
+        };
+
+        Some(ty)
+    }
+
+    /// The signedness and number of bits in an integer type.
+    fn integer(&self) -> Option<(bool, u32)> {
+        match self {
+            Type::SInt => Some((true, 8)),
+            Type::Int => Some((true, 16)),
+            Type::DInt => Some((true, 32)),
+            Type::LInt => Some((true, 64)),
+            Type::USInt => Some((false, 8)),
+            Type::UInt => Some((false, 16)),
+            Type::UDInt => Some((false, 32)),
+            Type::ULInt => Some((false, 64)),
+            _ => None,
+        }
+    }
+
+    /// The number of bits in a bit string.
+    fn bit_string(&self) -> Option<u32> {
+        match self {
+            Type::Byte => Some(8),
+            Type::Word => Some(16),
+            Type::DWord => Some(32),
+            Type::LWord => Some(64),
+            _ => None,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        *self == Type::AnyInt || self.integer().is_some()
+    }
+
+    pub fn is_signed(&self) -> bool {
+        match self.integer() {
+            Some((signed, _)) => signed,
+            None => self.is_real() || *self == Type::AnyInt,
+        }
+    }
+
+    pub fn is_real(&self) -> bool {
+        match self {
+            Type::Real | Type::LReal | Type::AnyReal => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool { self.is_integer() || self.is_real() }
+
+    pub fn is_bit_string(&self) -> bool {
+        *self == Type::Bool || self.bit_string().is_some()
+    }
+
+    /// Can a value of this type be implicitly converted to `target`?
+    pub fn widens_to(&self, target: &Type) -> bool {
+        if self == target || *self == Type::Unknown || *target == Type::Unknown
+        {
+            return true;
+        }
+
+        match (self, target) {
+            (Type::AnyInt, _) => {
+                return target.is_numeric() || target.bit_string().is_some()
+            },
+            (Type::AnyReal, _) => return target.is_real(),
+            (Type::Real, Type::LReal) => return true,
+            _ => {},
+        }
+
+        if let Some((from_signed, from_bits)) = self.integer() {
+            return match (target.integer(), target) {
+                // unsigned integers can become a larger signed integer, but
+                // not the other way around
+                (Some((to_signed, to_bits)), _) => {
+                    (from_signed == to_signed || to_signed)
+                        && to_bits > from_bits
+                },
+                (None, Type::Real) => from_bits <= 16,
+                (None, Type::LReal) => from_bits <= 32,
+                _ => false,
+            };
+        }
+
+        match (self.bit_string(), target.bit_string()) {
+            (Some(from), Some(to)) => to > from,
+            _ => false,
+        }
+    }
+
+    /// The type both operands of a binary expression can be converted to.
+    fn common(&self, other: &Type) -> Option<Type> {
+        if self.widens_to(other) {
+            Some(other.clone())
+        } else if other.widens_to(self) {
+            Some(self.clone())
+        } else {
+            None
+        }
+    }
+
+    fn is_elementary(&self) -> bool {
+        Type::elementary(&self.to_string()).is_some()
+    }
+
+    fn is_string(&self) -> bool {
+        *self == Type::String || *self == Type::WString
+    }
+
+    /// Is there a standard `X_TO_Y` function for converting this type to
+    /// `target` (3rd edition, table 22)?
+    fn has_conversion_to(&self, target: &Type) -> bool {
+        if !self.is_elementary() || !target.is_elementary() {
+            return false;
+        }
+
+        let convertible =
+            |ty: &Type| ty.is_numeric() || ty.is_bit_string() || ty.is_string();
+
+        match (self, target) {
+            (Type::DateAndTime, Type::Date)
+            | (Type::DateAndTime, Type::TimeOfDay) => true,
+            _ => convertible(self) && convertible(target),
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Type::Bool => "BOOL",
+            Type::SInt => "SINT",
+            Type::Int => "INT",
+            Type::DInt => "DINT",
+            Type::LInt => "LINT",
+            Type::USInt => "USINT",
+            Type::UInt => "UINT",
+            Type::UDInt => "UDINT",
+            Type::ULInt => "ULINT",
+            Type::Real => "REAL",
+            Type::LReal => "LREAL",
+            Type::Byte => "BYTE",
+            Type::Word => "WORD",
+            Type::DWord => "DWORD",
+            Type::LWord => "LWORD",
+            Type::Time => "TIME",
+            Type::Date => "DATE",
+            Type::TimeOfDay => "TIME_OF_DAY",
+            Type::DateAndTime => "DATE_AND_TIME",
+            Type::String => "STRING",
+            Type::WString => "WSTRING",
+            Type::AnyInt => "ANY_INT",
+            Type::AnyReal => "ANY_REAL",
+            Type::Enumerated(name)
+            | Type::Struct(name)
+            | Type::FunctionBlock(name) => name,
+            Type::Array {
+                element,
+                dimensions,
+            } => {
+                let dimensions: Vec<_> = dimensions
+                    .iter()
+                    .map(|d| format!("{}..{}", d.start(), d.end()))
+                    .collect();
+                return write!(
+                    f,
+                    "ARRAY [{}] OF {}",
+                    dimensions.join(", "),
+                    element
+                );
+            },
+            Type::Unknown => "{unknown}",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// A parameter for a function or function block.
+struct Parameter {
+    name: String,
+    input: bool,
+    ty: Type,
+}
+
+impl Parameter {
+    fn new(name: &str, input: bool, ty: Type) -> Parameter {
+        Parameter {
+            name: name.to_string(),
+            input,
+            ty,
+        }
+    }
+}
+
+/// The parameters for the function blocks from the standard library.
+fn standard_function_block(name: &str) -> Option<Vec<Parameter>> {
+    let bool_inputs = |names: &[&str]| -> Vec<Parameter> {
+        names
+            .iter()
+            .map(|name| Parameter::new(name, true, Type::Bool))
+            .collect()
+    };
+
+    let mut parameters = match name.to_uppercase().as_str() {
+        "TON" | "TOF" | "TP" => vec![
+            Parameter::new("IN", true, Type::Bool),
+            Parameter::new("PT", true, Type::Time),
+            Parameter::new("Q", false, Type::Bool),
+            Parameter::new("ET", false, Type::Time),
+        ],
+        "CTU" => bool_inputs(&["CU", "R"]),
+        "CTD" => bool_inputs(&["CD", "LD"]),
+        "CTUD" => bool_inputs(&["CU", "CD", "R", "LD"]),
+        "R_TRIG" | "F_TRIG" => bool_inputs(&["CLK"]),
+        "SR" => bool_inputs(&["S1", "R"]),
+        "RS" => bool_inputs(&["S", "R1"]),
+        _ => return None,
+    };
+
+    match name.to_uppercase().as_str() {
+        "CTU" | "CTD" => parameters.extend(vec![
+            Parameter::new("PV", true, Type::Int),
+            Parameter::new("Q", false, Type::Bool),
+            Parameter::new("CV", false, Type::Int),
+        ]),
+        "CTUD" => parameters.extend(vec![
+            Parameter::new("PV", true, Type::Int),
+            Parameter::new("QU", false, Type::Bool),
+            Parameter::new("QD", false, Type::Bool),
+            Parameter::new("CV", false, Type::Int),
+        ]),
+        "R_TRIG" | "F_TRIG" => {
+            parameters.push(Parameter::new("Q", false, Type::Bool))
+        },
+        "SR" | "RS" => parameters.push(Parameter::new("Q1", false, Type::Bool)),
+        _ => {},
+    }
+
+    Some(parameters)
+}
+
+fn literal_type(literal: &Literal) -> Type {
+    match literal {
+        Literal::Integer(_) => Type::AnyInt,
+        Literal::Float(_) => Type::AnyReal,
+        Literal::String(_) => Type::String,
+        Literal::WString(_) => Type::WString,
+        Literal::Boolean(_) => Type::Bool,
+        Literal::Typed(typed) => Type::elementary(&typed.declared_type.value)
+            .unwrap_or(Type::Unknown),
+        Literal::Duration(_) => Type::Time,
+        Literal::TimeOfDay(_) => Type::TimeOfDay,
+        Literal::Date(_) => Type::Date,
+        Literal::DateAndTime(_) => Type::DateAndTime,
+    }
+}
+
+fn anonymous_enum_name(e: &EnumeratedType) -> String {
+    let values: Vec<_> =
+        e.values.iter().map(|v| v.name.value.as_str()).collect();
+    format!("({})", values.join(", "))
+}
+
+/// Guards against type aliases which refer to themselves.
+const MAX_TYPE_DEPTH: usize = 32;
+
+struct TypeChecker<'r> {
+    resolution: &'r Resolution,
+    info: TypeInfo,
+}
+
+impl<'r> TypeChecker<'r> {
+    fn error(&mut self, message: &str, span: Span, label: String) {
+        self.info
+            .diagnostics
+            .push(Diagnostic::error(message, Label::new(span, label)));
+    }
+
+    /// Make sure a value of type `found` can be used where `expected` is
+    /// required.
+    fn expect(&mut self, expected: &Type, found: &Type, span: Span) {
+        if found.widens_to(expected) {
+            return;
+        }
+
+        let mut label = format!("expected {}, found {}", expected, found);
+        if found.has_conversion_to(expected) {
+            label.push_str(&format!(
+                " (use {}_TO_{} to convert explicitly)",
+                found, expected
+            ));
+        }
+        self.error("Mismatched types", span, label);
+    }
+
+    fn data_type(&self, data_type: &DataType) -> Type {
+        self.data_type_at_depth(data_type, 0, 0)
+    }
+
+    /// The type of a [`Symbol`], which may have been declared in another
+    /// file.
+    fn declared_type(&self, symbol: &Symbol) -> Type {
+        match symbol.data_type {
+            Some(ref data_type) => {
+                self.data_type_at_depth(data_type, symbol.file, 0)
+            },
+            None => Type::Unknown,
+        }
+    }
+
+    fn data_type_at_depth(
+        &self,
+        data_type: &DataType,
+        file: usize,
+        depth: usize,
+    ) -> Type {
+        if depth > MAX_TYPE_DEPTH {
+            return Type::Unknown;
+        }
+
+        match data_type {
+            DataType::Named(name) => self.named_type(name, file, depth + 1),
+            DataType::Enumerated(e) => Type::Enumerated(anonymous_enum_name(e)),
+            DataType::Subrange(s) => {
+                self.named_type(&s.base_type, file, depth + 1)
+            },
+            DataType::Array(a) => Type::Array {
+                element: Box::new(self.data_type_at_depth(
+                    &a.element_type,
+                    file,
+                    depth + 1,
+                )),
+                dimensions: a
+                    .dimensions
+                    .iter()
+                    .map(|d| d.start.value..=d.end.value)
+                    .collect(),
+            },
+            // structs can only be declared in a TYPE block, so this is
+            // handled by named_type()
+            DataType::Struct(_) => Type::Unknown,
+        }
+    }
+
+    fn named_type(&self, name: &Identifier, file: usize, depth: usize) -> Type {
+        if let Some(ty) = Type::elementary(&name.value) {
+            return ty;
+        }
+
+        let symbol = match self.resolution.lookup_in(file, name) {
+            Some(id) => self.resolution.symbol(id),
+            None if standard_function_block(&name.value).is_some() => {
+                return Type::FunctionBlock(name.value.to_uppercase())
+            },
+            None => return Type::Unknown,
+        };
+
+        match (&symbol.kind, &symbol.data_type) {
+            (SymbolKind::FunctionBlock, _) => {
+                Type::FunctionBlock(symbol.name.clone())
+            },
+            (SymbolKind::Type, Some(DataType::Struct(_))) => {
+                Type::Struct(symbol.name.clone())
+            },
+            (SymbolKind::Type, Some(DataType::Enumerated(_))) => {
+                Type::Enumerated(symbol.name.clone())
+            },
+            // aliases, subranges and arrays
+            (SymbolKind::Type, Some(definition)) => {
+                self.data_type_at_depth(definition, symbol.file, depth)
+            },
+            _ => Type::Unknown,
+        }
+    }
+
+    fn symbol_type(&self, id: SymbolId) -> Type {
+        let symbol = self.resolution.symbol(id);
+
+        match symbol.kind {
+            // assigning to a function's name sets its return value
+            SymbolKind::Variable(_)
+            | SymbolKind::Field
+            | SymbolKind::EnumeratedValue
+            | SymbolKind::Function => self.declared_type(symbol),
+            _ => Type::Unknown,
+        }
+    }
+
+    fn variable_type(&self, name: &Identifier) -> Type {
+        match self.resolution.lookup(name) {
+            Some(id) => self.symbol_type(id),
+            None => Type::Unknown,
+        }
+    }
+
+    /// The inputs and outputs of a user-defined function or function block.
+    fn parameters(&self, pou: SymbolId) -> Option<Vec<Parameter>> {
+        let scope = self.resolution.scope_of(pou)?;
+
+        let parameters = self
+            .resolution
+            .symbols
+            .iter()
+            .filter(|symbol| symbol.scope == scope)
+            .filter_map(|symbol| {
+                let input = match symbol.kind {
+                    SymbolKind::Variable(VarBlockKind::Input) => true,
+                    SymbolKind::Variable(VarBlockKind::Output) => false,
+                    _ => return None,
+                };
+                let ty = self.declared_type(symbol);
+
+                Some(Parameter::new(&symbol.name, input, ty))
+            })
+            .collect();
+
+        Some(parameters)
+    }
+
+    /// Get the type of a struct field or one of a function block's inputs
+    /// and outputs.
+    fn member_type(&self, ty: &Type, member: &str) -> Option<Type> {
+        let name = match ty {
+            Type::Struct(name) | Type::FunctionBlock(name) => name,
+            _ => return None,
+        };
+
+        if let Some(parameters) = standard_function_block(name) {
+            return parameters
+                .into_iter()
+                .find(|p| p.name.eq_ignore_ascii_case(member))
+                .map(|p| p.ty);
+        }
+
+        let file_scope = self.resolution.scope(Resolution::FILE_SCOPE);
+        let owner = file_scope.get(name)?;
+        let scope = self.resolution.scope(self.resolution.scope_of(owner)?);
+
+        scope.get(member).map(|id| self.symbol_type(id))
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Type {
+        let ty = match expression {
+            Expression::Variable(name) => self.variable_type(name),
+            Expression::Literal(literal) => literal_type(literal),
+            Expression::BinaryExpression(b) => self.binary_expression(b),
+            Expression::UnaryExpression(u) => self.unary_expression(u),
+            Expression::Call(call) => self.call(call),
+            Expression::MemberAccess(access) => self.member_access(access),
+        };
+
+        self.info.types.insert(expression.span(), ty.clone());
+        ty
+    }
+
+    fn binary_expression(&mut self, expression: &BinaryExpression) -> Type {
+        let left = self.expression(&expression.left);
+        let right = self.expression(&expression.right);
+        let op = expression.op;
+
+        let is_comparison = match op {
+            BinaryOp::Equals
+            | BinaryOp::NotEquals
+            | BinaryOp::LessThan
+            | BinaryOp::LessThanOrEqual
+            | BinaryOp::GreaterThan
+            | BinaryOp::GreaterThanOrEqual => true,
+            _ => false,
+        };
+
+        // durations can be scaled by a number (MUL_TIME and DIV_TIME)
+        if left == Type::Time
+            && right.is_numeric()
+            && (op == BinaryOp::Multiply || op == BinaryOp::Divide)
+        {
+            return Type::Time;
+        }
+
+        let operands = match left.common(&right) {
+            Some(ty) => ty,
+            None => {
+                self.error(
+                    "Mismatched types",
+                    expression.span,
+                    format!(
+                        "`{}` can't be applied to {} and {}",
+                        op.symbol(),
+                        left,
+                        right
+                    ),
+                );
+                return if is_comparison {
+                    Type::Bool
+                } else {
+                    Type::Unknown
+                };
+            },
+        };
+
+        let valid = match op {
+            BinaryOp::Add | BinaryOp::Subtract => {
+                operands.is_numeric() || operands == Type::Time
+            },
+            BinaryOp::Multiply | BinaryOp::Divide | BinaryOp::Exponent => {
+                operands.is_numeric()
+            },
+            BinaryOp::Modulo => operands.is_integer(),
+            BinaryOp::And | BinaryOp::Xor | BinaryOp::Or => {
+                operands.is_bit_string()
+            },
+            BinaryOp::Equals | BinaryOp::NotEquals => match operands {
+                Type::Struct(_)
+                | Type::FunctionBlock(_)
+                | Type::Array { .. } => false,
+                _ => true,
+            },
+            _ => {
+                operands.is_elementary()
+                    || operands == Type::AnyInt
+                    || operands == Type::AnyReal
+            },
+        };
+
+        if !valid && operands != Type::Unknown {
+            self.error(
+                "Invalid operand types",
+                expression.span,
+                format!("`{}` can't be applied to {}", op.symbol(), operands),
+            );
+        }
+
+        if is_comparison {
+            Type::Bool
+        } else if valid {
+            operands
+        } else {
+            Type::Unknown
+        }
+    }
+
+    fn unary_expression(&mut self, expression: &UnaryExpression) -> Type {
+        let value = self.expression(&expression.value);
+
+        let (valid, description) = match expression.op {
+            UnaryOp::Not => (value.is_bit_string(), "NOT"),
+            UnaryOp::Negate => {
+                (value.is_signed() || value == Type::Time, "negation")
+            },
+            UnaryOp::Plus => (value.is_numeric() || value == Type::Time, "`+`"),
+        };
+
+        if valid || value == Type::Unknown {
+            value
+        } else {
+            self.error(
+                "Invalid operand type",
+                expression.span,
+                format!("{} can't be applied to {}", description, value),
+            );
+            Type::Unknown
+        }
+    }
+
+    fn member_access(&mut self, access: &MemberAccess) -> Type {
+        let value = self.expression(&access.value);
+        if value == Type::Unknown {
+            return Type::Unknown;
+        }
+
+        match self.member_type(&value, &access.member.value) {
+            Some(ty) => ty,
+            None => {
+                self.error(
+                    "Unknown member",
+                    access.member.span,
+                    format!(
+                        "{} has no member `{}`",
+                        value, access.member.value
+                    ),
+                );
+                Type::Unknown
+            },
+        }
+    }
+
+    fn call(&mut self, call: &Call) -> Type {
+        let callee = match self.resolution.lookup(&call.function) {
+            Some(id) => id,
+            None => return self.standard_function(call),
+        };
+
+        let kind = &self.resolution.symbol(callee).kind;
+        let (parameters, return_type) = match kind {
+            SymbolKind::Function => {
+                (self.parameters(callee), self.symbol_type(callee))
+            },
+            SymbolKind::Variable(_) => match self.symbol_type(callee) {
+                Type::FunctionBlock(ref name) => {
+                    let parameters = standard_function_block(name).or_else(|| {
+                        let file_scope =
+                            self.resolution.scope(Resolution::FILE_SCOPE);
+                        self.parameters(file_scope.get(name)?)
+                    });
+                    (parameters, Type::Unknown)
+                },
+                Type::Unknown => (None, Type::Unknown),
+                other => {
+                    self.error(
+                        "Not callable",
+                        call.function.span,
+                        format!("{} is not a function block", other),
+                    );
+                    (None, Type::Unknown)
+                },
+            },
+            _ => (None, Type::Unknown),
+        };
+
+        match parameters {
+            Some(parameters) => self.arguments(call, &parameters),
+            None => {
+                for argument in &call.arguments {
+                    self.argument_type(argument);
+                }
+            },
+        }
+
+        return_type
+    }
+
+    fn argument_type(&mut self, argument: &Argument) -> Type {
+        match argument {
+            Argument::Positional(value) => self.expression(value),
+            Argument::Input(input) => self.expression(&input.value),
+            Argument::Output(output) => self.variable_type(&output.variable),
+        }
+    }
+
+    /// Check each argument against the parameter it's passed to.
+    fn arguments(&mut self, call: &Call, parameters: &[Parameter]) {
+        let mut inputs = parameters.iter().filter(|p| p.input);
+
+        for argument in &call.arguments {
+            let ty = self.argument_type(argument);
+
+            let (name, input) = match argument {
+                Argument::Positional(_) => match inputs.next() {
+                    Some(parameter) => {
+                        self.expect(&parameter.ty, &ty, argument.span());
+                        continue;
+                    },
+                    None => {
+                        self.error(
+                            "Too many arguments",
+                            argument.span(),
+                            format!(
+                                "`{}` doesn't take this many inputs",
+                                call.function.value
+                            ),
+                        );
+                        continue;
+                    },
+                },
+                Argument::Input(i) => (&i.name, true),
+                Argument::Output(o) => (&o.name, false),
+            };
+
+            let parameter = parameters.iter().find(|p| {
+                p.input == input && p.name.eq_ignore_ascii_case(&name.value)
+            });
+
+            match parameter {
+                Some(parameter) if input => {
+                    self.expect(&parameter.ty, &ty, argument.span())
+                },
+                // outputs are copied from the parameter to the variable
+                Some(parameter) => {
+                    self.expect(&ty, &parameter.ty, argument.span())
+                },
+                None => self.error(
+                    "Unknown parameter",
+                    name.span,
+                    format!(
+                        "`{}` has no {} called `{}`",
+                        call.function.value,
+                        if input { "input" } else { "output" },
+                        name.value
+                    ),
+                ),
+            }
+        }
+    }
+
+    fn standard_function(&mut self, call: &Call) -> Type {
+        let arguments: Vec<_> = call
+            .arguments
+            .iter()
+            .map(|argument| self.argument_type(argument))
+            .collect();
+        let name = call.function.value.to_uppercase();
+
+        // explicit conversions like REAL_TO_INT
+        if let Some(ix) = name.find("_TO_") {
+            let from = Type::elementary(&name[..ix]);
+            let to = Type::elementary(&name[ix + "_TO_".len()..]);
+
+            if let (Some(from), Some(argument)) = (from, arguments.first()) {
+                self.expect(&from, argument, call.arguments[0].span());
+            }
+
+            return to.unwrap_or(Type::Unknown);
+        }
+
+        match name.as_str() {
+            "ABS" | "MIN" | "MAX" | "LIMIT" | "MOVE" | "SQRT" | "LN" | "LOG"
+            | "EXP" | "SIN" | "COS" | "TAN" | "ASIN" | "ACOS" | "ATAN" => {
+                arguments
+                    .iter()
+                    .try_fold(Type::Unknown, |acc, ty| acc.common(ty))
+                    .unwrap_or(Type::Unknown)
+            },
+            "GT" | "GE" | "EQ" | "LE" | "LT" | "NE" => Type::Bool,
+            "LEN" | "FIND" => Type::Int,
+            "LEFT" | "RIGHT" | "MID" | "CONCAT" | "INSERT" | "DELETE"
+            | "REPLACE" => Type::String,
+            _ => Type::Unknown,
+        }
+    }
+
+    fn condition(&mut self, condition: &Expression) {
+        let ty = self.expression(condition);
+
+        if !ty.widens_to(&Type::Bool) {
+            self.error(
+                "Conditions must be a BOOL",
+                condition.span(),
+                format!("expected BOOL, found {}", ty),
+            );
+        }
+    }
+
+    fn initializer(&mut self, initializer: &Initializer, expected: &Type) {
+        match initializer {
+            Initializer::Expression(value) => {
+                let ty = self.expression(value);
+                self.expect(expected, &ty, value.span());
+            },
+            Initializer::Array(array) => {
+                let element = match expected {
+                    Type::Array { element, .. } => (**element).clone(),
+                    _ => Type::Unknown,
+                };
+                for item in &array.elements {
+                    self.initializer(&item.value, &element);
+                }
+            },
+            Initializer::Struct(s) => {
+                for field in &s.fields {
+                    let ty = self
+                        .member_type(expected, &field.name.value)
+                        .unwrap_or(Type::Unknown);
+                    self.initializer(&field.value, &ty);
+                }
+            },
+        }
+    }
+}
+
+impl<'ast, 'r> Visitor<'ast> for TypeChecker<'r> {
+    fn visit_type_declaration(&mut self, declaration: &'ast TypeDeclaration) {
+        visitor::walk_data_type(self, &declaration.data_type);
+
+        if let Some(ref initial_value) = declaration.initial_value {
+            let ty = self.named_type(&declaration.name, 0, 0);
+            self.initializer(initial_value, &ty);
+        }
+    }
+
+    fn visit_variable_declaration(
+        &mut self,
+        declaration: &'ast VariableDeclaration,
+    ) {
+        if let Some(ref initial_value) = declaration.initial_value {
+            let ty = self.data_type(&declaration.declared_type);
+            self.initializer(initial_value, &ty);
+        }
+    }
+
+    fn visit_assignment(&mut self, assignment: &'ast Assignment) {
+        let target = self.variable_type(&assignment.variable);
+        let value = self.expression(&assignment.value);
+
+        self.expect(&target, &value, assignment.value.span());
+    }
+
+    fn visit_conditional_branch(&mut self, branch: &'ast ConditionalBranch) {
+        self.condition(&branch.condition);
+        self.visit_block(&branch.block);
+    }
+
+    fn visit_while(&mut self, while_loop: &'ast While) {
+        self.condition(&while_loop.condition);
+        self.visit_block(&while_loop.body);
+    }
+
+    fn visit_repeat(&mut self, repeat: &'ast Repeat) {
+        self.visit_block(&repeat.block);
+        self.visit_assignment(&repeat.condition);
+        self.condition(&repeat.condition.value);
+    }
+
+    fn visit_for(&mut self, for_loop: &'ast For) {
+        let counter = self.variable_type(&for_loop.variable);
+        if !counter.is_integer() && counter != Type::Unknown {
+            self.error(
+                "FOR loop counters must be integers",
+                for_loop.variable.span,
+                format!("found {}", counter),
+            );
+        }
+
+        let bounds = std::iter::once(&for_loop.start)
+            .chain(std::iter::once(&for_loop.end))
+            .chain(&for_loop.step);
+        for bound in bounds {
+            let ty = self.expression(bound);
+            self.expect(&counter, &ty, bound.span());
+        }
+
+        self.visit_block(&for_loop.body);
+    }
+
+    fn visit_case(&mut self, case: &'ast Case) {
+        let selector = self.expression(&case.selector);
+
+        let valid = match selector {
+            Type::Enumerated(_) | Type::Unknown => true,
+            ref other => other.is_integer(),
+        };
+        if !valid {
+            self.error(
+                "CASE selectors must be an integer or enumerated value",
+                case.selector.span(),
+                format!("found {}", selector),
+            );
+        }
+
+        for branch in &case.branches {
+            for label in &branch.labels {
+                let (ty, span) = match label {
+                    CaseLabel::Integer(i) => (Type::AnyInt, i.span),
+                    CaseLabel::Range(r) => (Type::AnyInt, r.span),
+                    CaseLabel::Enumerated(name) => {
+                        (self.variable_type(name), name.span)
+                    },
+                };
+                if valid {
+                    self.expect(&selector, &ty, span);
+                }
+            }
+            self.visit_block(&branch.block);
+        }
+
+        if let Some(ref else_branch) = case.else_branch {
+            self.visit_block(else_branch);
+        }
+    }
+
+    fn visit_call(&mut self, call: &'ast Call) { self.call(call); }
+
+    fn visit_expression(&mut self, expression: &'ast Expression) {
+        self.expression(expression);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolve;
+    use pretty_assertions::assert_eq;
+
+    fn check_str(src: &str) -> (File, TypeInfo) {
+        let file: File = src.parse().unwrap();
+        let resolution = resolve::resolve(&file);
+        assert!(!resolution.has_errors(), "{:#?}", resolution.diagnostics);
+
+        let info = check(&file, &resolution);
+        (file, info)
+    }
+
+    fn errors(info: &TypeInfo, src: &str) -> Vec<(String, String)> {
+        info.diagnostics
+            .iter()
+            .map(|d| {
+                let span = d.primary_label.span;
+                let text = &src[span.start().to_usize()..span.end().to_usize()];
+                // expression spans may include trailing whitespace
+                (text.trim().to_string(), d.primary_label.message.clone())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn implicit_widening_rules() {
+        let inputs = vec![
+            (Type::SInt, Type::LInt, true),
+            (Type::Int, Type::SInt, false),
+            (Type::USInt, Type::Int, true),
+            (Type::UInt, Type::Int, false),
+            (Type::Int, Type::UDInt, false),
+            (Type::Int, Type::Real, true),
+            (Type::DInt, Type::Real, false),
+            (Type::DInt, Type::LReal, true),
+            (Type::Real, Type::LReal, true),
+            (Type::LReal, Type::Real, false),
+            (Type::Real, Type::Int, false),
+            (Type::Byte, Type::DWord, true),
+            (Type::Word, Type::Byte, false),
+            (Type::Byte, Type::Int, false),
+            (Type::AnyInt, Type::Word, true),
+            (Type::AnyInt, Type::LReal, true),
+            (Type::AnyReal, Type::Int, false),
+            (Type::Time, Type::LInt, false),
+        ];
+
+        for (from, to, should_be) in inputs {
+            assert_eq!(from.widens_to(&to), should_be, "{} -> {}", from, to);
+        }
+    }
+
+    #[test]
+    fn narrowing_needs_an_explicit_conversion() {
+        let src = "PROGRAM main
+                VAR i : INT; r : REAL; l : LINT; END_VAR
+                i := r;
+                i := REAL_TO_INT(r);
+                r := i;
+                l := i * 2;
+                i := l;
+                i := REAL_TO_INT(l);
+            END_PROGRAM";
+
+        let (_, info) = check_str(src);
+
+        assert_eq!(
+            errors(&info, src),
+            vec![
+                (
+                    "r".to_string(),
+                    "expected INT, found REAL (use REAL_TO_INT to convert \
+                     explicitly)"
+                        .to_string()
+                ),
+                (
+                    "l".to_string(),
+                    "expected INT, found LINT (use LINT_TO_INT to convert \
+                     explicitly)"
+                        .to_string()
+                ),
+                (
+                    "l".to_string(),
+                    "expected REAL, found LINT (use LINT_TO_REAL to convert \
+                     explicitly)"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_binary_expression() {
+        let src = "PROGRAM main
+                VAR i : INT; s : STRING; b : BOOL; END_VAR
+                b := i + s > 5;
+                i := i AND 3;
+                b := b AND i > 2;
+            END_PROGRAM";
+
+        let (_, info) = check_str(src);
+
+        assert_eq!(
+            errors(&info, src),
+            vec![
+                (
+                    "i + s".to_string(),
+                    "`+` can't be applied to INT and STRING".to_string()
+                ),
+                ("i AND 3".to_string(), "`AND` can't be applied to INT".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn conditions_must_be_booleans() {
+        let src = "PROGRAM main
+                VAR i : INT; done : BOOL; END_VAR
+                IF i THEN i := 0; ELSIF done THEN i := 1; END_IF;
+                WHILE i - 1 DO i := 0; END_WHILE;
+                REPEAT i := i + 1; UNTIL i := i + 1; END_REPEAT;
+                REPEAT i := i + 1; UNTIL done := i > 10; END_REPEAT;
+            END_PROGRAM";
+
+        let (_, info) = check_str(src);
+
+        let got: Vec<_> = errors(&info, src)
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect();
+        assert_eq!(got, vec!["i", "i - 1", "i + 1"]);
+    }
+
+    #[test]
+    fn function_arguments_are_checked() {
+        let src = "FUNCTION scale : REAL
+                VAR_INPUT value : INT; factor : REAL; END_VAR
+                scale := value * factor;
+            END_FUNCTION
+
+            PROGRAM main
+                VAR t : TON; done : BOOL; r : REAL; END_VAR
+                r := scale(3, 1.5);
+                r := scale(factor := 2, value := r);
+                r := scale(1, 2, 3);
+                t(IN := TRUE, PT := T#5s);
+                t(IN := TRUE, PT := 5, missing := 1);
+                done := t.Q;
+                done := t.ET;
+            END_PROGRAM";
+
+        let (_, info) = check_str(src);
+
+        assert_eq!(
+            errors(&info, src),
+            vec![
+                (
+                    "value := r".to_string(),
+                    "expected INT, found REAL (use REAL_TO_INT to convert \
+                     explicitly)"
+                        .to_string()
+                ),
+                (
+                    "3".to_string(),
+                    "`scale` doesn't take this many inputs".to_string()
+                ),
+                (
+                    "PT := 5".to_string(),
+                    "expected TIME, found ANY_INT".to_string()
+                ),
+                (
+                    "missing".to_string(),
+                    "`t` has no input called `missing`".to_string()
+                ),
+                (
+                    "t.ET".to_string(),
+                    "expected BOOL, found TIME".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn items_from_other_files_are_checked() {
+        let library: File = "TYPE Percent : INT(0..100); END_TYPE
+            FUNCTION Double : Percent
+                VAR_INPUT value : INT; END_VAR
+                Double := value * 2;
+            END_FUNCTION"
+            .parse()
+            .unwrap();
+        let src = "PROGRAM main
+                VAR p : INT; done : BOOL; END_VAR
+                p := Double(3);
+                done := Double(TRUE);
+            END_PROGRAM";
+        let file: File = src.parse().unwrap();
+        let resolution = resolve::resolve_with(&file, &[&library]);
+
+        let info = check(&file, &resolution);
+
+        assert_eq!(
+            errors(&info, src),
+            vec![
+                (
+                    "TRUE".to_string(),
+                    "expected INT, found BOOL (use BOOL_TO_INT to convert \
+                     explicitly)"
+                        .to_string()
+                ),
+                (
+                    "Double(TRUE)".to_string(),
+                    "expected BOOL, found INT (use INT_TO_BOOL to convert \
+                     explicitly)"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn user_defined_types() {
+        let src = "TYPE
+                Mode : (Idle, Running);
+                Percent : INT(0..100);
+                Axis : STRUCT position : REAL; mode : Mode; END_STRUCT;
+            END_TYPE
+
+            PROGRAM main
+                VAR axis : Axis; p : Percent; m : Mode := Idle; x : REAL;
+                END_VAR
+                x := axis.position + p;
+                m := axis.mode;
+                CASE m OF
+                    Idle: m := Running;
+                    1: m := 2;
+                END_CASE;
+                p := axis.velocity;
+            END_PROGRAM";
+
+        let (_, info) = check_str(src);
+
+        assert_eq!(
+            errors(&info, src),
+            vec![
+                ("1".to_string(), "expected Mode, found ANY_INT".to_string()),
+                ("2".to_string(), "expected Mode, found ANY_INT".to_string()),
+                (
+                    "velocity".to_string(),
+                    "Axis has no member `velocity`".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn arrays_keep_their_dimensions() {
+        let src = "PROGRAM main
+                VAR
+                    a : ARRAY [1..3] OF INT;
+                    b : ARRAY [1..3] OF INT;
+                    c : ARRAY [0..2, 0..1] OF INT;
+                END_VAR
+                a := b;
+                a := c;
+            END_PROGRAM";
+
+        let (_, info) = check_str(src);
+
+        assert_eq!(
+            errors(&info, src),
+            vec![(
+                "c".to_string(),
+                "expected ARRAY [1..3] OF INT, found ARRAY [0..2, 0..1] OF INT"
+                    .to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn expression_types_are_recorded() {
+        let src = "PROGRAM main
+                VAR i : SINT; r : LREAL; END_VAR
+                r := i * 2 + r;
+            END_PROGRAM";
+
+        let (file, info) = check_str(src);
+
+        let value = match &file.programs[0].body.statements[0] {
+            Statement::Assignment(a) => &a.value,
+            other => panic!("Expected an assignment, found {:?}", other),
+        };
+        assert_eq!(info.type_of(value), Some(&Type::LReal));
+        match value {
+            Expression::BinaryExpression(b) => {
+                assert_eq!(info.type_of(&b.left), Some(&Type::SInt));
+            },
+            other => panic!("Expected a binary expression, found {:?}", other),
+        }
+    }
+}
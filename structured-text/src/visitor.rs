@@ -0,0 +1,948 @@
+//! Traversal of the AST.
+//!
+//! The [`Visitor`] and [`VisitorMut`] traits have a `visit_*()` method for
+//! every node, with a default implementation which calls the corresponding
+//! `walk_*()` function to recurse into the node's children. Implementors only
+//! need to override the nodes they care about, calling the `walk_*()`
+//! function if they still want the children to be visited.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use rustmatic_structured_text::{
+//!     visitor::{self, Visitor},
+//!     Assignment,
+//! };
+//!
+//! #[derive(Default)]
+//! struct AssignedVariables(Vec<String>);
+//!
+//! impl<'ast> Visitor<'ast> for AssignedVariables {
+//!     fn visit_assignment(&mut self, assignment: &'ast Assignment) {
+//!         self.0.push(assignment.variable.value.clone());
+//!         visitor::walk_assignment(self, assignment);
+//!     }
+//! }
+//!
+//! let src = "PROGRAM main
+//!     IF x THEN y := 1; ELSE z := 2; END_IF;
+//! END_PROGRAM";
+//! let file = rustmatic_structured_text::parse(src).unwrap();
+//!
+//! let mut assigned = AssignedVariables::default();
+//! assigned.visit_file(&file);
+//!
+//! assert_eq!(assigned.0, vec!["y", "z"]);
+//! ```
+
+use crate::ast::*;
+
+macro_rules! visitor {
+    (
+        $(#[$attr:meta])*
+        trait $trait_name:ident $(<$lt:lifetime>)?,
+        type_ref = ($($r:tt)+),
+        expr_ref = ($($e:tt)+) $(,)?
+    ) => {
+        visitor! {
+            @trait
+            $(#[$attr])*
+            trait $trait_name $(<$lt>)?,
+            {
+                visit_file(file: $($r)+ File) => walk_file,
+                visit_configuration(
+                    configuration: $($r)+ Configuration,
+                ) => walk_configuration,
+                visit_resource(resource: $($r)+ Resource) => walk_resource,
+                visit_task(task: $($r)+ Task) => walk_task,
+                visit_program_configuration(
+                    program: $($r)+ ProgramConfiguration,
+                ) => walk_program_configuration,
+                visit_program(program: $($r)+ Program) => walk_program,
+                visit_function_block(
+                    function_block: $($r)+ FunctionBlock,
+                ) => walk_function_block,
+                visit_function(function: $($r)+ Function) => walk_function,
+                visit_type_declaration(
+                    declaration: $($r)+ TypeDeclaration,
+                ) => walk_type_declaration,
+                visit_data_type(data_type: $($r)+ DataType) => walk_data_type,
+                visit_struct_type(
+                    struct_type: $($r)+ StructType,
+                ) => walk_struct_type,
+                visit_enumerated_type(
+                    enumerated_type: $($r)+ EnumeratedType,
+                ) => walk_enumerated_type,
+                visit_enumerated_value(
+                    value: $($r)+ EnumeratedValue,
+                ) => walk_enumerated_value,
+                visit_subrange_type(
+                    subrange_type: $($r)+ SubrangeType,
+                ) => walk_subrange_type,
+                visit_array_type(
+                    array_type: $($r)+ ArrayType,
+                ) => walk_array_type,
+                visit_subrange(subrange: $($r)+ Subrange) => walk_subrange,
+                visit_signed_integer(
+                    integer: $($r)+ SignedInteger,
+                ) => walk_signed_integer,
+                visit_var_block(block: $($r)+ VarBlock) => walk_var_block,
+                visit_variable_declaration(
+                    declaration: $($r)+ VariableDeclaration,
+                ) => walk_variable_declaration,
+                visit_direct_address(
+                    address: $($r)+ DirectAddress,
+                ) => walk_direct_address,
+                visit_initializer(
+                    initializer: $($r)+ Initializer,
+                ) => walk_initializer,
+                visit_array_initializer(
+                    initializer: $($r)+ ArrayInitializer,
+                ) => walk_array_initializer,
+                visit_array_element(
+                    element: $($r)+ ArrayElement,
+                ) => walk_array_element,
+                visit_struct_initializer(
+                    initializer: $($r)+ StructInitializer,
+                ) => walk_struct_initializer,
+                visit_field_initializer(
+                    field: $($r)+ FieldInitializer,
+                ) => walk_field_initializer,
+                visit_block(block: $($r)+ Block) => walk_block,
+                visit_statement(statement: $($r)+ Statement) => walk_statement,
+                visit_assignment(
+                    assignment: $($r)+ Assignment,
+                ) => walk_assignment,
+                visit_conditional(
+                    conditional: $($r)+ Conditional,
+                ) => walk_conditional,
+                visit_conditional_branch(
+                    branch: $($r)+ ConditionalBranch,
+                ) => walk_conditional_branch,
+                visit_while(while_loop: $($r)+ While) => walk_while,
+                visit_for(for_loop: $($r)+ For) => walk_for,
+                visit_repeat(repeat: $($r)+ Repeat) => walk_repeat,
+                visit_case(case: $($r)+ Case) => walk_case,
+                visit_case_branch(
+                    branch: $($r)+ CaseBranch,
+                ) => walk_case_branch,
+                visit_case_label(label: $($r)+ CaseLabel) => walk_case_label,
+                visit_exit(exit: $($r)+ Exit) => walk_exit,
+                visit_call(call: $($r)+ Call) => walk_call,
+                visit_argument(argument: $($r)+ Argument) => walk_argument,
+                visit_input_argument(
+                    argument: $($r)+ InputArgument,
+                ) => walk_input_argument,
+                visit_output_argument(
+                    argument: $($r)+ OutputArgument,
+                ) => walk_output_argument,
+                visit_expression(
+                    expression: $($r)+ Expression,
+                ) => walk_expression,
+                visit_binary_expression(
+                    expression: $($r)+ BinaryExpression,
+                ) => walk_binary_expression,
+                visit_unary_expression(
+                    expression: $($r)+ UnaryExpression,
+                ) => walk_unary_expression,
+                visit_member_access(
+                    access: $($r)+ MemberAccess,
+                ) => walk_member_access,
+                visit_literal(literal: $($r)+ Literal) => walk_literal,
+                visit_typed_literal(
+                    literal: $($r)+ TypedLiteral,
+                ) => walk_typed_literal,
+                visit_duration_literal(
+                    literal: $($r)+ DurationLiteral,
+                ) => walk_duration_literal,
+                visit_time_of_day_literal(
+                    literal: $($r)+ TimeOfDayLiteral,
+                ) => walk_time_of_day_literal,
+                visit_date_literal(
+                    literal: $($r)+ DateLiteral,
+                ) => walk_date_literal,
+                visit_date_and_time_literal(
+                    literal: $($r)+ DateAndTimeLiteral,
+                ) => walk_date_and_time_literal,
+                visit_identifier(
+                    identifier: $($r)+ Identifier,
+                ) => walk_identifier,
+            }
+        }
+
+        visitor! {
+            @walk
+            $trait_name $(<$lt>)?,
+            ($($r)+),
+            ($($e)+)
+        }
+    };
+
+    (
+        @trait
+        $(#[$attr:meta])*
+        trait $trait_name:ident $(<$lt:lifetime>)?,
+        {
+            $(
+                $method:ident($arg:ident : $node:ty $(,)?) => $walk:ident,
+            )*
+        }
+    ) => {
+        $(#[$attr])*
+        pub trait $trait_name $(<$lt>)? {
+            $(
+                fn $method(&mut self, $arg: $node) {
+                    $walk(self, $arg);
+                }
+            )*
+        }
+    };
+
+    (
+        @walk
+        $trait_name:ident $(<$lt:lifetime>)?,
+        ($($r:tt)+),
+        ($($e:tt)+)
+    ) => {
+        pub fn walk_file<$($lt,)? V>(visitor: &mut V, file: $($r)+ File)
+        where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            for ty in $($e)+ file.types {
+                visitor.visit_type_declaration(ty);
+            }
+            for function in $($e)+ file.functions {
+                visitor.visit_function(function);
+            }
+            for function_block in $($e)+ file.function_blocks {
+                visitor.visit_function_block(function_block);
+            }
+            for program in $($e)+ file.programs {
+                visitor.visit_program(program);
+            }
+            for configuration in $($e)+ file.configurations {
+                visitor.visit_configuration(configuration);
+            }
+        }
+
+        pub fn walk_configuration<$($lt,)? V>(
+            visitor: &mut V,
+            configuration: $($r)+ Configuration,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ configuration.name);
+            for block in $($e)+ configuration.var_blocks {
+                visitor.visit_var_block(block);
+            }
+            for resource in $($e)+ configuration.resources {
+                visitor.visit_resource(resource);
+            }
+        }
+
+        pub fn walk_resource<$($lt,)? V>(
+            visitor: &mut V,
+            resource: $($r)+ Resource,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            if let Some(name) = $($e)+ resource.name {
+                visitor.visit_identifier(name);
+            }
+            if let Some(processor) = $($e)+ resource.processor {
+                visitor.visit_identifier(processor);
+            }
+            for block in $($e)+ resource.var_blocks {
+                visitor.visit_var_block(block);
+            }
+            for task in $($e)+ resource.tasks {
+                visitor.visit_task(task);
+            }
+            for program in $($e)+ resource.programs {
+                visitor.visit_program_configuration(program);
+            }
+        }
+
+        pub fn walk_task<$($lt,)? V>(visitor: &mut V, task: $($r)+ Task)
+        where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ task.name);
+            if let Some(single) = $($e)+ task.single {
+                visitor.visit_expression(single);
+            }
+            if let Some(interval) = $($e)+ task.interval {
+                visitor.visit_expression(interval);
+            }
+        }
+
+        pub fn walk_program_configuration<$($lt,)? V>(
+            visitor: &mut V,
+            program: $($r)+ ProgramConfiguration,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ program.name);
+            if let Some(task) = $($e)+ program.task {
+                visitor.visit_identifier(task);
+            }
+            visitor.visit_identifier($($e)+ program.program_type);
+        }
+
+        pub fn walk_program<$($lt,)? V>(
+            visitor: &mut V,
+            program: $($r)+ Program,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ program.name);
+            for block in $($e)+ program.var_blocks {
+                visitor.visit_var_block(block);
+            }
+            visitor.visit_block($($e)+ program.body);
+        }
+
+        pub fn walk_function_block<$($lt,)? V>(
+            visitor: &mut V,
+            function_block: $($r)+ FunctionBlock,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ function_block.name);
+            for block in $($e)+ function_block.var_blocks {
+                visitor.visit_var_block(block);
+            }
+            visitor.visit_block($($e)+ function_block.body);
+        }
+
+        pub fn walk_function<$($lt,)? V>(
+            visitor: &mut V,
+            function: $($r)+ Function,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ function.name);
+            visitor.visit_identifier($($e)+ function.return_type);
+            for block in $($e)+ function.var_blocks {
+                visitor.visit_var_block(block);
+            }
+            visitor.visit_block($($e)+ function.body);
+        }
+
+        pub fn walk_type_declaration<$($lt,)? V>(
+            visitor: &mut V,
+            declaration: $($r)+ TypeDeclaration,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ declaration.name);
+            visitor.visit_data_type($($e)+ declaration.data_type);
+            if let Some(initial_value) = $($e)+ declaration.initial_value {
+                visitor.visit_initializer(initial_value);
+            }
+        }
+
+        pub fn walk_data_type<$($lt,)? V>(
+            visitor: &mut V,
+            data_type: $($r)+ DataType,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            match data_type {
+                DataType::Named(name) => visitor.visit_identifier(name),
+                DataType::Struct(s) => visitor.visit_struct_type(s),
+                DataType::Enumerated(e) => visitor.visit_enumerated_type(e),
+                DataType::Subrange(s) => visitor.visit_subrange_type(s),
+                DataType::Array(a) => visitor.visit_array_type(a),
+            }
+        }
+
+        pub fn walk_struct_type<$($lt,)? V>(
+            visitor: &mut V,
+            struct_type: $($r)+ StructType,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            for field in $($e)+ struct_type.fields {
+                visitor.visit_variable_declaration(field);
+            }
+        }
+
+        pub fn walk_enumerated_type<$($lt,)? V>(
+            visitor: &mut V,
+            enumerated_type: $($r)+ EnumeratedType,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            for value in $($e)+ enumerated_type.values {
+                visitor.visit_enumerated_value(value);
+            }
+        }
+
+        pub fn walk_enumerated_value<$($lt,)? V>(
+            visitor: &mut V,
+            value: $($r)+ EnumeratedValue,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ value.name);
+            if let Some(integer) = $($e)+ value.value {
+                visitor.visit_signed_integer(integer);
+            }
+        }
+
+        pub fn walk_subrange_type<$($lt,)? V>(
+            visitor: &mut V,
+            subrange_type: $($r)+ SubrangeType,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ subrange_type.base_type);
+            visitor.visit_subrange($($e)+ subrange_type.range);
+        }
+
+        pub fn walk_array_type<$($lt,)? V>(
+            visitor: &mut V,
+            array_type: $($r)+ ArrayType,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            for dimension in $($e)+ array_type.dimensions {
+                visitor.visit_subrange(dimension);
+            }
+            visitor.visit_data_type($($e)+ *array_type.element_type);
+        }
+
+        pub fn walk_subrange<$($lt,)? V>(
+            visitor: &mut V,
+            subrange: $($r)+ Subrange,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_signed_integer($($e)+ subrange.start);
+            visitor.visit_signed_integer($($e)+ subrange.end);
+        }
+
+        pub fn walk_signed_integer<$($lt,)? V>(
+            _visitor: &mut V,
+            _integer: $($r)+ SignedInteger,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+        }
+
+        pub fn walk_var_block<$($lt,)? V>(
+            visitor: &mut V,
+            block: $($r)+ VarBlock,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            for declaration in $($e)+ block.declarations {
+                visitor.visit_variable_declaration(declaration);
+            }
+        }
+
+        pub fn walk_variable_declaration<$($lt,)? V>(
+            visitor: &mut V,
+            declaration: $($r)+ VariableDeclaration,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ declaration.name);
+            if let Some(address) = $($e)+ declaration.address {
+                visitor.visit_direct_address(address);
+            }
+            visitor.visit_data_type($($e)+ declaration.declared_type);
+            if let Some(initial_value) = $($e)+ declaration.initial_value {
+                visitor.visit_initializer(initial_value);
+            }
+        }
+
+        pub fn walk_direct_address<$($lt,)? V>(
+            _visitor: &mut V,
+            _address: $($r)+ DirectAddress,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+        }
+
+        pub fn walk_initializer<$($lt,)? V>(
+            visitor: &mut V,
+            initializer: $($r)+ Initializer,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            match initializer {
+                Initializer::Expression(e) => visitor.visit_expression(e),
+                Initializer::Array(a) => visitor.visit_array_initializer(a),
+                Initializer::Struct(s) => visitor.visit_struct_initializer(s),
+            }
+        }
+
+        pub fn walk_array_initializer<$($lt,)? V>(
+            visitor: &mut V,
+            initializer: $($r)+ ArrayInitializer,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            for element in $($e)+ initializer.elements {
+                visitor.visit_array_element(element);
+            }
+        }
+
+        pub fn walk_array_element<$($lt,)? V>(
+            visitor: &mut V,
+            element: $($r)+ ArrayElement,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_initializer($($e)+ element.value);
+        }
+
+        pub fn walk_struct_initializer<$($lt,)? V>(
+            visitor: &mut V,
+            initializer: $($r)+ StructInitializer,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            for field in $($e)+ initializer.fields {
+                visitor.visit_field_initializer(field);
+            }
+        }
+
+        pub fn walk_field_initializer<$($lt,)? V>(
+            visitor: &mut V,
+            field: $($r)+ FieldInitializer,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ field.name);
+            visitor.visit_initializer($($e)+ field.value);
+        }
+
+        pub fn walk_block<$($lt,)? V>(visitor: &mut V, block: $($r)+ Block)
+        where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            for statement in $($e)+ block.statements {
+                visitor.visit_statement(statement);
+            }
+        }
+
+        pub fn walk_statement<$($lt,)? V>(
+            visitor: &mut V,
+            statement: $($r)+ Statement,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            match statement {
+                Statement::Assignment(a) => visitor.visit_assignment(a),
+                Statement::Repeat(r) => visitor.visit_repeat(r),
+                Statement::Conditional(c) => visitor.visit_conditional(c),
+                Statement::While(w) => visitor.visit_while(w),
+                Statement::For(f) => visitor.visit_for(f),
+                Statement::Case(c) => visitor.visit_case(c),
+                Statement::Call(c) => visitor.visit_call(c),
+                Statement::Exit(e) => visitor.visit_exit(e),
+            }
+        }
+
+        pub fn walk_assignment<$($lt,)? V>(
+            visitor: &mut V,
+            assignment: $($r)+ Assignment,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ assignment.variable);
+            visitor.visit_expression($($e)+ assignment.value);
+        }
+
+        pub fn walk_conditional<$($lt,)? V>(
+            visitor: &mut V,
+            conditional: $($r)+ Conditional,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_conditional_branch($($e)+ conditional.true_branch);
+            for branch in $($e)+ conditional.else_if_branches {
+                visitor.visit_conditional_branch(branch);
+            }
+            if let Some(else_branch) = $($e)+ conditional.else_branch {
+                visitor.visit_block(else_branch);
+            }
+        }
+
+        pub fn walk_conditional_branch<$($lt,)? V>(
+            visitor: &mut V,
+            branch: $($r)+ ConditionalBranch,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_expression($($e)+ branch.condition);
+            visitor.visit_block($($e)+ branch.block);
+        }
+
+        pub fn walk_while<$($lt,)? V>(
+            visitor: &mut V,
+            while_loop: $($r)+ While,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_expression($($e)+ while_loop.condition);
+            visitor.visit_block($($e)+ while_loop.body);
+        }
+
+        pub fn walk_for<$($lt,)? V>(visitor: &mut V, for_loop: $($r)+ For)
+        where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ for_loop.variable);
+            visitor.visit_expression($($e)+ for_loop.start);
+            visitor.visit_expression($($e)+ for_loop.end);
+            if let Some(step) = $($e)+ for_loop.step {
+                visitor.visit_expression(step);
+            }
+            visitor.visit_block($($e)+ for_loop.body);
+        }
+
+        pub fn walk_repeat<$($lt,)? V>(
+            visitor: &mut V,
+            repeat: $($r)+ Repeat,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_block($($e)+ repeat.block);
+            visitor.visit_assignment($($e)+ repeat.condition);
+        }
+
+        pub fn walk_case<$($lt,)? V>(visitor: &mut V, case: $($r)+ Case)
+        where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_expression($($e)+ case.selector);
+            for branch in $($e)+ case.branches {
+                visitor.visit_case_branch(branch);
+            }
+            if let Some(else_branch) = $($e)+ case.else_branch {
+                visitor.visit_block(else_branch);
+            }
+        }
+
+        pub fn walk_case_branch<$($lt,)? V>(
+            visitor: &mut V,
+            branch: $($r)+ CaseBranch,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            for label in $($e)+ branch.labels {
+                visitor.visit_case_label(label);
+            }
+            visitor.visit_block($($e)+ branch.block);
+        }
+
+        pub fn walk_case_label<$($lt,)? V>(
+            visitor: &mut V,
+            label: $($r)+ CaseLabel,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            match label {
+                CaseLabel::Integer(integer) => {
+                    visitor.visit_signed_integer(integer)
+                },
+                CaseLabel::Range(range) => visitor.visit_subrange(range),
+                CaseLabel::Enumerated(name) => visitor.visit_identifier(name),
+            }
+        }
+
+        pub fn walk_exit<$($lt,)? V>(_visitor: &mut V, _exit: $($r)+ Exit)
+        where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+        }
+
+        pub fn walk_call<$($lt,)? V>(visitor: &mut V, call: $($r)+ Call)
+        where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ call.function);
+            for argument in $($e)+ call.arguments {
+                visitor.visit_argument(argument);
+            }
+        }
+
+        pub fn walk_argument<$($lt,)? V>(
+            visitor: &mut V,
+            argument: $($r)+ Argument,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            match argument {
+                Argument::Positional(value) => visitor.visit_expression(value),
+                Argument::Input(input) => visitor.visit_input_argument(input),
+                Argument::Output(output) => {
+                    visitor.visit_output_argument(output)
+                },
+            }
+        }
+
+        pub fn walk_input_argument<$($lt,)? V>(
+            visitor: &mut V,
+            argument: $($r)+ InputArgument,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ argument.name);
+            visitor.visit_expression($($e)+ argument.value);
+        }
+
+        pub fn walk_output_argument<$($lt,)? V>(
+            visitor: &mut V,
+            argument: $($r)+ OutputArgument,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ argument.name);
+            visitor.visit_identifier($($e)+ argument.variable);
+        }
+
+        pub fn walk_expression<$($lt,)? V>(
+            visitor: &mut V,
+            expression: $($r)+ Expression,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            match expression {
+                Expression::Variable(name) => visitor.visit_identifier(name),
+                Expression::Literal(l) => visitor.visit_literal(l),
+                Expression::BinaryExpression(b) => {
+                    visitor.visit_binary_expression(b)
+                },
+                Expression::UnaryExpression(u) => {
+                    visitor.visit_unary_expression(u)
+                },
+                Expression::Call(c) => visitor.visit_call(c),
+                Expression::MemberAccess(m) => visitor.visit_member_access(m),
+            }
+        }
+
+        pub fn walk_binary_expression<$($lt,)? V>(
+            visitor: &mut V,
+            expression: $($r)+ BinaryExpression,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_expression($($e)+ *expression.left);
+            visitor.visit_expression($($e)+ *expression.right);
+        }
+
+        pub fn walk_unary_expression<$($lt,)? V>(
+            visitor: &mut V,
+            expression: $($r)+ UnaryExpression,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_expression($($e)+ *expression.value);
+        }
+
+        pub fn walk_member_access<$($lt,)? V>(
+            visitor: &mut V,
+            access: $($r)+ MemberAccess,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_expression($($e)+ *access.value);
+            visitor.visit_identifier($($e)+ access.member);
+        }
+
+        pub fn walk_literal<$($lt,)? V>(
+            visitor: &mut V,
+            literal: $($r)+ Literal,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            match literal {
+                Literal::Typed(typed) => visitor.visit_typed_literal(typed),
+                Literal::Duration(d) => visitor.visit_duration_literal(d),
+                Literal::TimeOfDay(t) => visitor.visit_time_of_day_literal(t),
+                Literal::Date(d) => visitor.visit_date_literal(d),
+                Literal::DateAndTime(dt) => {
+                    visitor.visit_date_and_time_literal(dt)
+                },
+                _ => {},
+            }
+        }
+
+        pub fn walk_typed_literal<$($lt,)? V>(
+            visitor: &mut V,
+            literal: $($r)+ TypedLiteral,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_identifier($($e)+ literal.declared_type);
+            visitor.visit_literal($($e)+ *literal.value);
+        }
+
+        pub fn walk_duration_literal<$($lt,)? V>(
+            _visitor: &mut V,
+            _literal: $($r)+ DurationLiteral,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+        }
+
+        pub fn walk_time_of_day_literal<$($lt,)? V>(
+            _visitor: &mut V,
+            _literal: $($r)+ TimeOfDayLiteral,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+        }
+
+        pub fn walk_date_literal<$($lt,)? V>(
+            _visitor: &mut V,
+            _literal: $($r)+ DateLiteral,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+        }
+
+        pub fn walk_date_and_time_literal<$($lt,)? V>(
+            visitor: &mut V,
+            literal: $($r)+ DateAndTimeLiteral,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+            visitor.visit_date_literal($($e)+ literal.date);
+            visitor.visit_time_of_day_literal($($e)+ literal.time);
+        }
+
+        pub fn walk_identifier<$($lt,)? V>(
+            _visitor: &mut V,
+            _identifier: $($r)+ Identifier,
+        ) where
+            V: $trait_name $(<$lt>)? + ?Sized,
+        {
+        }
+    };
+}
+
+visitor! {
+    /// Read-only traversal of the AST.
+    trait Visitor<'ast>,
+    type_ref = (&'ast),
+    expr_ref = (&),
+}
+
+/// The `walk_*()` functions used by [`VisitorMut`].
+pub mod visit_mut {
+    use crate::ast::*;
+
+    visitor! {
+        /// Traversal of the AST which may modify nodes in place.
+        trait VisitorMut,
+        type_ref = (&mut),
+        expr_ref = (&mut),
+    }
+}
+
+pub use self::visit_mut::VisitorMut;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[derive(Default)]
+    struct Variables<'ast>(Vec<&'ast str>);
+
+    impl<'ast> Visitor<'ast> for Variables<'ast> {
+        fn visit_expression(&mut self, expression: &'ast Expression) {
+            if let Expression::Variable(name) = expression {
+                self.0.push(&name.value);
+            }
+            walk_expression(self, expression);
+        }
+
+        // skip the body of any nested loops
+        fn visit_while(&mut self, while_loop: &'ast While) {
+            self.visit_expression(&while_loop.condition);
+        }
+    }
+
+    #[test]
+    fn visit_every_expression_in_order() {
+        let src = "PROGRAM main
+            VAR x : INT := a; END_VAR
+            IF b AND fb.c THEN
+                y := f(d, IN := e) + INT#5;
+            ELSIF NOT g THEN
+                WHILE h DO z := i; END_WHILE;
+            END_IF;
+            CASE j OF 1: EXIT; ELSE k(); END_CASE;
+        END_PROGRAM";
+        let file: File = src.parse().unwrap();
+        let mut variables = Variables::default();
+
+        variables.visit_file(&file);
+
+        assert_eq!(
+            variables.0,
+            vec!["a", "b", "fb", "d", "e", "g", "h", "j"]
+        );
+    }
+
+    #[derive(Default)]
+    struct Integers(Vec<i64>);
+
+    impl<'ast> Visitor<'ast> for Integers {
+        fn visit_signed_integer(&mut self, integer: &'ast SignedInteger) {
+            self.0.push(integer.value);
+        }
+    }
+
+    #[test]
+    fn visit_enum_values_and_array_bounds() {
+        let src = "TYPE
+                Mode : (Idle := 1, Running := -2);
+                Levels : ARRAY [0..9, -3..3] OF INT(5..7);
+                Point : STRUCT x : INT; END_STRUCT := (x := 42);
+            END_TYPE";
+        let file: File = src.parse().unwrap();
+        let mut integers = Integers::default();
+
+        integers.visit_file(&file);
+
+        assert_eq!(integers.0, vec![1, -2, 0, 9, -3, 3, 5, 7]);
+    }
+
+    struct Rename;
+
+    impl VisitorMut for Rename {
+        fn visit_identifier(&mut self, identifier: &mut Identifier) {
+            if identifier.value == "x" {
+                identifier.value = String::from("renamed");
+            }
+        }
+    }
+
+    #[test]
+    fn rename_a_variable_everywhere() {
+        let src = "FUNCTION_BLOCK fb
+            VAR_INPUT x : INT; END_VAR
+            REPEAT x := x + 1; UNTIL done := x > 10; END_REPEAT;
+            FOR i := x TO 10 DO other(IN := x, Q => x); END_FOR;
+        END_FUNCTION_BLOCK";
+        let mut file: File = src.parse().unwrap();
+        let should_be: File = src.replace('x', "renamed").parse().unwrap();
+
+        Rename.visit_file(&mut file);
+
+        // compare the formatted code because all the spans have changed
+        let options = crate::FormatOptions::default();
+        assert_eq!(
+            crate::format(&file, &options),
+            crate::format(&should_be, &options)
+        );
+    }
+}
@@ -0,0 +1,663 @@
+//! Property tests which make sure formatted code can be parsed back into the
+//! same AST.
+
+use codespan::Span;
+use quickcheck::{Arbitrary, Gen};
+use quickcheck_macros::quickcheck;
+use rustmatic_structured_text::{
+    visitor::{visit_mut, VisitorMut},
+    *,
+};
+use std::time::Duration;
+
+#[quickcheck]
+fn formatting_round_trips(file: ArbitraryFile, options: ArbitraryOptions) {
+    let ArbitraryFile(file) = file;
+    let options = options.0;
+
+    let text = format(&file, &options);
+    let mut round_tripped = parse(&text)
+        .unwrap_or_else(|e| panic!("{:?}\n\n{}", e, text));
+
+    // the generated AST doesn't have meaningful spans
+    ZeroSpans.visit_file(&mut round_tripped);
+    assert_eq!(round_tripped, file, "{}", text);
+}
+
+#[test]
+fn formatting_the_sample_files_round_trips() {
+    let sources = vec![
+        include_str!("data/first_wikipedia_sample.st"),
+        include_str!("data/helper_functions.st"),
+        include_str!("data/user_types.st"),
+        include_str!("data/configuration.st"),
+        include_str!("data/state_machine.st"),
+        include_str!("data/loops.st"),
+    ];
+
+    for src in sources {
+        let options = FormatOptions::default();
+
+        let text = format(&parse(src).unwrap(), &options);
+        let canonical = parse(&text).unwrap();
+
+        assert_eq!(format(&canonical, &options), text);
+    }
+}
+
+/// Sets every [`Span`] in the AST to [`span()`].
+struct ZeroSpans;
+
+macro_rules! zero_spans {
+    ($($method:ident($node:ty) => $walk:ident),* $(,)*) => {
+        $(
+            fn $method(&mut self, node: &mut $node) {
+                node.span = span();
+                visit_mut::$walk(self, node);
+            }
+        )*
+    };
+}
+
+impl VisitorMut for ZeroSpans {
+    zero_spans! {
+        visit_file(File) => walk_file,
+        visit_configuration(Configuration) => walk_configuration,
+        visit_resource(Resource) => walk_resource,
+        visit_program_configuration(ProgramConfiguration)
+            => walk_program_configuration,
+        visit_program(Program) => walk_program,
+        visit_function_block(FunctionBlock) => walk_function_block,
+        visit_function(Function) => walk_function,
+        visit_type_declaration(TypeDeclaration) => walk_type_declaration,
+        visit_struct_type(StructType) => walk_struct_type,
+        visit_enumerated_type(EnumeratedType) => walk_enumerated_type,
+        visit_enumerated_value(EnumeratedValue) => walk_enumerated_value,
+        visit_subrange_type(SubrangeType) => walk_subrange_type,
+        visit_array_type(ArrayType) => walk_array_type,
+        visit_subrange(Subrange) => walk_subrange,
+        visit_signed_integer(SignedInteger) => walk_signed_integer,
+        visit_var_block(VarBlock) => walk_var_block,
+        visit_variable_declaration(VariableDeclaration)
+            => walk_variable_declaration,
+        visit_direct_address(DirectAddress) => walk_direct_address,
+        visit_array_initializer(ArrayInitializer) => walk_array_initializer,
+        visit_struct_initializer(StructInitializer)
+            => walk_struct_initializer,
+        visit_field_initializer(FieldInitializer) => walk_field_initializer,
+        visit_block(Block) => walk_block,
+        visit_assignment(Assignment) => walk_assignment,
+        visit_conditional(Conditional) => walk_conditional,
+        visit_while(While) => walk_while,
+        visit_for(For) => walk_for,
+        visit_repeat(Repeat) => walk_repeat,
+        visit_case(Case) => walk_case,
+        visit_case_branch(CaseBranch) => walk_case_branch,
+        visit_exit(Exit) => walk_exit,
+        visit_call(Call) => walk_call,
+        visit_input_argument(InputArgument) => walk_input_argument,
+        visit_output_argument(OutputArgument) => walk_output_argument,
+        visit_binary_expression(BinaryExpression) => walk_binary_expression,
+        visit_unary_expression(UnaryExpression) => walk_unary_expression,
+        visit_member_access(MemberAccess) => walk_member_access,
+        visit_typed_literal(TypedLiteral) => walk_typed_literal,
+        visit_duration_literal(DurationLiteral) => walk_duration_literal,
+        visit_time_of_day_literal(TimeOfDayLiteral)
+            => walk_time_of_day_literal,
+        visit_date_literal(DateLiteral) => walk_date_literal,
+        visit_date_and_time_literal(DateAndTimeLiteral)
+            => walk_date_and_time_literal,
+        visit_identifier(Identifier) => walk_identifier,
+    }
+
+    fn visit_task(&mut self, task: &mut Task) {
+        task.span = span();
+        if let Some(ref mut priority) = task.priority {
+            priority.span = span();
+        }
+        visit_mut::walk_task(self, task);
+    }
+
+    fn visit_array_element(&mut self, element: &mut ArrayElement) {
+        element.span = span();
+        if let Some(ref mut repetitions) = element.repetitions {
+            repetitions.span = span();
+        }
+        visit_mut::walk_array_element(self, element);
+    }
+
+    fn visit_literal(&mut self, literal: &mut Literal) {
+        match literal {
+            Literal::Integer(i) => i.span = span(),
+            Literal::Float(f) => f.span = span(),
+            Literal::String(s) | Literal::WString(s) => s.span = span(),
+            Literal::Boolean(b) => b.span = span(),
+            _ => {},
+        }
+        visit_mut::walk_literal(self, literal);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ArbitraryOptions(FormatOptions);
+
+impl Arbitrary for ArbitraryOptions {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        ArbitraryOptions(FormatOptions {
+            indent_width: 1 + choose(g, 8),
+            keyword_case: if bool::arbitrary(g) {
+                KeywordCase::Upper
+            } else {
+                KeywordCase::Lower
+            },
+            align_declarations: bool::arbitrary(g),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ArbitraryFile(File);
+
+impl Arbitrary for ArbitraryFile {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        ArbitraryFile(File {
+            configurations: many(g, 2, configuration),
+            programs: many(g, 3, |g| Program {
+                name: identifier(g),
+                var_blocks: many(g, 3, var_block),
+                body: block(g, 3),
+                span: span(),
+            }),
+            function_blocks: many(g, 2, |g| FunctionBlock {
+                name: identifier(g),
+                var_blocks: many(g, 3, var_block),
+                body: block(g, 3),
+                span: span(),
+            }),
+            functions: many(g, 2, |g| Function {
+                name: identifier(g),
+                return_type: type_name(g),
+                var_blocks: many(g, 3, var_block),
+                body: block(g, 3),
+                span: span(),
+            }),
+            types: many(g, 4, type_declaration),
+            span: span(),
+        })
+    }
+}
+
+fn span() -> Span { Span::new(0, 0) }
+
+fn choose<G: Gen>(g: &mut G, n: usize) -> usize { usize::arbitrary(g) % n }
+
+fn many<G, T, F>(g: &mut G, max: usize, mut f: F) -> Vec<T>
+where
+    G: Gen,
+    F: FnMut(&mut G) -> T,
+{
+    let count = choose(g, max + 1);
+    (0..count).map(|_| f(g)).collect()
+}
+
+fn maybe<G, T, F>(g: &mut G, f: F) -> Option<T>
+where
+    G: Gen,
+    F: FnOnce(&mut G) -> T,
+{
+    if bool::arbitrary(g) {
+        Some(f(g))
+    } else {
+        None
+    }
+}
+
+fn pick<G: Gen>(g: &mut G, items: &[&str]) -> Identifier {
+    Identifier {
+        value: items[choose(g, items.len())].to_string(),
+        span: span(),
+    }
+}
+
+fn identifier<G: Gen>(g: &mut G) -> Identifier {
+    pick(g, &["x", "y", "counter", "Motor_1", "speed", "done", "fb"])
+}
+
+fn type_name<G: Gen>(g: &mut G) -> Identifier {
+    pick(g, &["INT", "REAL", "BOOL", "TIME", "Setpoint"])
+}
+
+fn configuration<G: Gen>(g: &mut G) -> Configuration {
+    let resources = if bool::arbitrary(g) {
+        vec![Resource {
+            name: None,
+            processor: None,
+            var_blocks: Vec::new(),
+            tasks: many(g, 2, task),
+            programs: program_configurations(g),
+            span: span(),
+        }]
+    } else {
+        (0..1 + choose(g, 2))
+            .map(|_| Resource {
+                name: Some(identifier(g)),
+                processor: maybe(g, identifier),
+                var_blocks: many(g, 1, var_block),
+                tasks: many(g, 2, task),
+                programs: program_configurations(g),
+                span: span(),
+            })
+            .collect()
+    };
+
+    Configuration {
+        name: identifier(g),
+        var_blocks: many(g, 2, var_block),
+        resources,
+        span: span(),
+    }
+}
+
+fn task<G: Gen>(g: &mut G) -> Task {
+    let mut task = Task {
+        name: identifier(g),
+        single: maybe(g, |g| Expression::Variable(identifier(g))),
+        interval: maybe(g, |g| {
+            Expression::Literal(Literal::Duration(DurationLiteral {
+                value: Duration::from_millis(u16::arbitrary(g).into()),
+                span: span(),
+            }))
+        }),
+        priority: maybe(g, |g| integer(g)),
+        span: span(),
+    };
+
+    // a task needs at least one property
+    if task.priority.is_none() {
+        task.priority = Some(integer(g));
+    }
+
+    task
+}
+
+fn program_configurations<G: Gen>(g: &mut G) -> Vec<ProgramConfiguration> {
+    (0..1 + choose(g, 3))
+        .map(|_| ProgramConfiguration {
+            name: identifier(g),
+            task: maybe(g, identifier),
+            program_type: identifier(g),
+            span: span(),
+        })
+        .collect()
+}
+
+fn type_declaration<G: Gen>(g: &mut G) -> TypeDeclaration {
+    let data_type = if choose(g, 4) == 0 {
+        DataType::Struct(StructType {
+            fields: many(g, 3, variable_declaration),
+            span: span(),
+        })
+    } else {
+        data_type(g, 2)
+    };
+
+    TypeDeclaration {
+        name: identifier(g),
+        data_type,
+        initial_value: maybe(g, |g| initializer(g, 2)),
+        span: span(),
+    }
+}
+
+fn var_block<G: Gen>(g: &mut G) -> VarBlock {
+    let kinds = [
+        VarBlockKind::Normal,
+        VarBlockKind::Global,
+        VarBlockKind::External,
+        VarBlockKind::Input,
+        VarBlockKind::Output,
+    ];
+
+    VarBlock {
+        declarations: many(g, 4, variable_declaration),
+        kind: kinds[choose(g, kinds.len())].clone(),
+        span: span(),
+    }
+}
+
+fn variable_declaration<G: Gen>(g: &mut G) -> VariableDeclaration {
+    VariableDeclaration {
+        name: identifier(g),
+        address: maybe(g, direct_address),
+        declared_type: data_type(g, 2),
+        initial_value: maybe(g, |g| initializer(g, 2)),
+        span: span(),
+    }
+}
+
+fn direct_address<G: Gen>(g: &mut G) -> DirectAddress {
+    let areas = [AddressArea::Input, AddressArea::Output, AddressArea::Memory];
+    let sizes = [
+        AddressSize::Bit,
+        AddressSize::Byte,
+        AddressSize::Word,
+        AddressSize::DoubleWord,
+        AddressSize::LongWord,
+    ];
+    let size = sizes[choose(g, sizes.len())];
+
+    DirectAddress {
+        area: areas[choose(g, areas.len())],
+        size,
+        byte: u16::arbitrary(g).into(),
+        bit: if size == AddressSize::Bit {
+            Some(choose(g, 8) as u8)
+        } else {
+            None
+        },
+        span: span(),
+    }
+}
+
+fn data_type<G: Gen>(g: &mut G, depth: usize) -> DataType {
+    match choose(g, if depth == 0 { 1 } else { 4 }) {
+        0 => DataType::Named(type_name(g)),
+        1 => DataType::Enumerated(EnumeratedType {
+            values: (0..1 + choose(g, 3))
+                .map(|_| EnumeratedValue {
+                    name: identifier(g),
+                    value: maybe(g, signed_integer),
+                    span: span(),
+                })
+                .collect(),
+            span: span(),
+        }),
+        2 => DataType::Subrange(SubrangeType {
+            base_type: type_name(g),
+            range: subrange(g),
+            span: span(),
+        }),
+        _ => DataType::Array(ArrayType {
+            dimensions: (0..1 + choose(g, 3)).map(|_| subrange(g)).collect(),
+            element_type: Box::new(data_type(g, depth - 1)),
+            span: span(),
+        }),
+    }
+}
+
+fn initializer<G: Gen>(g: &mut G, depth: usize) -> Initializer {
+    match choose(g, if depth == 0 { 1 } else { 3 }) {
+        0 => Initializer::Expression(expression(g, 2)),
+        1 => Initializer::Array(ArrayInitializer {
+            elements: (0..1 + choose(g, 3))
+                .map(|_| ArrayElement {
+                    repetitions: maybe(g, integer),
+                    value: initializer(g, depth - 1),
+                    span: span(),
+                })
+                .collect(),
+            span: span(),
+        }),
+        _ => Initializer::Struct(StructInitializer {
+            fields: (0..1 + choose(g, 3))
+                .map(|_| FieldInitializer {
+                    name: identifier(g),
+                    value: initializer(g, depth - 1),
+                    span: span(),
+                })
+                .collect(),
+            span: span(),
+        }),
+    }
+}
+
+fn signed_integer<G: Gen>(g: &mut G) -> SignedInteger {
+    SignedInteger {
+        value: i16::arbitrary(g).into(),
+        span: span(),
+    }
+}
+
+fn subrange<G: Gen>(g: &mut G) -> Subrange {
+    Subrange {
+        start: signed_integer(g),
+        end: signed_integer(g),
+        span: span(),
+    }
+}
+
+fn integer<G: Gen>(g: &mut G) -> IntegerLiteral {
+    IntegerLiteral {
+        value: u16::arbitrary(g).into(),
+        span: span(),
+    }
+}
+
+fn block<G: Gen>(g: &mut G, depth: usize) -> Block {
+    Block {
+        statements: many(g, 3, |g| statement(g, depth)),
+        span: span(),
+    }
+}
+
+fn statement<G: Gen>(g: &mut G, depth: usize) -> Statement {
+    let assignment = |g: &mut G| Assignment {
+        variable: identifier(g),
+        value: expression(g, 3),
+        span: span(),
+    };
+
+    match choose(g, if depth == 0 { 3 } else { 8 }) {
+        0 => Statement::Assignment(assignment(g)),
+        1 => Statement::Call(call(g, 2)),
+        2 => Statement::Exit(Exit { span: span() }),
+        3 => Statement::Conditional(Conditional {
+            true_branch: ConditionalBranch {
+                condition: expression(g, 2),
+                block: block(g, depth - 1),
+            },
+            else_if_branches: many(g, 2, |g| ConditionalBranch {
+                condition: expression(g, 2),
+                block: block(g, depth - 1),
+            }),
+            else_branch: maybe(g, |g| block(g, depth - 1)),
+            span: span(),
+        }),
+        4 => Statement::While(While {
+            condition: expression(g, 2),
+            body: block(g, depth - 1),
+            span: span(),
+        }),
+        5 => Statement::For(For {
+            variable: identifier(g),
+            start: expression(g, 1),
+            end: expression(g, 1),
+            step: maybe(g, |g| expression(g, 1)),
+            body: block(g, depth - 1),
+            span: span(),
+        }),
+        6 => Statement::Repeat(Repeat {
+            block: block(g, depth - 1),
+            condition: assignment(g),
+            span: span(),
+        }),
+        _ => Statement::Case(Case {
+            selector: expression(g, 1),
+            branches: many(g, 3, |g| CaseBranch {
+                labels: (0..1 + choose(g, 3))
+                    .map(|_| match choose(g, 3) {
+                        0 => CaseLabel::Integer(signed_integer(g)),
+                        1 => CaseLabel::Range(subrange(g)),
+                        _ => CaseLabel::Enumerated(identifier(g)),
+                    })
+                    .collect(),
+                block: block(g, depth - 1),
+                span: span(),
+            }),
+            else_branch: maybe(g, |g| block(g, depth - 1)),
+            span: span(),
+        }),
+    }
+}
+
+fn call<G: Gen>(g: &mut G, depth: usize) -> Call {
+    Call {
+        function: identifier(g),
+        arguments: many(g, 3, |g| match choose(g, 3) {
+            0 => Argument::Positional(expression(g, depth)),
+            1 => Argument::Input(InputArgument {
+                name: identifier(g),
+                value: expression(g, depth),
+                span: span(),
+            }),
+            _ => Argument::Output(OutputArgument {
+                name: identifier(g),
+                variable: identifier(g),
+                span: span(),
+            }),
+        }),
+        span: span(),
+    }
+}
+
+fn expression<G: Gen>(g: &mut G, depth: usize) -> Expression {
+    let ops = [
+        BinaryOp::Equals,
+        BinaryOp::NotEquals,
+        BinaryOp::LessThan,
+        BinaryOp::LessThanOrEqual,
+        BinaryOp::GreaterThan,
+        BinaryOp::GreaterThanOrEqual,
+        BinaryOp::Add,
+        BinaryOp::Subtract,
+        BinaryOp::Multiply,
+        BinaryOp::Divide,
+        BinaryOp::Modulo,
+        BinaryOp::Exponent,
+        BinaryOp::And,
+        BinaryOp::Xor,
+        BinaryOp::Or,
+    ];
+    let unary_ops = [UnaryOp::Not, UnaryOp::Negate, UnaryOp::Plus];
+
+    match choose(g, if depth == 0 { 2 } else { 6 }) {
+        0 => Expression::Variable(identifier(g)),
+        1 => Expression::Literal(literal(g)),
+        2 | 3 => Expression::BinaryExpression(BinaryExpression {
+            left: Box::new(expression(g, depth - 1)),
+            right: Box::new(expression(g, depth - 1)),
+            op: ops[choose(g, ops.len())],
+            span: span(),
+        }),
+        4 => Expression::UnaryExpression(UnaryExpression {
+            op: unary_ops[choose(g, unary_ops.len())],
+            value: Box::new(expression(g, depth - 1)),
+            span: span(),
+        }),
+        _ if bool::arbitrary(g) => Expression::Call(call(g, depth - 1)),
+        _ => {
+            let mut value = Expression::Variable(identifier(g));
+            for _ in 0..1 + choose(g, 2) {
+                value = Expression::MemberAccess(MemberAccess {
+                    value: Box::new(value),
+                    member: identifier(g),
+                    span: span(),
+                });
+            }
+            value
+        },
+    }
+}
+
+fn literal<G: Gen>(g: &mut G) -> Literal {
+    let time = |g: &mut G| TimeOfDayLiteral {
+        hour: choose(g, 24) as u8,
+        minute: choose(g, 60) as u8,
+        second: choose(g, 60) as u8,
+        nanosecond: if bool::arbitrary(g) {
+            0
+        } else {
+            choose(g, 1_000_000_000) as u32
+        },
+        span: span(),
+    };
+    let date = |g: &mut G| DateLiteral {
+        year: 1970 + choose(g, 200) as u16,
+        month: 1 + choose(g, 12) as u8,
+        day: 1 + choose(g, 28) as u8,
+        span: span(),
+    };
+    let string = |g: &mut G| {
+        let pieces = ["a", "Hello", " ", "$", "'", "\"", "\n", "\t", "é"];
+        let value: String = (0..choose(g, 5))
+            .map(|_| pieces[choose(g, pieces.len())])
+            .collect();
+        StringLiteral { value, span: span() }
+    };
+
+    match choose(g, 10) {
+        0 => Literal::Integer(IntegerLiteral {
+            value: u64::arbitrary(g),
+            span: span(),
+        }),
+        1 => Literal::Float(float(g)),
+        2 => Literal::String(string(g)),
+        3 => Literal::WString(string(g)),
+        4 => Literal::Boolean(BooleanLiteral {
+            value: bool::arbitrary(g),
+            span: span(),
+        }),
+        5 => {
+            let (declared_type, negative, value) = match choose(g, 3) {
+                0 => (
+                    "INT",
+                    bool::arbitrary(g),
+                    Literal::Integer(IntegerLiteral {
+                        value: choose(g, 32768) as u64,
+                        span: span(),
+                    }),
+                ),
+                1 => ("REAL", bool::arbitrary(g), Literal::Float(float(g))),
+                _ => (
+                    "BOOL",
+                    false,
+                    Literal::Boolean(BooleanLiteral {
+                        value: bool::arbitrary(g),
+                        span: span(),
+                    }),
+                ),
+            };
+
+            Literal::Typed(TypedLiteral {
+                declared_type: Identifier {
+                    value: declared_type.to_string(),
+                    span: span(),
+                },
+                negative,
+                value: Box::new(value),
+                span: span(),
+            })
+        },
+        6 => Literal::Duration(DurationLiteral {
+            value: Duration::from_nanos(u64::arbitrary(g)),
+            span: span(),
+        }),
+        7 => Literal::TimeOfDay(time(g)),
+        8 => Literal::Date(date(g)),
+        _ => Literal::DateAndTime(DateAndTimeLiteral {
+            date: date(g),
+            time: time(g),
+            span: span(),
+        }),
+    }
+}
+
+fn float<G: Gen>(g: &mut G) -> FloatLiteral {
+    let value = f64::from(u32::arbitrary(g)) / 64.0;
+    let scale = [1.0, 1e-9, 1e20][choose(g, 3)];
+
+    FloatLiteral {
+        value: value * scale,
+        span: span(),
+    }
+}
@@ -20,12 +20,293 @@ macro_rules! parse_data_file {
 }
 
 parse_data_file! {
-    #[ignore]
     first_wikipedia_sample => file,
-    #[ignore]
     function_block => function_block,
     #[ignore]
     if_start_while_initialized_prime_system => conditional,
     if_chain => conditional,
     simple_program => program,
+    helper_functions => file,
+    conditional_program => program,
+    loops => program,
+    case_statement => case,
+    state_machine => program,
+    user_types => file,
+    configuration => file,
+}
+
+#[test]
+fn functions_are_collected_at_file_scope() {
+    let src = include_str!("data/helper_functions.st");
+
+    let file = rustmatic_structured_text::parse(src).unwrap();
+
+    let names: Vec<_> =
+        file.functions.iter().map(|f| f.name.value.as_str()).collect();
+    assert_eq!(names, &["Clamp", "Square"]);
+    let return_types: Vec<_> = file
+        .functions
+        .iter()
+        .map(|f| f.return_type.value.as_str())
+        .collect();
+    assert_eq!(return_types, &["INT", "REAL"]);
+    assert_eq!(file.function_blocks.len(), 1);
+    assert_eq!(file.programs.len(), 1);
+}
+
+#[test]
+fn if_statements_are_part_of_the_program_body() {
+    use rustmatic_structured_text::Statement;
+
+    let src = include_str!("data/conditional_program.st");
+
+    let file = rustmatic_structured_text::parse(src).unwrap();
+
+    let body = &file.programs[0].body;
+    assert_eq!(body.statements.len(), 1);
+    match &body.statements[0] {
+        Statement::Conditional(cond) => {
+            assert_eq!(cond.true_branch.block.statements.len(), 2);
+            assert_eq!(cond.else_if_branches.len(), 1);
+            assert!(cond.else_branch.is_some());
+        },
+        other => panic!("Expected a conditional, found {:?}", other),
+    }
+}
+
+#[test]
+fn loops_are_parsed_into_statements() {
+    use rustmatic_structured_text::Statement;
+
+    let src = include_str!("data/loops.st");
+
+    let file = rustmatic_structured_text::parse(src).unwrap();
+
+    let statements = &file.programs[0].body.statements;
+    assert_eq!(statements.len(), 4);
+    match &statements[1] {
+        Statement::For(f) => {
+            assert_eq!(f.variable.value, "i");
+            assert!(f.step.is_some());
+        },
+        other => panic!("Expected a FOR loop, found {:?}", other),
+    }
+    match &statements[2] {
+        Statement::While(w) => match w.body.statements[1] {
+            Statement::Exit(_) => {},
+            ref other => panic!("Expected EXIT, found {:?}", other),
+        },
+        other => panic!("Expected a WHILE loop, found {:?}", other),
+    }
+    match &statements[3] {
+        Statement::For(f) => assert!(f.step.is_none()),
+        other => panic!("Expected a FOR loop, found {:?}", other),
+    }
+}
+
+#[test]
+fn case_statements_drive_state_machines() {
+    use rustmatic_structured_text::{CaseLabel, Statement};
+
+    let src = include_str!("data/state_machine.st");
+
+    let file = rustmatic_structured_text::parse(src).unwrap();
+
+    let statements = &file.programs[0].body.statements;
+    match &statements[0] {
+        Statement::Case(case) => {
+            assert_eq!(case.branches.len(), 3);
+            assert_eq!(case.branches[1].labels.len(), 2);
+            match case.branches[2].labels[0] {
+                CaseLabel::Range(ref range) => {
+                    assert_eq!(range.start.value, 3);
+                    assert_eq!(range.end.value, 10);
+                },
+                ref other => panic!("Expected a range, found {:?}", other),
+            }
+            assert!(case.else_branch.is_some());
+        },
+        other => panic!("Expected a CASE statement, found {:?}", other),
+    }
+    match &statements[1] {
+        Statement::Case(case) => {
+            assert_eq!(case.branches.len(), 2);
+            assert!(case.else_branch.is_none());
+        },
+        other => panic!("Expected a CASE statement, found {:?}", other),
+    }
+}
+
+#[test]
+fn function_block_calls_and_member_access() {
+    use rustmatic_structured_text::{Expression, Statement};
+
+    let src = include_str!("data/function_block.st");
+
+    let file = rustmatic_structured_text::parse(src).unwrap();
+
+    let statements = &file.function_blocks[0].body.statements;
+    assert_eq!(statements.len(), 6);
+    match &statements[1] {
+        Statement::Call(call) => {
+            assert_eq!(call.function.value, "CycleTimer");
+            assert_eq!(call.arguments.len(), 2);
+        },
+        other => panic!("Expected a call, found {:?}", other),
+    }
+    match &statements[2] {
+        Statement::Assignment(assignment) => match &assignment.value {
+            Expression::MemberAccess(access) => {
+                assert_eq!(access.member.value, "Q")
+            },
+            other => panic!("Expected a member access, found {:?}", other),
+        },
+        other => panic!("Expected an assignment, found {:?}", other),
+    }
+}
+
+#[test]
+fn user_defined_types_are_collected_at_file_scope() {
+    use rustmatic_structured_text::{DataType, Initializer};
+
+    let src = include_str!("data/user_types.st");
+
+    let file = rustmatic_structured_text::parse(src).unwrap();
+
+    let names: Vec<_> =
+        file.types.iter().map(|t| t.name.value.as_str()).collect();
+    assert_eq!(
+        names,
+        &["Mode", "Percent", "Setpoints", "Matrix", "Speed", "Axis"]
+    );
+    match &file.types[3].data_type {
+        DataType::Array(array) => assert_eq!(array.dimensions.len(), 2),
+        other => panic!("Expected an array, found {:?}", other),
+    }
+    match &file.types[5].data_type {
+        DataType::Struct(s) => assert_eq!(s.fields.len(), 3),
+        other => panic!("Expected a struct, found {:?}", other),
+    }
+
+    let variables = &file.programs[0].var_blocks[0].declarations;
+    match variables[2].initial_value {
+        Some(Initializer::Array(ref array)) => {
+            assert_eq!(array.elements.len(), 3);
+            let repetitions = array.elements[2].repetitions.as_ref().unwrap();
+            assert_eq!(repetitions.value, 8);
+        },
+        ref other => panic!("Expected an array initializer, found {:?}", other),
+    }
+    match variables[3].initial_value {
+        Some(Initializer::Struct(ref s)) => assert_eq!(s.fields.len(), 2),
+        ref other => panic!("Expected a struct initializer, found {:?}", other),
+    }
+}
+
+#[test]
+fn configurations_describe_the_whole_deployment() {
+    let src = include_str!("data/configuration.st");
+
+    let file = rustmatic_structured_text::parse(src).unwrap();
+
+    assert_eq!(file.configurations.len(), 1);
+    assert_eq!(file.programs.len(), 1);
+    let config = &file.configurations[0];
+    assert_eq!(config.name.value, "Plant");
+    assert_eq!(config.var_blocks.len(), 1);
+    assert_eq!(config.resources.len(), 2);
+
+    let cpu1 = &config.resources[0];
+    assert_eq!(cpu1.name.as_ref().unwrap().value, "Cpu1");
+    assert_eq!(cpu1.processor.as_ref().unwrap().value, "PLC_CPU");
+    assert_eq!(cpu1.tasks.len(), 2);
+    assert!(cpu1.tasks[0].interval.is_some());
+    assert!(cpu1.tasks[1].single.is_some());
+    assert_eq!(cpu1.tasks[1].priority.as_ref().unwrap().value, 0);
+
+    let tasks: Vec<_> = cpu1
+        .programs
+        .iter()
+        .map(|p| p.task.as_ref().map(|t| t.value.as_str()))
+        .collect();
+    assert_eq!(tasks, &[Some("Fast"), Some("OnAlarm"), None]);
+    assert_eq!(cpu1.programs[2].program_type.value, "Housekeeping");
+}
+
+#[test]
+fn located_variables_are_bound_to_the_process_image() {
+    use rustmatic_structured_text::{AddressArea, AddressSize};
+
+    let src = include_str!("data/first_wikipedia_sample.st");
+
+    let file = rustmatic_structured_text::parse(src).unwrap();
+
+    let globals = &file.configurations[0].var_blocks[0].declarations;
+    assert!(globals[0].address.is_none());
+    let start_stop = globals[2].address.unwrap();
+    assert_eq!(start_stop.area, AddressArea::Input);
+    assert_eq!(start_stop.size, AddressSize::Bit);
+    assert_eq!((start_stop.byte, start_stop.bit), (0, Some(0)));
+    let on_off = globals[3].address.unwrap();
+    assert_eq!(on_off.area, AddressArea::Output);
+}
+
+#[test]
+fn every_error_in_a_file_is_reported() {
+    let src = include_str!("data/multiple_errors.st");
+
+    let (file, diagnostics) =
+        rustmatic_structured_text::parse_with_recovery(src);
+
+    let lines: Vec<_> = diagnostics
+        .iter()
+        .map(|d| {
+            let offset = d.primary_label.span.start().to_usize();
+            src[..offset].lines().count()
+        })
+        .collect();
+    assert_eq!(lines, &[4, 8, 12]);
+
+    let program = &file.programs[0];
+    assert_eq!(program.var_blocks[0].declarations.len(), 2);
+    assert_eq!(program.body.statements.len(), 2);
+    assert_eq!(file.function_blocks[0].name.value, "Fine");
+}
+
+#[test]
+fn the_concrete_syntax_tree_is_lossless() {
+    let sources = vec![
+        include_str!("data/first_wikipedia_sample.st"),
+        include_str!("data/helper_functions.st"),
+        include_str!("data/user_types.st"),
+        include_str!("data/configuration.st"),
+        include_str!("data/state_machine.st"),
+    ];
+
+    for src in sources {
+        let tree = rustmatic_structured_text::cst::parse(src).unwrap();
+
+        assert_eq!(tree.to_string(), src);
+    }
+}
+
+#[test]
+fn names_are_resolved_across_the_file() {
+    let src = include_str!("data/user_types.st");
+    let file = rustmatic_structured_text::parse(src).unwrap();
+
+    let resolution = rustmatic_structured_text::resolve::resolve(&file);
+
+    assert!(!resolution.has_errors(), "{:#?}", resolution.diagnostics);
+}
+
+#[test]
+fn user_defined_types_pass_the_type_checker() {
+    let src = include_str!("data/user_types.st");
+    let file = rustmatic_structured_text::parse(src).unwrap();
+    let resolution = rustmatic_structured_text::resolve::resolve(&file);
+
+    let info = rustmatic_structured_text::types::check(&file, &resolution);
+
+    assert!(info.diagnostics.is_empty(), "{:#?}", info.diagnostics);
 }
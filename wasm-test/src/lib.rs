@@ -7,6 +7,7 @@ pub use environment::TestEnvironment;
 pub use test_case::{Pass, Recipe, TestCase};
 
 use anyhow::{Context, Error};
+use rustmatic_wasm::Program;
 
 pub fn run_test_case(
     compiler: &Compiler,
@@ -15,12 +16,23 @@ pub fn run_test_case(
     let mut wasm = compiler
         .instantiate(&test_case.name, &test_case.src)
         .context("Unable to load the WASM module")?;
+
+    run_recipe(&test_case.name, &mut wasm, &test_case.recipe)
+}
+
+/// Run a [`Program`] which was compiled some other way (e.g. from
+/// *Structured Text*) against a [`Recipe`].
+pub fn run_recipe(
+    name: &str,
+    wasm: &mut Program,
+    recipe: &Recipe,
+) -> Result<(), Error> {
     let mut env = TestEnvironment::default();
 
-    for pass in &test_case.recipe.passes {
+    for pass in &recipe.passes {
         env.setup(pass);
 
-        log::debug!("Polling \"{}\" at {:?}", test_case.name, env.elapsed);
+        log::debug!("Polling \"{}\" at {:?}", name, env.elapsed);
         log::trace!("Environment: {:?}", env);
 
         wasm.poll(&mut env)
@@ -23,4 +23,14 @@ macro_rules! wasm_test {
     };
 }
 
-wasm_test!(example_program, blinky, set_outputs);
+wasm_test!(
+    example_program,
+    blinky,
+    ton_blinky,
+    set_outputs,
+    timers,
+    counters,
+    edges,
+    io,
+    globals
+);
@@ -0,0 +1,43 @@
+#![no_std]
+
+use rustmatic_iec_std::{
+    function_blocks::{Ctd, Ctu, Ctud},
+    io::{self, ix, qx, InputBit, OutputBit},
+};
+
+const COUNT_UP: InputBit = ix(0, 0);
+const COUNT_DOWN: InputBit = ix(0, 1);
+const RESET: InputBit = ix(0, 2);
+const LOAD: InputBit = ix(0, 3);
+
+const UP_Q: OutputBit = qx(0, 0);
+const DOWN_Q: OutputBit = qx(0, 1);
+const UP_DOWN_QU: OutputBit = qx(0, 2);
+const UP_DOWN_QD: OutputBit = qx(0, 3);
+
+static mut UP: Ctu = Ctu::new();
+static mut DOWN: Ctd = Ctd::new();
+static mut UP_DOWN: Ctud = Ctud::new();
+
+#[no_mangle]
+pub extern "C" fn poll() {
+    unsafe {
+        let count_up = COUNT_UP.read().unwrap();
+        let count_down = COUNT_DOWN.read().unwrap();
+        let reset = RESET.read().unwrap();
+        let load = LOAD.read().unwrap();
+
+        UP.call(count_up, reset, 2);
+        DOWN.call(count_down, load, 2);
+        UP_DOWN.call(count_up, count_down, reset, load, 3);
+
+        UP_Q.write(UP.q()).unwrap();
+        DOWN_Q.write(DOWN.q()).unwrap();
+        UP_DOWN_QU.write(UP_DOWN.qu()).unwrap();
+        UP_DOWN_QD.write(UP_DOWN.qd()).unwrap();
+
+        io::write_i8(1, UP.cv() as i8).unwrap();
+        io::write_i8(2, DOWN.cv() as i8).unwrap();
+        io::write_i8(3, UP_DOWN.cv() as i8).unwrap();
+    }
+}
@@ -0,0 +1,36 @@
+#![no_std]
+
+use rustmatic_iec_std::{
+    function_blocks::{FTrig, RTrig, Rs, Sr},
+    io::{ix, qx, InputBit, OutputBit},
+};
+
+const CLK: InputBit = ix(0, 0);
+const SET: InputBit = ix(0, 1);
+const RESET: InputBit = ix(0, 2);
+
+const RISING_Q: OutputBit = qx(0, 0);
+const FALLING_Q: OutputBit = qx(0, 1);
+const SET_DOMINANT_Q: OutputBit = qx(0, 2);
+const RESET_DOMINANT_Q: OutputBit = qx(0, 3);
+
+static mut RISING: RTrig = RTrig::new();
+static mut FALLING: FTrig = FTrig::new();
+static mut SET_DOMINANT: Sr = Sr::new();
+static mut RESET_DOMINANT: Rs = Rs::new();
+
+#[no_mangle]
+pub extern "C" fn poll() {
+    unsafe {
+        let clk = CLK.read().unwrap();
+        let set = SET.read().unwrap();
+        let reset = RESET.read().unwrap();
+
+        RISING_Q.write(RISING.call(clk)).unwrap();
+        FALLING_Q.write(FALLING.call(clk)).unwrap();
+        SET_DOMINANT_Q.write(SET_DOMINANT.call(set, reset)).unwrap();
+        RESET_DOMINANT_Q
+            .write(RESET_DOMINANT.call(set, reset))
+            .unwrap();
+    }
+}
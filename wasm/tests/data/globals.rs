@@ -0,0 +1,30 @@
+#![no_std]
+
+use rustmatic_iec_std::{
+    globals::{Error, Global},
+    io,
+};
+
+rustmatic_iec_std::globals! {
+    struct Counters {
+        polls: i32,
+    }
+}
+
+const COUNTERS: Counters = Counters::new();
+const POLLS_AS_BOOL: Global<bool> = Global::new("polls");
+
+#[no_mangle]
+pub extern "C" fn poll() {
+    let polls = match COUNTERS.polls.get() {
+        Ok(polls) => polls,
+        Err(Error::UnknownVariable) => 0,
+        Err(e) => panic!("Unable to read the poll count: {}", e),
+    };
+    COUNTERS.polls.set(polls + 1).unwrap();
+
+    let bad_type = POLLS_AS_BOOL.get() == Err(Error::BadVariableType);
+
+    io::write_u8(0, polls as u8).unwrap();
+    io::write_bit(1, 0, bad_type).unwrap();
+}
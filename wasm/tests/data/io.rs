@@ -0,0 +1,29 @@
+#![no_std]
+
+use rustmatic_iec_std::io::{
+    self, iw, ix, qw, qx, Error, Input, InputBit, Output, OutputBit,
+};
+
+const START: InputBit = ix(0, 0);
+const STOP: InputBit = ix(0, 1);
+const SETPOINT: Input<u16> = iw(1);
+
+const RUNNING: OutputBit = qx(0, 0);
+const STOPPED: OutputBit = qx(0, 2);
+const BAD_ADDRESS: OutputBit = qx(0, 7);
+const DOUBLED: Output<u16> = qw(1);
+const HALVED_ADDRESS: usize = 3;
+
+#[no_mangle]
+pub extern "C" fn poll() {
+    let start = START.read().unwrap();
+    let stop = STOP.read().unwrap();
+    let setpoint = SETPOINT.read().unwrap();
+    let out_of_bounds = io::read_u8(1000) == Err(Error::AddressOutOfBounds);
+
+    RUNNING.write(start && !stop).unwrap();
+    STOPPED.write(stop).unwrap();
+    BAD_ADDRESS.write(out_of_bounds).unwrap();
+    DOUBLED.write(setpoint.wrapping_mul(2)).unwrap();
+    io::write_f32(HALVED_ADDRESS, f32::from(setpoint) / 2.0).unwrap();
+}
@@ -0,0 +1,34 @@
+#![no_std]
+
+use core::time::Duration;
+use rustmatic_iec_std::{
+    function_blocks::{Tof, Ton, Tp},
+    io::{ix, qx, InputBit, OutputBit},
+};
+
+const PRESET: Duration = Duration::from_millis(100);
+
+const ON_DELAY_IN: InputBit = ix(0, 0);
+const OFF_DELAY_IN: InputBit = ix(0, 1);
+const PULSE_IN: InputBit = ix(0, 2);
+
+const ON_DELAY_Q: OutputBit = qx(0, 0);
+const OFF_DELAY_Q: OutputBit = qx(0, 1);
+const PULSE_Q: OutputBit = qx(0, 2);
+
+static mut ON_DELAY: Ton = Ton::new();
+static mut OFF_DELAY: Tof = Tof::new();
+static mut PULSE: Tp = Tp::new();
+
+#[no_mangle]
+pub extern "C" fn poll() {
+    unsafe {
+        let on_delay = ON_DELAY.call(ON_DELAY_IN.read().unwrap(), PRESET);
+        let off_delay = OFF_DELAY.call(OFF_DELAY_IN.read().unwrap(), PRESET);
+        let pulse = PULSE.call(PULSE_IN.read().unwrap(), PRESET);
+
+        ON_DELAY_Q.write(on_delay).unwrap();
+        OFF_DELAY_Q.write(off_delay).unwrap();
+        PULSE_Q.write(pulse).unwrap();
+    }
+}
@@ -0,0 +1,39 @@
+#![no_std]
+
+extern crate rustmatic_iec_std as iec_std;
+
+use core::time::Duration;
+use iec_std::{
+    function_blocks::Ton,
+    io::{ib, qx, Input, OutputBit},
+};
+
+const FREQUENCY: Input<u8> = ib(0);
+const LIGHT: OutputBit = qx(0, 0);
+
+static mut TIMER: Ton = Ton::new();
+static mut CURRENT_STATE: bool = false;
+
+#[no_mangle]
+pub extern "C" fn poll() {
+    unsafe {
+        let period = polling_period(FREQUENCY.read().unwrap());
+
+        if TIMER.call(true, period) {
+            CURRENT_STATE = !CURRENT_STATE;
+            // restart the timer so it measures the next period
+            TIMER.call(false, period);
+            TIMER.call(true, period);
+        }
+
+        LIGHT.write(CURRENT_STATE).unwrap();
+    }
+}
+
+fn polling_period(frequency: u8) -> Duration {
+    if frequency == 0 {
+        Duration::from_secs(1)
+    } else {
+        Duration::from_secs(1) / u32::from(frequency)
+    }
+}